@@ -1,4 +1,4 @@
-use log::{trace, warn};
+use log::{error, trace, warn};
 use std::{
     marker::PhantomData,
     sync::{
@@ -88,9 +88,26 @@ where
         };
 
         parameter.set_recv(rx);
-        request_tx
-            .send(Box::new(linker))
-            .expect(BACKEND_HUNG_UP_MSG);
+        send_request(request_tx, Box::new(linker));
+    }
+}
+
+/// Send `request` to the backend. If the backend hung up, the request is
+/// dropped (so that its receiver reports a closed channel) and `false` is
+/// returned, rather than panicking.
+pub fn send_request<S: BackendState>(
+    request_tx: &DynRequestSender<S>,
+    request: Box<dyn BackendRequest<S>>,
+) -> bool {
+    match request_tx.send(request) {
+        Ok(()) => true,
+        Err(err) => {
+            error!(
+                "{BACKEND_HUNG_UP_MSG}, dropping request '{}'",
+                err.0.describe()
+            );
+            false
+        }
     }
 }
 
@@ -1,5 +1,8 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
 use std::sync::mpsc::Receiver;
 use std::sync::mpsc::Sender;
+use std::sync::Arc;
 use std::thread::JoinHandle;
 
 use crate::backend::BackendLink;
@@ -12,21 +15,57 @@ where
 {
     pub state: S,
     request_rx: Receiver<Box<dyn BackendRequest<S>>>,
+    /// Requests that were received but did not run yet.
+    queue: VecDeque<Box<dyn BackendRequest<S>>>,
+    pending: PendingRequests,
     should_stop: bool,
 }
 
+/// Number of requests the backend received but did not finish yet
+/// (including the one currently running). It can be cloned and queried from
+/// other threads, e.g. to tell a hung backend from a deep queue.
+#[derive(Clone, Debug, Default)]
+pub struct PendingRequests(Arc<AtomicUsize>);
+
+impl PendingRequests {
+    pub fn get(&self) -> usize {
+        self.0.load(SeqCst)
+    }
+
+    fn set(&self, count: usize) {
+        self.0.store(count, SeqCst);
+    }
+}
+
 impl<S: BackendState + Send + 'static> BackendEventLoop<S> {
     pub fn update(&mut self) -> bool {
+        // Receive everything that was sent so far, so that the number of
+        // pending requests is known while running them.
         while let Ok(request) = self.request_rx.try_recv() {
+            self.queue.push_back(request);
+        }
+        if let Some(request) = self.queue.pop_front() {
+            self.pending.set(self.queue.len() + 1);
             request.run_on_backend(self);
             log::debug!("handled request '{}'", request.describe());
         }
+        self.pending.set(self.queue.len());
         self.should_stop
     }
     pub fn run(mut self) -> std::thread::JoinHandle<()> {
         std::thread::spawn(move || loop {
             let stop_loop = self.update();
             if stop_loop {
+                // Requests are handled in order, so everything sent before
+                // the stop signal already ran.
+                if !self.queue.is_empty() {
+                    log::debug!(
+                        "discarding {} request(s) sent after the stop signal",
+                        self.queue.len()
+                    );
+                    self.queue.clear();
+                    self.pending.set(0);
+                }
                 log::debug!("stopping backend event loop");
                 break;
             }
@@ -37,9 +76,16 @@ impl<S: BackendState + Send + 'static> BackendEventLoop<S> {
         Self {
             state,
             request_rx: command_rx,
+            queue: VecDeque::new(),
+            pending: PendingRequests::default(),
             should_stop: false,
         }
     }
+    /// Handle to query the number of pending requests, also after the event
+    /// loop was moved to its thread with `run`.
+    pub fn pending_requests(&self) -> PendingRequests {
+        self.pending.clone()
+    }
     pub fn signal_stop(&mut self) -> bool {
         self.should_stop = true;
         true
@@ -55,6 +101,8 @@ pub fn request_stop<S: BackendState + Send + 'static>(
             b.signal_stop();
             true
         });
+    // The signal is queued behind all requests sent so far, so these are
+    // finished before the event loop ends.
     log::debug!("sending signal to end backend event loop");
     if request_tx.send(Box::new(signal_end_linker)).is_ok() {
         if let Err(e) = rx.recv_timeout(std::time::Duration::from_secs(10)) {
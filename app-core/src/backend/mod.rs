@@ -2,8 +2,8 @@ mod backend_link;
 mod eventloop;
 
 pub use self::{
-    backend_link::{BackendLink, BackendRequest, LinkReceiver},
-    eventloop::{request_stop, BackendEventLoop},
+    backend_link::{send_request, BackendLink, BackendRequest, LinkReceiver},
+    eventloop::{request_stop, BackendEventLoop, PendingRequests},
 };
 
 pub trait BackendState {}
@@ -12,10 +12,12 @@ pub const BACKEND_HUNG_UP_MSG: &str = "backend thread hung up unexpectedly";
 mod tests {
     use std::time::Instant;
 
-    
     use log::trace;
 
-    use crate::backend::{request_stop, BackendEventLoop, BackendLink, BackendState};
+    use crate::{
+        backend::{request_stop, send_request, BackendEventLoop, BackendLink, BackendState},
+        frontend::UIParameter,
+    };
 
     struct TestState {}
     impl BackendState for TestState {}
@@ -48,4 +50,63 @@ mod tests {
         // ... thus this whole process here should take much less than 50 ms
         assert!(delta_time < 50);
     }
+
+    #[test]
+    fn test_pending_requests() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let (request_tx, request_rx) = std::sync::mpsc::channel();
+        let eventloop = BackendEventLoop::new(request_rx, TestState {});
+        let pending = eventloop.pending_requests();
+        let eventloop_handle = eventloop.run();
+
+        // The first request blocks the backend until we release it, so the
+        // second one stays queued.
+        let (release_tx, release_rx) = std::sync::mpsc::channel::<()>();
+        let release_rx = std::sync::Mutex::new(release_rx);
+        let (rx_blocking, linker) = BackendLink::new("block", move |_| {
+            let _ = release_rx.lock().unwrap().recv();
+        });
+        request_tx.send(Box::new(linker)).unwrap();
+        let (rx_queued, linker) = BackendLink::new("queued", |_| ());
+        request_tx.send(Box::new(linker)).unwrap();
+
+        let tic = Instant::now();
+        while pending.get() != 2 {
+            assert!(tic.elapsed().as_secs() < 5, "requests were never queued");
+            std::thread::yield_now();
+        }
+        release_tx.send(()).unwrap();
+        rx_queued
+            .recv_timeout(std::time::Duration::from_secs(5))
+            .unwrap();
+        drop(rx_blocking);
+        request_stop(&request_tx, eventloop_handle);
+        assert_eq!(pending.get(), 0);
+    }
+
+    #[test]
+    fn test_requests_after_backend_panicked() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let (mut request_tx, request_rx) = std::sync::mpsc::channel();
+        let eventloop_handle = BackendEventLoop::new(request_rx, TestState {}).run();
+        let (_rx, linker) = BackendLink::new("panic", |_| -> usize { panic!("backend crashed") });
+        request_tx.send(Box::new(linker)).unwrap();
+        assert!(eventloop_handle.join().is_err());
+
+        // Requests are dropped instead of panicking the frontend.
+        let (_rx, linker) = BackendLink::new("after panic", |_| 1);
+        assert!(!send_request(&request_tx, Box::new(linker)));
+        let mut parameter = UIParameter::new(0);
+        BackendLink::request_parameter_update(
+            &mut parameter,
+            "after panic",
+            |_| 1,
+            &mut request_tx,
+        );
+        parameter.try_update();
+        assert!(parameter.is_up_to_date());
+        assert_eq!(*parameter.value(), 0);
+    }
 }
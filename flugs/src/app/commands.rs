@@ -0,0 +1,198 @@
+use std::{path::PathBuf, str::FromStr};
+
+use super::{palette::Action, FileID};
+
+/// A command typed into the command line, see [`HELP`] for the syntax.
+/// Files are referred to by the ID shown in their settings, groups by their
+/// slot (0 to 9).
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    /// Add the file at the path to the first group.
+    Load(PathBuf),
+    XOffset(FileID, f64),
+    YOffset(FileID, f64),
+    YScale(FileID, f64),
+    Select(FileID),
+    /// Set the integration region, switching to integrate mode.
+    Integrate(f64, f64),
+    /// Show or hide a group.
+    Plot(usize, bool),
+    /// Run an action, by its ID (e.g. `reset_view`).
+    Run(Action),
+}
+
+pub const HELP: &str = "\
+load <path>            add a file to the first group
+offset <id> <y>        set the y-offset of a file
+xoffset <id> <x>       set the x-offset of a file
+scale <id> <factor>    set the y-scale of a file
+select <id>            select a file
+integrate <from> <to>  set the integration region
+show <group>           plot a group
+hide <group>           hide a group
+<action>               run an action, e.g. reset_view or export_svg";
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let line = line.trim();
+        let (name, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+        let args: Vec<&str> = rest.split_whitespace().collect();
+        let arity = |n: usize| {
+            if args.len() == n {
+                Ok(())
+            } else {
+                Err(format!(
+                    "'{name}' takes {n} argument(s), got {}",
+                    args.len()
+                ))
+            }
+        };
+        let number = |arg: &str| {
+            arg.parse::<f64>()
+                .map_err(|_| format!("'{arg}' is not a number"))
+        };
+        let fid = |arg: &str| {
+            arg.parse::<FileID>()
+                .map_err(|_| format!("'{arg}' is not a file ID"))
+        };
+        let gid = |arg: &str| match arg.parse::<usize>() {
+            Ok(gid) if gid < 10 => Ok(gid),
+            _ => Err(format!("'{arg}' is not a group (0 to 9)")),
+        };
+        match name {
+            "" => Err("empty command".to_string()),
+            "load" if rest.is_empty() => Err("'load' takes a path".to_string()),
+            // Paths may contain spaces, so the rest of the line is the path.
+            "load" => Ok(Command::Load(rest.trim_matches('"').into())),
+            "offset" | "xoffset" | "scale" => {
+                arity(2)?;
+                let (fid, value) = (fid(args[0])?, number(args[1])?);
+                Ok(match name {
+                    "offset" => Command::YOffset(fid, value),
+                    "xoffset" => Command::XOffset(fid, value),
+                    _ => Command::YScale(fid, value),
+                })
+            }
+            "select" => {
+                arity(1)?;
+                Ok(Command::Select(fid(args[0])?))
+            }
+            "integrate" => {
+                arity(2)?;
+                Ok(Command::Integrate(number(args[0])?, number(args[1])?))
+            }
+            "show" | "hide" => {
+                arity(1)?;
+                Ok(Command::Plot(gid(args[0])?, name == "show"))
+            }
+            _ => match Action::from_id(name) {
+                Some(action) => {
+                    arity(0)?;
+                    Ok(Command::Run(action))
+                }
+                None => Err(format!("unknown command '{name}'")),
+            },
+        }
+    }
+}
+
+/// Text input for commands, toggled with `Action::ToggleCommandLine`.
+#[derive(Debug, Default)]
+pub struct CommandLine {
+    text: String,
+    /// Commands entered before, the latest last.
+    history: Vec<String>,
+    /// Position in `history` while stepping through it with the arrow keys.
+    browsing: Option<usize>,
+    /// Why the last command failed, if it did.
+    pub error: Option<String>,
+}
+
+impl CommandLine {
+    /// Show the input, returns the line entered with Enter, if any. Arrow
+    /// up and down step through the entered lines.
+    pub fn render(&mut self, ui: &mut egui::Ui) -> Option<String> {
+        let mut entered = None;
+        ui.horizontal(|ui| {
+            ui.label("❯").on_hover_text(HELP);
+            let resp = ui.add(
+                egui::TextEdit::singleline(&mut self.text)
+                    .hint_text("command, hover ❯ for help")
+                    .font(egui::TextStyle::Monospace)
+                    .desired_width(f32::INFINITY),
+            );
+            if resp.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                let line = std::mem::take(&mut self.text);
+                if !line.trim().is_empty() {
+                    self.history.push(line.clone());
+                    entered = Some(line);
+                }
+                self.browsing = None;
+                resp.request_focus();
+            } else if resp.has_focus() {
+                let (up, down) = ui.input(|i| {
+                    (
+                        i.key_pressed(egui::Key::ArrowUp),
+                        i.key_pressed(egui::Key::ArrowDown),
+                    )
+                });
+                self.browse(up, down);
+            }
+        });
+        if let Some(err) = &self.error {
+            ui.colored_label(ui.visuals().error_fg_color, err);
+        }
+        entered
+    }
+
+    fn browse(&mut self, up: bool, down: bool) {
+        let position = match (self.browsing, up, down) {
+            (None, true, _) => self.history.len().checked_sub(1),
+            (Some(i), true, _) => Some(i.saturating_sub(1)),
+            (Some(i), _, true) => Some(i + 1).filter(|i| *i < self.history.len()),
+            _ => return,
+        };
+        self.browsing = position;
+        self.text = position
+            .map(|i| self.history[i].clone())
+            .unwrap_or_default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_commands() {
+        let fid: FileID = "3".parse().unwrap();
+        let parse = |line: &str| line.parse::<Command>();
+        assert_eq!(
+            parse("load /data/run 42.csv"),
+            Ok(Command::Load("/data/run 42.csv".into()))
+        );
+        assert_eq!(parse("  offset 3 1.5 "), Ok(Command::YOffset(fid, 1.5)));
+        assert_eq!(parse("scale 3 -2"), Ok(Command::YScale(fid, -2.0)));
+        assert_eq!(
+            parse("integrate 400 500"),
+            Ok(Command::Integrate(400.0, 500.0))
+        );
+        assert_eq!(parse("hide 2"), Ok(Command::Plot(2, false)));
+        assert_eq!(parse("reset_view"), Ok(Command::Run(Action::ResetView)));
+
+        assert!(parse("").is_err());
+        assert!(parse("load").is_err());
+        assert!(parse("offset 3").is_err());
+        assert!(parse("offset x 1").is_err());
+        assert!(parse("integrate 400 abc").is_err());
+        assert!(parse("show 10").is_err());
+        assert!(parse("reset_view now").is_err());
+        assert_eq!(
+            parse("frobnicate"),
+            Err("unknown command 'frobnicate'".into())
+        );
+    }
+}
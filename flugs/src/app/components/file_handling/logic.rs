@@ -1,9 +1,15 @@
-use std::path::Path;
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::mpsc::TryRecvError,
+    time::{Duration, Instant},
+};
+
+use regex::Regex;
 
 use app_core::{
-    backend::{BackendEventLoop, BackendLink, LinkReceiver},
+    backend::{send_request, BackendEventLoop, BackendLink, LinkReceiver},
     frontend::UIParameter,
-    BACKEND_HUNG_UP_MSG,
 };
 
 use crate::{
@@ -12,13 +18,30 @@ use crate::{
             search::{Match, ParsedData},
             Search,
         },
+        config::FileNameDisplay,
         DynRequestSender,
     },
-    backend_state::PlotData,
+    backend_state::{Corrections, LoadOptions, PlotData, SourceStamp, TOO_LARGE_MSG},
+    utils::{
+        baseline::{self, Polynomial},
+        expression::Expr,
+        integration,
+        interpolation::{self, Interpolation},
+        peaks::find_peaks,
+        simplify::Reduction,
+        smoothing::gaussian_smooth,
+        transform::{DataTransform, OffsetScale},
+    },
     BackendAppState,
 };
 
-use super::{File, FileHandler, FileID, Group};
+use super::{
+    BaselinePlot, File, FileHandler, FileID, FileInfo, FilePreview, FileProperties, FileStamp,
+    Group, LoadRequest, Structure, TransformMode, UNGROUPED,
+};
+
+/// How often [`FileHandler::reload_changed_files`] checks the files.
+pub const CHANGE_CHECK_INTERVAL: Duration = Duration::from_millis(200);
 
 impl File {
     pub fn get_cache(&self) -> Option<&Vec<[f64; 2]>> {
@@ -29,10 +52,478 @@ impl File {
             .ok()
     }
 
+    /// Read the data from disk again, e.g. after the file changed. Only the
+    /// data is replaced, the properties are kept. Pasted data is kept as is.
+    pub fn reload(&mut self, request_tx: &mut DynRequestSender) {
+        if self.is_pasted() {
+            log::info!("{:?} was pasted, it cannot be reloaded", self.path);
+            return;
+        }
+        self.request_data(request_tx);
+    }
+
+    /// Request the data from the backend as set by `load_request`.
+    fn request_data(&mut self, request_tx: &mut DynRequestSender) {
+        let sample_rate = self.properties.sample_rate();
+        let rx = match &self.load_request {
+            LoadRequest::Default => request_load(&self.path, true, sample_rate, request_tx),
+            LoadRequest::IgnoringSizeLimit => {
+                request_load(&self.path, false, sample_rate, request_tx)
+            }
+            LoadRequest::Options(options) => {
+                let (path, options) = (self.path.clone(), options.clone());
+                let (rx, linker) = BackendLink::new(
+                    &format!("load CSV data from file {:?}", path),
+                    move |_: &mut BackendEventLoop<BackendAppState>| {
+                        PlotData::from_path_with_sample_rate(&path, &options, sample_rate).map_err(
+                            |err| {
+                                log::error!("{}", err);
+                                err
+                            },
+                        )
+                    },
+                );
+                send_request(request_tx, Box::new(linker));
+                rx
+            }
+        };
+        self.data.set_recv(rx);
+    }
+
+    /// Size and modification time of the file when its data was read, if it
+    /// is loaded.
+    fn read_source(&self) -> Option<SourceStamp> {
+        match self.data.value() {
+            Ok(data) if self.data.is_up_to_date() => data.source(),
+            _ => None,
+        }
+    }
+
+    /// Whether the data was pasted or derived rather than read from a file
+    /// (see [`FileHandler::add_pasted`] and [`FileHandler::normalize_group`]).
+    pub fn is_pasted(&self) -> bool {
+        self.data
+            .value()
+            .as_ref()
+            .is_ok_and(|data| data.is_pasted())
+    }
+
+    /// Whether the file was not loaded because it exceeds the size limit.
+    pub fn is_too_large(&self) -> bool {
+        self.load_error()
+            .is_some_and(|err| err.starts_with(TOO_LARGE_MSG))
+    }
+
+    /// Load the file although it exceeds the size limit, also when it is
+    /// reloaded.
+    pub fn load_ignoring_size_limit(&mut self, request_tx: &mut DynRequestSender) {
+        self.load_request = LoadRequest::IgnoringSizeLimit;
+        self.request_data(request_tx);
+    }
+
+    /// Load the file with `options` rather than the options of the backend,
+    /// e.g. after checking them in a preview. Reloading keeps the options.
+    pub fn load_with_options(&mut self, options: LoadOptions, request_tx: &mut DynRequestSender) {
+        self.load_request = LoadRequest::Options(options);
+        self.request_data(request_tx);
+    }
+
+    /// The transform expression of the file, if one is set.
+    pub fn transform(&self) -> Option<Result<Expr, String>> {
+        let src = self.properties.transform.trim();
+        (!src.is_empty()).then(|| Expr::parse(src))
+    }
+
+    /// `data` with x and y swapped, if the file is plotted with x as the
+    /// second column.
+    fn oriented(&self, data: &[[f64; 2]]) -> Vec<[f64; 2]> {
+        if self.properties.swap_xy {
+            data.iter().map(|[x, y]| [*y, *x]).collect()
+        } else {
+            data.to_vec()
+        }
+    }
+
+    /// The x- and y-column of the data, see [`FileProperties::swap_xy`].
+    fn xy_columns<'a>(&self, data: &'a PlotData) -> Option<(&'a Vec<f64>, &'a Vec<f64>)> {
+        let (first, second) = (data.columns.first()?, data.columns.get(1)?);
+        if self.properties.swap_xy {
+            Some((second, first))
+        } else {
+            Some((first, second))
+        }
+    }
+
+    /// Lowest y-value of the data, before the transform, offsets and scaling.
+    pub fn ymin(&self) -> Option<f64> {
+        let (_, ys) = self.xy_columns(self.data.value().as_ref().ok()?)?;
+        ys.iter().copied().reduce(f64::min)
+    }
+
+    /// Whether the data is changed by more than offsets and scaling.
+    fn is_transformed(&self) -> bool {
+        self.has_expression_or_smoothing()
+            || self.subtracts_baseline()
+            || !self.transforms.is_empty()
+            || !self.properties.masked_ranges.is_empty()
+    }
+
+    /// Short descriptions of what is applied to the data before it is
+    /// plotted, in that order, e.g. for the header of the data table.
+    pub fn processing_steps(&self) -> Vec<String> {
+        let props = &self.properties;
+        let mut steps = Vec::new();
+        if props.swap_xy {
+            steps.push("x and y swapped".to_string());
+        }
+        if let Some(Ok(_)) = self.transform() {
+            steps.push(format!("transform {}", props.transform.trim()));
+        }
+        if props.smoothing_sigma > 0.0 {
+            steps.push(format!("smoothed (σ = {})", props.smoothing_sigma));
+        }
+        if self.subtracts_baseline() {
+            steps.push("baseline subtracted".to_string());
+        }
+        steps.extend(self.transforms.iter().map(|t| t.name().to_string()));
+        if !props.masked_ranges.is_empty() {
+            steps.push("ranges masked".to_string());
+        }
+        if props.xoffset != 0.0 {
+            steps.push(format!("x offset {}", props.xoffset));
+        }
+        if props.yoffset != 0.0 {
+            steps.push(format!("y offset {}", props.yoffset));
+        }
+        if props.yscale != 1.0 {
+            steps.push(format!("y scaled by {}", props.yscale));
+        }
+        steps
+    }
+
+    /// Whether `x` (before the offset) lies in one of the masked ranges.
+    fn is_masked(&self, x: f64) -> bool {
+        self.properties
+            .masked_ranges
+            .iter()
+            .any(|(a, b)| (a.min(*b)..=a.max(*b)).contains(&x))
+    }
+
+    /// Split the plotted `data` between points with a masked range in
+    /// between, so that the ranges are drawn as gaps (if enabled).
+    pub fn split_at_masks(&self, data: Vec<[f64; 2]>) -> Vec<Vec<[f64; 2]>> {
+        if !self.properties.show_mask_gaps || self.properties.masked_ranges.is_empty() {
+            return vec![data];
+        }
+        let xoffset = self.properties.xoffset;
+        let masked_between = |x0: f64, x1: f64| {
+            let (left, right) = (x0.min(x1), x0.max(x1));
+            self.properties
+                .masked_ranges
+                .iter()
+                .any(|(a, b)| a.min(*b) + xoffset < right && a.max(*b) + xoffset > left)
+        };
+        let mut segments: Vec<Vec<[f64; 2]>> = vec![Vec::new()];
+        for point in data {
+            let segment = segments.last_mut().expect("there is always a segment");
+            match segment.last() {
+                Some([x, _]) if masked_between(*x, point[0]) => segments.push(vec![point]),
+                _ => segment.push(point),
+            }
+        }
+        segments
+    }
+
+    fn has_expression_or_smoothing(&self) -> bool {
+        matches!(self.transform(), Some(Ok(_))) || self.properties.smoothing_sigma > 0.0
+    }
+
+    fn subtracts_baseline(&self) -> bool {
+        self.properties.subtract_baseline && !self.properties.baseline_anchors.is_empty()
+    }
+
+    /// The polynomial fitted through the baseline anchors of `data` (the
+    /// data before the baseline, offsets and scaling are applied).
+    fn fit_baseline(&self, data: &[[f64; 2]]) -> Option<Polynomial> {
+        let anchors = baseline::anchor_points(data, &self.properties.baseline_anchors);
+        baseline::polyfit(&anchors, self.properties.baseline_degree)
+    }
+
+    /// The fitted baseline, with offsets and scaling applied like to the
+    /// plotted data. Once subtracted, the baseline is zero (before the
+    /// offsets).
+    pub fn baseline(&self) -> Option<BaselinePlot> {
+        if self.properties.baseline_anchors.is_empty() {
+            return None;
+        }
+        let data = self.transformed_data(self.data.value().as_ref().ok()?);
+        let poly = self.fit_baseline(&data)?;
+        let subtract = self.properties.subtract_baseline;
+        let value = |x: f64| if subtract { 0.0 } else { poly.eval(x) };
+        let mut curve: Vec<_> = data
+            .iter()
+            .filter(|[x, _]| !self.is_masked(*x))
+            .map(|[x, _]| [*x, value(*x)])
+            .collect();
+        curve.sort_by(|[a, _], [b, _]| a.total_cmp(b));
+        let anchors: Vec<_> = baseline::anchor_points(&data, &self.properties.baseline_anchors)
+            .into_iter()
+            .map(|[x, _]| [x, value(x)])
+            .collect();
+        let ymin = plotted_ymin(&self.corrected_data()?);
+        Some(BaselinePlot {
+            curve: self.with_offsets(&curve, ymin),
+            anchors: self.with_offsets(&anchors, ymin),
+        })
+    }
+
+    /// The cached data of `plot_data` (the data of the file) with the
+    /// transform and smoothing applied. An invalid transform is ignored.
+    fn transformed_data(&self, plot_data: &PlotData) -> Vec<[f64; 2]> {
+        if !self.has_expression_or_smoothing() {
+            return self.oriented(&plot_data.get_cache().data);
+        }
+        let sigma = self.properties.smoothing_sigma;
+        let key = (
+            self.properties.transform.as_str(),
+            sigma,
+            self.properties.swap_xy,
+        );
+        plot_data.transformed_data(key, |cache| {
+            let mut transformed = self.oriented(cache);
+            if let Some(Ok(expr)) = self.transform() {
+                for [x, y] in transformed.iter_mut() {
+                    *y = expr.eval(*x, *y);
+                }
+            }
+            if sigma > 0.0 {
+                let ys: Vec<_> = transformed.iter().map(|[_, y]| *y).collect();
+                for ([_, y], smoothed) in transformed.iter_mut().zip(gaussian_smooth(&ys, sigma)) {
+                    *y = smoothed;
+                }
+            }
+            transformed
+        })
+    }
+
+    /// The transformed data with the baseline (fitted to it) subtracted, the
+    /// custom transforms applied and the masked ranges removed, i.e. the
+    /// plotted data before the offsets and scaling. It is cached until one of
+    /// these properties changes.
+    fn corrected_data(&self) -> Option<Vec<[f64; 2]>> {
+        self.with_corrected_data(<[_]>::to_vec)
+    }
+
+    /// Call `f` with the corrected data (see [`File::corrected_data`]),
+    /// without copying it.
+    fn with_corrected_data<R>(&self, f: impl FnOnce(&[[f64; 2]]) -> R) -> Option<R> {
+        let plot_data = self.data.value().as_ref().ok()?;
+        let compute = || {
+            let mut data = self.transformed_data(plot_data);
+            if self.subtracts_baseline() {
+                if let Some(poly) = self.fit_baseline(&data) {
+                    for [x, y] in data.iter_mut() {
+                        *y -= poly.eval(*x);
+                    }
+                }
+            }
+            for transform in &self.transforms {
+                transform.apply(&mut data);
+            }
+            if !self.properties.masked_ranges.is_empty() {
+                data.retain(|[x, _]| !self.is_masked(*x));
+            }
+            data
+        };
+        Some(plot_data.with_corrected_data(&self.corrections(), compute, f))
+    }
+
+    /// The properties the corrected data depends on.
+    fn corrections(&self) -> Corrections {
+        let properties = &self.properties;
+        Corrections {
+            transform: properties.transform.clone(),
+            sigma: properties.smoothing_sigma,
+            swap_xy: properties.swap_xy,
+            baseline: self.subtracts_baseline().then(|| {
+                (
+                    properties.baseline_anchors.clone(),
+                    properties.baseline_degree,
+                )
+            }),
+            transforms: self
+                .transforms
+                .iter()
+                .map(|transform| transform.name().to_string())
+                .collect(),
+            masked_ranges: properties.masked_ranges.clone(),
+        }
+    }
+
+    /// `data` with the offsets applied and scaled around `ymin`.
+    fn with_offsets(&self, data: &[[f64; 2]], ymin: f64) -> Vec<[f64; 2]> {
+        let mut data = data.to_vec();
+        self.offset_scale(ymin).apply(&mut data);
+        data
+    }
+
+    /// The offsets and scaling of the file, scaling around `ymin`.
+    fn offset_scale(&self, ymin: f64) -> OffsetScale {
+        OffsetScale {
+            xoffset: self.properties.xoffset,
+            yoffset: self.properties.yoffset,
+            yscale: self.properties.yscale,
+            ymin,
+        }
+    }
+
+    /// The data as read (with x and y swapped, if set), without transform
+    /// or offsets.
+    pub fn raw_data(&self) -> Option<Vec<[f64; 2]>> {
+        Some(self.oriented(self.get_cache()?))
+    }
+
+    /// The cached data with the transform, smoothing, baseline, offsets and
+    /// scaling applied, as it is plotted. An invalid transform is ignored.
+    pub fn plot_data(&self) -> Option<Vec<[f64; 2]>> {
+        self.with_corrected_data(|data| self.with_offsets(data, plotted_ymin(data)))
+    }
+
+    /// Call `f` with the corrected data, without copying it, and the offsets
+    /// and scaling that turn it into the plotted data (see
+    /// [`OffsetScale::point`]).
+    pub fn with_plot_data<R>(&self, f: impl FnOnce(&[[f64; 2]], OffsetScale) -> R) -> Option<R> {
+        self.with_corrected_data(|data| f(data, self.offset_scale(plotted_ymin(data))))
+    }
+
+    /// Whether transform and smoothing edits are applied right away,
+    /// depending on the mode and the number of points.
+    pub fn transforms_live(&self, max_live_points: usize) -> bool {
+        match self.properties.transform_mode {
+            TransformMode::Live => true,
+            TransformMode::Apply => false,
+            TransformMode::Auto => {
+                max_live_points == 0
+                    || self
+                        .get_cache()
+                        .is_none_or(|data| data.len() <= max_live_points)
+            }
+        }
+    }
+
+    /// Apply the staged transform and smoothing, if there are any.
+    pub fn apply_staged_transform(&mut self) {
+        if let Some((transform, sigma)) = self.staged_transform.take() {
+            self.properties.transform = transform;
+            self.properties.smoothing_sigma = sigma;
+        }
+    }
+
+    /// Why the file cannot be plotted, if it failed to parse or contains no
+    /// data. Files that are still loading did not fail (yet).
+    pub fn load_error(&self) -> Option<String> {
+        if !self.data.is_up_to_date() {
+            return None;
+        }
+        match self.get_cache() {
+            Some(data) if data.is_empty() => Some("file contains no data".to_string()),
+            Some(_) => None,
+            None => Some(
+                self.data
+                    .value()
+                    .as_ref()
+                    .err()
+                    .cloned()
+                    .unwrap_or_default(),
+            ),
+        }
+    }
+
+    /// The plotted data, reduced with `reduction`. Offsets and scaling do
+    /// not change which points are dropped, so the reduction is only computed
+    /// when it changes. This does not hold for transforms and smoothing, such
+    /// data is reduced each time.
+    pub fn reduced_plot_data(&self, reduction: Reduction) -> Option<Vec<[f64; 2]>> {
+        if self.is_transformed() {
+            let data = self.plot_data()?;
+            return Some(
+                reduction
+                    .indices(&data)
+                    .into_iter()
+                    .map(|i| data[i])
+                    .collect(),
+            );
+        }
+        let indices = self.data.value().as_ref().ok()?.reduced_indices(reduction);
+        let data = self.plot_data()?;
+        Some(indices.into_iter().map(|i| data[i]).collect())
+    }
+
+    /// Extent (xmin, xmax, ymin, ymax) of the data as it is plotted.
+    pub fn extent(&self) -> Option<[f64; 4]> {
+        self.plot_data()?
+            .iter()
+            .filter(|[x, y]| x.is_finite() && y.is_finite())
+            .fold(None, |extent, [x, y]| {
+                let [xmin, xmax, ymin, ymax] = extent.unwrap_or([*x, *x, *y, *y]);
+                Some([xmin.min(*x), xmax.max(*x), ymin.min(*y), ymax.max(*y)])
+            })
+    }
+
+    /// Indices of the peaks of the plotted data (see [`find_peaks`]), ordered
+    /// by x. Peaks must rise at least `prominence` (a fraction of the y-range)
+    /// above their surroundings. They are cached with the corrected data.
+    pub fn peak_indices(&self, prominence: f64) -> Option<Vec<usize>> {
+        let plot_data = self.data.value().as_ref().ok()?;
+        let inverted = self.properties.yscale < 0.0;
+        Some(
+            plot_data.corrected_peaks(&self.corrections(), prominence, inverted, || {
+                let Some(data) = self.plot_data() else {
+                    return Vec::new();
+                };
+                let mut order: Vec<usize> = (0..data.len()).collect();
+                order.sort_by(|a, b| data[*a][0].total_cmp(&data[*b][0]));
+                let sorted: Vec<_> = order.iter().map(|i| data[*i]).collect();
+                let (ymin, ymax) = sorted
+                    .iter()
+                    .filter(|[x, y]| x.is_finite() && y.is_finite())
+                    .fold(
+                        (f64::INFINITY, f64::NEG_INFINITY),
+                        |(ymin, ymax), [_, y]| (ymin.min(*y), ymax.max(*y)),
+                    );
+                find_peaks(&sorted, prominence * (ymax - ymin))
+                    .into_iter()
+                    .map(|k| order[k])
+                    .collect()
+            }),
+        )
+    }
+
+    /// Range (ymin, ymax) of the data as it is plotted, considering only
+    /// points with `xmin <= x <= xmax`.
+    pub fn y_range_within(&self, xmin: f64, xmax: f64) -> Option<(f64, f64)> {
+        self.plot_data()?
+            .iter()
+            .filter(|[x, y]| (xmin..=xmax).contains(x) && y.is_finite())
+            .fold(None, |range, [_, y]| {
+                let (ymin, ymax) = range.unwrap_or((*y, *y));
+                Some((ymin.min(*y), ymax.max(*y)))
+            })
+    }
+
+    /// Label of the file in plot legends.
+    pub fn label(&self, group_name: &str) -> String {
+        if self.properties.alias.is_empty() {
+            format!("{} ({})", self.display_name, group_name)
+        } else {
+            format!("{} ({})", self.properties.alias, group_name)
+        }
+    }
+
     // Integrate data numerically using trapezoidal method.
     //
     // Returns NaN if something goes wrong.
-    pub fn integrate(&mut self, left: f64, right: f64, local_baseline: bool) -> f64 {
+    pub fn integrate(&self, left: f64, right: f64, local_baseline: bool) -> f64 {
         // Retrieve its data, if it was parsed correctly.
         let Ok(data) = self.data.value() else {
             log::error!(
@@ -49,33 +540,40 @@ impl File {
         );
 
         // TODO: in the end we want to have the columns to be selectable.
-        let Some(xs) = data.columns.first() else {
+        let Some((xs, ys)) = self.xy_columns(data) else {
             log::error!("{msg}");
             return f64::NAN;
         };
-        let Some(ys) = data.columns.get(1) else {
-            log::error!("{msg}");
-            return f64::NAN;
+        // Integrals are of the read data, without the transform and
+        // smoothing, so the baseline is fitted to the read data here. The
+        // plotted baseline is fitted to the transformed data, without a
+        // transform both are the same.
+        let corrected;
+        let ys = if self.subtracts_baseline() {
+            let points: Vec<_> = xs.iter().zip(ys).map(|(x, y)| [*x, *y]).collect();
+            match self.fit_baseline(&points) {
+                Some(poly) => {
+                    corrected = points.iter().map(|[x, y]| y - poly.eval(*x)).collect();
+                    &corrected
+                }
+                None => ys,
+            }
+        } else {
+            ys
+        };
+        let unmasked: (Vec<_>, Vec<_>);
+        let (xs, ys) = if self.properties.masked_ranges.is_empty() {
+            (xs, ys)
+        } else {
+            unmasked = xs
+                .iter()
+                .zip(ys)
+                .filter(|(x, _)| !self.is_masked(**x))
+                .unzip();
+            (&unmasked.0, &unmasked.1)
         };
 
-        // Filter out data points where both x- and y-value are finite and not NaN.
-        let (xs, ys) = xs
-            .iter()
-            .zip(ys)
-            .filter(|(x, y)| x.is_finite() && y.is_finite())
-            .fold(
-                (Vec::with_capacity(xs.len()), Vec::with_capacity(xs.len())),
-                |(mut xs, mut ys), (x, y)| {
-                    xs.push(*x);
-                    ys.push(*y);
-                    (xs, ys)
-                },
-            );
-
-        // Apply trapezoidal integration.
-        
-
-        match trapz(&xs, &ys, left, right, local_baseline) {
+        match integration::integrate(xs, ys, left, right, local_baseline) {
             Ok(area) => area,
             Err(err) => {
                 log::error!("Failed to integrate file {}: {err}", self.file_name());
@@ -84,7 +582,7 @@ impl File {
         }
     }
 
-    pub fn local_minimum(&mut self, left: f64, right: f64, after_scaling: bool) -> f64 {
+    pub fn local_minimum(&self, left: f64, right: f64, after_scaling: bool) -> f64 {
         // Retrieve its data, if it was parsed correctly.
         let Ok(data) = self.data.value() else {
             log::error!(
@@ -101,11 +599,7 @@ impl File {
         );
 
         // TODO: in the end we want to have the columns to be selectable.
-        let Some(xs) = data.columns.first() else {
-            log::error!("{msg}");
-            return f64::NAN;
-        };
-        let Some(ys) = data.columns.get(1) else {
+        let Some((xs, ys)) = self.xy_columns(data) else {
             log::error!("{msg}");
             return f64::NAN;
         };
@@ -154,8 +648,9 @@ impl FileHandler {
         {
             let gid =
                 gid.expect("file handler was handed a search result not assigned to any group");
-            if gid > 9 {
+            if gid > UNGROUPED {
                 log::warn!("Group ID > 9 invalid, only 10 slots available, ignoring");
+                continue;
             }
             // If file is already registered, we pull its ID from the registry,
             // otherwise we create a new ID and add the file to the registry.
@@ -182,32 +677,313 @@ impl FileHandler {
                 self.registry.insert(
                     fid,
                     File {
+                        display_name: super::basename(&fp),
                         path: search_path.join(fp),
                         data: csv_data,
                         properties: super::FileProperties::default(),
+                        staged_transform: None,
+                        picking_anchors: false,
+                        transforms: Vec::new(),
+                        load_request: LoadRequest::Default,
                     },
                 );
                 fid
             };
 
-            // Add the ID to the group requested by user, if it is not already a member.
-            if let Some(grp) = &mut self.groups[gid] {
-                if !grp.file_ids.contains(&fid) {
-                    grp.file_ids.push(fid);
-                }
-            } else {
-                let mut new_file_id_set = Vec::new();
-                new_file_id_set.push(fid);
-                let name = format!("Group {}", gid);
-                self.groups[gid] = Some(Group {
-                    file_ids: new_file_id_set,
-                    name,
-                    ..Default::default()
-                });
+            self.add_to_group(fid, gid);
+        }
+    }
+
+    /// Load the file at `path` (unless it is already registered) and add it
+    /// to the group with ID `gid`.
+    pub fn add_file(&mut self, path: &Path, gid: usize, request_tx: &mut DynRequestSender) {
+        let fid = if let Some((fid, _)) = self.registry.iter().find(|(_, file)| file.path == path) {
+            *fid
+        } else {
+            let fid = self.next_id();
+            let mut data = UIParameter::new(Err("Data no loaded".to_string()));
+            data.set_recv(parse_csv(path, request_tx));
+            self.registry.insert(
+                fid,
+                File {
+                    path: path.to_path_buf(),
+                    data,
+                    properties: FileProperties::default(),
+                    display_name: super::basename(path),
+                    staged_transform: None,
+                    picking_anchors: false,
+                    transforms: Vec::new(),
+                    load_request: LoadRequest::Default,
+                },
+            );
+            fid
+        };
+        self.add_to_group(fid, gid);
+    }
+
+    /// Add pasted `text` (e.g. columns copied from a spreadsheet) as a new
+    /// file named "clipboard" to the group `gid`. It is parsed on the
+    /// backend with its load options, errors are shown like load errors.
+    pub fn add_pasted(&mut self, text: String, gid: usize, request_tx: &mut DynRequestSender) {
+        let name = (1..)
+            .map(|i| match i {
+                1 => PathBuf::from("clipboard"),
+                i => PathBuf::from(format!("clipboard {i}")),
+            })
+            .find(|path| self.registry.values().all(|file| file.path != *path))
+            .expect("there are fewer files than names");
+        let fid = self.next_id();
+        let path = name.clone();
+        let (rx, linker) = BackendLink::new(
+            "parse pasted data",
+            move |b: &mut BackendEventLoop<BackendAppState>| {
+                PlotData::from_text(&text, &path, &b.state.get_load_options()).map_err(|err| {
+                    log::error!("unable to parse pasted data: {}", err);
+                    err
+                })
+            },
+        );
+        send_request(request_tx, Box::new(linker));
+        let mut data = UIParameter::new(Err("Data no loaded".to_string()));
+        data.set_recv(rx);
+        self.registry.insert(
+            fid,
+            File {
+                display_name: super::basename(&name),
+                path: name,
+                data,
+                properties: FileProperties::default(),
+                staged_transform: None,
+                picking_anchors: false,
+                transforms: Vec::new(),
+                load_request: LoadRequest::Default,
+            },
+        );
+        self.add_to_group(fid, gid);
+    }
+
+    /// Add files to groups named after the part of their file name captured
+    /// by `pattern`, using the capture named `group` (or the first capture,
+    /// if there is none of that name). Groups with that name are reused,
+    /// otherwise created in free slots. Files that do not match go to
+    /// `default_gid`, as do all files once the slots are used up.
+    pub fn add_files_by_pattern(
+        &mut self,
+        paths: &[PathBuf],
+        pattern: &Regex,
+        default_gid: usize,
+        request_tx: &mut DynRequestSender,
+    ) {
+        for path in paths {
+            let name = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| pattern.captures(name))
+                .and_then(|caps| caps.name("group").or_else(|| caps.get(1)))
+                .map(|capture| capture.as_str().to_string());
+            let gid = match name {
+                Some(name) => self.group_by_name(&name).unwrap_or_else(|| {
+                    log::warn!(
+                        "no free group for '{name}', adding {path:?} to group {default_gid}"
+                    );
+                    default_gid
+                }),
+                None => default_gid,
+            };
+            self.add_file(path, gid, request_tx);
+        }
+    }
+
+    /// Copy the group with ID `gid` into the first free slot and return its
+    /// ID. With `duplicate_files`, the copy gets its own copies of the files
+    /// (with the same properties), otherwise both groups share the files.
+    /// Returns `None` if there is no such group or no free slot.
+    pub fn duplicate_group(
+        &mut self,
+        gid: usize,
+        duplicate_files: bool,
+        request_tx: &mut DynRequestSender,
+    ) -> Option<usize> {
+        let mut copy = self.groups.get(gid)?.clone()?;
+        let new_gid = self.groups.iter().position(|grp| grp.is_none())?;
+        copy.name = format!("{} (copy)", copy.name);
+        if duplicate_files {
+            copy.file_ids = copy
+                .file_ids
+                .iter()
+                .filter_map(|fid| {
+                    let file = self.registry.get(fid)?;
+                    let (path, properties) = (file.path.clone(), file.properties.clone());
+                    // Data that was already loaded does not need to be read again.
+                    let file = match file.data.value() {
+                        Ok(data) if file.data.is_up_to_date() => {
+                            File::from_data(path, properties, data.clone())
+                        }
+                        _ => File::new(path, properties, request_tx),
+                    };
+                    let new_fid = self.next_id();
+                    self.registry.insert(new_fid, file);
+                    Some(new_fid)
+                })
+                .collect();
+        }
+        self.groups[new_gid] = Some(copy);
+        Some(new_gid)
+    }
+
+    /// Add a group with the other files of group `gid` divided by the file
+    /// `reference`, both corrected but without offsets and scaling, and
+    /// interpolated at the x-values of each file with `method`. The derived files only cover the x-range both files
+    /// share, files without overlap are skipped. Returns the new group.
+    pub fn normalize_group(
+        &mut self,
+        gid: usize,
+        reference: FileID,
+        method: Interpolation,
+    ) -> Result<usize, String> {
+        let grp = self
+            .groups
+            .get(gid)
+            .and_then(|grp| grp.as_ref())
+            .ok_or(format!("there is no group with ID {gid}"))?;
+        let ref_file = self
+            .registry
+            .get(&reference)
+            .ok_or("the reference file is missing")?;
+        let ref_data = ref_file
+            .corrected_data()
+            .ok_or(format!("{} is not loaded", ref_file.display_name))?;
+        let ref_name = ref_file.display_name.clone();
+        let name = format!("{} / {ref_name}", grp.name);
+
+        let mut derived = Vec::new();
+        for fid in grp.file_ids.iter().filter(|fid| **fid != reference) {
+            let Some(file) = self.registry.get(fid) else {
+                continue;
+            };
+            let Some(data) = file.corrected_data() else {
+                log::warn!("{} is not loaded, it is not normalized", file.display_name);
+                continue;
+            };
+            let points = interpolation::ratio(&data, &ref_data, method);
+            if points.is_empty() {
+                log::warn!("{} does not overlap with {ref_name}", file.display_name);
+                continue;
+            }
+            let path = PathBuf::from(format!("{} / {ref_name}", file.display_name));
+            derived.push(File::from_data(
+                path.clone(),
+                FileProperties::default(),
+                PlotData::from_points(&points, &path)?,
+            ));
+        }
+        if derived.is_empty() {
+            return Err(format!("no file of {name:?} overlaps with the reference"));
+        }
+        let new_gid = self
+            .groups
+            .iter()
+            .position(|grp| grp.is_none())
+            .ok_or("there is no free group slot")?;
+        let file_ids = derived
+            .into_iter()
+            .map(|file| {
+                let fid = self.next_id();
+                self.registry.insert(fid, file);
+                fid
+            })
+            .collect();
+        self.groups[new_gid] = Some(Group {
+            name,
+            file_ids,
+            ..Default::default()
+        });
+        Ok(new_gid)
+    }
+
+    /// The slot of the group called `name`, which is created in the first
+    /// free slot if it does not exist yet.
+    fn group_by_name(&mut self, name: &str) -> Option<usize> {
+        let existing = self
+            .groups
+            .iter()
+            .position(|grp| grp.as_ref().is_some_and(|grp| grp.name == name));
+        existing.or_else(|| {
+            let gid = self.groups.iter().position(|grp| grp.is_none())?;
+            self.groups[gid] = Some(Group {
+                name: name.to_string(),
+                ..Default::default()
+            });
+            Some(gid)
+        })
+    }
+
+    /// Set the names of the files shown in the file list and legend.
+    ///
+    /// The names only depend on the paths of all files, so they are only set
+    /// again if files were added or removed, or the mode changed.
+    pub fn update_display_names(&mut self, mode: FileNameDisplay) {
+        let mut fids: Vec<FileID> = self.registry.keys().copied().collect();
+        fids.sort_unstable();
+        let key = Some((mode, fids));
+        if self.display_names_for == key {
+            return;
+        }
+        self.display_names_for = key;
+        let base = match mode {
+            FileNameDisplay::Relative => self.common_directory(),
+            _ => None,
+        };
+        for file in self.registry.values_mut() {
+            file.display_name = match mode {
+                FileNameDisplay::Basename => super::basename(&file.path),
+                FileNameDisplay::Relative => base
+                    .as_ref()
+                    .and_then(|base| file.path.strip_prefix(base).ok())
+                    .unwrap_or(&file.path)
+                    .to_string_lossy()
+                    .into_owned(),
+                FileNameDisplay::Full => file.path.to_string_lossy().into_owned(),
             };
         }
     }
 
+    /// The deepest directory that contains all files of the registry.
+    fn common_directory(&self) -> Option<PathBuf> {
+        self.registry
+            .values()
+            .filter_map(|file| file.path.parent())
+            .map(Path::to_path_buf)
+            .reduce(|common, dir| {
+                common
+                    .components()
+                    .zip(dir.components())
+                    .take_while(|(a, b)| a == b)
+                    .map(|(a, _)| a)
+                    .collect()
+            })
+    }
+
+    /// Add the ID to the group, if it is not already a member.
+    fn add_to_group(&mut self, fid: FileID, gid: usize) {
+        if gid == UNGROUPED {
+            if !self.inspected.contains(&fid) {
+                self.inspected.push(fid);
+            }
+        } else if let Some(grp) = &mut self.groups[gid] {
+            if !grp.file_ids.contains(&fid) {
+                grp.file_ids.push(fid);
+            }
+        } else {
+            let name = format!("Group {}", gid);
+            self.groups[gid] = Some(Group {
+                file_ids: vec![fid],
+                name,
+                ..Default::default()
+            });
+        };
+    }
+
     pub fn remove(
         &mut self,
         groups_to_delete: Vec<usize>,
@@ -215,6 +991,16 @@ impl FileHandler {
     ) {
         let mut item_was_removed = false;
 
+        // Ungrouped files are only removed from the list of inspected files.
+        let (ungrouped, files_to_delete): (Vec<_>, Vec<_>) = files_to_delete
+            .into_iter()
+            .partition(|(gid, _)| *gid == UNGROUPED);
+        for (_, fid) in ungrouped {
+            let n = self.inspected.len();
+            self.inspected.retain(|ifid| *ifid != fid);
+            item_was_removed |= self.inspected.len() < n;
+        }
+
         // Just in case, we filter `gid`s which would lead to a panic when used as index.
         for (gid, fid) in files_to_delete.into_iter().filter(|(gid, _)| *gid < 10) {
             let file_name = self.fid_to_filename_str(&fid).to_string();
@@ -248,27 +1034,23 @@ impl FileHandler {
         for gid in groups_to_delete.into_iter().filter(|gid| *gid < 10) {
             if let Some(Some(grp)) = self.groups.get(gid) {
                 log::debug!("removed group '{}' with ID '{gid}'", grp.name);
+                item_was_removed = true;
             } else {
                 log::warn!("trying to remove group with ID {gid} which does not exist");
             }
             self.groups[gid] = None;
         }
 
-        // If for some reason nothing was remove (which is currently impossible,
-        // but maybe can occur in the future) we omit checking the registry for
-        // files to remove.
+        // If nothing was removed, we omit checking the registry for files to
+        // remove.
         if !item_was_removed {
             return;
         }
-        // Remove files from registry which are not member of any group.
+        // Remove files from registry which are not member of any group (and
+        // not inspected).
         let mut mark_delete = Vec::new();
         for fid in self.registry.keys() {
-            if self
-                .groups
-                .iter()
-                .filter_map(|x| x.as_ref())
-                .all(|grp| !grp.file_ids.contains(fid))
-            {
+            if !self.is_grouped(fid) && !self.inspected.contains(fid) {
                 mark_delete.push(*fid);
             }
         }
@@ -277,8 +1059,166 @@ impl FileHandler {
                 "remove file '{}' from registry",
                 self.fid_to_filename_str(&fid)
             );
-            self.registry.remove(&fid);
+            if let Some(file) = self.registry.remove(&fid) {
+                self.removed.insert(fid, file);
+            }
+        }
+    }
+
+    /// Add the `groups` of another session into free slots, without changing
+    /// the present groups. Their files are taken from `file` (by their IDs
+    /// in that session), which is only called once the groups fit, and once
+    /// per file. The files get new IDs, names of groups that are taken
+    /// already are prefixed with `prefix`. Returns the slots of the added
+    /// groups, fails without changes if there are not enough free slots.
+    pub fn merge(
+        &mut self,
+        prefix: &str,
+        groups: [Option<Group>; 10],
+        mut file: impl FnMut(FileID) -> Option<File>,
+    ) -> Result<Vec<usize>, String> {
+        let groups: Vec<Group> = groups
+            .into_iter()
+            .flatten()
+            .filter(|grp| !grp.file_ids.is_empty())
+            .collect();
+        let free: Vec<usize> = (0..self.groups.len())
+            .filter(|gid| self.groups[*gid].is_none())
+            .collect();
+        if groups.len() > free.len() {
+            return Err(format!(
+                "the session has {} group(s), but only {} slot(s) are free",
+                groups.len(),
+                free.len()
+            ));
+        }
+        let mut new_ids = HashMap::new();
+        let mut added = Vec::new();
+        for (mut grp, gid) in groups.into_iter().zip(free) {
+            let mut file_ids = Vec::new();
+            for fid in std::mem::take(&mut grp.file_ids) {
+                // Files may be in several groups, but are added only once.
+                let new_fid = match new_ids.get(&fid) {
+                    Some(new_fid) => *new_fid,
+                    None => {
+                        let Some(file) = file(fid) else {
+                            continue;
+                        };
+                        let new_fid = self.next_id();
+                        self.registry.insert(new_fid, file);
+                        new_ids.insert(fid, new_fid);
+                        new_fid
+                    }
+                };
+                file_ids.push(new_fid);
+            }
+            let taken = self
+                .groups
+                .iter()
+                .flatten()
+                .any(|other| other.name == grp.name);
+            if taken {
+                grp.name = format!("{prefix}: {}", grp.name);
+            }
+            grp.file_ids = file_ids;
+            self.groups[gid] = Some(grp);
+            added.push(gid);
         }
+        Ok(added)
+    }
+
+    /// The current groups and inspected files, to restore them later.
+    pub fn structure(&self) -> Structure {
+        let mut groups = self.groups.clone();
+        for grp in groups.iter_mut().flatten() {
+            grp.is_plotted = false;
+        }
+        Structure {
+            groups,
+            inspected: self.inspected.clone(),
+        }
+    }
+
+    /// Restore groups and inspected files that were recorded with
+    /// [`FileHandler::structure`]. Removed files come back with their data
+    /// if they were not forgotten yet (see [`FileHandler::forget_removed`]),
+    /// groups that exist still keep whether they are plotted.
+    pub fn restore_structure(&mut self, structure: Structure) {
+        let Structure {
+            mut groups,
+            inspected,
+        } = structure;
+        for (grp, current) in groups.iter_mut().zip(self.groups.iter()) {
+            if let Some(grp) = grp {
+                grp.is_plotted = current.as_ref().is_none_or(|current| current.is_plotted);
+            }
+        }
+        self.groups = groups;
+        self.inspected = inspected;
+
+        let referenced: Vec<FileID> = self
+            .groups
+            .iter()
+            .flatten()
+            .flat_map(|grp| grp.file_ids.iter())
+            .chain(self.inspected.iter())
+            .copied()
+            .collect();
+        for fid in referenced.iter() {
+            if let Some(file) = self.removed.remove(fid) {
+                self.registry.insert(*fid, file);
+            }
+        }
+        let unreferenced: Vec<FileID> = self
+            .registry
+            .keys()
+            .filter(|fid| !referenced.contains(fid))
+            .copied()
+            .collect();
+        for fid in unreferenced {
+            if let Some(file) = self.registry.remove(&fid) {
+                self.removed.insert(fid, file);
+            }
+        }
+        // Drop references to files that could not be restored.
+        for grp in self.groups.iter_mut().flatten() {
+            grp.file_ids.retain(|fid| self.registry.contains_key(fid));
+        }
+        self.inspected.retain(|fid| self.registry.contains_key(fid));
+    }
+
+    /// Drop the removed files except for the ones in `keep`, e.g. those the
+    /// undo history still refers to.
+    pub fn forget_removed(&mut self, keep: &HashSet<FileID>) {
+        self.removed.retain(|fid, _| keep.contains(fid));
+    }
+
+    /// Check whether the file is member of any group.
+    pub fn is_grouped(&self, fid: &FileID) -> bool {
+        self.groups
+            .iter()
+            .flatten()
+            .any(|grp| grp.file_ids.contains(fid))
+    }
+
+    /// Move a group to the last (`to_front == true`) or first occupied slot,
+    /// shifting the groups in between by one. Since groups are drawn in slot
+    /// order, this brings the group to the front or sends it to the back of
+    /// the plot (and legend). Returns the new slot of the group.
+    pub fn move_group(&mut self, gid: usize, to_front: bool) -> Option<usize> {
+        self.groups.get(gid)?.as_ref()?;
+        let first = self.groups.iter().position(|grp| grp.is_some())?;
+        let last = self.groups.iter().rposition(|grp| grp.is_some())?;
+        let new_gid = if to_front {
+            self.groups[gid..=last].rotate_left(1);
+            last
+        } else {
+            self.groups[first..=gid].rotate_right(1);
+            first
+        };
+        // Group IDs changed, so the settings panel should follow the group.
+        self.active_element = super::ActiveElement::Group(new_gid);
+        Some(new_gid)
     }
 
     pub fn current_id(&self) -> FileID {
@@ -301,155 +1241,900 @@ impl FileHandler {
     pub fn try_update(&mut self) {
         for file in self.registry.values_mut() {
             file.data.try_update();
+            // The transforms enabled in the properties may change anytime,
+            // e.g. by undo or loading a session.
+            let resolved = file.transforms.iter().map(|transform| transform.name());
+            let enabled = file
+                .properties
+                .custom_transforms
+                .iter()
+                .filter(|name| self.transforms.names().any(|known| known == *name));
+            if !resolved.eq(enabled) {
+                file.transforms = self.transforms.resolve(&file.properties.custom_transforms);
+            }
+        }
+        if let Some(preview) = &mut self.preview {
+            preview.columns.try_update();
+        }
+        self.report_reloads();
+    }
+
+    /// Reload all files from disk, keeping their properties and groups.
+    /// Failures are logged per file once loaded, see
+    /// [`FileHandler::reload_progress`].
+    pub fn reload_all(&mut self, request_tx: &mut DynRequestSender) {
+        log::info!("reloading {} file(s)", self.registry.len());
+        for file in self.registry.values_mut() {
+            file.reload(request_tx);
+        }
+        self.reloading = self.registry.keys().copied().collect();
+        self.reloading_total = self.reloading.len();
+    }
+
+    /// How many of the files reloaded by [`FileHandler::reload_all`] are
+    /// loaded, and how many were reloaded, while some are still loading.
+    pub fn reload_progress(&self) -> Option<(usize, usize)> {
+        (!self.reloading.is_empty()).then(|| {
+            (
+                self.reloading_total - self.reloading.len(),
+                self.reloading_total,
+            )
+        })
+    }
+
+    /// Log the reloads that failed since the last call.
+    fn report_reloads(&mut self) {
+        if self.reloading_total == 0 {
+            return;
+        }
+        self.reloading.retain(|fid| match self.registry.get(fid) {
+            Some(file) if !file.data.is_up_to_date() => true,
+            Some(file) => {
+                if let Some(err) = file.load_error() {
+                    log::error!("reloading {:?} failed: {err}", file.path);
+                }
+                false
+            }
+            None => false,
+        });
+        if self.reloading.is_empty() {
+            log::info!("reloaded {} file(s)", self.reloading_total);
+            self.reloading_total = 0;
         }
     }
+
+    /// Reload files that changed on disk once they did not change for
+    /// `debounce`, so that files which are still being written are not read
+    /// over and over (zero reloads on every change that is seen). Files are
+    /// checked at most every [`CHANGE_CHECK_INTERVAL`] by the backend, files
+    /// that failed to load are not checked.
+    pub fn reload_changed_files(&mut self, debounce: Duration, request_tx: &mut DynRequestSender) {
+        let now = Instant::now();
+        if let Some(rx) = &self.source_stamps {
+            match rx.try_recv() {
+                Ok(stamps) => {
+                    self.source_stamps = None;
+                    for fid in self.settled_changes(stamps, now, debounce) {
+                        if let Some(file) = self.registry.get_mut(&fid) {
+                            log::info!("reloading {:?}, it changed on disk", file.path);
+                            file.reload(request_tx);
+                        }
+                    }
+                }
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => self.source_stamps = None,
+            }
+        }
+        if self
+            .last_change_check
+            .is_some_and(|last| now - last < CHANGE_CHECK_INTERVAL)
+        {
+            return;
+        }
+        self.last_change_check = Some(now);
+        let paths: Vec<_> = self
+            .registry
+            .iter()
+            .filter(|(_, file)| file.read_source().is_some())
+            .map(|(fid, file)| (*fid, file.path.clone()))
+            .collect();
+        if paths.is_empty() {
+            return;
+        }
+        let (rx, linker) = BackendLink::new(
+            "check files for changes",
+            move |_: &mut BackendEventLoop<BackendAppState>| {
+                paths
+                    .iter()
+                    .map(|(fid, path)| (*fid, SourceStamp::from_path(path)))
+                    .collect()
+            },
+        );
+        send_request(request_tx, Box::new(linker));
+        self.source_stamps = Some(rx);
+    }
+
+    /// The files that changed on disk and then did not change for
+    /// `debounce`, according to their current state on disk in `stamps`.
+    fn settled_changes(
+        &mut self,
+        stamps: Vec<FileStamp>,
+        now: Instant,
+        debounce: Duration,
+    ) -> Vec<FileID> {
+        let mut settled = Vec::new();
+        for (fid, current) in stamps {
+            // A file that is missing may be replaced right away.
+            let read = self.registry.get(&fid).and_then(File::read_source);
+            let (Some(read), Some(current)) = (read, current) else {
+                continue;
+            };
+            if read == current {
+                self.changed_files.remove(&fid);
+                continue;
+            }
+            let (seen, since) = self.changed_files.entry(fid).or_insert((current, now));
+            if *seen != current {
+                // Still changing.
+                *seen = current;
+                *since = now;
+            }
+            if now - *since >= debounce {
+                self.changed_files.remove(&fid);
+                settled.push(fid);
+            }
+        }
+        settled
+    }
+
+    /// Extent (xmin, xmax, ymin, ymax) of the data of all plotted groups.
+    pub fn plotted_extent(&self) -> Option<[f64; 4]> {
+        self.groups
+            .iter()
+            .flatten()
+            .filter(|grp| grp.is_plotted)
+            .flat_map(|grp| grp.file_ids.iter())
+            .filter_map(|fid| self.registry.get(fid)?.extent())
+            .reduce(|[a0, a1, a2, a3], [b0, b1, b2, b3]| {
+                [a0.min(b0), a1.max(b1), a2.min(b2), a3.max(b3)]
+            })
+    }
+
+    /// Range (ymin, ymax) of all plotted files within `xmin <= x <= xmax`.
+    pub fn plotted_y_range_within(&self, xmin: f64, xmax: f64) -> Option<(f64, f64)> {
+        self.groups
+            .iter()
+            .flatten()
+            .filter(|grp| grp.is_plotted)
+            .flat_map(|grp| grp.file_ids.iter())
+            .filter_map(|fid| self.registry.get(fid)?.y_range_within(xmin, xmax))
+            .reduce(|(a0, a1), (b0, b1)| (a0.min(b0), a1.max(b1)))
+    }
+
+    /// All files in the registry, ordered by ID, with the groups they are
+    /// in and their current properties.
+    pub fn files(&self) -> impl Iterator<Item = FileInfo<'_>> {
+        let mut ids: Vec<_> = self.registry.keys().copied().collect();
+        ids.sort();
+        ids.into_iter().filter_map(move |fid| {
+            let file = self.registry.get(&fid)?;
+            let mut groups: Vec<_> = self
+                .groups
+                .iter()
+                .enumerate()
+                .filter(|(_, grp)| grp.as_ref().is_some_and(|grp| grp.file_ids.contains(&fid)))
+                .map(|(gid, _)| gid)
+                .collect();
+            if self.inspected.contains(&fid) {
+                groups.push(UNGROUPED);
+            }
+            Some(FileInfo {
+                id: fid,
+                path: &file.path,
+                groups,
+                properties: &file.properties,
+                loaded: file.data.value().as_ref().map(|_| ()).map_err(Clone::clone),
+            })
+        })
+    }
+
+    /// Legend names of all plotted files. If `disambiguate` is set, files
+    /// that would share a name get their file ID appended.
+    pub fn legend_names(&self, disambiguate: bool) -> HashMap<FileID, String> {
+        let plotted_groups = || self.groups.iter().flatten().filter(|grp| grp.is_plotted);
+        let grouped = plotted_groups()
+            .filter(|grp| !grp.single_legend_entry)
+            .flat_map(|grp| grp.file_ids.iter().map(|fid| (fid, grp.name.as_str())));
+        let inspected = self
+            .inspected
+            .iter()
+            .filter(|_| !self.hide_inspected)
+            .map(|fid| (fid, "inspect"));
+        let mut names: HashMap<FileID, String> = grouped
+            .chain(inspected)
+            .filter_map(|(fid, group_name)| Some((*fid, self.registry.get(fid)?.label(group_name))))
+            .collect();
+        if disambiguate {
+            let mut counts: HashMap<String, usize> = HashMap::new();
+            for name in names.values() {
+                *counts.entry(name.clone()).or_default() += 1;
+            }
+            for (fid, name) in names.iter_mut() {
+                if counts[name.as_str()] > 1 {
+                    *name = format!("{name} #{}", fid.0);
+                }
+            }
+        }
+        // Plot items with the same name share a legend entry.
+        for grp in plotted_groups().filter(|grp| grp.single_legend_entry) {
+            for fid in grp.file_ids.iter() {
+                names.insert(*fid, grp.name.clone());
+            }
+        }
+        names
+    }
+
+    /// Whether any file is still being loaded by the backend.
+    pub fn is_busy(&self) -> bool {
+        self.registry
+            .values()
+            .any(|file| !file.data.is_up_to_date())
+            || self
+                .preview
+                .as_ref()
+                .is_some_and(|preview| !preview.columns.is_up_to_date())
+    }
+}
+
+/// Lowest y-value of `data`, which scaling keeps in place.
+fn plotted_ymin(data: &[[f64; 2]]) -> f64 {
+    data.iter()
+        .map(|[_, y]| *y)
+        .reduce(|current_min, yi| if yi < current_min { yi } else { current_min })
+        .unwrap_or(0.0)
+}
+
+/// Request a preview of the first `rows` rows of the file, parsed with
+/// `options`.
+pub(super) fn request_preview(
+    fid: FileID,
+    path: &Path,
+    options: &LoadOptions,
+    rows: usize,
+    request_tx: &mut DynRequestSender,
+) -> FilePreview {
+    let path = path.to_owned();
+    let request_options = options.clone();
+    let (rx, linker) = BackendLink::new(
+        &format!("preview file {:?}", path),
+        move |_: &mut BackendEventLoop<BackendAppState>| {
+            PlotData::preview(&path, &request_options, rows)
+        },
+    );
+    send_request(request_tx, Box::new(linker));
+    let mut columns = UIParameter::new(Ok(Vec::new()));
+    columns.set_recv(rx);
+    FilePreview {
+        fid,
+        options: options.clone(),
+        edited: options.clone(),
+        columns,
+    }
 }
 
 pub fn parse_csv(
     path: &Path,
     request_tx: &mut DynRequestSender,
+) -> LinkReceiver<Result<PlotData, String>> {
+    request_load(path, true, None, request_tx)
+}
+
+/// Request to load the file at `path`, `check_size` applies the size limit of
+/// the load options. With `sample_rate`, the x-column is generated.
+pub(super) fn request_load(
+    path: &Path,
+    check_size: bool,
+    sample_rate: Option<f64>,
+    request_tx: &mut DynRequestSender,
 ) -> LinkReceiver<Result<PlotData, String>> {
     let path = path.to_owned();
     let (rx, linker) = BackendLink::new(
         &format!("load CSV data from file {:?}", path),
-        move |_b: &mut BackendEventLoop<BackendAppState>| {
-            PlotData::from_path(&path).map_err(|err| {
-                log::error!("{}", err);
-                err
-            })
+        move |b: &mut BackendEventLoop<BackendAppState>| {
+            b.state
+                .load_file(&path, check_size, sample_rate)
+                .map_err(|err| {
+                    log::error!("{}", err);
+                    err
+                })
         },
     );
-    request_tx
-        .send(Box::new(linker))
-        .expect(BACKEND_HUNG_UP_MSG);
+    send_request(request_tx, Box::new(linker));
     rx
 }
 
-// ----------------------------------------------------------------------------
-//
-//
-// Integration Utilities
-//
-//
-// ----------------------------------------------------------------------------
-
-/// Trapezoidal integration.
-///
-/// Local baseline subtracts a linear baseline ranging from the start (left) to
-/// the end (right) point of the integration window.
-pub fn trapz(
-    x: &[f64],
-    y: &[f64],
-    left: f64,
-    right: f64,
-    local_baseline: bool,
-) -> Result<f64, String> {
-    let (mut left, right) = (left.min(right), left.max(right));
-
-    let n = x.len().min(y.len());
-    if n <= 1 {
-        return Err("Not enough values to integrate".into());
-    }
-    if x[0] >= right || x[n - 1] <= left {
-        return Err("Integration window out of bounds".into());
-    }
-
-    let mut area: f64;
-    // subtract local linear baseline, defined by start and end-point of integration window
-    if local_baseline {
-        let xs = vec![left, right];
-        let ys = linear_resample_array(x, y, &xs);
-        if ys.iter().any(|y| (*y).is_nan()) {
-            return Err("Integration window out of bounds.".into());
-        }
-        area = -singletrapz(left, right, ys[0], ys[1])
-    } else {
-        area = 0.0_f64;
-    }
-
-    let mut inside_integration_window = false;
-    let mut lastiter = false;
-    let mut j = 2;
-
-    while j <= n {
-        let mut x0 = x[j - 1];
-        let mut x1 = x[j];
-        let mut y0 = y[j - 1];
-        let mut y1 = y[j];
-
-        if x1 <= left {
-            j += 1;
-            continue;
-        } else if !inside_integration_window {
-            // this will only run once, when we enter the integration window
-            // test whether x0 should be replaced by left
-            if x0 < left {
-                y0 = lininterp(left, x0, x1, y0, y1);
-                x0 = left;
-            } else {
-                // this case means that left <= x[0]
-                left = x0;
-            }
-            inside_integration_window = true;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_files_by_pattern() {
+        let (mut request_tx, _request_rx) = std::sync::mpsc::channel();
+        let mut file_handler = FileHandler::default();
+        file_handler.groups[0] = Some(Group {
+            name: "default".to_string(),
+            ..Default::default()
+        });
+        let paths: Vec<PathBuf> = ["s1_a.csv", "s2_a.csv", "s1_b.csv", "other.csv"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        let pattern = Regex::new(r"^(?P<group>s\d+)_").unwrap();
+        file_handler.add_files_by_pattern(&paths, &pattern, 0, &mut request_tx);
+
+        let group_sizes: Vec<_> = file_handler
+            .groups
+            .iter()
+            .flatten()
+            .map(|grp| (grp.name.as_str(), grp.file_ids.len()))
+            .collect();
+        assert_eq!(group_sizes, [("default", 1), ("s1", 2), ("s2", 1)]);
+    }
+
+    #[test]
+    fn test_files_reflects_registry_and_groups() {
+        let (mut request_tx, _request_rx) = std::sync::mpsc::channel();
+        let mut file_handler = FileHandler::default();
+        assert_eq!(file_handler.files().count(), 0);
+
+        file_handler.add_file(Path::new("a.csv"), 0, &mut request_tx);
+        file_handler.add_file(Path::new("b.csv"), 2, &mut request_tx);
+        file_handler.add_file(Path::new("a.csv"), 2, &mut request_tx);
+        file_handler.add_file(Path::new("c.csv"), UNGROUPED, &mut request_tx);
+        let fid = file_handler.files().next().unwrap().id;
+        file_handler.registry.get_mut(&fid).unwrap().properties = FileProperties::custom();
+
+        let files: Vec<_> = file_handler.files().collect();
+        let summary: Vec<_> = files
+            .iter()
+            .map(|info| (info.path.to_str().unwrap(), info.groups.clone()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("a.csv", vec![0, 2]),
+                ("b.csv", vec![2]),
+                ("c.csv", vec![UNGROUPED])
+            ]
+        );
+        assert_eq!(files[0].properties, &FileProperties::custom());
+        assert_eq!(files[1].properties, &FileProperties::default());
+        // Nothing was loaded, since there is no backend.
+        assert!(files.iter().all(|info| info.loaded.is_err()));
+    }
+
+    #[test]
+    fn test_duplicate_group() {
+        let (mut request_tx, _request_rx) = std::sync::mpsc::channel();
+        let mut file_handler = FileHandler::default();
+        file_handler.add_file(Path::new("a.csv"), 0, &mut request_tx);
+        file_handler.add_file(Path::new("b.csv"), 0, &mut request_tx);
+        let fids = file_handler.groups[0].as_ref().unwrap().file_ids.clone();
+        file_handler.registry.get_mut(&fids[0]).unwrap().properties = FileProperties::custom();
+
+        let shared = file_handler.duplicate_group(0, false, &mut request_tx);
+        assert_eq!(shared, Some(1));
+        let copy = file_handler.groups[1].as_ref().unwrap();
+        assert_eq!(copy.name, "Group 0 (copy)");
+        assert_eq!(copy.file_ids, fids);
+        assert_eq!(file_handler.registry.len(), 2);
+
+        let duplicated = file_handler.duplicate_group(0, true, &mut request_tx);
+        assert_eq!(duplicated, Some(2));
+        let copy = file_handler.groups[2].as_ref().unwrap();
+        assert_eq!(copy.file_ids.len(), 2);
+        assert!(copy.file_ids.iter().all(|fid| !fids.contains(fid)));
+        assert_eq!(file_handler.registry.len(), 4);
+        let first_copy = &file_handler.registry[&copy.file_ids[0]];
+        assert_eq!(first_copy.path, Path::new("a.csv"));
+        assert_eq!(first_copy.properties, FileProperties::custom());
+
+        assert_eq!(
+            file_handler.duplicate_group(5, false, &mut request_tx),
+            None
+        );
+    }
+
+    #[test]
+    fn test_sample_rate() {
+        let mut properties = FileProperties {
+            generate_x: true,
+            sample_rate: 48_000.0,
+            ..Default::default()
+        };
+        assert_eq!(properties.sample_rate(), Some(48_000.0));
+        for rate in [0.0, -1.0, f64::NAN] {
+            properties.sample_rate = rate;
+            assert_eq!(properties.sample_rate(), None);
         }
+        properties.sample_rate = 48_000.0;
+        properties.generate_x = false;
+        assert_eq!(properties.sample_rate(), None);
+    }
 
-        // test whether x1 should be replaced by right
-        if x1 >= right {
-            // we move out of the integration window
+    #[test]
+    fn test_swap_xy() {
+        let mut data = PlotData::default();
+        // y in the first, x in the second column.
+        data.columns = vec![vec![2.0, 2.0, 2.0], vec![0.0, 1.0, 3.0]];
+        let mut file = File::from_data("data.csv".into(), FileProperties::default(), data);
+        assert_ne!(file.integrate(0.0, 3.0, false), 6.0);
+        file.properties.swap_xy = true;
+        assert_eq!(file.integrate(0.0, 3.0, false), 6.0);
+        assert_eq!(file.local_minimum(0.0, 3.0, false), 2.0);
+    }
 
-            if x1 != right {
-                y1 = lininterp(right, x0, x1, y0, y1)
-            };
-            x1 = right;
-            lastiter = true; // we shall break the loop after this iteration
+    #[test]
+    fn test_integrate_subtracts_baseline() {
+        let mut data = PlotData::default();
+        // A sloped background with a peak at x = 2.
+        data.columns = vec![vec![0.0, 1.0, 2.0, 3.0, 4.0], vec![1.0, 2.0, 5.0, 4.0, 5.0]];
+        let mut file = File::from_data("data.csv".into(), FileProperties::default(), data);
+        assert_eq!(file.integrate(0.0, 4.0, false), 14.0);
+        file.properties.baseline_anchors = vec![0.0, 1.0, 3.1, 4.0];
+        file.properties.baseline_degree = 1;
+        assert!((file.integrate(0.0, 4.0, false) - 2.0).abs() < 1e-9);
+        // A baseline that is only drawn does not change the integral.
+        file.properties.subtract_baseline = false;
+        assert_eq!(file.integrate(0.0, 4.0, false), 14.0);
+    }
+
+    #[test]
+    fn test_corrected_data_follows_properties() {
+        let points = [[0.0, 1.0], [1.0, 2.0], [2.0, 5.0], [3.0, 4.0], [4.0, 5.0]];
+        let mut file = File::from_points(&points);
+        let rounded = |data: Vec<[f64; 2]>| {
+            data.iter()
+                .map(|[x, y]| [*x, (y * 1e6).round() / 1e6])
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(file.plot_data().unwrap(), points);
+
+        // The cached data is computed again whenever the properties change.
+        file.properties.baseline_anchors = vec![0.0, 4.0];
+        file.properties.baseline_degree = 1;
+        let corrected = [[0.0, 0.0], [1.0, 0.0], [2.0, 2.0], [3.0, 0.0], [4.0, 0.0]];
+        assert_eq!(rounded(file.plot_data().unwrap()), corrected);
+        file.properties.masked_ranges = vec![(1.5, 2.5)];
+        let unmasked = [0, 1, 3, 4].map(|i| corrected[i]);
+        assert_eq!(rounded(file.plot_data().unwrap()), unmasked);
+
+        // The drawn baseline is fitted to the same data, and masked as well.
+        file.properties.subtract_baseline = false;
+        assert_eq!(file.plot_data().unwrap(), [0, 1, 3, 4].map(|i| points[i]));
+        let baseline = file.baseline().unwrap();
+        assert_eq!(rounded(baseline.curve), [0, 1, 3, 4].map(|i| points[i]));
+
+        // The offsets can be applied to single points of the cached data.
+        (file.properties.yscale, file.properties.yoffset) = (2.0, 1.0);
+        let rows = file.with_plot_data(|data, offsets| {
+            data.iter()
+                .map(|point| offsets.point(*point))
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(rows, file.plot_data());
+    }
+
+    #[test]
+    fn test_peak_indices_follow_properties() {
+        // Unsorted, the indices refer to the plotted data as it is.
+        let points = [[4.0, 0.0], [1.0, 3.0], [0.0, 0.0], [2.0, 1.0], [3.0, 2.0]];
+        let mut file = File::from_points(&points);
+        assert_eq!(file.peak_indices(0.0).unwrap(), [1, 4]);
+        assert_eq!(file.peak_indices(0.5).unwrap(), [1]);
+        // Scaling by a negative factor turns the valleys into peaks.
+        file.properties.yscale = -1.0;
+        assert_eq!(file.peak_indices(0.0).unwrap(), [3]);
+        file.properties.yscale = 1.0;
+        // Masking the smaller peak removes it.
+        file.properties.masked_ranges = vec![(2.5, 3.5)];
+        assert_eq!(file.peak_indices(0.0).unwrap(), [1]);
+    }
+
+    #[test]
+    fn test_masked_ranges() {
+        let mut data = PlotData::default();
+        data.columns = vec![vec![0.0, 1.0, 2.0, 3.0, 4.0], vec![1.0, 1.0, 9.0, 1.0, 1.0]];
+        let mut file = File::from_data("data.csv".into(), FileProperties::default(), data);
+        assert_eq!(file.integrate(0.0, 4.0, false), 12.0);
+        // The spike is removed, the neighbours are connected.
+        file.properties.masked_ranges = vec![(2.5, 1.5)];
+        assert_eq!(file.integrate(0.0, 4.0, false), 4.0);
+
+        file.properties.xoffset = 10.0;
+        let plotted = vec![[10.0, 1.0], [11.0, 1.0], [13.0, 1.0], [14.0, 1.0]];
+        assert_eq!(
+            file.split_at_masks(plotted.clone()),
+            [
+                vec![[10.0, 1.0], [11.0, 1.0]],
+                vec![[13.0, 1.0], [14.0, 1.0]]
+            ]
+        );
+        file.properties.show_mask_gaps = false;
+        assert_eq!(file.split_at_masks(plotted.clone()), [plotted]);
+    }
+
+    #[test]
+    fn test_processing_steps() {
+        let mut file = File::from_data(
+            "data.csv".into(),
+            FileProperties::default(),
+            PlotData::default(),
+        );
+        assert!(file.processing_steps().is_empty());
+        file.properties.transform = "2 *".to_string();
+        file.properties.yscale = 2.0;
+        file.properties.masked_ranges = vec![(1.0, 2.0)];
+        // Invalid transforms are not applied.
+        assert_eq!(file.processing_steps(), ["ranges masked", "y scaled by 2"]);
+        file.properties.transform = "2 * y".to_string();
+        file.properties.xoffset = -1.5;
+        assert_eq!(
+            file.processing_steps(),
+            [
+                "transform 2 * y",
+                "ranges masked",
+                "x offset -1.5",
+                "y scaled by 2"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_normalize_group() {
+        let file = |ys: &[f64]| {
+            let points: Vec<_> = ys.iter().enumerate().map(|(i, y)| [i as f64, *y]).collect();
+            File::from_points(&points)
+        };
+        let mut file_handler = FileHandler::default();
+        let fids: Vec<_> = [vec![2.0, 4.0, 6.0], vec![1.0, 1.0, 1.0], vec![1.0, 2.0]]
+            .iter()
+            .map(|ys| {
+                let fid = file_handler.next_id();
+                file_handler.registry.insert(fid, file(ys));
+                fid
+            })
+            .collect();
+        // Offsets and scaling do not change the ratios.
+        for fid in [fids[0], fids[2]] {
+            let properties = &mut file_handler.registry.get_mut(&fid).unwrap().properties;
+            properties.yoffset = 3.0;
+            properties.yscale = 2.0;
         }
+        file_handler.groups[0] = Some(Group {
+            name: "spectra".to_string(),
+            file_ids: fids.clone(),
+            ..Default::default()
+        });
+        let gid = file_handler
+            .normalize_group(0, fids[2], Interpolation::Linear)
+            .unwrap();
+        let grp = file_handler.groups[gid].as_ref().unwrap();
+        assert_eq!(grp.name, "spectra / data.csv");
+        let ratios: Vec<_> = grp
+            .file_ids
+            .iter()
+            .map(|fid| file_handler.registry[fid].plot_data().unwrap())
+            .collect();
+        // Only the overlap with the reference at x = 0..1 remains.
+        assert_eq!(
+            ratios,
+            [vec![[0.0, 2.0], [1.0, 2.0]], vec![[0.0, 1.0], [1.0, 0.5]]]
+        );
+        assert!(file_handler.registry[&grp.file_ids[0]].is_pasted());
+    }
+
+    #[test]
+    fn test_file_ids_are_not_reused() {
+        let (mut request_tx, _request_rx) = std::sync::mpsc::channel();
+        let mut file_handler = FileHandler::default();
+        let path = Path::new("a.csv");
+        file_handler.add_file(path, 0, &mut request_tx);
+        file_handler.add_file(Path::new("b.csv"), 0, &mut request_tx);
+        let first = file_handler.groups[0].as_ref().unwrap().file_ids.clone();
+        // Adding a registered file again keeps its ID.
+        file_handler.add_file(path, 1, &mut request_tx);
+        assert_eq!(
+            file_handler.groups[1].as_ref().unwrap().file_ids,
+            first[..1]
+        );
+
+        file_handler.remove(vec![0, 1], vec![]);
+        assert!(file_handler.registry.is_empty());
+        file_handler.add_file(path, 0, &mut request_tx);
+        let again = file_handler.groups[0].as_ref().unwrap().file_ids[0];
+        assert!(first.iter().all(|fid| again > *fid));
+
+        // A session with a stale next ID does not hand out its IDs again.
+        let registry = std::mem::take(&mut file_handler.registry);
+        let mut file_handler = FileHandler::new(Default::default(), registry, FileID(0));
+        assert_eq!(file_handler.next_id(), FileID(again.0 + 1));
+    }
 
-        area += singletrapz(x0, x1, y0, y1);
+    #[test]
+    fn test_display_names() {
+        let (mut request_tx, _request_rx) = std::sync::mpsc::channel();
+        let mut file_handler = FileHandler::default();
+        file_handler.add_file(Path::new("data/a/x.csv"), 0, &mut request_tx);
+        let names = |file_handler: &FileHandler| {
+            let mut names: Vec<_> = file_handler
+                .registry
+                .values()
+                .map(|file| file.display_name.clone())
+                .collect();
+            names.sort();
+            names
+        };
+        file_handler.update_display_names(FileNameDisplay::Relative);
+        assert_eq!(names(&file_handler), ["x.csv"]);
+
+        // Names change with the files and the mode.
+        file_handler.add_file(Path::new("data/b/x.csv"), 0, &mut request_tx);
+        file_handler.update_display_names(FileNameDisplay::Relative);
+        assert_eq!(names(&file_handler), ["a/x.csv", "b/x.csv"]);
+        file_handler.update_display_names(FileNameDisplay::Basename);
+        assert_eq!(names(&file_handler), ["x.csv", "x.csv"]);
+        file_handler.update_display_names(FileNameDisplay::Full);
+        assert_eq!(names(&file_handler), ["data/a/x.csv", "data/b/x.csv"]);
+        file_handler.update_display_names(FileNameDisplay::Relative);
+        let fid = file_handler.groups[0].as_ref().unwrap().file_ids[1];
+        file_handler.remove(vec![], vec![(0, fid)]);
+        file_handler.update_display_names(FileNameDisplay::Relative);
+        assert_eq!(names(&file_handler), ["x.csv"]);
+    }
 
-        if lastiter {
-            break;
+    #[test]
+    fn test_legend_names_disambiguate() {
+        let (mut request_tx, _request_rx) = std::sync::mpsc::channel();
+        let mut file_handler = FileHandler::default();
+        for path in ["a/data.csv", "b/data.csv", "c.csv"] {
+            file_handler.add_file(Path::new(path), 0, &mut request_tx);
         }
+        let fids = file_handler.groups[0].as_ref().unwrap().file_ids.clone();
 
-        j += 1;
+        let same = "data.csv (Group 0)".to_string();
+        assert_eq!(
+            file_handler.legend_names(false),
+            HashMap::from([
+                (fids[0], same.clone()),
+                (fids[1], same.clone()),
+                (fids[2], "c.csv (Group 0)".to_string()),
+            ])
+        );
+        assert_eq!(
+            file_handler.legend_names(true),
+            HashMap::from([
+                (fids[0], format!("{same} #{}", fids[0].0)),
+                (fids[1], format!("{same} #{}", fids[1].0)),
+                (fids[2], "c.csv (Group 0)".to_string()),
+            ])
+        );
     }
-    Ok(area)
-}
 
-pub fn linear_resample_array(xs: &[f64], ys: &[f64], grid: &[f64]) -> Vec<f64> {
-    let segments = xs
-        .iter()
-        .zip(ys.iter())
-        .zip(xs.iter().skip(1).zip(ys.iter().skip(1)))
-        .map(|((x0, y0), (x1, y1))| (*x0, *y0, *x1, *y1))
-        .collect::<Vec<_>>();
+    #[test]
+    fn test_legend_names_single_entry() {
+        let (mut request_tx, _request_rx) = std::sync::mpsc::channel();
+        let mut file_handler = FileHandler::default();
+        file_handler.add_file(Path::new("a/data.csv"), 0, &mut request_tx);
+        file_handler.add_file(Path::new("b/data.csv"), 0, &mut request_tx);
+        file_handler.add_file(Path::new("c.csv"), 1, &mut request_tx);
+        let fids = |gid: usize| file_handler.groups[gid].as_ref().unwrap().file_ids.clone();
+        let (grouped, other) = (fids(0), fids(1));
+        file_handler.groups[0].as_mut().unwrap().single_legend_entry = true;
 
-    let mut yp = Vec::with_capacity(grid.len());
+        // The group name is shared, and not disambiguated.
+        assert_eq!(
+            file_handler.legend_names(true),
+            HashMap::from([
+                (grouped[0], "Group 0".to_string()),
+                (grouped[1], "Group 0".to_string()),
+                (other[0], "c.csv (Group 1)".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_merge_session() {
+        let file = |y: f64| File::from_points(&[[0.0, y], [1.0, 1.0]]);
+        let mut file_handler = FileHandler::default();
+        let fid = file_handler.next_id();
+        file_handler.registry.insert(fid, file(0.0));
+        file_handler.groups[0] = Some(Group {
+            name: "sample".into(),
+            file_ids: vec![fid],
+            ..Default::default()
+        });
 
-    for xi in grid.iter() {
-        if let Some((x0, y0, x1, y1)) = segments.iter().find(|(x0, _, x1, _)| xi >= x0 && xi < x1) {
-            yp.push(lininterp(*xi, *x0, *x1, *y0, *y1));
-            continue;
+        // The other session uses the same ID, and the file twice.
+        let mut groups: [Option<Group>; 10] = Default::default();
+        groups[0] = Some(Group {
+            name: "sample".into(),
+            file_ids: vec![fid],
+            ..Default::default()
+        });
+        groups[3] = Some(Group {
+            name: "reference".into(),
+            file_ids: vec![fid],
+            ..Default::default()
+        });
+        groups[5] = Some(Group::default());
+        // Only the files of the merged groups are taken, each once.
+        let mut taken = Vec::new();
+        let mut files = HashMap::from([(fid, file(2.0)), (file_handler.next_id(), file(3.0))]);
+        let added = file_handler.merge("run2", groups.clone(), |fid| {
+            taken.push(fid);
+            files.remove(&fid)
+        });
+        assert_eq!(added, Ok(vec![1, 2]));
+        assert_eq!(taken, [fid]);
+        let merged = |gid: usize| file_handler.groups[gid].as_ref().unwrap();
+        assert_eq!(merged(0).file_ids, [fid]);
+        assert_eq!(merged(1).name, "run2: sample");
+        assert_eq!(merged(2).name, "reference");
+        let new_fid = merged(1).file_ids[0];
+        assert_ne!(new_fid, fid);
+        assert_eq!(merged(2).file_ids, [new_fid]);
+        assert_eq!(file_handler.registry.len(), 2);
+
+        // Nothing is added if the groups do not fit.
+        for gid in 3..9 {
+            file_handler.groups[gid] = Some(Group::default());
         }
-        // only applies if xi happens to be == the last value in xs
-        else if let Some((_, _, _, y1)) = segments.iter().last().filter(|(_, _, x1, _)| xi == x1)
-        {
-            yp.push(*y1);
-            continue;
+        let before = file_handler.structure();
+        let merged = file_handler.merge("run2", groups, |_| panic!("no file is taken"));
+        assert!(merged.is_err());
+        assert_eq!(file_handler.structure(), before);
+    }
+
+    #[test]
+    fn test_restore_structure_after_delete() {
+        let mut file_handler = FileHandler::default();
+        let fids: Vec<_> = (0..3)
+            .map(|i| {
+                let fid = file_handler.next_id();
+                let file = File::from_points(&[[0.0, i as f64], [1.0, 1.0]]);
+                file_handler.registry.insert(fid, file);
+                fid
+            })
+            .collect();
+        for (gid, file_ids) in [&fids[..2], &fids[2..]].iter().enumerate() {
+            file_handler.groups[gid] = Some(Group {
+                name: format!("group {gid}"),
+                file_ids: file_ids.to_vec(),
+                ..Default::default()
+            });
+        }
+        let before = file_handler.structure();
+
+        file_handler.remove(vec![1], vec![(0, fids[0])]);
+        assert_eq!(file_handler.registry.len(), 1);
+        // Hiding a group is not part of the structure.
+        file_handler.groups[0].as_mut().unwrap().is_plotted = false;
+        let after = file_handler.structure();
+
+        file_handler.restore_structure(before.clone());
+        assert_eq!(file_handler.structure(), before);
+        assert_eq!(file_handler.registry.len(), 3);
+        assert!(!file_handler.groups[0].as_ref().unwrap().is_plotted);
+        assert!(file_handler.groups[1].as_ref().unwrap().is_plotted);
+        // The data comes back without reloading.
+        assert_eq!(
+            file_handler.registry[&fids[2]].plot_data(),
+            Some(vec![[0.0, 2.0], [1.0, 1.0]])
+        );
+
+        // Redo, then drop the removed files: undoing cannot restore them.
+        file_handler.restore_structure(after.clone());
+        assert_eq!(file_handler.structure(), after);
+        file_handler.forget_removed(&HashSet::from([fids[0]]));
+        file_handler.restore_structure(before);
+        assert_eq!(file_handler.registry.len(), 2);
+        assert_eq!(file_handler.groups[0].as_ref().unwrap().file_ids, fids[..2]);
+        assert!(file_handler.groups[1].as_ref().unwrap().file_ids.is_empty());
+    }
+
+    #[test]
+    fn test_sort_by_alias_number() {
+        let (mut request_tx, _request_rx) = std::sync::mpsc::channel();
+        let mut file_handler = FileHandler::default();
+        let aliases = ["10 ms", "no number", "2 ms", "t = -1.5 ms", "also none"];
+        for i in 0..aliases.len() {
+            file_handler.add_file(Path::new(&format!("{i}.csv")), 0, &mut request_tx);
+        }
+        let grp = file_handler.groups[0].as_mut().unwrap();
+        for (fid, alias) in grp.file_ids.iter().zip(aliases) {
+            file_handler.registry.get_mut(fid).unwrap().properties.alias = alias.to_string();
+        }
+        let order = |grp: &Group| -> Vec<_> {
+            grp.file_ids_by_z_order(&file_handler.registry)
+                .iter()
+                .map(|fid| file_handler.registry[fid].properties.alias.clone())
+                .collect()
+        };
+        assert_eq!(order(grp), aliases);
+        grp.sort_by_alias_number = true;
+        assert_eq!(
+            order(grp),
+            ["t = -1.5 ms", "2 ms", "10 ms", "no number", "also none"]
+        );
+    }
+
+    #[test]
+    fn test_reload_all_progress() {
+        let (mut request_tx, _request_rx) = std::sync::mpsc::channel();
+        let mut file_handler = FileHandler::default();
+        assert_eq!(file_handler.reload_progress(), None);
+        for path in ["a.csv", "b.csv"] {
+            file_handler.add_file(std::path::Path::new(path), 0, &mut request_tx);
         }
-        // applies if xi does not lie within the range of xs
-        else {
-            yp.push(f64::NAN)
+        file_handler.reload_all(&mut request_tx);
+        assert_eq!(file_handler.reload_progress(), Some((0, 2)));
+
+        // Removed files no longer count as loading.
+        let fid = *file_handler.registry.keys().next().unwrap();
+        file_handler.registry.remove(&fid);
+        file_handler.try_update();
+        assert_eq!(file_handler.reload_progress(), Some((1, 2)));
+    }
+
+    #[test]
+    fn test_reload_debounce() {
+        let path = std::env::temp_dir().join("flugs_test_reload_debounce.csv");
+        std::fs::write(&path, "1,2\n").unwrap();
+        let data = PlotData::from_path(&path, &LoadOptions::default()).unwrap();
+        let mut file_handler = FileHandler::default();
+        let fid = file_handler.next_id();
+        let file = File::from_data(path.clone(), FileProperties::default(), data);
+        file_handler.registry.insert(fid, file);
+        let stamps = || vec![(fid, SourceStamp::from_path(&path))];
+        let write = |content: &str| std::fs::write(&path, content).unwrap();
+        let debounce = Duration::from_millis(500);
+        let start = Instant::now();
+        let mut settled_after = |ms: u64| {
+            let now = start + Duration::from_millis(ms);
+            file_handler.settled_changes(stamps(), now, debounce)
         };
+
+        assert!(settled_after(0).is_empty());
+        // The file is reloaded once it stopped changing for the debounce.
+        write("1,2\n3,4\n");
+        assert!(settled_after(100).is_empty());
+        write("1,2\n3,4\n5,6\n");
+        assert!(settled_after(400).is_empty());
+        assert!(settled_after(800).is_empty());
+        assert_eq!(settled_after(900), [fid]);
+
+        // Without debounce, every change is reloaded right away.
+        write("7,8,9\n");
+        let settled = file_handler.settled_changes(stamps(), start, Duration::ZERO);
+        assert_eq!(settled, [fid]);
+        std::fs::remove_file(&path).unwrap();
     }
-    yp
-}
 
-/// Calculate area of single trapezoid.
-fn singletrapz(x0: f64, x1: f64, y0: f64, y1: f64) -> f64 {
-    0.5 * f64::abs(x1 - x0) * (y1 + y0)
-}
+    #[test]
+    fn test_reload_keeps_properties() {
+        let (mut request_tx, _request_rx) = std::sync::mpsc::channel();
+        let data = PlotData::default();
+        let mut file = File::from_data("data.csv".into(), FileProperties::custom(), data);
+        assert_ne!(file.properties, FileProperties::default());
+
+        file.reload(&mut request_tx);
+        assert!(!file.data.is_up_to_date(), "reload should request new data");
+        assert_eq!(file.properties, FileProperties::custom());
 
-/// Linearly interpolate y-value at position xp between two points (x0, y0) and (x1, y1).
-pub fn lininterp(xp: f64, x0: f64, x1: f64, y0: f64, y1: f64) -> f64 {
-    (y1 * (xp - x0) + y0 * (x1 - xp)) / (x1 - x0)
+        // A file loaded despite the size limit is reloaded the same way.
+        file.load_ignoring_size_limit(&mut request_tx);
+        file.reload(&mut request_tx);
+        assert_eq!(file.load_request, LoadRequest::IgnoringSizeLimit);
+    }
 }
@@ -1,27 +1,78 @@
 mod logic;
 mod ui;
 
+pub use logic::CHANGE_CHECK_INTERVAL;
+
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
 
+use crate::app::config::FileNameDisplay;
 use crate::app::DynRequestSender;
-use crate::backend_state::PlotData;
+use crate::backend_state::{LoadOptions, PlotData, SourceStamp};
+use crate::utils::simplify::Reduction;
+use crate::utils::transform::{DataTransform, TransformRegistry};
+use app_core::backend::LinkReceiver;
 use app_core::frontend::UIParameter;
-use logic::parse_csv;
+use logic::request_load;
 use serde::{Deserialize, Serialize};
 
+/// Identifies a file in the [`FileHandler::registry`].
+///
+/// IDs are handed out in increasing order and never reused while the app
+/// runs: removing a file retires its ID, files added later (also after
+/// resetting the session) get new ones. Thus state that refers to files by
+/// ID (the selection, the plot IDs of the plotter, the undo history or
+/// other plot tabs) cannot end up pointing at a different file. Loading a
+/// session is the exception: it brings back the IDs stored in it, which may
+/// have been handed out in this run already, so the plotter, the plot tabs
+/// and the undo history are reset then.
 #[derive(
     Clone, Copy, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize,
 )]
 pub struct FileID(usize);
 
+/// Pseudo group ID of files that are inspected without being in a group.
+pub const UNGROUPED: usize = 10;
+
+/// A file and its state on disk, if it exists.
+type FileStamp = (FileID, Option<SourceStamp>);
+
 #[derive(Default, Debug)]
 pub struct FileHandler {
     pub groups: [Option<Group>; 10],
     pub registry: HashMap<FileID, File>,
+    /// Files that are in the registry and plotted, but not in any group.
+    pub inspected: Vec<FileID>,
+    pub hide_inspected: bool,
     next_id: FileID,
     group_name_buffer: [String; 10],
     active_element: ActiveElement,
+    preview: Option<FilePreview>,
+    /// List all files alphabetically instead of under their groups.
+    flat_file_list: bool,
+    /// Which groups are expanded in the (tree) file list, kept by slot so
+    /// that renaming a group or switching the list does not collapse it.
+    expanded_groups: [bool; 10],
+    /// Transforms that can be enabled in the file properties.
+    pub transforms: TransformRegistry,
+    /// Files that changed on disk, with their state on disk and when it was
+    /// first seen, see [`FileHandler::reload_changed_files`].
+    changed_files: HashMap<FileID, (SourceStamp, Instant)>,
+    last_change_check: Option<Instant>,
+    /// The state on disk of the loaded files, read by the backend.
+    source_stamps: Option<LinkReceiver<Vec<FileStamp>>>,
+    /// Files reloaded by [`FileHandler::reload_all`] that are still loading,
+    /// and how many were reloaded.
+    reloading: Vec<FileID>,
+    reloading_total: usize,
+    /// Files removed from the registry, kept (with their data) while the
+    /// undo history may restore them, see [`FileHandler::restore_structure`].
+    removed: HashMap<FileID, File>,
+    /// Name mode and (sorted) files the display names were last set for, see
+    /// [`FileHandler::update_display_names`].
+    display_names_for: Option<(FileNameDisplay, Vec<FileID>)>,
 }
 
 #[derive(Debug)]
@@ -29,22 +80,200 @@ pub struct File {
     pub data: UIParameter<Result<PlotData, String>>,
     pub path: PathBuf,
     pub properties: FileProperties,
+    /// Name shown in the file list and legend, depends on the config (see
+    /// `FileHandler::update_display_names`).
+    pub display_name: String,
+    /// Transform expression and smoothing width that were edited, but not
+    /// applied yet (see [`TransformMode`]).
+    pub staged_transform: Option<(String, f64)>,
+    /// Clicking the plot adds baseline anchors to the file while it is
+    /// selected (see [`FileProperties::baseline_anchors`]).
+    pub picking_anchors: bool,
+    /// The registered transforms named in
+    /// [`FileProperties::custom_transforms`], see [`FileHandler::try_update`].
+    pub transforms: Vec<Arc<dyn DataTransform>>,
+    /// How the data was last requested, reloading requests it the same way.
+    load_request: LoadRequest,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// How the data of a file is requested from the backend.
+#[derive(Clone, Debug, Default, PartialEq)]
+enum LoadRequest {
+    /// With the load options of the backend.
+    #[default]
+    Default,
+    /// With the load options of the backend, but without the size limit.
+    IgnoringSizeLimit,
+    /// With these options, e.g. after checking them in a preview.
+    Options(LoadOptions),
+}
+
+// Missing fields fall back to their default, so that sessions saved by older
+// versions can still be loaded.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct FileProperties {
     pub alias: String,
     pub xoffset: f64,
     pub yoffset: f64,
     pub yscale: f64,
     pub comment: String,
+    /// Files with a higher z-order are drawn above others of their group.
+    pub z_order: i32,
+    /// Draw markers at the data points in addition to the line.
+    pub show_markers: bool,
+    /// Draw only every n-th marker, so that at most this many are drawn
+    /// (0 draws all).
+    pub max_markers: usize,
+    /// Label the peaks (see `Config::peak_prominence`) above them.
+    pub label_peaks: bool,
+    /// Text of the peak labels, the `Config::peak_label_format` is used if
+    /// empty.
+    pub peak_label: String,
+    /// Expression in `x` and `y` that replaces the y-values before offsets
+    /// and scaling are applied, e.g. `log(y)`. Empty means no transform.
+    pub transform: String,
+    /// Width (standard deviation, in points) of the Gaussian kernel the data
+    /// is smoothed with after the transform, 0 means no smoothing.
+    pub smoothing_sigma: f64,
+    /// How the points of the line are reduced before drawing it.
+    pub reduction: Reduction,
+    pub transform_mode: TransformMode,
+    /// Use the second column as x and the first as y.
+    pub swap_xy: bool,
+    /// X-positions (before the offset) of the points a polynomial baseline
+    /// is fitted through, no baseline without anchors.
+    pub baseline_anchors: Vec<f64>,
+    /// Degree of the baseline, lowered if there are too few anchors.
+    pub baseline_degree: usize,
+    /// Subtract the baseline from the data, otherwise it is only drawn.
+    pub subtract_baseline: bool,
+    /// Generate the x-column from the row index and `sample_rate` when the
+    /// file is loaded, all columns of the file are y-columns then.
+    pub generate_x: bool,
+    /// Samples per unit of x (e.g. Hz for x in seconds).
+    pub sample_rate: f64,
+    /// Names of registered transforms (see [`TransformRegistry`]) that are
+    /// applied in this order after the baseline. Names that are not
+    /// registered, e.g. in a session saved with other transforms, are
+    /// ignored.
+    pub custom_transforms: Vec<String>,
+    /// X-ranges (before the offset, in either order) whose points are
+    /// removed from the plotted and integrated data, e.g. artifacts.
+    pub masked_ranges: Vec<(f64, f64)>,
+    /// Interrupt the line at masked ranges, otherwise it connects the points
+    /// next to them.
+    pub show_mask_gaps: bool,
+    /// Shape of the line ends in exported plots.
+    pub line_cap: LineCap,
+    /// Shape of the line corners (e.g. at sharp peaks) in exported plots.
+    pub line_join: LineJoin,
+}
+
+/// Shape of line ends in exports, see [`FileProperties::line_cap`]. The
+/// default matches the plot, which does not extend lines beyond their ends.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LineCap {
+    #[default]
+    Butt,
+    Round,
+    Square,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// Shape of line corners in exports, see [`FileProperties::line_join`]. The
+/// default matches the plot, which draws pointed corners.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LineJoin {
+    #[default]
+    Miter,
+    Round,
+    Bevel,
+}
+
+/// Whether edits of the transform and smoothing are applied right away or
+/// only on request, which keeps dragging responsive for large files.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransformMode {
+    /// Live for files with at most `Config::live_transform_max_points` points.
+    #[default]
+    Auto,
+    Live,
+    Apply,
+}
+
+impl TransformMode {
+    pub const OPTIONS: [TransformMode; 3] = [
+        TransformMode::Auto,
+        TransformMode::Live,
+        TransformMode::Apply,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TransformMode::Auto => "auto",
+            TransformMode::Live => "live",
+            TransformMode::Apply => "on apply",
+        }
+    }
+}
+
+/// Points to draw the baseline of a file, see [`File::baseline`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct BaselinePlot {
+    /// The baseline at the unmasked x-values of the data, sorted by x.
+    pub curve: Vec<[f64; 2]>,
+    /// The baseline at the anchors.
+    pub anchors: Vec<[f64; 2]>,
+}
+
+/// Read-only view of a loaded file and its state, see [`FileHandler::files`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct FileInfo<'a> {
+    pub id: FileID,
+    pub path: &'a Path,
+    /// IDs of the groups the file is a member of, [`UNGROUPED`] if it is
+    /// inspected without being in a group.
+    pub groups: Vec<usize>,
+    pub properties: &'a FileProperties,
+    /// Whether data was loaded, `Err` holds the reason if loading failed
+    /// (or is still pending).
+    pub loaded: Result<(), String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Group {
     pub file_ids: Vec<FileID>,
     pub is_plotted: bool,
     pub name: String,
+    /// Show a single legend entry (the group name) for all files of the
+    /// group instead of one entry per file.
+    #[serde(default)]
+    pub single_legend_entry: bool,
+    /// Draw (and list in exported legends) the files ordered by the first
+    /// number in their alias, e.g. "1 ms" before "10 ms", rather than in
+    /// the order they were added.
+    #[serde(default)]
+    pub sort_by_alias_number: bool,
+}
+
+/// Which files are in which group and which are inspected, without the
+/// files themselves, see [`FileHandler::structure`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Structure {
+    /// The groups with `is_plotted` unset, visibility is not undone.
+    groups: [Option<Group>; 10],
+    inspected: Vec<FileID>,
+}
+
+/// The first rows of a file, to check the load options before loading it.
+#[derive(Debug)]
+struct FilePreview {
+    fid: FileID,
+    /// The options the preview was requested with.
+    options: LoadOptions,
+    /// The options as edited in the preview, they only apply to the file.
+    edited: LoadOptions,
+    columns: UIParameter<Result<Vec<Vec<f64>>, String>>,
 }
 
 #[derive(Debug)]
@@ -54,17 +283,37 @@ enum ActiveElement {
 }
 
 impl FileHandler {
+    /// File handler with the files of `registry`. IDs are allocated from
+    /// `next_id` on, or after the highest ID in `registry` if that is not
+    /// larger (e.g. in an edited session).
     pub fn new(
         groups: [Option<Group>; 10],
         registry: HashMap<FileID, File>,
         next_id: FileID,
     ) -> Self {
+        let next_id = registry
+            .keys()
+            .map(|fid| FileID(fid.0 + 1))
+            .fold(next_id, FileID::max);
         Self {
             groups,
             registry,
+            inspected: Vec::new(),
+            hide_inspected: false,
             next_id,
             group_name_buffer: [const { String::new() }; 10],
             active_element: ActiveElement::Group(0),
+            preview: None,
+            flat_file_list: false,
+            expanded_groups: [false; 10],
+            transforms: TransformRegistry::default(),
+            changed_files: HashMap::new(),
+            last_change_check: None,
+            source_stamps: None,
+            reloading: Vec::new(),
+            reloading_total: 0,
+            removed: HashMap::new(),
+            display_names_for: None,
         }
     }
 }
@@ -76,11 +325,35 @@ impl File {
         request_tx: &mut DynRequestSender,
     ) -> Self {
         let mut csv_data = UIParameter::new(Err("Data no loaded".to_string()));
-        csv_data.set_recv(parse_csv(&path, request_tx));
+        csv_data.set_recv(request_load(
+            &path,
+            true,
+            properties.sample_rate(),
+            request_tx,
+        ));
         File {
             data: csv_data,
+            display_name: basename(&path),
             path,
             properties,
+            staged_transform: None,
+            picking_anchors: false,
+            transforms: Vec::new(),
+            load_request: LoadRequest::Default,
+        }
+    }
+    /// Create a file from data that was already parsed, e.g. restored from a
+    /// session cache, without sending a request to the backend.
+    pub fn from_data(path: PathBuf, properties: FileProperties, data: PlotData) -> Self {
+        File {
+            data: UIParameter::new(Ok(data)),
+            display_name: basename(&path),
+            path,
+            properties,
+            staged_transform: None,
+            picking_anchors: false,
+            transforms: Vec::new(),
+            load_request: LoadRequest::Default,
         }
     }
     pub fn file_name(&self) -> &str {
@@ -91,6 +364,12 @@ impl File {
     }
 }
 
+fn basename(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
 impl Default for FileProperties {
     fn default() -> Self {
         Self {
@@ -99,22 +378,146 @@ impl Default for FileProperties {
             yoffset: 0.0,
             yscale: 1.0,
             comment: String::new(),
+            z_order: 0,
+            show_markers: false,
+            max_markers: 0,
+            label_peaks: false,
+            peak_label: String::new(),
+            transform: String::new(),
+            smoothing_sigma: 0.0,
+            reduction: Reduction::Global,
+            transform_mode: TransformMode::Auto,
+            swap_xy: false,
+            baseline_anchors: Vec::new(),
+            baseline_degree: 2,
+            subtract_baseline: true,
+            generate_x: false,
+            sample_rate: 1.0,
+            custom_transforms: Vec::new(),
+            masked_ranges: Vec::new(),
+            show_mask_gaps: true,
+            line_cap: LineCap::default(),
+            line_join: LineJoin::default(),
         }
     }
 }
 
+#[cfg(test)]
+impl FileProperties {
+    /// Properties with every field set to a value different from the
+    /// default, so that tests notice fields that are not kept.
+    pub(crate) fn custom() -> Self {
+        FileProperties {
+            alias: "alias".to_string(),
+            xoffset: 1.5,
+            yoffset: -2.0,
+            yscale: 3.0,
+            comment: "comment".to_string(),
+            z_order: 4,
+            show_markers: true,
+            max_markers: 50,
+            label_peaks: true,
+            peak_label: "peak at {x}".to_string(),
+            transform: "log(y)".to_string(),
+            smoothing_sigma: 2.0,
+            reduction: Reduction::Lttb(500),
+            transform_mode: TransformMode::Apply,
+            swap_xy: true,
+            baseline_anchors: vec![10.0, 20.0],
+            baseline_degree: 1,
+            subtract_baseline: false,
+            generate_x: true,
+            sample_rate: 48_000.0,
+            custom_transforms: vec!["custom".to_string()],
+            masked_ranges: vec![(1.0, 2.0)],
+            show_mask_gaps: false,
+            line_cap: LineCap::Round,
+            line_join: LineJoin::Bevel,
+        }
+    }
+}
+
+#[cfg(test)]
+impl File {
+    /// A loaded file `data.csv` with `points` and default properties.
+    pub(crate) fn from_points(points: &[[f64; 2]]) -> Self {
+        let path = PathBuf::from("data.csv");
+        let data = PlotData::from_points(points, &path).unwrap();
+        File::from_data(path, FileProperties::default(), data)
+    }
+}
+
+impl FileProperties {
+    /// The rate to generate the x-column with, if it is generated.
+    pub fn sample_rate(&self) -> Option<f64> {
+        (self.generate_x && self.sample_rate > 0.0).then_some(self.sample_rate)
+    }
+}
+
+impl std::str::FromStr for FileID {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(id: &str) -> Result<Self, Self::Err> {
+        id.parse().map(FileID)
+    }
+}
+
 impl From<FileID> for i32 {
     fn from(val: FileID) -> Self {
         val.0 as i32
     }
 }
 
+impl Group {
+    /// File IDs in drawing order, sorted by z-order. Ties are ordered by the
+    /// number in the alias if [`Group::sort_by_alias_number`] is set (files
+    /// without one last), otherwise or then by insertion order.
+    pub fn file_ids_by_z_order(&self, registry: &HashMap<FileID, File>) -> Vec<FileID> {
+        let mut file_ids = self.file_ids.clone();
+        if self.sort_by_alias_number {
+            file_ids.sort_by(|a, b| {
+                let number = |fid| {
+                    registry
+                        .get(fid)
+                        .and_then(|file: &File| first_number(&file.properties.alias))
+                };
+                match (number(a), number(b)) {
+                    (Some(a), Some(b)) => a.total_cmp(&b),
+                    (a, b) => a.is_none().cmp(&b.is_none()),
+                }
+            });
+        }
+        file_ids.sort_by_key(|fid| registry.get(fid).map(|file| file.properties.z_order));
+        file_ids
+    }
+}
+
+/// The first (decimal, possibly negative) number in `text`, e.g. 2.5 in
+/// "t = 2.5 ms".
+fn first_number(text: &str) -> Option<f64> {
+    let start = text.find(|chr: char| chr.is_ascii_digit())?;
+    let end = text[start..]
+        .find(|chr: char| !chr.is_ascii_digit() && chr != '.')
+        .map_or(text.len(), |len| start + len);
+    let negative = text[..start].ends_with('-');
+    // A trailing point (or a second one, e.g. in a date) ends the number.
+    let digits = text[start..end].trim_end_matches('.');
+    let digits = match digits.match_indices('.').nth(1) {
+        Some((second, _)) => &digits[..second],
+        None => digits,
+    };
+    let number: f64 = digits.parse().ok()?;
+    Some(if negative { -number } else { number })
+}
+
 impl Default for Group {
     fn default() -> Self {
         Self {
             file_ids: Default::default(),
             is_plotted: true,
             name: Default::default(),
+            single_legend_entry: false,
+            sort_by_alias_number: false,
         }
     }
 }
@@ -4,17 +4,30 @@ use egui::{text::LayoutJob, Color32, FontId};
 
 use crate::{
     app::{
-        events::{CopyFile, EventQueue, MoveFile, RemoveFile, RemoveGroup},
+        config::{option_combo_box, parse_options_ui, Config, ConfigOption},
+        events::{
+            CopyFile, EventQueue, MoveFile, NormalizeGroup, RemoveFile, RemoveGroup, ReorderGroup,
+            SelectFile,
+        },
         DynRequestSender,
     },
+    backend_state::LoadOptions,
+    utils::{
+        expression::Expr,
+        simplify::Reduction,
+        transform::{OffsetScale, TransformRegistry},
+    },
     EguiApp,
 };
 
-use super::{ActiveElement, File, FileHandler, FileID};
+use super::{
+    logic::request_preview, ActiveElement, File, FileHandler, FileID, TransformMode, UNGROUPED,
+};
 
 impl FileHandler {
     pub(crate) fn render(
         &mut self,
+        config: &mut Config,
         _request_tx: &mut DynRequestSender,
         event_queue: &mut EventQueue<EguiApp>,
         ui: &mut egui::Ui,
@@ -48,7 +61,7 @@ impl FileHandler {
                 self.group_settings(*gid, _request_tx, event_queue, ui, ctx)
             }
             super::ActiveElement::File(fid, gid) => {
-                self.file_settings(*fid, *gid, _request_tx, event_queue, ui, ctx)
+                self.file_settings(*fid, *gid, config, _request_tx, event_queue, ui)
             }
         });
     }
@@ -56,7 +69,7 @@ impl FileHandler {
     pub(crate) fn left_panel(
         &mut self,
         _request_tx: &mut DynRequestSender,
-        _event_queue: &mut EventQueue<EguiApp>,
+        event_queue: &mut EventQueue<EguiApp>,
         ui: &mut egui::Ui,
         ctx: &egui::Context,
     ) {
@@ -74,8 +87,50 @@ impl FileHandler {
             }
         }
 
-        ui.heading("Groups");
+        ui.horizontal(|ui| {
+            ui.heading(if self.flat_file_list {
+                "Files"
+            } else {
+                "Groups"
+            });
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                ui.toggle_value(&mut self.flat_file_list, "Flat")
+                    .on_hover_text("list all files alphabetically instead of under their groups");
+            });
+        });
 
+        if self.flat_file_list {
+            self.flat_file_list(event_queue, ui);
+        } else {
+            self.group_tree(event_queue, ui, ctx);
+        }
+
+        if self.inspected.is_empty() {
+            return;
+        }
+        // Files which were loaded without adding them to a group, they can be
+        // promoted to a group with "Move to Group" in the file settings.
+        ui.separator();
+        ui.heading("Inspected");
+        for fid in self.inspected.iter() {
+            let Some(file) = self.registry.get_mut(fid) else {
+                log::warn!("could not render file label for fid {fid:?}");
+                continue;
+            };
+            let hover_text = "not in any group, move it to a group in its settings";
+            if let Some(element) = file_row(*fid, UNGROUPED, file, hover_text, event_queue, ui) {
+                self.active_element = element;
+            }
+        }
+    }
+
+    /// Groups as collapsible headers with their files below.
+    fn group_tree(
+        &mut self,
+        event_queue: &mut EventQueue<EguiApp>,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+    ) {
         for (gid, grp) in self
             .groups
             .iter_mut()
@@ -91,9 +146,10 @@ impl FileHandler {
                     Color32::WHITE
                 },
             );
+            let expanded = &mut self.expanded_groups[gid];
             let collapsing = egui::CollapsingHeader::new(grp_label_txt)
-                .id_salt((&grp.name, gid))
-                .default_open(false);
+                .id_salt(("group tree", gid))
+                .open(Some(*expanded));
             let resp = collapsing.show(ui, |ui| {
                 for fid in grp.file_ids.iter() {
                     let file = match self.registry.get_mut(fid) {
@@ -105,39 +161,69 @@ impl FileHandler {
                             continue;
                         }
                     };
-                    let file_label_txt = match file_name_layout(file) {
-                        Some(value) => value,
-                        None => {
-                            log::warn!("could not render file label for fid {fid:?}");
-                            continue;
-                        }
+                    let hover_text = match file.load_error() {
+                        Some(err) => format!("could not load file:\n{err}"),
+                        None => "double-click to show in plot".to_string(),
                     };
-                    let label = egui::Label::new(file_label_txt).truncate();
-                    if ui
-                        .add(label)
-                        .on_hover_cursor(egui::CursorIcon::PointingHand)
-                        .clicked()
-                    {
-                        self.active_element = ActiveElement::File(*fid, gid);
+                    if let Some(element) = file_row(*fid, gid, file, &hover_text, event_queue, ui) {
+                        self.active_element = element;
                     }
                 }
             });
 
-            if resp
+            let header = resp
                 .header_response
                 .on_hover_cursor(egui::CursorIcon::PointingHand)
-                .on_hover_text_at_pointer("right-click to rename")
-                .secondary_clicked()
-            {
+                .on_hover_text_at_pointer("right-click to rename");
+            if header.clicked() {
+                *expanded = !*expanded;
+            }
+            if header.secondary_clicked() {
                 self.active_element = ActiveElement::Group(gid);
             }
         }
     }
 
+    /// All grouped files sorted by name, with the names of their groups.
+    fn flat_file_list(&mut self, event_queue: &mut EventQueue<EguiApp>, ui: &mut egui::Ui) {
+        let mut fids: Vec<_> = self
+            .registry
+            .keys()
+            .filter(|fid| self.is_grouped(fid))
+            .copied()
+            .collect();
+        fids.sort_by_cached_key(|fid| (self.registry[fid].display_name.to_lowercase(), *fid));
+        for fid in fids {
+            let member_of: Vec<_> = self
+                .groups
+                .iter()
+                .enumerate()
+                .filter_map(|(gid, grp)| Some(gid).zip(grp.as_ref()))
+                .filter(|(_, grp)| grp.file_ids.contains(&fid))
+                .collect();
+            let group_names = member_of
+                .iter()
+                .map(|(_, grp)| grp.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            let gid = member_of.first().map_or(UNGROUPED, |(gid, _)| *gid);
+            let Some(file) = self.registry.get_mut(&fid) else {
+                continue;
+            };
+            let hover_text = match file.load_error() {
+                Some(err) => format!("in {group_names}\ncould not load file:\n{err}"),
+                None => format!("in {group_names}\ndouble-click to show in plot"),
+            };
+            if let Some(element) = file_row(fid, gid, file, &hover_text, event_queue, ui) {
+                self.active_element = element;
+            }
+        }
+    }
+
     pub(crate) fn group_settings(
         &mut self,
         gid: usize,
-        _request_tx: &mut DynRequestSender,
+        request_tx: &mut DynRequestSender,
         event_queue: &mut EventQueue<EguiApp>,
         ui: &mut egui::Ui,
         _ctx: &egui::Context,
@@ -152,21 +238,67 @@ impl FileHandler {
             let lab = ui.label("rename:");
             ui.text_edit_singleline(&mut grp.name).labelled_by(lab.id);
         });
+        ui.checkbox(&mut grp.single_legend_entry, "single legend entry")
+            .on_hover_text("show the group name once in the legend instead of every file");
+        ui.checkbox(&mut grp.sort_by_alias_number, "sort by number in alias")
+            .on_hover_text(
+                "draw the files ordered by the first number in their alias (e.g. \"2 ms\" before \"10 ms\"), files without one last",
+            );
+        ui.menu_button("Divide by Reference", |ui| {
+            ui.label("new group with the other files divided by:");
+            for fid in &grp.file_ids {
+                let Some(file) = self.registry.get(fid) else {
+                    continue;
+                };
+                if ui.button(&file.display_name).clicked() {
+                    event_queue.queue_event(Box::new(NormalizeGroup::new(gid, *fid)));
+                    ui.close_menu();
+                }
+            }
+        })
+        .response
+        .on_hover_text(
+            "compare line shapes: the ratio is taken where the x-ranges overlap, interpolating the reference as set in the preferences",
+        );
         ui.horizontal(|ui| {
             ui.label("delete group:");
             if ui.small_button("🗑").clicked() {
                 event_queue.queue_event(Box::new(RemoveGroup::new(gid)));
             }
         });
+        ui.horizontal(|ui| {
+            if ui.button("Bring to Front").clicked() {
+                event_queue.queue_event(Box::new(ReorderGroup::new(gid, true)));
+            }
+            if ui.button("Send to Back").clicked() {
+                event_queue.queue_event(Box::new(ReorderGroup::new(gid, false)));
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("duplicate:");
+            let share = ui
+                .button("Share Files")
+                .on_hover_text("new group with the same files, changing a file changes both groups")
+                .clicked();
+            let copy = ui
+                .button("Copy Files")
+                .on_hover_text(
+                    "new group with copies of the files, which can be changed independently",
+                )
+                .clicked();
+            if (share || copy) && self.duplicate_group(gid, copy, request_tx).is_none() {
+                log::warn!("no free slot to duplicate group {gid}");
+            }
+        });
     }
     pub fn file_settings(
         &mut self,
         fid: FileID,
         gid: usize,
-        _request_tx: &mut DynRequestSender,
+        config: &mut Config,
+        request_tx: &mut DynRequestSender,
         event_queue: &mut EventQueue<EguiApp>,
         ui: &mut egui::Ui,
-        _ctx: &egui::Context,
     ) {
         let file = match self.registry.get_mut(&fid) {
             Some(file) => file,
@@ -176,12 +308,27 @@ impl FileHandler {
             }
         };
 
-        ui.push_id(file.file_name(), |ui| {
+        let id = file.file_name().to_owned();
+        ui.push_id(id, |ui| {
             ui.horizontal(|ui| {
-                let label = egui::Label::new(file.file_name()).truncate();
+                let label = egui::Label::new(&file.display_name).truncate();
                 ui.add(label);
                 // Identifier and delete button.
                 ui.label(format!("(ID {})", fid.0));
+                if ui
+                    .small_button("🔍")
+                    .on_hover_text("show in plot")
+                    .clicked()
+                {
+                    event_queue.queue_event(Box::new(SelectFile::new(fid, true)));
+                }
+                if ui
+                    .small_button("🔄")
+                    .on_hover_text("reload data from disk, keeping the display options")
+                    .clicked()
+                {
+                    file.reload(request_tx);
+                }
                 if ui.small_button("🗑").clicked() {
                     event_queue.queue_event(Box::new(RemoveFile::new(fid, gid)));
                 }
@@ -190,31 +337,156 @@ impl FileHandler {
 
         ui.separator();
 
-        // Display error if csv could not be parsed.
+        // The x-column is generated when the file is loaded, so changes
+        // reload it (once dragging the rate stopped).
+        ui.horizontal(|ui| {
+            let toggled = ui
+                .checkbox(&mut file.properties.generate_x, "Generate X")
+                .on_hover_text("use the row index divided by the sampling rate as x, all columns of the file are y-columns")
+                .changed();
+            let rate = ui.add_enabled(
+                file.properties.generate_x,
+                egui::DragValue::new(&mut file.properties.sample_rate)
+                    .range(1e-9..=f64::INFINITY)
+                    .prefix("rate: ")
+                    .suffix(" / unit of x"),
+            );
+            if toggled || rate.drag_stopped() || (rate.changed() && !rate.dragged()) {
+                file.reload(request_tx);
+            }
+        });
+
+        ui.separator();
+
+        if let Some(count) = file
+            .data
+            .value()
+            .as_ref()
+            .ok()
+            .map(|data| data.infinite_values())
+            .filter(|count| *count > 0)
+        {
+            ui.label(format!("{count} infinite values")).on_hover_text(
+                "dropped, clamped or kept when the file was loaded, depending on the load options",
+            );
+        }
+        // A wrongly detected number format can be corrected in the preview.
+        if let Some(parsed) = file
+            .data
+            .value()
+            .as_ref()
+            .ok()
+            .and_then(|data| data.number_format())
+        {
+            ui.horizontal(|ui| {
+                ui.label(format!(
+                    "decimal separator: {}, thousands separator: {}{}",
+                    parsed.format.decimal_separator.label(),
+                    parsed.format.thousands_separator.label(),
+                    if parsed.detected { " (detected)" } else { "" }
+                ));
+                if ui
+                    .small_button("Preview")
+                    .on_hover_text(
+                        "check other load options on the first rows and load the file with them",
+                    )
+                    .clicked()
+                {
+                    self.preview = Some(request_preview(
+                        fid,
+                        &file.path,
+                        &config.load_options,
+                        config.preview_rows,
+                        request_tx,
+                    ));
+                }
+            });
+        }
+
+        // Display error if csv could not be parsed. Such files (and those
+        // that are too large) can be previewed to check the load options.
         if let Err(error) = file.data.value() {
             ui.label(error).highlight();
         };
+        if file.load_error().is_some() {
+            ui.horizontal(|ui| {
+                if file.is_too_large()
+                    && ui
+                        .button("Load Anyway")
+                        .on_hover_text(
+                            "loading large files may take a while and use a lot of memory",
+                        )
+                        .clicked()
+                {
+                    file.load_ignoring_size_limit(request_tx);
+                }
+                if ui
+                    .button("Preview")
+                    .on_hover_text(format!(
+                        "show the first {} rows before loading the file",
+                        config.preview_rows
+                    ))
+                    .clicked()
+                {
+                    self.preview = Some(request_preview(
+                        fid,
+                        &file.path,
+                        &config.load_options,
+                        config.preview_rows,
+                        request_tx,
+                    ));
+                }
+            });
+        }
+        let mut close_preview = false;
+        if let Some(preview) = self.preview.as_mut().filter(|preview| preview.fid == fid) {
+            ui.separator();
+            ui.heading("Preview");
+            // The options only apply to this file, the global ones are kept.
+            parse_options_ui(&mut preview.edited, ui);
+            // Changing the options parses the first rows again.
+            if preview.options != preview.edited {
+                let options = preview.edited.clone();
+                *preview =
+                    request_preview(fid, &file.path, &options, config.preview_rows, request_tx);
+            } else {
+                match preview.columns.value() {
+                    Ok(columns) => preview_table(columns, ui),
+                    Err(error) => {
+                        ui.label(error).highlight();
+                    }
+                }
+            }
+            ui.horizontal(|ui| {
+                if ui
+                    .button("Load")
+                    .on_hover_text("load the whole file with these options")
+                    .clicked()
+                {
+                    let options = LoadOptions {
+                        max_file_size_mb: 0,
+                        ..preview.edited.clone()
+                    };
+                    file.load_with_options(options, request_tx);
+                    close_preview = true;
+                }
+                if ui.button("Close").clicked() {
+                    close_preview = true;
+                }
+            });
+            ui.separator();
+        }
+        if close_preview {
+            self.preview = None;
+        }
 
-        file.render_property_settings(ui);
+        file.render_property_settings(config, &self.transforms, ui);
 
         ui.separator();
 
         egui::CollapsingHeader::new("Contents").show(ui, |ui| {
-            egui::ScrollArea::new([true, false]).show(ui, |ui| {
-                let mut contents = String::with_capacity(10_000);
-                let Ok(data) = file.data.value() else { return };
-                let Some(xs) = data.columns.first() else {
-                    return;
-                };
-                let Some(ys) = data.columns.first() else {
-                    return;
-                };
-                for (x, y) in xs.iter().zip(ys) {
-                    let _ = writeln!(contents, "{x}, {y}");
-                }
-
-                ui.label(contents);
-            })
+            let steps = file.processing_steps();
+            file.with_plot_data(|data, offsets| data_table(data, offsets, &steps, config, ui));
         });
 
         // Menu to move/copy file to other group.
@@ -253,8 +525,89 @@ impl FileHandler {
     }
 }
 
+/// Table of the plotted points of a file (`data` with `offsets` applied),
+/// headed by the axis titles and the processing `steps`. Only the visible
+/// rows are laid out, so that large files stay responsive.
+fn data_table(
+    data: &[[f64; 2]],
+    offsets: OffsetScale,
+    steps: &[String],
+    config: &Config,
+    ui: &mut egui::Ui,
+) {
+    let title = |title: String, axis: &str| {
+        if title.is_empty() {
+            axis.to_string()
+        } else {
+            title
+        }
+    };
+    let (x_title, y_title) = (title(config.x_title(), "x"), title(config.y_title(), "y"));
+    let row_height = ui.text_style_height(&egui::TextStyle::Body);
+    egui::ScrollArea::both().max_height(300.0).show_rows(
+        ui,
+        row_height,
+        data.len() + 1,
+        |ui, rows| {
+            egui::Grid::new("data table")
+                .striped(true)
+                .min_col_width(80.0)
+                .show(ui, |ui| {
+                    for row in rows {
+                        match row.checked_sub(1).and_then(|i| data.get(i)) {
+                            Some(point) => {
+                                let [x, y] = offsets.point(*point);
+                                ui.label(x.to_string());
+                                ui.label(y.to_string());
+                            }
+                            None => {
+                                ui.strong(&x_title);
+                                ui.strong(&y_title);
+                                if !steps.is_empty() {
+                                    ui.weak(format!("processed: {}", steps.join(", ")))
+                                        .on_hover_text("the values are shown as plotted");
+                                }
+                            }
+                        }
+                        ui.end_row();
+                    }
+                });
+        },
+    );
+}
+
+/// Table of the first rows of a file, one column per data column.
+fn preview_table(columns: &[Vec<f64>], ui: &mut egui::Ui) {
+    if columns.is_empty() {
+        ui.label("no data found in the first rows");
+        return;
+    }
+    let rows = columns.iter().map(Vec::len).max().unwrap_or_default();
+    egui::ScrollArea::horizontal().show(ui, |ui| {
+        egui::Grid::new("file preview")
+            .striped(true)
+            .show(ui, |ui| {
+                for i in 0..columns.len() {
+                    ui.strong(format!("column {}", i + 1));
+                }
+                ui.end_row();
+                for row in 0..rows {
+                    for column in columns {
+                        ui.label(column.get(row).map(f64::to_string).unwrap_or_default());
+                    }
+                    ui.end_row();
+                }
+            });
+    });
+}
+
 impl File {
-    pub fn render_property_settings(&mut self, ui: &mut egui::Ui) {
+    pub fn render_property_settings(
+        &mut self,
+        config: &Config,
+        transforms: &TransformRegistry,
+        ui: &mut egui::Ui,
+    ) {
         ui.horizontal(|ui| {
             let label = ui.label("Alias: ");
             ui.text_edit_singleline(&mut self.properties.alias)
@@ -270,6 +623,242 @@ impl File {
         ui.label("Y-Scale: ");
         let dragv = egui::DragValue::new(&mut self.properties.yscale);
         ui.add(dragv);
+        ui.label("Z-Order: ")
+            .on_hover_text("files with higher values are drawn above others in their group");
+        let dragv = egui::DragValue::new(&mut self.properties.z_order);
+        ui.add(dragv);
+        ui.checkbox(&mut self.properties.swap_xy, "Swap X/Y")
+            .on_hover_text("use the second column as x and the first as y");
+        ui.checkbox(&mut self.properties.show_markers, "Markers");
+        if self.properties.show_markers {
+            ui.label("Max. Markers: ")
+                .on_hover_text("markers are thinned out for larger series (0 = all)");
+            let dragv = egui::DragValue::new(&mut self.properties.max_markers).speed(10);
+            ui.add(dragv);
+        }
+        ui.checkbox(&mut self.properties.label_peaks, "Peak Labels")
+            .on_hover_text("label the peaks with their position, see the preferences");
+        if self.properties.label_peaks {
+            ui.label("Label Text: ").on_hover_text(
+                "{x} and {y} are replaced by the position of the peak, empty uses the preferences",
+            );
+            ui.add(
+                egui::TextEdit::singleline(&mut self.properties.peak_label)
+                    .hint_text(&config.peak_label_format),
+            );
+        }
+        // Unless edits are live, the transform and smoothing are edited in a
+        // staged copy, so that the data is not recomputed while dragging.
+        let live = self.transforms_live(config.live_transform_max_points);
+        if live {
+            self.apply_staged_transform();
+        }
+        let sigma = if live {
+            &mut self.properties.smoothing_sigma
+        } else {
+            let staged = self.staged_transform.get_or_insert_with(|| {
+                (
+                    self.properties.transform.clone(),
+                    self.properties.smoothing_sigma,
+                )
+            });
+            &mut staged.1
+        };
+        ui.label("Smoothing: ")
+            .on_hover_text("width of the Gaussian smoothing kernel in points (0 = off)");
+        let dragv = egui::DragValue::new(sigma)
+            .range(0.0..=f64::INFINITY)
+            .speed(0.1);
+        ui.add(dragv);
+        let pending = !live
+            && self
+                .staged_transform
+                .as_ref()
+                .is_some_and(|(transform, sigma)| {
+                    *transform != self.properties.transform
+                        || *sigma != self.properties.smoothing_sigma
+                });
+        if pending {
+            ui.horizontal(|ui| {
+                if ui
+                    .button("Apply")
+                    .on_hover_text("apply the edited transform and smoothing")
+                    .clicked()
+                {
+                    self.apply_staged_transform();
+                }
+                if ui.button("Discard").clicked() {
+                    self.staged_transform = None;
+                }
+            });
+        }
+        egui::CollapsingHeader::new("Advanced").show(ui, |ui| {
+            ui.label("Y-Transform: ")
+                .on_hover_text("expression in x and y that replaces y, e.g. log(y)+3");
+            let transform = match &mut self.staged_transform {
+                Some((transform, _)) if !live => transform,
+                _ => &mut self.properties.transform,
+            };
+            ui.add(egui::TextEdit::singleline(transform).hint_text("e.g. log(y)+3"));
+            if let (false, Err(err)) = (transform.trim().is_empty(), Expr::parse(transform.trim())) {
+                ui.colored_label(ui.visuals().error_fg_color, format!("ignored: {err}"));
+            }
+            ui.label("Apply Transforms: ").on_hover_text(
+                "\"auto\" applies edits of the transform and smoothing right away for files with at most the number of points set in the preferences",
+            );
+            egui::ComboBox::from_id_salt("transform mode")
+                .selected_text(self.properties.transform_mode.label())
+                .show_ui(ui, |ui| {
+                    for option in TransformMode::OPTIONS {
+                        ui.selectable_value(
+                            &mut self.properties.transform_mode,
+                            option,
+                            option.label(),
+                        );
+                    }
+                });
+            ui.label("Point Reduction: ")
+                .on_hover_text("how points are dropped to draw long series faster");
+            let reduction = &mut self.properties.reduction;
+            egui::ComboBox::from_id_salt("point reduction")
+                .selected_text(reduction.label())
+                .show_ui(ui, |ui| {
+                    for option in Reduction::OPTIONS {
+                        // Switching keeps the parameter of the current strategy.
+                        let same =
+                            std::mem::discriminant(&option) == std::mem::discriminant(reduction);
+                        if ui.selectable_label(same, option.label()).clicked() && !same {
+                            *reduction = option;
+                        }
+                    }
+                });
+            match reduction {
+                Reduction::Stride(step) => {
+                    ui.add(
+                        egui::DragValue::new(step)
+                            .range(1..=usize::MAX)
+                            .prefix("every "),
+                    );
+                }
+                Reduction::Lttb(points) => {
+                    ui.add(
+                        egui::DragValue::new(points)
+                            .range(3..=usize::MAX)
+                            .speed(10)
+                            .suffix(" points"),
+                    );
+                }
+                Reduction::Rdp(tolerance) => {
+                    ui.add(
+                        egui::DragValue::new(tolerance)
+                            .range(0.0..=1.0)
+                            .speed(0.0001)
+                            .prefix("tolerance: "),
+                    )
+                    .on_hover_text("fraction of the data range");
+                }
+                Reduction::Global | Reduction::None | Reduction::Adaptive => (),
+            }
+            ui.label("Line Ends (Export): ")
+                .on_hover_text("shape of the line ends in exported plots");
+            option_combo_box(ui, "line cap", &mut self.properties.line_cap);
+            ui.label("Line Corners (Export): ")
+                .on_hover_text("shape of the line corners in exported plots, e.g. at sharp peaks");
+            option_combo_box(ui, "line join", &mut self.properties.line_join);
+            if !transforms.is_empty() {
+                ui.label("Custom Transforms: ")
+                    .on_hover_text("applied in the order they are enabled, after the baseline");
+                let enabled = &mut self.properties.custom_transforms;
+                for name in transforms.names() {
+                    let mut on = enabled.iter().any(|enabled| enabled == name);
+                    if ui.checkbox(&mut on, name).changed() {
+                        if on {
+                            enabled.push(name.to_string());
+                        } else {
+                            enabled.retain(|enabled| enabled != name);
+                        }
+                    }
+                }
+            }
+        });
+        egui::CollapsingHeader::new("Baseline").show(ui, |ui| {
+            ui.checkbox(&mut self.picking_anchors, "Add Anchors by Clicking")
+                .on_hover_text("clicking the plot adds an anchor while this file is selected");
+            ui.checkbox(&mut self.properties.subtract_baseline, "Subtract")
+                .on_hover_text("subtract the baseline from the data, otherwise it is only drawn");
+            ui.label("Degree: ")
+                .on_hover_text("degree of the polynomial, lowered if there are fewer anchors");
+            ui.add(egui::DragValue::new(&mut self.properties.baseline_degree).range(0..=10));
+            let anchors = &mut self.properties.baseline_anchors;
+            if anchors.is_empty() {
+                ui.weak("no anchors");
+            }
+            let mut remove = None;
+            for (i, x) in anchors.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(x).prefix("x = "));
+                    if ui
+                        .small_button("🗑")
+                        .on_hover_text("remove anchor")
+                        .clicked()
+                    {
+                        remove = Some(i);
+                    }
+                });
+            }
+            if let Some(i) = remove {
+                anchors.remove(i);
+            }
+            if !anchors.is_empty() && ui.button("Remove All").clicked() {
+                anchors.clear();
+            }
+        });
+        egui::CollapsingHeader::new("Masked Ranges").show(ui, |ui| {
+            ui.checkbox(&mut self.properties.show_mask_gaps, "Show as Gaps")
+                .on_hover_text("interrupt the line at masked ranges, otherwise the points next to them are connected");
+            let ranges = &mut self.properties.masked_ranges;
+            if ranges.is_empty() {
+                ui.weak("no masked ranges");
+            }
+            let mut remove = None;
+            for (i, (from, to)) in ranges.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(from).prefix("from: "));
+                    ui.add(egui::DragValue::new(to).prefix("to: "));
+                    if ui
+                        .small_button("🗑")
+                        .on_hover_text("remove range")
+                        .clicked()
+                    {
+                        remove = Some(i);
+                    }
+                });
+            }
+            if let Some(i) = remove {
+                ranges.remove(i);
+            }
+            if ui
+                .button("Add Range")
+                .on_hover_text("mask the middle tenth of the data, then adjust the limits (x before the offset)")
+                .clicked()
+            {
+                let (xmin, xmax) = self.get_cache().map_or((0.0, 1.0), |data| {
+                    data.iter()
+                        .map(|point| point[usize::from(self.properties.swap_xy)])
+                        .filter(|x| x.is_finite())
+                        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), x| {
+                            (min.min(x), max.max(x))
+                        })
+                });
+                let (center, half) = ((xmin + xmax) / 2.0, (xmax - xmin) / 20.0);
+                let range = if half.is_finite() {
+                    (center - half, center + half)
+                } else {
+                    (0.0, 1.0)
+                };
+                self.properties.masked_ranges.push(range);
+            }
+        });
 
         ui.label("Comment:");
         egui::TextEdit::multiline(&mut self.properties.comment)
@@ -278,20 +867,47 @@ impl File {
     }
 }
 
+/// Label of a file in the file list. Clicking selects the file in the plot
+/// as well, a double-click also zooms the plot on it. Returns the element to
+/// show the settings of, if the file was clicked.
+fn file_row(
+    fid: FileID,
+    gid: usize,
+    file: &mut File,
+    hover_text: &str,
+    event_queue: &mut EventQueue<EguiApp>,
+    ui: &mut egui::Ui,
+) -> Option<ActiveElement> {
+    let Some(file_label_txt) = file_name_layout(file) else {
+        log::warn!("could not render file label for fid {fid:?}");
+        return None;
+    };
+    let label = egui::Label::new(file_label_txt).truncate();
+    let resp = ui
+        .add(label)
+        .on_hover_cursor(egui::CursorIcon::PointingHand)
+        .on_hover_text_at_pointer(hover_text);
+    let clicked = resp.clicked();
+    if clicked {
+        event_queue.queue_event(Box::new(SelectFile::new(fid, false)));
+    }
+    if resp.double_clicked() {
+        event_queue.queue_event(Box::new(SelectFile::new(fid, true)));
+    }
+    clicked.then_some(ActiveElement::File(fid, gid))
+}
+
 fn file_name_layout(file: &mut File) -> Option<LayoutJob> {
-    let file_label_txt = if let Some(name) = file.path.file_name().and_then(|name| name.to_str()) {
-        if file.data.value().is_ok() {
-            egui::text::LayoutJob::single_section(name.to_owned(), egui::TextFormat::default())
-        } else {
-            // Make file label red if parsin CSV data failed.
-            egui::text::LayoutJob::simple_singleline(
-                name.to_owned(),
-                FontId::default(),
-                egui::Color32::RED,
-            )
-        }
+    let name = file.display_name.as_str();
+    let file_label_txt = if file.load_error().is_none() {
+        egui::text::LayoutJob::single_section(name.to_owned(), egui::TextFormat::default())
     } else {
-        return None;
+        // Make file label red if parsing CSV data failed or the file is empty.
+        egui::text::LayoutJob::simple_singleline(
+            format!("⚠ {name}"),
+            FontId::default(),
+            egui::Color32::RED,
+        )
     };
     Some(file_label_txt)
 }
@@ -2,7 +2,14 @@ mod file_handling;
 mod plotter;
 mod search;
 
-pub use file_handling::{File, FileHandler};
-pub(in crate::app) use file_handling::{FileID, FileProperties, Group};
-pub use plotter::{save_svg, Plotter, PlotterMode};
+pub use file_handling::{
+    File, FileHandler, FileID, FileInfo, FileProperties, LineCap, LineJoin, TransformMode,
+    CHANGE_CHECK_INTERVAL,
+};
+pub(in crate::app) use file_handling::{Group, Structure, UNGROUPED};
+pub use plotter::{
+    bundle_entries, peak_fits_csv, render_svg, save_group_csvs, save_region_csv, save_svg,
+    BundleContents, PlotTabs, Plotter, PlotterMode,
+};
+pub(in crate::app) use plotter::{AxisTicks, StackMode, TickMode};
 pub use search::Search;
@@ -1,8 +1,15 @@
-use std::io::Write;
+use std::{fmt::Write as _, io::Write, path::Path};
 
 use egui::Vec2;
 
-use crate::{app::components::File, EguiApp};
+use crate::{
+    app::{
+        components::{File, FileHandler},
+        config::ConfigOption,
+    },
+    utils::{fit::PeakFit, window::WindowFunction},
+    EguiApp,
+};
 
 impl super::Plotter {
     pub(super) fn manipulate_file(
@@ -36,8 +43,6 @@ impl super::Plotter {
 }
 
 pub fn save_svg(app: &EguiApp, path: &std::path::Path) {
-    use svg_export::{self, Axis, Figure, LinePlot};
-
     log::debug!("requested to save svg at '{:?}'", path);
 
     let mut file = match std::fs::File::create(path) {
@@ -48,17 +53,47 @@ pub fn save_svg(app: &EguiApp, path: &std::path::Path) {
         }
     };
 
+    if let Err(err) = file.write_all(&render_svg(app).into_bytes()) {
+        log::error!("could not write svg file {:?}: {:?}", path, err)
+    }
+}
+
+/// Render the currently plotted data as SVG.
+pub fn render_svg(app: &EguiApp) -> String {
+    use svg_export::{self, Axis, Figure, LinePlot};
+
     let [xmin, xmax, ymin, ymax] = app.plotter.current_plot_bounds;
+    let shifts = app.plotter.stack_shifts(&app.file_handler);
+    // Reversed limits reverse the axis.
+    let (xmin, xmax) = if app.plotter.reverse_x {
+        (xmax, xmin)
+    } else {
+        (xmin, xmax)
+    };
 
-    let mut fig = Figure::empty(app.config.svg_width, app.config.svg_height);
-    let mut ax = Axis::default()
+    let (width, height) = app.config.svg_size();
+    let mut fig = Figure::empty(width, height);
+    if let Some((width, height)) = app.config.svg_physical_size() {
+        fig = fig.with_physical_size(&width, &height);
+    }
+    let mut ax = Axis::from(app.config.svg_axis_placement())
         .with_xlim(xmin, xmax)
-        .with_ylim(ymin, ymax)
-        .with_xlabel(&app.config.x_label)
-        .with_ylabel(&app.config.y_label)
+        .with_ylim(ymin, ymax);
+    // Ticks as in the plot, set before the y-label, which is placed next to
+    // the tick labels.
+    let x_range = (xmin.min(xmax), xmin.max(xmax));
+    if let Some(positions) = app.plotter.x_ticks.positions(x_range) {
+        ax = ax.with_xticks(&positions);
+    }
+    if let Some(positions) = app.plotter.y_ticks.positions((ymin, ymax)) {
+        ax = ax.with_yticks(&positions);
+    }
+    ax = ax
+        .with_xlabel(&app.config.x_title())
+        .with_ylabel(&app.config.y_title())
         .with_legend(true);
 
-    for (_, grp) in app
+    for (gid, grp) in app
         .file_handler
         .groups
         .iter()
@@ -68,34 +103,25 @@ pub fn save_svg(app: &EguiApp, path: &std::path::Path) {
         if !grp.is_plotted {
             continue;
         }
-        for fid in grp.file_ids.iter() {
+        for fid in grp.file_ids_by_z_order(&app.file_handler.registry).iter() {
             // I'll need `file` later for labels.
-            if let Some((cached_data, plot_file)) =
-                app.file_handler
-                    .registry
-                    .get(fid)
-                    .and_then(|file| file.get_cache().map(|cache| (cache, file)))
+            if let Some((plot_data, plot_file)) = app
+                .file_handler
+                .registry
+                .get(fid)
+                .and_then(|file| file.plot_data().map(|data| (data, file)))
             {
                 // Color for current file.
                 let color: String = {
-                    let color_id: i32 = (*fid).into();
-                    super::ui::auto_color(color_id)
+                    let position = super::ui::GroupPosition::of(gid, &grp.file_ids, fid);
+                    super::ui::file_color(*fid, position, app.config.color_mode)
                         .to_hex()
                         .chars()
                         .take(7)
                         .collect()
                 };
 
-                let (scale, x0, y0) = (
-                    plot_file.properties.yscale,
-                    plot_file.properties.xoffset,
-                    plot_file.properties.yoffset,
-                );
-                let label = if !plot_file.properties.alias.is_empty() {
-                    format!("{} ({})", &plot_file.properties.alias, grp.name)
-                } else {
-                    format!("{} ({})", plot_file.file_name(), grp.name)
-                };
+                let label = plot_file.label(&grp.name);
                 log::debug!("plotting line with label {}", label);
 
                 // Downsample to a maximum of 1000 points.
@@ -113,29 +139,336 @@ pub fn save_svg(app: &EguiApp, path: &std::path::Path) {
                 //     .collect();
                 //
 
-                let xs: Vec<_> = cached_data.iter().map(|[x, _]| x + x0).collect();
-                let ymin = cached_data
-                    .iter()
-                    .map(|[_, y]| y)
-                    .reduce(|current_min, yi| if yi < current_min { yi } else { current_min })
-                    .unwrap_or(&0.0);
-                let ys: Vec<_> = cached_data
-                    .iter()
-                    .map(|[_, y]| (y - ymin) * scale + y0 + ymin)
-                    .collect();
-
-                let line = LinePlot::new(&xs, &ys)
-                    .with_color(&color)
-                    .with_linewidth(1.0)
-                    .with_name(&label);
-
-                ax.add_line(line);
+                let shift = shifts.get(fid).copied().unwrap_or_default();
+                // Masked ranges are gaps as in the plot. Only the first
+                // segment is named, so there is one legend entry.
+                let segments = plot_file.split_at_masks(plot_data);
+                for (i, segment) in segments.iter().enumerate() {
+                    let (xs, ys): (Vec<_>, Vec<_>) =
+                        segment.iter().map(|[x, y]| (*x, *y + shift)).unzip();
+
+                    let mut line = LinePlot::new(&xs, &ys)
+                        .with_color(&color)
+                        .with_linewidth(1.0)
+                        .with_linecap(plot_file.properties.line_cap.label())
+                        .with_linejoin(plot_file.properties.line_join.label());
+                    if i == 0 {
+                        line = line.with_name(&label);
+                    }
+
+                    ax.add_line(line);
+                }
             }
         }
     }
     ax.insert_into(&mut fig);
+    fig.render()
+}
 
-    if let Err(err) = file.write_all(&fig.render().into_bytes()) {
-        log::error!("could not write svg file {:?}: {:?}", path, err)
+/// Data points of `data` with x-values within the region bounded by `xmin`
+/// and `xmax` (in any order).
+pub(super) fn region_slice(data: &[[f64; 2]], xmin: f64, xmax: f64) -> Vec<[f64; 2]> {
+    let (xmin, xmax) = (xmin.min(xmax), xmin.max(xmax));
+    data.iter()
+        .filter(|[x, _]| *x >= xmin && *x <= xmax)
+        .copied()
+        .collect()
+}
+
+/// Write the data of all plotted files within the integration region to CSV,
+/// with the export window of the config applied.
+///
+/// If `combined` is true, `path` is the file to write to and the files are
+/// written as pairs of columns side by side. Otherwise, `path` is a directory
+/// in which one file per input is created.
+pub fn save_region_csv(app: &EguiApp, path: &Path, combined: bool) -> Result<(), String> {
+    if combined {
+        return write_file(path, &region_csv(app)?);
+    }
+    for (file_name, contents) in region_csv_files(app)? {
+        write_file(&path.join(file_name), &contents)?;
+    }
+    Ok(())
+}
+
+/// The data within the integration region of all plotted files as pairs of
+/// columns side by side.
+fn region_csv(app: &EguiApp) -> Result<String, String> {
+    let (bounds, slices) = region_slices(app)?;
+    let mut contents = String::new();
+    for (label, area, _) in slices.iter() {
+        region_header(&mut contents, label, bounds, *area);
+    }
+    let columns: Vec<_> = slices.iter().map(|(_, _, data)| data.as_slice()).collect();
+    write_side_by_side(&mut contents, &columns);
+    Ok(contents)
+}
+
+/// Append the rows of `datasets` as pairs of columns side by side, shorter
+/// datasets are padded with empty fields.
+fn write_side_by_side(contents: &mut String, datasets: &[&[[f64; 2]]]) {
+    let nrows = datasets.iter().map(|data| data.len()).max();
+    for i in 0..nrows.unwrap_or_default() {
+        let row: Vec<_> = datasets
+            .iter()
+            .map(|data| match data.get(i) {
+                Some([x, y]) => format!("{x},{y}"),
+                None => ",".to_string(),
+            })
+            .collect();
+        let _ = writeln!(contents, "{}", row.join(","));
+    }
+}
+
+/// Write one CSV per plotted group into the directory `dir`, each with the
+/// plotted data (with offsets, scaling and the export window applied) of the
+/// group's files as pairs of columns side by side, like the combined region
+/// export.
+pub fn save_group_csvs(app: &EguiApp, dir: &Path) -> Result<(), String> {
+    let files = group_csv_files(&app.file_handler, app.config.export_window);
+    if files.is_empty() {
+        return Err("no plotted group with data, nothing to export".into());
+    }
+    for (file_name, contents) in files.iter() {
+        write_file(&dir.join(file_name), contents)?;
+    }
+    log::info!("exported {} group(s) to {dir:?}", files.len());
+    Ok(())
+}
+
+/// The plotted data as one (file name, contents) per plotted group, named
+/// by the group (prefixed with its slot).
+fn group_csv_files(file_handler: &FileHandler, window: WindowFunction) -> Vec<(String, String)> {
+    let mut files = Vec::new();
+    for (gid, grp) in file_handler.groups.iter().enumerate() {
+        let Some(grp) = grp.as_ref().filter(|grp| grp.is_plotted) else {
+            continue;
+        };
+        let mut contents = String::new();
+        let mut datasets = Vec::new();
+        for file in grp
+            .file_ids
+            .iter()
+            .filter_map(|fid| file_handler.registry.get(fid))
+        {
+            if let Some(mut data) = file.plot_data() {
+                window.apply_to_points(&mut data);
+                let _ = writeln!(contents, "# {}", file.label(&grp.name));
+                datasets.push(data);
+            }
+        }
+        if datasets.is_empty() {
+            continue;
+        }
+        let columns: Vec<_> = datasets.iter().map(|data| data.as_slice()).collect();
+        write_side_by_side(&mut contents, &columns);
+        files.push((csv_file_name(gid, &grp.name), contents));
+    }
+    files
+}
+
+/// The data within the integration region as one (file name, contents) per
+/// plotted file.
+fn region_csv_files(app: &EguiApp) -> Result<Vec<(String, String)>, String> {
+    let (bounds, slices) = region_slices(app)?;
+    let mut files = Vec::new();
+    for (i, (label, area, data)) in slices.iter().enumerate() {
+        let mut contents = String::new();
+        region_header(&mut contents, label, bounds, *area);
+        for [x, y] in data {
+            let _ = writeln!(contents, "{x},{y}");
+        }
+        files.push((csv_file_name(i, label), contents));
+    }
+    Ok(files)
+}
+
+/// All plotted data (with offsets, scaling and the export window applied) as
+/// one (file name, contents) per file.
+fn data_csv_files(file_handler: &FileHandler, window: WindowFunction) -> Vec<(String, String)> {
+    let mut files = Vec::new();
+    for grp in file_handler.groups.iter().flatten() {
+        if !grp.is_plotted {
+            continue;
+        }
+        for file in grp
+            .file_ids
+            .iter()
+            .filter_map(|fid| file_handler.registry.get(fid))
+        {
+            if let Some(mut data) = file.plot_data() {
+                window.apply_to_points(&mut data);
+                let label = file.label(&grp.name);
+                let mut contents = format!("# {label}\n");
+                for [x, y] in data {
+                    let _ = writeln!(contents, "{x},{y}");
+                }
+                files.push((csv_file_name(files.len(), &label), contents));
+            }
+        }
+    }
+    files
+}
+
+/// File name for the `i`-th exported file with `label`. The index is
+/// prefixed to keep file names unique if labels repeat.
+fn csv_file_name(i: usize, label: &str) -> String {
+    let file_name: String = label
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{i}_{file_name}.csv")
+}
+
+/// The Gaussian `fits` of the peaks of the file `name` as CSV, with one row
+/// per peak. Poor fits are marked in the last column.
+pub fn peak_fits_csv(name: &str, fits: &[PeakFit]) -> String {
+    let mut contents = format!("# {name}\ncenter,height,fwhm,area,r_squared,poor\n");
+    for fit in fits {
+        let _ = match fit.gaussian {
+            Some(g) => writeln!(
+                contents,
+                "{},{},{},{},{},{}",
+                g.center,
+                g.height,
+                g.fwhm(),
+                g.area(),
+                fit.r_squared,
+                fit.is_poor()
+            ),
+            None => writeln!(contents, "{},,,,,true", fit.x),
+        };
+    }
+    contents
+}
+
+/// Which artifacts to put into an exported bundle.
+#[derive(Clone, Copy, Debug)]
+pub struct BundleContents {
+    pub svg: bool,
+    pub data: bool,
+    pub region: bool,
+}
+
+impl Default for BundleContents {
+    fn default() -> Self {
+        Self {
+            svg: true,
+            data: true,
+            region: false,
+        }
+    }
+}
+
+/// Collect the (file name, contents) of all artifacts selected in `contents`
+/// to be written into a bundle.
+pub fn bundle_entries(
+    app: &EguiApp,
+    contents: &BundleContents,
+) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let mut entries = Vec::new();
+    if contents.svg {
+        entries.push(("plot.svg".to_string(), render_svg(app).into_bytes()));
+    }
+    if contents.data {
+        for (file_name, csv) in data_csv_files(&app.file_handler, app.config.export_window) {
+            entries.push((format!("data/{file_name}"), csv.into_bytes()));
+        }
+    }
+    if contents.region {
+        entries.push(("region.csv".to_string(), region_csv(app)?.into_bytes()));
+    }
+    Ok(entries)
+}
+
+/// Label, integral and data of a plotted file within the integration region.
+type RegionSlice = (String, f64, Vec<[f64; 2]>);
+
+/// The integration region and the slices of all plotted files within it,
+/// with the export window applied to each slice. The integrals are those of
+/// the data as plotted.
+fn region_slices(app: &EguiApp) -> Result<((f64, f64), Vec<RegionSlice>), String> {
+    let Some((left, right)) = app.plotter.current_integral else {
+        return Err("no integration region defined, nothing to export".into());
+    };
+    let local_baseline = app.plotter.integrate_with_local_baseline;
+
+    // Collect (label, area, region data) for each plotted file.
+    let mut slices = Vec::new();
+    for grp in app.file_handler.groups.iter().flatten() {
+        if !grp.is_plotted {
+            continue;
+        }
+        for file in grp
+            .file_ids
+            .iter()
+            .filter_map(|fid| app.file_handler.registry.get(fid))
+        {
+            if let Some(data) = file.plot_data() {
+                let area = file.integrate(left, right, local_baseline);
+                let mut slice = region_slice(&data, left, right);
+                app.config.export_window.apply_to_points(&mut slice);
+                slices.push((file.label(&grp.name), area, slice));
+            }
+        }
+    }
+
+    Ok(((left, right), slices))
+}
+
+fn region_header(contents: &mut String, label: &str, (left, right): (f64, f64), area: f64) {
+    let _ = writeln!(contents, "# {label}");
+    let _ = writeln!(contents, "# integral from {left} to {right} = {area}");
+}
+
+fn write_file(path: &Path, contents: &str) -> Result<(), String> {
+    log::debug!("writing data to {path:?}");
+    std::fs::write(path, contents).map_err(|err| format!("could not write {path:?}: {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::{
+        app::components::{FileID, FileProperties, Group},
+        backend_state::PlotData,
+    };
+
+    #[test]
+    fn test_export_window() {
+        let mut file_handler = FileHandler::default();
+        let points = [[0.0, 2.0], [1.0, 2.0], [2.0, 2.0]];
+        let data = PlotData::from_points(&points, Path::new("flat.csv")).unwrap();
+        let fid: FileID = "0".parse().unwrap();
+        let file = File::from_data("flat.csv".into(), FileProperties::default(), data);
+        file_handler.registry.insert(fid, file);
+        file_handler.groups[0] = Some(Group {
+            name: "flat".into(),
+            file_ids: vec![fid],
+            ..Default::default()
+        });
+
+        let rows = |window| {
+            let files = data_csv_files(&file_handler, window);
+            let (_, contents) = files[0].clone();
+            contents
+                .lines()
+                .skip(1)
+                .map(String::from)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(rows(WindowFunction::Rectangular), ["0,2", "1,2", "2,2"]);
+        assert_eq!(rows(WindowFunction::Hann), ["0,0", "1,2", "2,0"]);
+
+        let groups = group_csv_files(&file_handler, WindowFunction::Hann);
+        assert_eq!(groups[0].1, "# flat.csv (flat)\n0,0\n1,2\n2,0\n");
     }
 }
@@ -1,22 +1,66 @@
 mod logic;
+mod tabs;
 mod ui;
 
-pub use logic::save_svg;
+pub use logic::{
+    bundle_entries, peak_fits_csv, render_svg, save_group_csvs, save_region_csv, save_svg,
+    BundleContents,
+};
+pub use tabs::PlotTabs;
 
 use std::collections::HashMap;
 
-use super::FileID;
+use serde::{Deserialize, Serialize};
+
+use super::{File, FileHandler, FileID};
+use crate::utils::fit::{fit_peaks, PeakFit};
 
 pub struct Plotter {
     /// We use this as a buffer to store egui IDs to correlate them with file
     /// IDs. We need this to detect interactions with plotted files.
     files_plot_ids: HashMap<egui::Id, FileID>,
     selected_fid: Option<FileID>,
+    /// File whose line was under the pointer in the last frame.
+    hovered_fid: Option<FileID>,
     current_plot_bounds: [f64; 4],
+    /// Bounds (xmin, xmax, ymin, ymax) to apply to the plot in the next frame.
+    pending_bounds: Option<[f64; 4]>,
     current_integral: Option<(f64, f64)>,
     integrate_with_local_baseline: bool,
     auto_shift_after_scaling: bool,
+    /// Show the x-axis in reverse direction. Data is then plotted at -x,
+    /// bounds and the integral are still stored in data coordinates.
+    reverse_x: bool,
+    /// Hide the axes and draw a scale bar of `scale_bar_length` (x data
+    /// units, 0 picks a length from the visible range) instead.
+    show_scale_bar: bool,
+    scale_bar_length: f64,
+    /// Draw the data of the selected file as read, before any transform,
+    /// baseline, offset or scaling, beneath its line.
+    show_raw: bool,
+    /// Offset the plotted files vertically from each other (on screen and
+    /// in exports, the data is not changed), see [`Plotter::stack_shifts`].
+    stack_mode: StackMode,
+    /// Offset between consecutive files with [`StackMode::Fixed`].
+    stack_offset: f64,
+    /// Factor on the automatic offsets of [`StackMode::Auto`], 1 makes the
+    /// y-ranges of consecutive files touch.
+    stack_spacing: f64,
+    /// The offsets of the current frame.
+    stack_shifts: HashMap<FileID, f64>,
+    /// Opacity of files that are not selected, animated when the selection
+    /// changes.
+    unselected_opacity: f32,
+    /// Grid lines and tick labels of the x- and y-axis.
+    x_ticks: AxisTicks,
+    y_ticks: AxisTicks,
+    /// Peak fits of the selected file, with its name, as shown in the Fit
+    /// Peaks menu. They are computed when the menu is opened.
+    peak_fits: Option<(String, Vec<PeakFit>)>,
     pub mode: PlotterMode,
+    /// Mode of the last frame, to notice switches however they were made
+    /// (menu, shortcut, palette or session).
+    last_mode: PlotterMode,
 }
 
 impl Plotter {
@@ -24,18 +68,487 @@ impl Plotter {
         Self {
             files_plot_ids: HashMap::with_capacity(10),
             selected_fid: None,
+            hovered_fid: None,
             current_plot_bounds: [0.0, 0.0, 0.0, 0.0],
+            pending_bounds: None,
             current_integral: None,
             mode: PlotterMode::Display,
             // TODO: make this a global option
             integrate_with_local_baseline: true,
             auto_shift_after_scaling: false,
+            reverse_x: false,
+            show_scale_bar: false,
+            scale_bar_length: 0.0,
+            show_raw: false,
+            stack_mode: StackMode::Off,
+            stack_offset: 1.0,
+            stack_spacing: 1.1,
+            stack_shifts: HashMap::new(),
+            unselected_opacity: 1.0,
+            x_ticks: AxisTicks::default(),
+            y_ticks: AxisTicks::default(),
+            peak_fits: None,
+            last_mode: PlotterMode::Display,
+        }
+    }
+
+    /// Start in `mode`, without an integration region (one is created by
+    /// dragging in integrate mode).
+    pub fn with_mode(mut self, mode: PlotterMode) -> Self {
+        self.mode = mode;
+        self.last_mode = mode;
+        self.current_integral = None;
+        self
+    }
+}
+
+impl Plotter {
+    /// The current plot bounds and the selected file, used to detect whether
+    /// the plot changed.
+    pub fn view(&self) -> ([f64; 4], Option<FileID>) {
+        (self.current_plot_bounds, self.selected_fid)
+    }
+
+    /// Map an x-value from data to plot coordinates or back (both are the
+    /// same operation).
+    fn flip_x(&self, x: f64) -> f64 {
+        if self.reverse_x {
+            -x
+        } else {
+            x
+        }
+    }
+
+    /// Map the x-range from data to plot coordinates or back, keeping the
+    /// order (min, max).
+    fn flip_x_range(&self, xmin: f64, xmax: f64) -> (f64, f64) {
+        let (a, b) = (self.flip_x(xmin), self.flip_x(xmax));
+        (a.min(b), a.max(b))
+    }
+
+    /// The tick settings of the x- and y-axis, which are stored in sessions.
+    pub fn ticks(&self) -> [AxisTicks; 2] {
+        [self.x_ticks, self.y_ticks]
+    }
+
+    pub fn set_ticks(&mut self, [x_ticks, y_ticks]: [AxisTicks; 2]) {
+        self.x_ticks = x_ticks;
+        self.y_ticks = y_ticks;
+    }
+
+    /// The selected file and the integration region (if integrating), which
+    /// are stored in sessions.
+    pub fn measurement(&self) -> (Option<FileID>, Option<(f64, f64)>) {
+        let region = self
+            .current_integral
+            .filter(|_| self.mode == PlotterMode::Integrate);
+        (self.selected_fid, region)
+    }
+
+    /// Restore the selection and region returned by
+    /// [`Plotter::measurement`], e.g. from a session. A region switches to
+    /// integrate mode. Files that are not in `file_handler` are not selected,
+    /// files that fail to load are deselected once loading failed (see
+    /// `Plotter::render`).
+    pub fn restore_measurement(
+        &mut self,
+        selected_fid: Option<FileID>,
+        region: Option<(f64, f64)>,
+        file_handler: &FileHandler,
+    ) {
+        self.selected_fid = selected_fid.filter(|fid| file_handler.registry.contains_key(fid));
+        self.current_integral = region;
+        if region.is_some() {
+            self.mode = PlotterMode::Integrate;
+        }
+        // The restored selection belongs to the restored mode.
+        self.last_mode = self.mode;
+    }
+
+    /// Deselect the selected file if the mode was switched since the last
+    /// call and the selection should not be kept across mode switches.
+    fn handle_mode_switch(&mut self, keep_selection: bool) {
+        if self.mode != self.last_mode {
+            if !keep_selection {
+                self.selected_fid = None;
+            }
+            self.last_mode = self.mode;
+        }
+    }
+
+    /// Select a file and, if `extent` is given, zoom the plot to it (with
+    /// some margin).
+    pub fn select_file(&mut self, fid: FileID, extent: Option<[f64; 4]>) {
+        self.selected_fid = Some(fid);
+        if let Some(extent) = extent {
+            self.pending_bounds = Some(with_margin(extent));
+        }
+    }
+
+    /// Show the bounds of the last frame again, e.g. after another plotter
+    /// was shown. A plotter that was not shown yet fits the bounds to the
+    /// data.
+    pub fn resume(&mut self, file_handler: &FileHandler) {
+        if self.current_plot_bounds == [0.0; 4] {
+            self.reset_view(file_handler);
+        } else {
+            self.pending_bounds = Some(self.current_plot_bounds);
+        }
+    }
+
+    /// Fit the bounds (with some margin) to the data of all plotted groups.
+    /// Applied in the next frame.
+    pub fn reset_view(&mut self, file_handler: &FileHandler) {
+        let shifts = self.stack_shifts(file_handler);
+        if let Some(extent) = file_handler.plotted_extent() {
+            // Stacked files extend the y-range.
+            let (low, high) = shifts
+                .values()
+                .fold((0.0, 0.0), |(low, high): (f64, f64), shift| {
+                    (low.min(*shift), high.max(*shift))
+                });
+            let [xmin, xmax, ymin, ymax] = extent;
+            self.pending_bounds = Some(with_margin([xmin, xmax, ymin + low, ymax + high]));
+        }
+    }
+
+    /// Whether files are loaded, but hidden because no group is plotted (and
+    /// there are no inspected files shown either).
+    pub fn all_groups_hidden(file_handler: &FileHandler) -> bool {
+        let mut groups = file_handler
+            .groups
+            .iter()
+            .flatten()
+            .filter(|grp| !grp.file_ids.is_empty())
+            .peekable();
+        groups.peek().is_some()
+            && groups.all(|grp| !grp.is_plotted)
+            && (file_handler.inspected.is_empty() || file_handler.hide_inspected)
+    }
+
+    /// Vertical offsets of the plotted files (in drawing order, groups
+    /// before inspected files) for the [`StackMode`]. The first file is not
+    /// moved, with [`StackMode::Auto`] each further file is moved by the
+    /// distance between the maximum of the file before and its own minimum,
+    /// times the spacing factor.
+    pub fn stack_shifts(&self, file_handler: &FileHandler) -> HashMap<FileID, f64> {
+        if self.stack_mode == StackMode::Off {
+            return HashMap::new();
+        }
+        let mut fids: Vec<_> = file_handler
+            .groups
+            .iter()
+            .flatten()
+            .filter(|grp| grp.is_plotted)
+            .flat_map(|grp| grp.file_ids_by_z_order(&file_handler.registry))
+            .collect();
+        if !file_handler.hide_inspected {
+            fids.extend(file_handler.inspected.iter().copied());
+        }
+        let mut shifts = HashMap::new();
+        let (mut shift, mut previous_max) = (0.0, None);
+        for fid in fids {
+            // Files in several groups are drawn once.
+            if shifts.contains_key(&fid) {
+                continue;
+            }
+            let Some([_, _, ymin, ymax]) = file_handler.registry.get(&fid).and_then(File::extent)
+            else {
+                continue;
+            };
+            if let Some(previous_max) = previous_max {
+                shift += match self.stack_mode {
+                    StackMode::Fixed => self.stack_offset,
+                    _ => self.stack_spacing * (previous_max - ymin),
+                };
+            }
+            previous_max = Some(ymax);
+            shifts.insert(fid, shift);
+        }
+        shifts
+    }
+
+    /// Keep the current x-range, but fit the y-range (with some margin) to
+    /// the data that is visible in it. Applied in the next frame.
+    pub fn fit_y_to_visible(&mut self, file_handler: &FileHandler) {
+        let [xmin, xmax, _, _] = self.current_plot_bounds;
+        if let Some((ymin, ymax)) = file_handler.plotted_y_range_within(xmin, xmax) {
+            // A flat line would result in an empty y-range.
+            let (ymin, ymax) = if ymax > ymin {
+                (ymin, ymax)
+            } else {
+                (ymin - 1.0, ymax + 1.0)
+            };
+            let [_, _, ymin, ymax] = with_margin([xmin, xmax, ymin, ymax]);
+            self.pending_bounds = Some([xmin, xmax, ymin, ymax]);
+        }
+    }
+
+    /// Center the view on the maximum of the selected file, showing
+    /// `window` (a fraction of the x-range of the file) around it. Applied
+    /// in the next frame.
+    pub fn center_on_peak(&mut self, file_handler: &FileHandler, window: f64) {
+        let Some(file) = self
+            .selected_fid
+            .and_then(|fid| file_handler.registry.get(&fid))
+        else {
+            return;
+        };
+        let (Some(data), Some([xmin, xmax, _, _])) = (file.plot_data(), file.extent()) else {
+            return;
+        };
+        let Some([x, _]) = data
+            .into_iter()
+            .filter(|[x, y]| x.is_finite() && y.is_finite())
+            .max_by(|[_, a], [_, b]| a.total_cmp(b))
+        else {
+            return;
+        };
+        let half_width = (xmax - xmin) * window / 2.0;
+        let half_width = if half_width > 0.0 { half_width } else { 1.0 };
+        self.zoom_to(file, x - half_width, x + half_width);
+    }
+
+    /// Move the view to the next (or previous) peak of the selected file
+    /// right (left) of the center of the view, keeping the width of the
+    /// view. Peaks must rise at least `prominence` (a fraction of the
+    /// y-range of the file) above their surroundings. Applied in the next
+    /// frame.
+    pub fn step_peak(&mut self, file_handler: &FileHandler, forward: bool, prominence: f64) {
+        let Some(file) = self
+            .selected_fid
+            .and_then(|fid| file_handler.registry.get(&fid))
+        else {
+            return;
+        };
+        let (Some(data), Some(peaks)) = (file.plot_data(), file.peak_indices(prominence)) else {
+            return;
+        };
+        let [xmin, xmax, _, _] = self.current_plot_bounds;
+        let (center, half_width) = ((xmin + xmax) / 2.0, (xmax - xmin) / 2.0);
+        // Do not get stuck at a peak we centered on before.
+        let tolerance = half_width * 1e-6;
+        let mut xs = peaks.into_iter().map(|i| data[i][0]);
+        let target = if forward {
+            xs.find(|x| *x > center + tolerance)
+        } else {
+            xs.rev().find(|x| *x < center - tolerance)
+        };
+        if let Some(x) = target {
+            self.zoom_to(file, x - half_width, x + half_width);
+        }
+    }
+
+    /// Gaussian fits of the peaks of the selected file (see
+    /// [`Plotter::step_peak`] for `prominence`), with the file name.
+    pub fn selected_peak_fits(
+        &self,
+        file_handler: &FileHandler,
+        prominence: f64,
+    ) -> Option<(String, Vec<PeakFit>)> {
+        let file = file_handler.registry.get(&self.selected_fid?)?;
+        let (mut data, [_, _, ymin, ymax]) = (file.plot_data()?, file.extent()?);
+        data.sort_by(|[a, _], [b, _]| a.total_cmp(b));
+        let fits = fit_peaks(&data, prominence * (ymax - ymin));
+        Some((file.file_name().to_string(), fits))
+    }
+
+    /// Show `xmin..xmax`, with the y-range fitted (with some margin) to the
+    /// data of `file` within it.
+    fn zoom_to(&mut self, file: &File, xmin: f64, xmax: f64) {
+        let Some((ymin, ymax)) = file.y_range_within(xmin, xmax) else {
+            return;
+        };
+        let (ymin, ymax) = if ymax > ymin {
+            (ymin, ymax)
+        } else {
+            (ymin - 1.0, ymax + 1.0)
+        };
+        let [_, _, ymin, ymax] = with_margin([xmin, xmax, ymin, ymax]);
+        self.pending_bounds = Some([xmin, xmax, ymin, ymax]);
+    }
+
+    /// Integral of each plotted file over the visible x-range, together with
+    /// its legend name, ordered by file ID.
+    pub fn view_integrals(&self, file_handler: &FileHandler) -> Vec<(String, f64)> {
+        let [xmin, xmax, _, _] = self.current_plot_bounds;
+        self.integrals_within(file_handler, xmin, xmax, None)
+    }
+
+    /// Integral over the integration region of the selected file or, if no
+    /// file is selected, of each plotted file. Empty without a region.
+    pub fn region_integrals(&self, file_handler: &FileHandler) -> Vec<(String, f64)> {
+        let Some((left, right)) = self.current_integral else {
+            return Vec::new();
+        };
+        self.integrals_within(file_handler, left, right, self.selected_fid)
+    }
+
+    /// Integral of each plotted file with its legend name, ordered by file
+    /// ID. With `only`, just that file is integrated.
+    fn integrals_within(
+        &self,
+        file_handler: &FileHandler,
+        xmin: f64,
+        xmax: f64,
+        only: Option<FileID>,
+    ) -> Vec<(String, f64)> {
+        let mut names: Vec<_> = file_handler
+            .legend_names(true)
+            .into_iter()
+            .filter(|(fid, _)| only.is_none_or(|only| only == *fid))
+            .collect();
+        names.sort_by_key(|(fid, _)| *fid);
+        names
+            .into_iter()
+            .filter_map(|(fid, name)| {
+                let file = file_handler
+                    .registry
+                    .get(&fid)
+                    .filter(|file| file.get_cache().is_some())?;
+                Some((
+                    name,
+                    file.integrate(xmin, xmax, self.integrate_with_local_baseline),
+                ))
+            })
+            .collect()
+    }
+
+    /// Switch between display and integrate mode. Entering integrate mode
+    /// starts a region in the middle of the visible x-range (unless there
+    /// is one), leaving it removes the region. Whether the selection is kept
+    /// is up to `Config::keep_selection_on_mode_switch`.
+    pub fn toggle_integrate(&mut self) {
+        match self.mode {
+            PlotterMode::Display => {
+                self.mode = PlotterMode::Integrate;
+                if self.current_integral.is_none() {
+                    let [xmin, xmax, _, _] = self.current_plot_bounds;
+                    let third = (xmax - xmin) / 3.0;
+                    self.current_integral = Some((xmin + third, xmax - third));
+                }
+            }
+            PlotterMode::Integrate => {
+                self.mode = PlotterMode::Display;
+                self.current_integral = None;
+            }
+        }
+    }
+
+    /// Integrate from `left` to `right`, switching to integrate mode.
+    pub fn set_integration_region(&mut self, left: f64, right: f64) {
+        self.mode = PlotterMode::Integrate;
+        self.current_integral = Some((left, right));
+    }
+
+    /// Set the bounds to `extent` (with some margin) right away, without
+    /// waiting for the next frame. Used when there is no UI.
+    pub fn fit_bounds(&mut self, extent: [f64; 4]) {
+        self.current_plot_bounds = with_margin(extent);
+    }
+}
+
+fn with_margin([xmin, xmax, ymin, ymax]: [f64; 4]) -> [f64; 4] {
+    let (dx, dy) = ((xmax - xmin) * 0.05, (ymax - ymin) * 0.05);
+    [xmin - dx, xmax + dx, ymin - dy, ymax + dy]
+}
+
+/// How the plotted files are offset vertically, e.g. for waterfall plots.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StackMode {
+    #[default]
+    Off,
+    /// By the same offset between each file.
+    Fixed,
+    /// By offsets computed from the y-ranges, so that the files do not
+    /// overlap.
+    Auto,
+}
+
+/// How the ticks (and grid lines) of an axis are placed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TickMode {
+    /// By egui, depending on the zoom.
+    #[default]
+    Auto,
+    /// About `count` ticks at a round spacing (1, 2 or 5 times a power of
+    /// ten) over the visible range.
+    Count,
+    /// At the multiples of `spacing`.
+    Spacing,
+}
+
+/// The ticks of one axis, see [`TickMode`].
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AxisTicks {
+    mode: TickMode,
+    count: usize,
+    spacing: f64,
+}
+
+impl Default for AxisTicks {
+    fn default() -> Self {
+        Self {
+            mode: TickMode::Auto,
+            count: 5,
+            spacing: 1.0,
         }
     }
 }
 
-#[derive(Debug, PartialEq)]
+impl AxisTicks {
+    /// More ticks than this are thinned out (to multiples of the spacing), so
+    /// that zooming out does not produce thousands of grid lines.
+    const MAX_TICKS: f64 = 100.0;
+
+    /// The tick positions within `(min, max)`, `None` to place them
+    /// automatically.
+    fn positions(&self, bounds: (f64, f64)) -> Option<Vec<f64>> {
+        let marks = self.marks(bounds)?;
+        Some(marks.iter().map(|mark| mark.value).collect())
+    }
+
+    /// The grid marks within `(min, max)`, `None` to let egui place them.
+    fn marks(&self, (min, max): (f64, f64)) -> Option<Vec<egui_plot::GridMark>> {
+        let span = max - min;
+        if !span.is_finite() || span <= 0.0 {
+            return None;
+        }
+        let step = match self.mode {
+            TickMode::Auto => return None,
+            TickMode::Count => round_step(span / self.count.max(1) as f64),
+            TickMode::Spacing if self.spacing > 0.0 => {
+                self.spacing * (span / self.spacing / Self::MAX_TICKS).ceil().max(1.0)
+            }
+            TickMode::Spacing => return None,
+        };
+        // Multiplying instead of adding up steps avoids accumulating errors.
+        let first = (min / step).ceil() as i64;
+        let last = (max / step).floor() as i64;
+        Some(
+            (first..=last)
+                .map(|i| egui_plot::GridMark {
+                    value: i as f64 * step,
+                    step_size: step,
+                })
+                .collect(),
+        )
+    }
+}
+
+/// The closest (by ratio) of 1, 2 or 5 times a power of ten to `step`.
+fn round_step(step: f64) -> f64 {
+    let magnitude = 10f64.powf(step.log10().floor());
+    [1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .map(|factor| factor * magnitude)
+        .min_by(|a, b| (a / step).ln().abs().total_cmp(&(b / step).ln().abs()))
+        .expect("there are candidates")
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub enum PlotterMode {
+    #[default]
     Display,
     Integrate,
 }
@@ -48,3 +561,100 @@ impl PlotterMode {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::components::Group;
+
+    #[test]
+    fn test_axis_ticks() {
+        let values = |ticks: AxisTicks, bounds| ticks.positions(bounds);
+        assert_eq!(values(AxisTicks::default(), (0.0, 10.0)), None);
+
+        let count = AxisTicks {
+            mode: TickMode::Count,
+            count: 4,
+            ..Default::default()
+        };
+        assert_eq!(values(count, (0.3, 9.0)), Some(vec![2.0, 4.0, 6.0, 8.0]));
+        assert_eq!(round_step(0.4), 0.5);
+        assert_eq!(round_step(0.3), 0.2);
+        assert_eq!(round_step(200.0), 200.0);
+        assert_eq!(round_step(8.0), 10.0);
+
+        let spacing = AxisTicks {
+            mode: TickMode::Spacing,
+            spacing: 2.5,
+            ..Default::default()
+        };
+        assert_eq!(
+            values(spacing, (-3.0, 5.0)),
+            Some(vec![-2.5, 0.0, 2.5, 5.0])
+        );
+        // Far out, only multiples of the spacing are kept.
+        let marks = spacing.marks((0.0, 1e6)).unwrap();
+        assert!(marks.len() <= 101);
+        assert!(marks.iter().all(|mark| (mark.value / 2.5).fract() == 0.0));
+        assert_eq!(values(spacing, (1.0, 1.0)), None);
+    }
+
+    #[test]
+    fn test_stack_shifts() {
+        let mut file_handler = FileHandler::default();
+        let fids: Vec<FileID> = [[0.0, 2.0], [1.0, 4.0], [-1.0, 1.0]]
+            .iter()
+            .enumerate()
+            .map(|(i, ys)| {
+                let file = File::from_points(&[[0.0, ys[0]], [1.0, ys[1]]]);
+                let fid = i.to_string().parse().unwrap();
+                file_handler.registry.insert(fid, file);
+                fid
+            })
+            .collect();
+        file_handler.groups[0] = Some(Group {
+            file_ids: fids.clone(),
+            ..Default::default()
+        });
+        // A file in two groups is only shifted once.
+        file_handler.groups[1] = Some(Group {
+            file_ids: vec![fids[0]],
+            ..Default::default()
+        });
+        let shifts = |plotter: &Plotter| -> Vec<f64> {
+            let shifts = plotter.stack_shifts(&file_handler);
+            assert_eq!(shifts.len(), fids.len());
+            fids.iter().map(|fid| shifts[fid]).collect()
+        };
+
+        let mut plotter = Plotter::new();
+        assert!(plotter.stack_shifts(&file_handler).is_empty());
+        plotter.stack_mode = StackMode::Fixed;
+        plotter.stack_offset = 0.5;
+        assert_eq!(shifts(&plotter), vec![0.0, 0.5, 1.0]);
+        plotter.stack_mode = StackMode::Auto;
+        plotter.stack_spacing = 1.5;
+        assert_eq!(shifts(&plotter), vec![0.0, 1.5, 9.0]);
+
+        file_handler.groups[0].as_mut().unwrap().is_plotted = false;
+        assert_eq!(
+            plotter.stack_shifts(&file_handler),
+            HashMap::from([(fids[0], 0.0)])
+        );
+    }
+
+    #[test]
+    fn test_selection_on_mode_switch() {
+        let mut plotter = Plotter::new();
+        plotter.select_file(FileID::default(), None);
+        plotter.toggle_integrate();
+        plotter.handle_mode_switch(true);
+        assert_eq!(plotter.selected_fid, Some(FileID::default()));
+        plotter.handle_mode_switch(false);
+        assert_eq!(plotter.selected_fid, Some(FileID::default()));
+
+        plotter.mode = plotter.mode.next();
+        plotter.handle_mode_switch(false);
+        assert_eq!(plotter.selected_fid, None);
+    }
+}
@@ -0,0 +1,205 @@
+use super::Plotter;
+use crate::app::components::FileHandler;
+
+/// The tabs of the plot view, each with its own [`Plotter`] (mode, bounds,
+/// selection) and plotted groups, sharing the files. The app renders the
+/// plotter of the active tab, the other tabs are kept here as they were
+/// left, so that switching does not recompute anything.
+pub struct PlotTabs {
+    tabs: Vec<Tab>,
+    active: usize,
+}
+
+struct Tab {
+    name: String,
+    /// `None` for the active tab, whose state is in use.
+    stored: Option<StoredTab>,
+}
+
+struct StoredTab {
+    plotter: Plotter,
+    /// Whether each group (by ID) is plotted in the tab, groups added since
+    /// the tab was left are plotted.
+    plotted_groups: Vec<bool>,
+}
+
+impl Default for PlotTabs {
+    fn default() -> Self {
+        Self {
+            tabs: vec![Tab {
+                name: "Plot 1".to_string(),
+                stored: None,
+            }],
+            active: 0,
+        }
+    }
+}
+
+impl PlotTabs {
+    /// Make tab `index` the active one: its plotter is swapped into
+    /// `plotter` and its plotted groups applied to `file_handler`.
+    pub fn switch(&mut self, index: usize, plotter: &mut Plotter, file_handler: &mut FileHandler) {
+        if index == self.active || index >= self.tabs.len() {
+            return;
+        }
+        let stored = self.tabs[index]
+            .stored
+            .take()
+            .expect("inactive tabs are stored");
+        let plotted_groups = file_handler
+            .groups
+            .iter()
+            .map(|grp| grp.as_ref().is_some_and(|grp| grp.is_plotted))
+            .collect();
+        for (gid, grp) in file_handler.groups.iter_mut().enumerate() {
+            if let Some(grp) = grp {
+                grp.is_plotted = stored.plotted_groups.get(gid).copied().unwrap_or(true);
+            }
+        }
+        let left = std::mem::replace(plotter, stored.plotter);
+        self.tabs[self.active].stored = Some(StoredTab {
+            plotter: left,
+            plotted_groups,
+        });
+        self.active = index;
+        plotter.resume(file_handler);
+    }
+
+    /// Add a tab showing the currently plotted groups with `new_plotter`,
+    /// and switch to it.
+    pub fn add(
+        &mut self,
+        new_plotter: Plotter,
+        plotter: &mut Plotter,
+        file_handler: &mut FileHandler,
+    ) {
+        let plotted_groups = file_handler
+            .groups
+            .iter()
+            .map(|grp| grp.as_ref().is_some_and(|grp| grp.is_plotted))
+            .collect();
+        let number = (1..)
+            .find(|n| self.tabs.iter().all(|tab| tab.name != format!("Plot {n}")))
+            .expect("there is an unused number");
+        self.tabs.push(Tab {
+            name: format!("Plot {number}"),
+            stored: Some(StoredTab {
+                plotter: new_plotter,
+                plotted_groups,
+            }),
+        });
+        self.switch(self.tabs.len() - 1, plotter, file_handler);
+    }
+
+    /// Close tab `index`, the last tab cannot be closed. Closing the active
+    /// tab switches to its neighbour.
+    pub fn close(&mut self, index: usize, plotter: &mut Plotter, file_handler: &mut FileHandler) {
+        if self.tabs.len() < 2 || index >= self.tabs.len() {
+            return;
+        }
+        if index == self.active {
+            let neighbour = if index > 0 { index - 1 } else { 1 };
+            self.switch(neighbour, plotter, file_handler);
+        }
+        self.tabs.remove(index);
+        if index < self.active {
+            self.active -= 1;
+        }
+    }
+
+    /// Row of tab buttons, right-clicking one renames or closes it.
+    /// `new_plotter` creates the plotter of added tabs.
+    pub fn render(
+        &mut self,
+        plotter: &mut Plotter,
+        file_handler: &mut FileHandler,
+        new_plotter: impl FnOnce() -> Plotter,
+        ui: &mut egui::Ui,
+    ) {
+        let (mut switch, mut close) = (None, None);
+        ui.horizontal(|ui| {
+            let closable = self.tabs.len() > 1;
+            for (i, tab) in self.tabs.iter_mut().enumerate() {
+                let resp = ui.selectable_label(i == self.active, &tab.name);
+                if resp.clicked() {
+                    switch = Some(i);
+                }
+                resp.context_menu(|ui| {
+                    ui.label("Name");
+                    ui.text_edit_singleline(&mut tab.name);
+                    if ui
+                        .add_enabled(closable, egui::Button::new("Close Tab"))
+                        .clicked()
+                    {
+                        close = Some(i);
+                        ui.close_menu();
+                    }
+                });
+            }
+            if ui
+                .small_button("➕")
+                .on_hover_text("add a plot with its own view, selection and plotted groups")
+                .clicked()
+            {
+                self.add(new_plotter(), plotter, file_handler);
+            }
+        });
+        if let Some(index) = switch {
+            self.switch(index, plotter, file_handler);
+        }
+        if let Some(index) = close {
+            self.close(index, plotter, file_handler);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::{components::Group, PlotterMode};
+
+    #[test]
+    fn test_tabs_keep_their_state() {
+        let mut file_handler = FileHandler::default();
+        for (gid, name) in ["a", "b"].iter().enumerate() {
+            file_handler.groups[gid] = Some(Group {
+                name: name.to_string(),
+                ..Default::default()
+            });
+        }
+        let plotted = |file_handler: &FileHandler| -> Vec<bool> {
+            file_handler.groups[..2]
+                .iter()
+                .flatten()
+                .map(|grp| grp.is_plotted)
+                .collect()
+        };
+        let mut tabs = PlotTabs::default();
+        let mut plotter = Plotter::new();
+
+        tabs.add(
+            Plotter::new().with_mode(PlotterMode::Integrate),
+            &mut plotter,
+            &mut file_handler,
+        );
+        assert_eq!((tabs.tabs.len(), tabs.active), (2, 1));
+        assert_eq!(tabs.tabs[1].name, "Plot 2");
+        assert_eq!(plotter.mode, PlotterMode::Integrate);
+        file_handler.groups[1].as_mut().unwrap().is_plotted = false;
+
+        tabs.switch(0, &mut plotter, &mut file_handler);
+        assert_eq!(plotter.mode, PlotterMode::Display);
+        assert_eq!(plotted(&file_handler), [true, true]);
+        tabs.switch(1, &mut plotter, &mut file_handler);
+        assert_eq!(plotter.mode, PlotterMode::Integrate);
+        assert_eq!(plotted(&file_handler), [true, false]);
+
+        // Closing the active tab switches to its neighbour.
+        tabs.close(1, &mut plotter, &mut file_handler);
+        assert_eq!((tabs.tabs.len(), tabs.active), (1, 0));
+        assert_eq!(plotter.mode, PlotterMode::Display);
+        assert_eq!(plotted(&file_handler), [true, true]);
+        tabs.close(0, &mut plotter, &mut file_handler);
+        assert_eq!(tabs.tabs.len(), 1);
+    }
+}
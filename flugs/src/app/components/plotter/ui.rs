@@ -1,151 +1,606 @@
 use egui::Vec2;
 use egui_plot::Legend;
 
-use crate::app::components::{File, FileHandler, FileID};
+use crate::{
+    app::{
+        components::{File, FileHandler, FileID},
+        config::{
+            ColorMode, Config, ConfigOption, DuplicateLegendNames, EmptyAreaAction, ToolbarLayout,
+        },
+        events::{EventQueue, ExportPeakFitsRequested, ExportRegionRequested},
+    },
+    utils::simplify::{self, Reduction},
+    EguiApp,
+};
 
 impl super::Plotter {
     pub fn render(
         &mut self,
+        config: &Config,
         file_handler: &mut FileHandler,
+        event_queue: &mut EventQueue<EguiApp>,
         ui: &mut egui::Ui,
         ctx: &egui::Context,
     ) {
-        // Horizontal stripe of switch buttons enabeling/disabeling groups
-        ui.horizontal(|ui| {
-            for grp in file_handler.groups.iter_mut().filter_map(|x| x.as_mut()) {
-                ui.toggle_value(&mut grp.is_plotted, &grp.name);
+        // A selected file that was removed or failed to load cannot be
+        // manipulated.
+        if self
+            .selected_fid
+            .and_then(|fid| file_handler.registry.get(&fid))
+            .is_none_or(|file| file.load_error().is_some())
+        {
+            self.selected_fid = None;
+        }
+        self.handle_mode_switch(config.keep_selection_on_mode_switch);
+        self.stack_shifts = self.stack_shifts(file_handler);
+
+        // Horizontal stripe of switch buttons enabeling/disabeling groups,
+        // right-click allows to change the layering of the groups. With many
+        // groups, the stripe wraps or scrolls, depending on the config.
+        let mut reorder = None;
+        let stripe = |ui: &mut egui::Ui| {
+            for (gid, grp) in file_handler
+                .groups
+                .iter_mut()
+                .enumerate()
+                .filter_map(|(id, x)| Some(id).zip(x.as_mut()))
+            {
+                // Mark groups with files that failed to load, hovering lists
+                // the files and errors.
+                let failed: Vec<_> = grp
+                    .file_ids
+                    .iter()
+                    .filter_map(|fid| file_handler.registry.get(fid))
+                    .filter_map(|file| Some((file.file_name(), file.load_error()?)))
+                    .collect();
+                let resp = if failed.is_empty() {
+                    ui.toggle_value(&mut grp.is_plotted, &grp.name)
+                } else {
+                    let text = egui::RichText::new(format!("⚠ {}", grp.name))
+                        .color(ui.visuals().warn_fg_color);
+                    ui.toggle_value(&mut grp.is_plotted, text)
+                };
+                let loading = grp
+                    .file_ids
+                    .iter()
+                    .filter_map(|fid| file_handler.registry.get(fid))
+                    .filter(|file| !file.data.is_up_to_date())
+                    .count();
+                let show_details = config.group_tooltips;
+                let resp = if show_details || !failed.is_empty() {
+                    resp.on_hover_ui(|ui| {
+                        if show_details {
+                            let count = grp.file_ids.len();
+                            ui.label(match count {
+                                1 => "1 file".to_string(),
+                                count => format!("{count} files"),
+                            });
+                            if loading > 0 {
+                                ui.label(format!("{loading} loading"));
+                            }
+                        }
+                        if !failed.is_empty() {
+                            ui.label("files that could not be loaded:");
+                            for (name, err) in failed {
+                                ui.label(format!("{name}: {err}"));
+                            }
+                        }
+                    })
+                } else {
+                    resp
+                };
+                resp.context_menu(|ui| {
+                    if ui.button("Bring to Front").clicked() {
+                        reorder = Some((gid, true));
+                        ui.close_menu();
+                    }
+                    if ui.button("Send to Back").clicked() {
+                        reorder = Some((gid, false));
+                        ui.close_menu();
+                    }
+                });
             }
-        });
+            if !file_handler.inspected.is_empty() {
+                let mut show_inspected = !file_handler.hide_inspected;
+                ui.toggle_value(&mut show_inspected, "Inspected")
+                    .on_hover_text("files loaded without adding them to a group");
+                file_handler.hide_inspected = !show_inspected;
+            }
+            ui.separator();
+            if ui
+                .button("Fit Y")
+                .on_hover_text("fit the y-axis to the data within the visible x-range")
+                .clicked()
+            {
+                self.fit_y_to_visible(file_handler);
+            }
+            ui.menu_button("∫ View", |ui| {
+                ui.label("area of each plotted file within the visible x-range:");
+                egui::Grid::new("view integrals")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for (name, area) in self.view_integrals(file_handler) {
+                            ui.label(name);
+                            ui.label(config.format_integral(area));
+                            ui.end_row();
+                        }
+                    });
+            })
+            .response
+            .on_hover_text("integrate all plotted files over the visible x-range");
+            let response = ui.menu_button("Fit Peaks", |ui| {
+                if self.peak_fits.is_none() {
+                    self.peak_fits = self.selected_peak_fits(file_handler, config.peak_prominence);
+                }
+                let Some((name, fits)) = &self.peak_fits else {
+                    ui.label("select a file to fit its peaks");
+                    return;
+                };
+                ui.label(format!("Gaussian fits of the peaks of {name}:"));
+                egui::ScrollArea::vertical()
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("peak fits").striped(true).show(ui, |ui| {
+                            for header in ["center", "height", "FWHM", "area", "R²"] {
+                                ui.strong(header);
+                            }
+                            ui.end_row();
+                            for fit in fits.iter() {
+                                let color = if fit.is_poor() {
+                                    ui.visuals().warn_fg_color
+                                } else {
+                                    ui.visuals().text_color()
+                                };
+                                let cells = match fit.gaussian {
+                                    Some(g) => {
+                                        vec![g.center, g.height, g.fwhm(), g.area(), fit.r_squared]
+                                    }
+                                    None => vec![fit.x],
+                                };
+                                for value in cells.iter() {
+                                    ui.colored_label(color, format!("{value:.4}"));
+                                }
+                                if fit.gaussian.is_none() {
+                                    ui.colored_label(color, "no fit");
+                                }
+                                ui.end_row();
+                            }
+                        });
+                    });
+                if fits.iter().any(|fit| fit.is_poor()) {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        "⚠ highlighted peaks are not well described by a Gaussian",
+                    );
+                }
+                if ui.button("Export CSV").clicked() {
+                    log::debug!("open dialog to select peak fit export path");
+                    let handle = std::thread::spawn(|| {
+                        rfd::FileDialog::new()
+                            .set_file_name("peaks.csv")
+                            .save_file()
+                    });
+                    let event =
+                        ExportPeakFitsRequested::new(name.clone(), fits.clone(), Some(handle));
+                    event_queue.queue_event(Box::new(event));
+                    ui.close_menu();
+                }
+            });
+            if response.inner.is_none() {
+                self.peak_fits = None;
+            }
+            response
+                .response
+                .on_hover_text("fit a Gaussian to each peak of the selected file");
+            ui.toggle_value(&mut self.reverse_x, "Reverse X")
+                .on_hover_text("show the x-axis from high to low values");
+            ui.toggle_value(&mut self.show_raw, "Raw Data")
+                .on_hover_text(
+                    "show the data of the selected file before transforms, offsets and scaling",
+                );
+            ui.menu_button("Stack", |ui| {
+                for mode in super::StackMode::OPTIONS {
+                    ui.radio_value(&mut self.stack_mode, *mode, mode.label());
+                }
+                match self.stack_mode {
+                    super::StackMode::Off => {}
+                    super::StackMode::Fixed => {
+                        ui.add(
+                            egui::DragValue::new(&mut self.stack_offset)
+                                .speed(0.01)
+                                .prefix("offset: "),
+                        );
+                    }
+                    super::StackMode::Auto => {
+                        ui.add(
+                            egui::Slider::new(&mut self.stack_spacing, 0.0..=3.0).text("spacing"),
+                        )
+                        .on_hover_text("1 makes the y-ranges of consecutive files touch");
+                    }
+                }
+            })
+            .response
+            .on_hover_text("offset the plotted files vertically, e.g. for waterfall plots");
+            ui.menu_button("Ticks", |ui| {
+                for (axis, ticks) in [("x", &mut self.x_ticks), ("y", &mut self.y_ticks)] {
+                    ui.strong(format!("{axis}-axis"));
+                    ui.horizontal(|ui| {
+                        for mode in super::TickMode::OPTIONS {
+                            ui.radio_value(&mut ticks.mode, *mode, mode.label());
+                        }
+                    });
+                    match ticks.mode {
+                        super::TickMode::Auto => {}
+                        super::TickMode::Count => {
+                            ui.add(
+                                egui::DragValue::new(&mut ticks.count)
+                                    .range(1..=50)
+                                    .prefix("about: "),
+                            )
+                            .on_hover_text(
+                                "the spacing is rounded to 1, 2 or 5 times a power of ten",
+                            );
+                        }
+                        super::TickMode::Spacing => {
+                            ui.add(
+                                egui::DragValue::new(&mut ticks.spacing)
+                                    .speed(0.1)
+                                    .range(f64::MIN_POSITIVE..=f64::INFINITY)
+                                    .prefix("every: "),
+                            );
+                        }
+                    }
+                }
+            })
+            .response
+            .on_hover_text("place the ticks by count or spacing instead of automatically");
+            ui.toggle_value(&mut self.show_scale_bar, "Scale Bar")
+                .on_hover_text("hide the axes and show a scale bar instead");
+            if self.show_scale_bar {
+                ui.add(
+                    egui::DragValue::new(&mut self.scale_bar_length)
+                        .range(0.0..=f64::INFINITY)
+                        .prefix("length: "),
+                )
+                .on_hover_text("length in units of the x-axis, 0 picks a length automatically");
+            }
+        };
+        match config.toolbar_layout {
+            ToolbarLayout::Wrap => {
+                ui.horizontal_wrapped(stripe);
+            }
+            ToolbarLayout::Scroll => {
+                egui::ScrollArea::horizontal()
+                    .id_salt("group toggles")
+                    .show(ui, |ui| ui.horizontal(stripe));
+            }
+        }
+        if let Some((gid, to_front)) = reorder {
+            file_handler.move_group(gid, to_front);
+        }
+
+        if config.show_navigator {
+            self.navigator(file_handler, ui);
+        }
 
         // These are needed to apply modifications to the selected file.
         let mut spans = (0.0, 0.0);
         let mut drag = Vec2::default();
 
+        // Get modifier input (we need this here already, to disallow the plot
+        // to be panned).
+        let modifiers = ctx.input(|i| [i.modifiers.alt, i.modifiers.ctrl, i.modifiers.shift]);
+        let modifier_down = modifiers.iter().any(|x| *x);
+
         let auto_bounds = self.mode == super::PlotterMode::Display;
-        let allow_drag = self.selected_fid.is_none() && self.mode == super::PlotterMode::Display;
+        // Dragging with the primary button either pans the plot or zooms into
+        // the dragged box, depending on the config. Modifiers take precedence,
+        // since they are used to manipulate the selected file.
+        let box_zoom = config.empty_area_action == EmptyAreaAction::BoxZoom
+            && self.mode == super::PlotterMode::Display
+            && !modifier_down;
+        // The selected file is manipulated by dragging with the configured
+        // button while a modifier is held, in both modes. If that is not the
+        // primary button, panning with the primary button stays possible with
+        // a selection. In integrate mode, the plot is never panned: dragging
+        // without a modifier moves the region, while manipulating it does not
+        // (so the region does not jump when the selection is kept).
+        let manipulate_button = config.manipulate_button.pointer_button();
+        let manipulating = modifier_down && ctx.input(|i| i.pointer.button_down(manipulate_button));
+        let allow_drag = !box_zoom
+            && (self.selected_fid.is_none() || manipulate_button != egui::PointerButton::Primary)
+            && !manipulating
+            && self.mode == super::PlotterMode::Display;
+        let boxed_zoom_button = if box_zoom {
+            egui::PointerButton::Primary
+        } else {
+            egui::PointerButton::Secondary
+        };
 
         self.files_plot_ids.drain();
-        let response = egui_plot::Plot::new("Plot")
+        // Fade the other files in and out when a file is (de)selected.
+        let dim = ctx.animate_bool_with_time(
+            egui::Id::new("dim unselected files"),
+            config.dim_unselected && self.selected_fid.is_some(),
+            0.2,
+        );
+        self.unselected_opacity = 1.0 - 0.6 * dim;
+        let size = config.plot_dimensions(ui.available_size());
+        let mut plot = egui_plot::Plot::new("Plot")
+            .width(size.x)
+            .height(size.y)
             .allow_drag(allow_drag)
+            .boxed_zoom_pointer_button(boxed_zoom_button)
+            .allow_boxed_zoom(!(modifier_down && boxed_zoom_button == manipulate_button))
             .auto_bounds(egui::Vec2b {
                 x: auto_bounds,
                 y: auto_bounds,
             })
             .legend(Legend::default())
-            .show(ui, |plot_ui| {
-                // Context menu, based on current mode.
-                match self.mode {
-                    // In display mode, we show the file properties menu.
-                    super::PlotterMode::Display => {
-                        if let Some(file) = self
-                            .selected_fid
-                            .and_then(|fid| file_handler.registry.get_mut(&fid))
-                        {
-                            plot_ui
-                                .response()
-                                .context_menu(|ui| file.render_property_settings(ui));
-                        }
+            .x_axis_label(config.x_title())
+            .y_axis_label(config.y_title());
+        if self.show_scale_bar {
+            plot = plot.show_axes(false).show_grid(false);
+        }
+        if self.x_ticks.mode != super::TickMode::Auto {
+            let ticks = self.x_ticks;
+            plot = plot.x_grid_spacer(move |input| ticks.marks(input.bounds).unwrap_or_default());
+        }
+        if self.y_ticks.mode != super::TickMode::Auto {
+            let ticks = self.y_ticks;
+            plot = plot.y_grid_spacer(move |input| ticks.marks(input.bounds).unwrap_or_default());
+        }
+        if self.reverse_x {
+            // Data is plotted at -x, so the labels show -x as well.
+            plot = plot
+                .x_axis_formatter(|mark, _range| {
+                    let decimals = (-mark.step_size.log10()).ceil().max(0.0) as usize;
+                    // Adding 0.0 avoids printing "-0".
+                    format!("{:.*}", decimals, -mark.value + 0.0)
+                })
+                .label_formatter(|name, point| {
+                    let xy = format!("x = {:.4}\ny = {:.4}", -point.x + 0.0, point.y);
+                    if name.is_empty() {
+                        xy
+                    } else {
+                        format!("{name}\n{xy}")
                     }
-                    // In integrate mode, we show the integrate menu.
-                    super::PlotterMode::Integrate => {
-                        plot_ui
-                            .response()
-                            .context_menu(|ui| self.integrate_menu(file_handler, ui));
+                });
+        }
+        let response = plot.show(ui, |plot_ui| {
+            // Apply bounds requested from outside, e.g. when jumping to a file.
+            if let Some([xmin, xmax, ymin, ymax]) = self.pending_bounds.take() {
+                let (xmin, xmax) = self.flip_x_range(xmin, xmax);
+                plot_ui.set_plot_bounds(egui_plot::PlotBounds::from_min_max(
+                    [xmin, ymin],
+                    [xmax, ymax],
+                ));
+            }
+
+            if self.show_scale_bar {
+                self.scale_bar(config, plot_ui);
+            }
+
+            // Context menu, based on current mode.
+            match self.mode {
+                // In display mode, we show the file properties menu.
+                super::PlotterMode::Display => {
+                    if let Some(file) = self
+                        .selected_fid
+                        .and_then(|fid| file_handler.registry.get_mut(&fid))
+                    {
+                        let transforms = &file_handler.transforms;
+                        plot_ui.response().context_menu(|ui| {
+                            file.render_property_settings(config, transforms, ui)
+                        });
                     }
                 }
+                // In integrate mode, we show the integrate menu.
+                super::PlotterMode::Integrate => {
+                    plot_ui.response().context_menu(|ui| {
+                        self.integrate_menu(file_handler, config, event_queue, ui)
+                    });
+                }
+            }
 
-                // Plot integration region, if intgrate mode is active.
-                if let super::PlotterMode::Integrate = self.mode {
-                    if let Some((xmin, xmax)) = self.current_integral {
-                        let y = plot_ui.plot_bounds().center().y;
-                        plot_ui.line(
-                            egui_plot::Line::new(vec![[xmin, y], [xmax, y]])
-                                .color(egui::Color32::RED)
-                                .width(3.0),
-                        );
-                    }
+            // Plot integration region, if intgrate mode is active.
+            if let super::PlotterMode::Integrate = self.mode {
+                if let Some((xmin, xmax)) = self.current_integral {
+                    let y = plot_ui.plot_bounds().center().y;
+                    let (xmin, xmax) = (self.flip_x(xmin), self.flip_x(xmax));
+                    plot_ui.line(
+                        egui_plot::Line::new(vec![[xmin, y], [xmax, y]])
+                            .color(egui::Color32::RED)
+                            .width(3.0),
+                    );
+                }
 
-                    // Handle mouse clicks (draging integral area).
-                    //
-                    // Reading this before the if statement is required to avoid a dead lock.
-                    let inside_plot = pointer_inside_plot(plot_ui);
-                    plot_ui.ctx().input(|i| {
-                        if i.pointer.button_down(egui::PointerButton::Primary)
-                            && plot_ui.response().contains_pointer()
-                            && inside_plot
+                // Handle mouse clicks (draging integral area).
+                //
+                // Reading this before the if statement is required to avoid a dead lock.
+                let inside_plot = pointer_inside_plot(plot_ui);
+                plot_ui.ctx().input(|i| {
+                    if !manipulating
+                        && i.pointer.button_down(egui::PointerButton::Primary)
+                        && plot_ui.response().contains_pointer()
+                        && inside_plot
+                    {
+                        if let (Some(origin), Some(current_position)) =
+                            (i.pointer.press_origin(), i.pointer.latest_pos())
                         {
-                            if let (Some(origin), Some(current_position)) = (i.pointer.press_origin(), i.pointer.latest_pos()) {
-                                // Pointer positions are in screen coordinates and must be translated into
-                                // the coordinate system of the plot.
-                                let origin =
-                                    plot_ui.transform().value_from_position(origin).x;
-                                let current_position =
-                                    plot_ui.transform().value_from_position(current_position).x;
-                                self.current_integral = Some((origin, current_position))
-                            }
+                            // Pointer positions are in screen coordinates and must be translated into
+                            // the coordinate system of the plot.
+                            let origin =
+                                self.flip_x(plot_ui.transform().value_from_position(origin).x);
+                            let current_position = self.flip_x(
+                                plot_ui.transform().value_from_position(current_position).x,
+                            );
+                            self.current_integral = Some((origin, current_position))
                         }
-                    });
-                }
+                    }
+                });
+            }
 
-                // Plot files of currently active groups.
-                for (_, grp) in file_handler
-                    .groups
-                    .iter_mut()
-                    .enumerate()
-                    .filter_map(|(id, x)| Some(id).zip(x.as_mut()))
-                {
-                    if !grp.is_plotted {
-                        continue;
+            // Plot files of currently active groups.
+            let disambiguate = config.duplicate_legend_names == DuplicateLegendNames::AppendId;
+            let mut legend_names = file_handler.legend_names(disambiguate);
+            for (gid, grp) in file_handler
+                .groups
+                .iter_mut()
+                .enumerate()
+                .filter_map(|(id, x)| Some(id).zip(x.as_mut()))
+            {
+                if !grp.is_plotted {
+                    continue;
+                }
+                for fid in grp.file_ids_by_z_order(&file_handler.registry).iter() {
+                    if let Some(file) = file_handler
+                        .registry
+                        .get(fid)
+                        .filter(|file| file.get_cache().is_some())
+                    {
+                        let name = legend_names.remove(fid).unwrap_or_default();
+                        let position = GroupPosition::of(gid, &grp.file_ids, fid);
+                        let egui_id = self.plot(fid, position, file, name, config, plot_ui);
+                        self.files_plot_ids.insert(egui_id, *fid);
                     }
-                    for fid in grp.file_ids.iter() {
-                        if let Some(file) = file_handler
-                            .registry
-                            .get(fid)
-                            .filter(|file| file.get_cache().is_some())
-                        {
-                            let egui_id = self.plot(fid, file, &grp.name, plot_ui);
-                            self.files_plot_ids.insert(egui_id, *fid);
-                        }
+                }
+            }
+            // Plot files which are inspected without being in a group.
+            if !file_handler.hide_inspected {
+                for fid in file_handler.inspected.iter() {
+                    if let Some(file) = file_handler
+                        .registry
+                        .get(fid)
+                        .filter(|file| file.get_cache().is_some())
+                    {
+                        let name = legend_names.remove(fid).unwrap_or_default();
+                        let egui_id = self.plot(fid, None, file, name, config, plot_ui);
+                        self.files_plot_ids.insert(egui_id, *fid);
                     }
                 }
-                drag = plot_ui.pointer_coordinate_drag_delta();
-                spans = {
-                    let bounds = plot_ui.plot_bounds();
-                    let xspan = (bounds.max()[0] - bounds.min()[0]).abs();
-                    let yspan = (bounds.max()[1] - bounds.min()[1]).abs();
-                    (xspan, yspan)
-                };
-                self.current_plot_bounds = {
-                    let [xmin, ymin] = plot_ui.plot_bounds().min();
-                    let [xmax, ymax] = plot_ui.plot_bounds().max();
-                    [xmin, xmax, ymin, ymax]
+            }
+
+            // Label the integration region with the integrals, so that they
+            // can be read off while dragging.
+            if let (super::PlotterMode::Integrate, Some((xmin, xmax))) =
+                (self.mode, self.current_integral)
+            {
+                let integrals = self.region_integrals(file_handler);
+                let text = match integrals.as_slice() {
+                    [] => None,
+                    [(_, area)] if self.selected_fid.is_some() => {
+                        Some(format!("∫ = {}", config.format_integral(*area)))
+                    }
+                    integrals => Some(
+                        integrals
+                            .iter()
+                            .map(|(name, area)| {
+                                format!("{name}: {}", config.format_integral(*area))
+                            })
+                            .collect::<Vec<_>>()
+                            .join("\n"),
+                    ),
                 };
+                if let Some(text) = text {
+                    let x = self.flip_x((xmin + xmax) / 2.0);
+                    let y = plot_ui.plot_bounds().center().y;
+                    plot_ui.text(
+                        egui_plot::Text::new([x, y].into(), text)
+                            .color(egui::Color32::RED)
+                            .anchor(egui::Align2::CENTER_BOTTOM),
+                    );
+                }
+            }
+            drag = plot_ui.pointer_coordinate_drag_delta();
+            if self.reverse_x {
+                drag.x = -drag.x;
+            }
+            spans = {
+                let bounds = plot_ui.plot_bounds();
+                let xspan = (bounds.max()[0] - bounds.min()[0]).abs();
+                let yspan = (bounds.max()[1] - bounds.min()[1]).abs();
+                (xspan, yspan)
+            };
+            self.current_plot_bounds = {
+                let [xmin, ymin] = plot_ui.plot_bounds().min();
+                let [xmax, ymax] = plot_ui.plot_bounds().max();
+                let (xmin, xmax) = self.flip_x_range(xmin, xmax);
+                [xmin, xmax, ymin, ymax]
+            };
 
-                // We need to "exfiltrate" the corrent plot bounds
-                // and whether the plot was clicked from this closure.
-                (plot_ui.plot_bounds(), plot_ui.response().clicked())
-            });
+            // A click close to a line selects it as well, not only a click
+            // exactly on it.
+            let clicked = plot_ui.response().clicked();
+            let click_x = clicked
+                .then(|| plot_ui.pointer_coordinate())
+                .flatten()
+                .map(|point| self.flip_x(point.x));
+            let nearest_fid = if clicked {
+                self.nearest_file(file_handler, config.click_tolerance_px, plot_ui)
+            } else {
+                None
+            };
 
-        // Get modifier input (we need this here already, to disallow the plot
-        // to be panned).
-        let modifiers = ctx.input(|i| [i.modifiers.alt, i.modifiers.ctrl, i.modifiers.shift]);
-        let modifier_down = modifiers.iter().any(|x| *x);
-        let plot_clicked = response.inner.1;
+            // We need to "exfiltrate" the corrent plot bounds
+            // and whether the plot was clicked from this closure.
+            (plot_ui.plot_bounds(), clicked, nearest_fid, click_x)
+        });
+
+        if config.hidden_groups_hint && Self::all_groups_hidden(file_handler) {
+            ui.painter().text(
+                response.response.rect.center(),
+                egui::Align2::CENTER_CENTER,
+                "all groups are hidden, enable one above to plot its files",
+                egui::TextStyle::Body.resolve(ui.style()),
+                ui.visuals().weak_text_color(),
+            );
+        }
+
+        // While anchors are picked, clicks add them rather than changing the
+        // selection.
+        let picking_file = self
+            .selected_fid
+            .and_then(|fid| file_handler.registry.get_mut(&fid))
+            .filter(|file| file.picking_anchors && self.mode == super::PlotterMode::Display);
+        self.hovered_fid = response
+            .hovered_plot_item
+            .and_then(|id| self.files_plot_ids.get(&id))
+            .copied();
+        let plot_clicked = match (picking_file, response.inner.3) {
+            (Some(file), Some(x)) if !modifier_down => {
+                file.properties
+                    .baseline_anchors
+                    .push(x - file.properties.xoffset);
+                false
+            }
+            _ => response.inner.1,
+        };
 
         if let Some(hovered_fid) = response
             .hovered_plot_item
             .and_then(|id| self.files_plot_ids.get(&id))
+            .or(response.inner.2.as_ref())
         {
-            // Select file, if its plot was clicked this frame.
+            // Select file, if its plot was clicked this frame. In box zoom
+            // mode, clicking the selected file again deselects it, since
+            // clicking on empty space does not.
             if plot_clicked {
-                self.selected_fid = Some(*hovered_fid);
+                if box_zoom && self.selected_fid == Some(*hovered_fid) {
+                    self.selected_fid = None;
+                } else {
+                    self.selected_fid = Some(*hovered_fid);
+                }
             }
         } else {
             // FIXME: The context menu for file property settings disappears due to this
             // when user tries to click the UI elements
             // If we clicked somewhere and no modifier was pressed, we deselect
             // the currently selected file.
-            if plot_clicked && !modifier_down {
+            if plot_clicked
+                && !modifier_down
+                && config.empty_area_action == EmptyAreaAction::Deselect
+            {
                 self.selected_fid = None;
             }
         }
@@ -155,72 +610,144 @@ impl super::Plotter {
         {
             // `yspan` is needed to determine speed of y-scaling.
             let yspan = response.inner.0.height();
-            let should_modify = modifier_down && drag.length() > 0.0;
+            let should_modify = manipulating && drag.length() > 0.0;
             if should_modify {
                 self.manipulate_file(selected_file, modifiers, drag, yspan);
             }
         }
     }
 
+    /// Plot `file`, `position` is where it is in its group (`None` for
+    /// inspected files), which may determine its color.
     fn plot(
         &self,
         fid: &FileID,
+        position: Option<GroupPosition>,
         file: &File,
-        group_name: &str,
+        name: String,
+        config: &Config,
         plot_iu: &mut egui_plot::PlotUi,
     ) -> egui::Id {
-        if let Some(data) = file.get_cache() {
-            let ymin = data
-                .iter()
-                .map(|[_, y]| y)
-                .reduce(|current_min, yi| if yi < current_min { yi } else { current_min })
-                .unwrap_or(&0.0);
-            // Apply custom shifting/scaling to data.
-            let data: Vec<[f64; 2]> = data
-                .iter()
-                .map(|[x, y]| {
-                    [
-                        x + file.properties.xoffset,
-                        (y - ymin) * file.properties.yscale + file.properties.yoffset + ymin,
-                    ]
-                })
-                .collect();
-
+        if let Some(data) = file.plot_data() {
             // Plot the data.
-            let color = auto_color(Into::<i32>::into(*fid));
-            let width = if self.selected_fid.is_some_and(|sfid| sfid == *fid) {
-                2.5
+            let is_selected = self.selected_fid.is_some_and(|sfid| sfid == *fid);
+            let color = file_color(*fid, position, config.color_mode);
+            // The line under the pointer is highlighted, so that it is clear
+            // which file a click selects.
+            let highlight = if !is_selected && self.hovered_fid == Some(*fid) {
+                config.hover_highlight.clamp(0.0, 1.0)
             } else {
-                1.0
+                0.0
             };
-            let name = if file.properties.alias.is_empty() {
-                format!("{} ({})", file.file_name(), group_name)
+            let color = if is_selected {
+                color
+            } else if highlight > 0.0 {
+                color.lerp_to_gamma(egui::Color32::WHITE, 0.5 * highlight)
             } else {
-                format!("{} ({})", file.properties.alias, group_name)
+                color.gamma_multiply(self.unselected_opacity)
             };
-            let egui_id = name.clone().into();
-            plot_iu.line(
-                egui_plot::Line::new(data.to_owned())
-                    .color(color)
-                    .width(width)
-                    .name(name)
-                    .id(egui_id),
-            );
+            let width = if is_selected {
+                2.5
+            } else {
+                1.0 + 1.5 * highlight
+            };
+            let egui_id = plot_id(fid);
+            let shift = self.stack_shifts.get(fid).copied().unwrap_or_default();
+            let flip = |data: &[[f64; 2]]| -> Vec<[f64; 2]> {
+                data.iter()
+                    .map(|[x, y]| [self.flip_x(*x), *y + shift])
+                    .collect()
+            };
+            // The raw data has no ID, so that it cannot be selected.
+            if let Some(raw) = file.raw_data().filter(|_| is_selected && self.show_raw) {
+                plot_iu.line(
+                    egui_plot::Line::new(flip(&raw))
+                        .color(color.gamma_multiply(0.5))
+                        .width(1.0)
+                        .style(egui_plot::LineStyle::dotted_dense()),
+                );
+            }
+            // Markers, extrema and the integral still use all points.
+            let line_data = match file.properties.reduction.resolve(config.simplify_tolerance) {
+                Reduction::None => data.clone(),
+                Reduction::Adaptive => {
+                    // While the bounds follow the data, all of it is in view.
+                    let [xmin, xmax, ..] =
+                        if plot_iu.auto_bounds().x || self.current_plot_bounds == [0.0; 4] {
+                            [f64::NEG_INFINITY, f64::INFINITY, 0.0, 0.0]
+                        } else {
+                            self.current_plot_bounds
+                        };
+                    let width = plot_iu.transform().frame().width() as f64;
+                    let max_points = (config.adaptive_points_per_px * width).max(3.0) as usize;
+                    simplify::adaptive(&data, xmin, xmax, max_points)
+                        .into_iter()
+                        .map(|i| data[i])
+                        .collect()
+                }
+                reduction => file
+                    .reduced_plot_data(reduction)
+                    .unwrap_or_else(|| data.clone()),
+            };
+            // Segments share the name, so there is one legend entry.
+            for segment in file.split_at_masks(line_data) {
+                plot_iu.line(
+                    egui_plot::Line::new(flip(&segment))
+                        .color(color)
+                        .width(width)
+                        .name(&name)
+                        .id(egui_id),
+                );
+            }
+
+            if file.properties.show_markers {
+                let max_markers = file.properties.max_markers;
+                let step = if max_markers > 0 {
+                    data.len().div_ceil(max_markers).max(1)
+                } else {
+                    1
+                };
+                let markers: Vec<_> = data
+                    .iter()
+                    .step_by(step)
+                    .map(|[x, y]| [self.flip_x(*x), *y + shift])
+                    .collect();
+                plot_iu.points(
+                    egui_plot::Points::new(markers)
+                        .color(color)
+                        .radius(width + 1.5)
+                        .id(egui_id),
+                );
+            }
+
+            if config.show_extrema {
+                plot_extrema(&data, color, self.reverse_x, shift, plot_iu);
+            }
+
+            if file.properties.label_peaks {
+                self.plot_peak_labels(file, &data, shift, config, color, plot_iu);
+            }
+
+            if let Some(baseline) = file.baseline().filter(|_| is_selected) {
+                plot_iu.line(
+                    egui_plot::Line::new(flip(&baseline.curve))
+                        .color(color)
+                        .style(egui_plot::LineStyle::dashed_loose())
+                        .id(egui_id),
+                );
+                plot_iu.points(
+                    egui_plot::Points::new(flip(&baseline.anchors))
+                        .color(color)
+                        .shape(egui_plot::MarkerShape::Diamond)
+                        .radius(5.0)
+                        .id(egui_id),
+                );
+            }
 
             if self.mode == super::PlotterMode::Integrate {
                 if let Some((xmin, xmax)) = self.current_integral {
-                    let (xmin, xmax) = (xmin.min(xmax), xmin.max(xmax));
                     // Plot area under curve.
-                    let mut plot_data = data
-                        .iter()
-                        .filter_map(|[x, y]| {
-                            if x >= &xmin && x <= &xmax {
-                                Some([*x, *y])
-                            } else {
-                                None
-                            }
-                        })
-                        .collect::<Vec<[f64; 2]>>();
+                    let mut plot_data = super::logic::region_slice(&data, xmin, xmax);
                     // TODO: how to plot area under curve down to a local baseline.
                     if let (Some([x0, y0]), Some([x1, y1])) = (plot_data.first(), plot_data.last()) {
                         // Local baseline.
@@ -232,7 +759,7 @@ impl super::Plotter {
                         plot_data.extend(line_data);
                     }
                     plot_iu.line(
-                        egui_plot::Line::new(plot_data)
+                        egui_plot::Line::new(flip(&plot_data))
                             .color(egui::Color32::WHITE)
                             .width(width)
                             .id(egui_id),
@@ -251,7 +778,135 @@ impl super::Plotter {
         }
     }
 
-    pub fn integrate_menu(&mut self, file_handler: &mut FileHandler, ui: &mut egui::Ui) {
+    /// The plotted file with the line closest to the pointer, if it is within
+    /// `tolerance` pixels.
+    fn nearest_file(
+        &self,
+        file_handler: &FileHandler,
+        tolerance: f32,
+        plot_ui: &egui_plot::PlotUi,
+    ) -> Option<FileID> {
+        let pointer = plot_ui.response().hover_pos()?;
+        let transform = plot_ui.transform();
+        self.files_plot_ids
+            .values()
+            .filter_map(|fid| {
+                let data = file_handler.registry.get(fid)?.plot_data()?;
+                let shift = self.stack_shifts.get(fid).copied().unwrap_or_default();
+                let points: Vec<_> = data
+                    .iter()
+                    .map(|[x, y]| {
+                        let point = egui_plot::PlotPoint::new(self.flip_x(*x), *y + shift);
+                        transform.position_from_point(&point)
+                    })
+                    .collect();
+                Some((*fid, distance_to_line(pointer, &points)?))
+            })
+            .filter(|(_, distance)| *distance <= tolerance)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(fid, _)| fid)
+    }
+
+    /// Draw a horizontal scale bar in the lower left corner of the plot,
+    /// labeled with its length in x data units.
+    fn scale_bar(&self, config: &Config, plot_ui: &mut egui_plot::PlotUi) {
+        let bounds = plot_ui.plot_bounds();
+        let (width, height) = (bounds.width(), bounds.height());
+        let length = if self.scale_bar_length > 0.0 {
+            self.scale_bar_length
+        } else {
+            nice_length(0.2 * width)
+        };
+        let [xmin, ymin] = bounds.min();
+        let (x0, y) = (xmin + 0.05 * width, ymin + 0.08 * height);
+        let color = plot_ui.ctx().style().visuals.text_color();
+        plot_ui.line(
+            egui_plot::Line::new(vec![[x0, y], [x0 + length, y]])
+                .color(color)
+                .width(2.0),
+        );
+        let label = if config.x_unit.is_empty() {
+            format!("{length}")
+        } else {
+            format!("{length} {}", config.x_unit)
+        };
+        plot_ui.text(
+            egui_plot::Text::new(
+                egui_plot::PlotPoint::new(x0 + 0.5 * length, y + 0.02 * height),
+                label,
+            )
+            .color(color)
+            .anchor(egui::Align2::CENTER_BOTTOM),
+        );
+    }
+
+    /// Draw a strip representing the full x-extent of all plotted data, with
+    /// a rectangle marking the currently visible range. Dragging or clicking
+    /// the strip pans the plot.
+    fn navigator(&mut self, file_handler: &FileHandler, ui: &mut egui::Ui) {
+        let extent = file_handler
+            .plotted_extent()
+            .map(|[xmin, xmax, _, _]| (xmin, xmax));
+
+        let desired_size = egui::vec2(ui.available_width(), 16.0);
+        let (rect, resp) = ui.allocate_exact_size(desired_size, egui::Sense::click_and_drag());
+        let painter = ui.painter_at(rect);
+        let visuals = ui.visuals();
+        painter.rect_filled(rect, 2.0, visuals.extreme_bg_color);
+
+        let [xmin, xmax, ymin, ymax] = self.current_plot_bounds;
+        let Some((data_min, data_max)) = extent else {
+            return;
+        };
+        // Cover the visible range as well, if we zoomed out beyond the data.
+        let (full_min, full_max) = (data_min.min(xmin), data_max.max(xmax));
+        let full_span = full_max - full_min;
+        if !full_span.is_normal() {
+            return;
+        }
+        // Fraction of the strip width from the left, considering the direction
+        // of the x-axis (the mapping is its own inverse).
+        let reverse_x = self.reverse_x;
+        let frac = |f: f64| if reverse_x { 1.0 - f } else { f };
+        let to_screen =
+            |x: f64| rect.left() + frac((x - full_min) / full_span) as f32 * rect.width();
+
+        let (left, right) = (to_screen(xmin), to_screen(xmax));
+        let view_rect =
+            egui::Rect::from_x_y_ranges(left.min(right)..=left.max(right), rect.y_range());
+        painter.rect_filled(
+            view_rect,
+            2.0,
+            visuals.selection.bg_fill.gamma_multiply(0.5),
+        );
+        painter.rect_stroke(view_rect, 2.0, visuals.selection.stroke);
+
+        // Pan the plot, so that the current range follows the pointer.
+        let shift = if resp.dragged() {
+            let dx = resp.drag_delta().x as f64 / rect.width() as f64 * full_span;
+            Some(if reverse_x { -dx } else { dx })
+        } else if resp.clicked() {
+            resp.interact_pointer_pos().map(|pos| {
+                let x = full_min + frac(((pos.x - rect.left()) / rect.width()) as f64) * full_span;
+                x - (xmin + xmax) * 0.5
+            })
+        } else {
+            None
+        };
+        if let Some(dx) = shift.filter(|dx| *dx != 0.0) {
+            self.current_plot_bounds = [xmin + dx, xmax + dx, ymin, ymax];
+            self.pending_bounds = Some(self.current_plot_bounds);
+        }
+        resp.on_hover_cursor(egui::CursorIcon::Grab);
+    }
+
+    pub fn integrate_menu(
+        &mut self,
+        file_handler: &mut FileHandler,
+        config: &Config,
+        event_queue: &mut EventQueue<EguiApp>,
+        ui: &mut egui::Ui,
+    ) {
         ui.set_min_width(200.0);
 
         // UI to set integral bounds.
@@ -270,15 +925,67 @@ impl super::Plotter {
             &mut self.auto_shift_after_scaling,
             "Align curves after scaling?",
         );
-        // Integration window.
+        // Integration window, the bounds can be typed after clicking them.
+        // They are swapped once editing is done if left > right, not while
+        // dragging, which would switch the dragged bound.
+        if self.current_integral.is_none()
+            && ui
+                .button("Set Bounds")
+                .on_hover_text("start a region in the middle of the view, to type its bounds")
+                .clicked()
+        {
+            let [xmin, xmax, _, _] = self.current_plot_bounds;
+            let third = (xmax - xmin) / 3.0;
+            self.current_integral = Some((xmin + third, xmax - third));
+        }
         if let Some((a, b)) = self.current_integral.iter_mut().next() {
             let [xmin, xmax, _, _] = self.current_plot_bounds;
+            let speed = (xmax - xmin).abs() / 500.0;
             ui.label("Left bound");
-            ui.add(egui::DragValue::new(a).speed((xmax - xmin).abs() / 500.0))
+            let left = ui
+                .add(egui::DragValue::new(a).speed(speed))
                 .on_hover_cursor(egui::CursorIcon::Text);
             ui.label("Right bound");
-            ui.add(egui::DragValue::new(b).speed((xmax - xmin).abs() / 500.0))
+            let right = ui
+                .add(egui::DragValue::new(b).speed(speed))
                 .on_hover_cursor(egui::CursorIcon::Text);
+            let edited = [left, right]
+                .iter()
+                .any(|response| response.lost_focus() || response.drag_stopped());
+            if edited && *a > *b {
+                std::mem::swap(a, b);
+            }
+
+            ui.separator();
+
+            // UI to export the data within the region.
+            ui.heading("Export Region");
+            ui.horizontal(|ui| {
+                if ui
+                    .button("Combined")
+                    .on_hover_text("export all plotted datasets into one file")
+                    .clicked()
+                {
+                    log::debug!("open dialog to select region export path");
+                    let handle = std::thread::spawn(|| {
+                        rfd::FileDialog::new()
+                            .set_file_name("region.csv")
+                            .save_file()
+                    });
+                    let event = ExportRegionRequested::new(true, Some(handle));
+                    event_queue.queue_event(Box::new(event));
+                }
+                if ui
+                    .button("Per File")
+                    .on_hover_text("export each plotted dataset into a separate file")
+                    .clicked()
+                {
+                    log::debug!("open dialog to select region export directory");
+                    let handle = std::thread::spawn(|| rfd::FileDialog::new().pick_folder());
+                    let event = ExportRegionRequested::new(false, Some(handle));
+                    event_queue.queue_event(Box::new(event));
+                }
+            });
 
             ui.separator();
 
@@ -317,12 +1024,13 @@ impl super::Plotter {
                         ui.horizontal(|ui| {
                             let scale_button = egui::Button::new(label_text.clone()).truncate();
                             let area = file.integrate(*a, *b, self.integrate_with_local_baseline);
+                            let area_text = config.format_integral(area);
                             if scale_all
                                 || ui
                                     .add(scale_button)
                                     .on_hover_ui(|ui| {
                                         ui.label(label_text);
-                                        ui.label(format!("(area = {area})"));
+                                        ui.label(format!("(area: {area_text})"));
                                     })
                                     .clicked()
                             {
@@ -332,10 +1040,7 @@ impl super::Plotter {
                                 if self.auto_shift_after_scaling {
                                     let offset = file.local_minimum(*a, *b, false);
                                     // dbg!(offset);
-                                    let ymin = match file.data.value() {
-                                        Ok(data) => data.ymin().unwrap_or_default(),
-                                        Err(_) => 0.0,
-                                    };
+                                    let ymin = file.ymin().unwrap_or_default();
                                     // Because we add/subtract ymin from the
                                     // data when plotting (see plot method), we
                                     // have to incorporate it here as well.
@@ -343,6 +1048,7 @@ impl super::Plotter {
                                         -(offset - ymin) * file.properties.yscale - ymin;
                                 }
                             }
+                            ui.label(format!("area: {area_text}"));
                         });
                     }
                 }
@@ -351,13 +1057,162 @@ impl super::Plotter {
             self.current_integral = Some((0.0, 0.0));
         }
     }
+
+    /// Label the visible peaks of `data` (the plotted data of `file`, drawn
+    /// `shift` higher) above them. Labels that would overlap one placed
+    /// before are moved up until they fit.
+    fn plot_peak_labels(
+        &self,
+        file: &File,
+        data: &[[f64; 2]],
+        shift: f64,
+        config: &Config,
+        color: egui::Color32,
+        plot_ui: &mut egui_plot::PlotUi,
+    ) {
+        let Some(peaks) = file.peak_indices(config.peak_prominence) else {
+            return;
+        };
+        let bounds = plot_ui.plot_bounds();
+        let (xmin, xmax) = self.flip_x_range(bounds.min()[0], bounds.max()[0]);
+        let font = egui::TextStyle::Body.resolve(plot_ui.ctx().style().as_ref());
+        let mut placed: Vec<egui::Rect> = Vec::new();
+        for [x, y] in peaks.into_iter().map(|i| data[i]) {
+            if !(xmin..=xmax).contains(&x) {
+                continue;
+            }
+            let text = config.peak_label(&file.properties.peak_label, x, y);
+            let size = plot_ui.ctx().fonts(|fonts| {
+                fonts
+                    .layout_no_wrap(text.clone(), font.clone(), color)
+                    .size()
+            });
+            let peak = plot_ui
+                .transform()
+                .position_from_point(&egui_plot::PlotPoint::new(self.flip_x(x), y + shift));
+            let mut rect = egui::Rect::from_center_size(
+                peak - egui::vec2(0.0, config.peak_label_offset + size.y / 2.0),
+                size,
+            );
+            // Give up on crowded areas rather than stacking forever.
+            for _ in 0..20 {
+                if !placed.iter().any(|other| other.intersects(rect)) {
+                    break;
+                }
+                rect = rect.translate(egui::vec2(0.0, -size.y));
+            }
+            placed.push(rect);
+            let position = plot_ui
+                .transform()
+                .value_from_position(rect.center_bottom());
+            plot_ui.text(
+                egui_plot::Text::new(position, text)
+                    .color(color)
+                    .anchor(egui::Align2::CENTER_BOTTOM),
+            );
+        }
+    }
+}
+
+/// ID of the plot items of a file. It is derived from the file ID rather than
+/// the legend name, which files can share.
+fn plot_id(fid: &FileID) -> egui::Id {
+    egui::Id::new(("plotted file", fid))
+}
+
+/// Mark the minimum and maximum of `data` (drawn `shift` higher) with their
+/// position.
+fn plot_extrema(
+    data: &[[f64; 2]],
+    color: egui::Color32,
+    reverse_x: bool,
+    shift: f64,
+    plot_ui: &mut egui_plot::PlotUi,
+) {
+    let finite = || data.iter().filter(|[x, y]| x.is_finite() && y.is_finite());
+    let (Some(min), Some(max)) = (
+        finite().min_by(|a, b| a[1].total_cmp(&b[1])),
+        finite().max_by(|a, b| a[1].total_cmp(&b[1])),
+    ) else {
+        return;
+    };
+    let position = |[x, y]: [f64; 2]| {
+        let y = y + shift;
+        if reverse_x {
+            [-x, y]
+        } else {
+            [x, y]
+        }
+    };
+    plot_ui.points(
+        egui_plot::Points::new(vec![position(*min), position(*max)])
+            .color(color)
+            .radius(4.0),
+    );
+    for ([x, y], anchor) in [
+        (min, egui::Align2::CENTER_TOP),
+        (max, egui::Align2::CENTER_BOTTOM),
+    ] {
+        plot_ui.text(
+            egui_plot::Text::new(position([*x, *y]).into(), format!("({x:.4}, {y:.4})"))
+                .color(color)
+                .anchor(anchor),
+        );
+    }
 }
 
 pub fn auto_color(color_idx: i32) -> egui::Color32 {
-    // analog to egui_plot
-    let golden_ratio = (5.0_f32.sqrt() - 1.0) / 2.0; // 0.61803398875
-    let h = color_idx as f32 * golden_ratio;
-    egui::epaint::Hsva::new(h, 0.85, 0.5, 1.0).into()
+    egui::epaint::Hsva::new(auto_hue(color_idx), 0.85, 0.5, 1.0).into()
+}
+
+/// Position of a file within its group, see [`file_color`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GroupPosition {
+    pub gid: usize,
+    /// Index of the file among the `count` files of the group.
+    pub index: usize,
+    pub count: usize,
+}
+
+impl GroupPosition {
+    /// Position of `fid` within `file_ids` of group `gid`, `None` if it is
+    /// not in there.
+    pub fn of(gid: usize, file_ids: &[FileID], fid: &FileID) -> Option<Self> {
+        Some(Self {
+            gid,
+            index: file_ids.iter().position(|other| other == fid)?,
+            count: file_ids.len(),
+        })
+    }
+}
+
+/// Color of the file `fid` at `position` with `mode`. Files without group
+/// are colored by file ID in any mode.
+pub fn file_color(fid: FileID, position: Option<GroupPosition>, mode: ColorMode) -> egui::Color32 {
+    match (mode, position) {
+        (ColorMode::Group, Some(position)) => {
+            // Spread the files of a group from dark to light, a single file
+            // gets the lightness of the colors by file.
+            let value = if position.count > 1 {
+                0.3 + 0.5 * position.index as f32 / (position.count - 1) as f32
+            } else {
+                0.5
+            };
+            egui::epaint::Hsva::new(auto_hue(position.gid as i32), 0.85, value, 1.0).into()
+        }
+        _ => auto_color(fid.into()),
+    }
+}
+
+/// Hue in [0, 1) for the color with index `color_idx`.
+///
+/// Consecutive indices are spread by the golden ratio (analog to egui_plot),
+/// which keeps hues well separated. The fractional part is computed in double
+/// precision, because in single precision the product loses its fractional
+/// digits for large indices and hues start to coincide.
+fn auto_hue(color_idx: i32) -> f32 {
+    let golden_ratio = (5.0_f64.sqrt() - 1.0) / 2.0; // 0.61803398875
+    (color_idx as f64 * golden_ratio).rem_euclid(1.0) as f32
 }
 
 fn pointer_inside_plot(plot_ui: &egui_plot::PlotUi) -> bool {
@@ -373,3 +1228,124 @@ fn pointer_inside_plot(plot_ui: &egui_plot::PlotUi) -> bool {
     }
     false
 }
+
+/// Smallest distance of `pos` to the line through `points` (in screen
+/// coordinates), or `None` if there are no points.
+fn distance_to_line(pos: egui::Pos2, points: &[egui::Pos2]) -> Option<f32> {
+    let distance_to_segment = |a: egui::Pos2, b: egui::Pos2| {
+        let ab = b - a;
+        let t = if ab.length_sq() > 0.0 {
+            ((pos - a).dot(ab) / ab.length_sq()).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        pos.distance(a + t * ab)
+    };
+    match points {
+        [] => None,
+        [point] => Some(pos.distance(*point)),
+        _ => points
+            .windows(2)
+            .map(|segment| distance_to_segment(segment[0], segment[1]))
+            .filter(|distance| distance.is_finite())
+            .min_by(f32::total_cmp),
+    }
+}
+
+/// The largest length of 1, 2 or 5 times a power of ten that does not exceed
+/// `max_length`.
+fn nice_length(max_length: f64) -> f64 {
+    if !(max_length > 0.0 && max_length.is_finite()) {
+        return 1.0;
+    }
+    let magnitude = 10f64.powf(max_length.log10().floor());
+    [5.0, 2.0, 1.0]
+        .into_iter()
+        .map(|factor| factor * magnitude)
+        .find(|length| *length <= max_length)
+        .unwrap_or(magnitude)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Smallest distance between any two hues on the color circle.
+    fn min_hue_separation(hues: &[f32]) -> f32 {
+        let mut min = 1.0_f32;
+        for (i, a) in hues.iter().enumerate() {
+            for b in hues.iter().skip(i + 1) {
+                let d = (a - b).abs();
+                min = min.min(d.min(1.0 - d));
+            }
+        }
+        min
+    }
+
+    #[test]
+    fn test_auto_color_hue_separation() {
+        let n = 16;
+        for offset in [0, 1_000, 1_000_000, i32::MAX - n] {
+            let hues: Vec<_> = (offset..offset + n).map(auto_hue).collect();
+            // For the golden ratio sequence, the smallest gap among n hues is
+            // a bit above 1 / (2 * n).
+            assert!(
+                min_hue_separation(&hues) > 0.5 / n as f32,
+                "hues too close for IDs starting at {offset}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_auto_color_matches_previous_colors() {
+        let golden_ratio = (5.0_f32.sqrt() - 1.0) / 2.0;
+        for idx in 0..10 {
+            let previous: egui::Color32 =
+                egui::epaint::Hsva::new(idx as f32 * golden_ratio, 0.85, 0.5, 1.0).into();
+            assert_eq!(auto_color(idx), previous);
+        }
+    }
+
+    #[test]
+    fn test_file_color_by_group() {
+        let fid = FileID::default();
+        let at = |gid, index, count| Some(GroupPosition { gid, index, count });
+        let hsva = |color| egui::epaint::Hsva::from(color);
+        // Without a group, or by file, the file ID decides.
+        assert_eq!(file_color(fid, None, ColorMode::Group), auto_color(0));
+        assert_eq!(file_color(fid, at(3, 0, 2), ColorMode::File), auto_color(0));
+
+        let first = hsva(file_color(fid, at(3, 0, 3), ColorMode::Group));
+        let last = hsva(file_color(fid, at(3, 2, 3), ColorMode::Group));
+        // The hues differ slightly from rounding to 8-bit channels.
+        assert!((first.h - last.h).abs() < 0.02);
+        assert!(first.v < last.v);
+        let other = hsva(file_color(fid, at(4, 0, 3), ColorMode::Group));
+        assert!((first.h - other.h).abs() > 0.1);
+        assert_eq!(file_color(fid, at(3, 0, 1), ColorMode::Group), {
+            egui::epaint::Hsva::new(auto_hue(3), 0.85, 0.5, 1.0).into()
+        });
+    }
+
+    #[test]
+    fn test_same_named_files_have_distinct_plot_ids() {
+        let (mut request_tx, _request_rx) = std::sync::mpsc::channel();
+        let mut file_handler = FileHandler::default();
+        for path in ["a/data.csv", "b/data.csv"] {
+            file_handler.add_file(std::path::Path::new(path), 0, &mut request_tx);
+        }
+        // Merged legend names are identical, the plot IDs must still differ.
+        let names = file_handler.legend_names(false);
+        assert!(names.values().all(|name| name == "data.csv (Group 0)"));
+
+        // Both files can be looked up from their plot items.
+        let mut plotter = super::super::Plotter::new();
+        for fid in names.keys() {
+            plotter.files_plot_ids.insert(plot_id(fid), *fid);
+        }
+        assert_eq!(plotter.files_plot_ids.len(), 2);
+        for fid in names.keys() {
+            assert_eq!(plotter.files_plot_ids.get(&plot_id(fid)), Some(fid));
+        }
+    }
+}
@@ -1,4 +1,7 @@
-use std::path::Path;
+use std::{
+    path::Path,
+    time::{Duration, Instant},
+};
 
 use app_core::backend::{BackendEventLoop, BackendLink};
 
@@ -22,6 +25,24 @@ impl super::Search {
         }
     }
 
+    /// Whether the search popup is shown.
+    pub fn is_open(&self) -> bool {
+        self.mode != super::SearchMode::Disabled
+    }
+
+    /// Whether the search waits for the backend or a dialog.
+    pub fn is_busy(&self) -> bool {
+        !self.search_path.is_up_to_date()
+            || !self.matches.is_up_to_date()
+            || self.awaiting_search_path_selection.is_some()
+    }
+
+    /// Send future requests with `request_tx`, e.g. after the backend was
+    /// restarted.
+    pub fn set_request_tx(&mut self, request_tx: DynRequestSender) {
+        self.request_tx = request_tx;
+    }
+
     pub fn set_search_path(&mut self, path: &Path) {
         let new_path = path.to_owned();
         BackendLink::request_parameter_update(
@@ -39,6 +60,30 @@ impl super::Search {
         self.search_path.value()
     }
 
+    /// Send the search query to the backend once it has not been edited for
+    /// `debounce`, so that typing does not fire a request per keystroke.
+    pub(super) fn query_when_settled(
+        &mut self,
+        debounce: Duration,
+        request_tx: &mut DynRequestSender,
+        ctx: &egui::Context,
+    ) {
+        let Some(edited_at) = self.query_edited_at else {
+            return;
+        };
+        let elapsed = edited_at.elapsed();
+        if elapsed >= debounce {
+            self.query_edited_at = None;
+            self.query_current_path(request_tx);
+        } else {
+            ctx.request_repaint_after(debounce - elapsed);
+        }
+    }
+
+    pub(super) fn mark_query_edited(&mut self) {
+        self.query_edited_at = Some(Instant::now());
+    }
+
     pub(super) fn query_current_path(&mut self, request_tx: &mut DynRequestSender) {
         let query = self.search_query.to_owned();
         BackendLink::request_parameter_update(
@@ -6,6 +6,7 @@ use std::{
     hash::{Hash, Hasher},
     path::PathBuf,
     thread::JoinHandle,
+    time::Instant,
 };
 
 use app_core::frontend::UIParameter;
@@ -17,6 +18,8 @@ pub struct Search {
     pub matches: UIParameter<Vec<Match>>,
     search_path: UIParameter<PathBuf>,
     search_query: String,
+    // Time of the last edit of the query that was not yet sent to the backend.
+    query_edited_at: Option<Instant>,
     mode: SearchMode,
     selected_match: Option<usize>,
     awaiting_search_path_selection: Option<JoinHandle<Option<PathBuf>>>,
@@ -52,6 +55,7 @@ impl Search {
             matches: Default::default(),
             search_path: Default::default(),
             search_query: Default::default(),
+            query_edited_at: None,
             mode: SearchMode::Disabled,
             selected_match: None,
             awaiting_search_path_selection: Default::default(),
@@ -1,18 +1,23 @@
 use std::{
     collections::HashSet,
     path::{Path, PathBuf},
+    time::Duration,
 };
 
 use app_core::frontend::UIParameter;
 use egui::{text::LayoutJob, Color32, FontId, InputState, Label, Pos2, TextFormat};
 
-use crate::{app::DynRequestSender, backend_state::PlotData};
+use crate::{
+    app::{components::UNGROUPED, config::Config, DynRequestSender},
+    backend_state::{LoadOptions, PlotData},
+};
 
 use super::SearchMode;
 
 impl super::Search {
     pub fn render(
         &mut self,
+        config: &Config,
         request_tx: &mut DynRequestSender,
         _ui: &mut egui::Ui,
         ctx: &egui::Context,
@@ -25,6 +30,14 @@ impl super::Search {
             };
         }
 
+        // Also while the search is hidden, an edited query is still sent
+        // once it settled.
+        self.query_when_settled(
+            Duration::from_millis(config.search_debounce_ms),
+            request_tx,
+            ctx,
+        );
+
         if self.mode == SearchMode::Disabled {
             return false;
         }
@@ -114,12 +127,12 @@ impl super::Search {
             }
 
             if phrase_input.changed() {
-                self.query_current_path(request_tx);
+                self.mark_query_edited();
             };
 
             // Render the matched file list.
             ui.add_enabled_ui(self.matches.is_up_to_date(), |ui| {
-                self.matches_ui(ui, phrase_input, ctx);
+                self.matches_ui(ui, phrase_input, &config.load_options, ctx);
             });
         };
 
@@ -146,7 +159,13 @@ impl super::Search {
         false
     }
 
-    fn matches_ui(&mut self, ui: &mut egui::Ui, phrase_input: egui::Response, ctx: &egui::Context) {
+    fn matches_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        phrase_input: egui::Response,
+        load_options: &LoadOptions,
+        ctx: &egui::Context,
+    ) {
         let width = 800.0;
         let height = 600.0;
 
@@ -171,7 +190,14 @@ impl super::Search {
 
                 // Fancy hover ui for each match.
                 let hover_ui = |ui: &mut egui::Ui| {
-                    match_hover_ui(ui, csv_data, &mut cursor, fp, &self.search_path);
+                    match_hover_ui(
+                        ui,
+                        csv_data,
+                        &mut cursor,
+                        fp,
+                        &self.search_path,
+                        load_options,
+                    );
                 };
 
                 // Render the matched entry.
@@ -232,6 +258,7 @@ fn match_hover_ui(
     cursor: &mut egui::CursorIcon,
     fp: &mut std::path::PathBuf,
     search_path: &UIParameter<PathBuf>,
+    load_options: &LoadOptions,
 ) {
     ui.set_min_width(300.0);
     match csv_data {
@@ -241,13 +268,15 @@ fn match_hover_ui(
             ui.label(txt);
             *cursor = egui::CursorIcon::NotAllowed;
         }
-        super::ParsedData::None => match PlotData::from_path(&search_path.value().join(fp)) {
-            Ok(data) => *csv_data = super::ParsedData::Ok(data),
-            Err(err) => *csv_data = super::ParsedData::Failed(err.to_string()),
-        },
+        super::ParsedData::None => {
+            match PlotData::from_path(&search_path.value().join(fp), load_options) {
+                Ok(data) => *csv_data = super::ParsedData::Ok(data),
+                Err(err) => *csv_data = super::ParsedData::Failed(err.to_string()),
+            }
+        }
         // If data was parsed, we show a mini plot.
         super::ParsedData::Ok(csv_data) => {
-            ui.label("press '0' to '9' to add to group, <enter> to accept");
+            ui.label("press '0' to '9' to add to group, 'i' to inspect, <enter> to accept");
             ui.separator();
             ui.label("preview:");
             egui_plot::Plot::new("Plot")
@@ -315,6 +344,7 @@ fn render_match_label(
         Some(8) => label_text.append("８ ", 2.0, fmt),
         Some(9) => label_text.append("９ ", 2.0, fmt),
         Some(0) => label_text.append("０ ", 2.0, fmt),
+        Some(UNGROUPED) => label_text.append(" inspect ", 2.0, fmt),
         Some(_) => {}
         None => {}
     };
@@ -353,5 +383,9 @@ fn number_key_released(i: &InputState) -> Option<usize> {
     if i.key_released(egui::Key::Num0) {
         return Some(0);
     }
+    // 'I' inspects the file without adding it to a group.
+    if i.key_released(egui::Key::I) {
+        return Some(UNGROUPED);
+    }
     None
 }
@@ -1,9 +1,24 @@
 use app_core::string_error::ErrorStringExt;
+
+use crate::{
+    app::{
+        components::{LineCap, LineJoin, StackMode, TickMode},
+        palette::Action,
+        shortcuts::Keymap,
+        PlotterMode,
+    },
+    backend_state::{
+        Delimiter, InfiniteValues, LoadOptions, SingleColumnMode, WhitespaceMode, LOAD_PRESETS,
+    },
+    utils::{interpolation::Interpolation, window::WindowFunction},
+};
+use regex::Regex;
 use std::{
     io::{Read, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
     str::FromStr,
 };
+use turbo_csv::{DecimalSeparator, Encoding, ThousandsSeparator};
 
 #[derive(Debug)]
 pub struct Config {
@@ -12,6 +27,584 @@ pub struct Config {
     pub svg_height: u64,
     pub x_label: String,
     pub y_label: String,
+    pub x_unit: String,
+    pub y_unit: String,
+    pub show_navigator: bool,
+    pub empty_area_action: EmptyAreaAction,
+    pub cache_file_data: bool,
+    pub search_debounce_ms: u64,
+    pub undo_depth: usize,
+    pub continuous_repaint: bool,
+    pub show_extrema: bool,
+    pub export_window: WindowFunction,
+    pub interpolation: Interpolation,
+    pub plot_size: PlotSize,
+    pub simplify_tolerance: f64,
+    pub click_tolerance_px: f32,
+    pub group_files_by: String,
+    pub file_names: FileNameDisplay,
+    pub svg_margin_left: u64,
+    pub svg_margin_right: u64,
+    pub svg_margin_top: u64,
+    pub svg_margin_bottom: u64,
+    pub manipulate_button: ManipulateButton,
+    pub integral_format: IntegralFormat,
+    pub integral_precision: usize,
+    pub integral_unit: String,
+    pub duplicate_legend_names: DuplicateLegendNames,
+    pub dim_unselected: bool,
+    pub preview_rows: usize,
+    pub live_transform_max_points: usize,
+    pub startup_mode: PlotterMode,
+    pub on_backend_crash: BackendCrashAction,
+    pub hover_highlight: f32,
+    pub auto_reload: bool,
+    pub auto_reload_debounce_ms: u64,
+    pub peak_window: f64,
+    pub peak_prominence: f64,
+    pub toolbar_layout: ToolbarLayout,
+    pub keep_selection_on_mode_switch: bool,
+    /// Text of peak labels, `{x}` and `{y}` are replaced by the position
+    /// of the peak (see `Config::peak_label`).
+    pub peak_label_format: String,
+    pub peak_label_decimals: usize,
+    pub peak_label_offset: f32,
+    pub paste_as_file: bool,
+    pub hidden_groups_hint: bool,
+    pub shortcuts: Keymap,
+    pub adaptive_points_per_px: f64,
+    pub group_tooltips: bool,
+    pub color_mode: ColorMode,
+    pub svg_unit: SizeUnit,
+    pub svg_physical_width: f64,
+    pub svg_physical_height: f64,
+    pub load_options: LoadOptions,
+    /// Where the config is saved to, not part of the config file itself.
+    pub file_path: Option<PathBuf>,
+}
+
+/// Environment variable that selects the directory of the config file.
+pub const CONFIG_DIR_VAR: &str = "FLUGS_CONFIG_DIR";
+const CONFIG_FILE_NAME: &str = ".flugs";
+/// File (in the config directory) in which the window state is persisted.
+const WINDOW_STATE_FILE_NAME: &str = "window.ron";
+
+/// Location of the config file. The directory given on the command line takes
+/// precedence over [`CONFIG_DIR_VAR`], which takes precedence over the home
+/// directory.
+pub fn config_file_path(cli_dir: Option<&Path>) -> Option<PathBuf> {
+    config_dir(cli_dir).map(|dir| dir.join(CONFIG_FILE_NAME))
+}
+
+/// Location of the persisted window state, if a config directory was set
+/// explicitly. Otherwise, eframe picks the platform default.
+pub fn window_state_path(cli_dir: Option<&Path>) -> Option<PathBuf> {
+    explicit_config_dir(cli_dir).map(|dir| dir.join(WINDOW_STATE_FILE_NAME))
+}
+
+fn explicit_config_dir(cli_dir: Option<&Path>) -> Option<PathBuf> {
+    cli_dir
+        .map(|dir| dir.to_path_buf())
+        .or_else(|| std::env::var_os(CONFIG_DIR_VAR).map(PathBuf::from))
+}
+
+fn config_dir(cli_dir: Option<&Path>) -> Option<PathBuf> {
+    #[allow(deprecated)]
+    explicit_config_dir(cli_dir).or_else(std::env::home_dir)
+}
+
+/// How files are named in the file list and legend (unless they have an
+/// alias).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FileNameDisplay {
+    /// Only the file name.
+    #[default]
+    Basename,
+    /// The path relative to the directory all loaded files have in common.
+    Relative,
+    /// The full path.
+    Full,
+}
+
+/// The mouse button that manipulates the selected file when dragged with a
+/// modifier held.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ManipulateButton {
+    #[default]
+    Primary,
+    Middle,
+    Secondary,
+}
+
+impl ManipulateButton {
+    pub fn pointer_button(&self) -> egui::PointerButton {
+        match self {
+            Self::Primary => egui::PointerButton::Primary,
+            Self::Middle => egui::PointerButton::Middle,
+            Self::Secondary => egui::PointerButton::Secondary,
+        }
+    }
+}
+
+/// What happens to legend entries of files that have the same name.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DuplicateLegendNames {
+    /// Append the file ID, so that each file has its own entry.
+    #[default]
+    AppendId,
+    /// Keep the names, the files share a legend entry.
+    Merge,
+}
+
+/// What happens when the backend thread stops unexpectedly, e.g. because
+/// parsing a file panicked.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BackendCrashAction {
+    /// Show the error and ask whether to restart the backend.
+    #[default]
+    Ask,
+    /// Restart the backend right away.
+    Restart,
+}
+
+/// How integrals are formatted in readouts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IntegralFormat {
+    /// As many digits as needed to represent the value.
+    #[default]
+    Auto,
+    /// A fixed number of decimal places.
+    Fixed,
+    /// Scientific notation with a fixed number of decimal places, e.g. 1.23e3.
+    Scientific,
+}
+
+/// What clicking/dragging on an empty area of the plot does in display mode.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EmptyAreaAction {
+    /// Clicking deselects the selected file, dragging pans the plot.
+    #[default]
+    Deselect,
+    /// Dragging zooms into the dragged box, clicking keeps the selection.
+    BoxZoom,
+}
+
+/// How the buttons above the plot (group toggles and view options) are laid
+/// out if they do not fit into one row.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ToolbarLayout {
+    /// Continue in further rows.
+    #[default]
+    Wrap,
+    /// Keep one row that can be scrolled horizontally.
+    Scroll,
+}
+
+/// Unit of the size of exported figures.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SizeUnit {
+    /// Pixels, without a physical size.
+    #[default]
+    Px,
+    Cm,
+    In,
+}
+
+impl SizeUnit {
+    /// Pixels per unit, figures with a physical size are drawn with the 96
+    /// pixels per inch of CSS, at which fonts and lines have their nominal
+    /// size.
+    fn pixels(&self) -> f64 {
+        match self {
+            SizeUnit::Px => 1.0,
+            SizeUnit::Cm => 96.0 / 2.54,
+            SizeUnit::In => 96.0,
+        }
+    }
+}
+
+/// How the plotted files are colored automatically.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorMode {
+    /// A distinct hue per file.
+    #[default]
+    File,
+    /// A hue per group, files within a group differ in lightness.
+    Group,
+}
+
+/// How much space the plot takes on screen.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PlotSize {
+    /// Fill the available space.
+    #[default]
+    Fill,
+    /// Use as much space as possible with the aspect ratio of the export.
+    ExportAspect,
+    /// Use the size of the export (shrunk if there is not enough space).
+    ExportSize,
+}
+
+/// A setting with a fixed set of choices. It is shown as a combo box in the
+/// preferences and stored by its label in the config file.
+pub trait ConfigOption: Sized + Copy + PartialEq + 'static {
+    const OPTIONS: &'static [Self];
+
+    fn label(&self) -> &'static str;
+
+    fn from_label(label: &str) -> Option<Self> {
+        Self::OPTIONS
+            .iter()
+            .find(|option| option.label() == label.trim())
+            .copied()
+    }
+}
+
+pub(crate) fn option_combo_box<T: ConfigOption>(ui: &mut egui::Ui, id: &str, value: &mut T) {
+    egui::ComboBox::from_id_salt(id)
+        .selected_text(value.label())
+        .show_ui(ui, |ui| {
+            for option in T::OPTIONS {
+                ui.selectable_value(value, *option, option.label());
+            }
+        });
+}
+
+/// Controls for the load options that determine how the contents of a file
+/// are parsed, shown in the preferences and when previewing a file.
+pub fn parse_options_ui(load_options: &mut LoadOptions, ui: &mut egui::Ui) {
+    ui.horizontal(|ui| {
+        ui.label("Presets:");
+        for preset in LOAD_PRESETS {
+            if ui
+                .button(preset.name)
+                .on_hover_text(preset.description)
+                .clicked()
+            {
+                (preset.apply)(load_options);
+            }
+        }
+    });
+    ui.label("Column delimiter");
+    option_combo_box(ui, "delimiter", &mut load_options.delimiter);
+    ui.label("Decimal separator");
+    option_combo_box(ui, "decimal_separator", &mut load_options.decimal_separator);
+    ui.label("Thousands separator");
+    option_combo_box(
+        ui,
+        "thousands_separator",
+        &mut load_options.thousands_separator,
+    );
+    ui.checkbox(
+        &mut load_options.detect_number_format,
+        "Detect separators of numbers",
+    )
+    .on_hover_text(
+        "Guess the decimal and thousands separator from the first rows of each file, the ones above are used if that is ambiguous",
+    );
+    ui.label("Infinite values")
+        .on_hover_text("'drop' skips points with infinite values, 'clamp' replaces them with the largest or smallest value of their column");
+    option_combo_box(ui, "infinite_values", &mut load_options.infinite_values);
+    ui.label("Files with a single column");
+    option_combo_box(ui, "single_column", &mut load_options.single_column);
+    ui.label("Split columns on whitespace only")
+        .on_hover_text("Commas and semicolons are not treated as delimiters");
+    option_combo_box(
+        ui,
+        "whitespace_delimited",
+        &mut load_options.whitespace_delimited,
+    );
+    ui.label("Text encoding of files")
+        .on_hover_text("'detect' uses a byte order mark or guesses UTF-8, UTF-16 or Latin-1");
+    option_combo_box(ui, "encoding", &mut load_options.encoding);
+}
+
+impl ConfigOption for PlotterMode {
+    const OPTIONS: &'static [Self] = &[Self::Display, Self::Integrate];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Display => "display",
+            Self::Integrate => "integrate",
+        }
+    }
+}
+
+impl ConfigOption for Delimiter {
+    const OPTIONS: &'static [Self] = &[Self::Auto, Self::Semicolon];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Semicolon => "semicolon",
+        }
+    }
+}
+
+impl ConfigOption for DecimalSeparator {
+    const OPTIONS: &'static [Self] = &[Self::Point, Self::Comma];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Point => "point",
+            Self::Comma => "comma",
+        }
+    }
+}
+
+impl ConfigOption for ThousandsSeparator {
+    const OPTIONS: &'static [Self] = &[Self::None, Self::Point, Self::Comma, Self::Apostrophe];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Point => "point",
+            Self::Comma => "comma",
+            Self::Apostrophe => "apostrophe",
+        }
+    }
+}
+
+impl ConfigOption for InfiniteValues {
+    const OPTIONS: &'static [Self] = &[Self::Drop, Self::Clamp, Self::Keep];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Drop => "drop",
+            Self::Clamp => "clamp",
+            Self::Keep => "keep",
+        }
+    }
+}
+
+impl ConfigOption for SingleColumnMode {
+    const OPTIONS: &'static [Self] = &[Self::RowIndex, Self::Reject];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::RowIndex => "row index as x",
+            Self::Reject => "reject",
+        }
+    }
+}
+
+impl ConfigOption for EmptyAreaAction {
+    const OPTIONS: &'static [Self] = &[Self::Deselect, Self::BoxZoom];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Deselect => "deselect on click",
+            Self::BoxZoom => "box zoom on drag",
+        }
+    }
+}
+
+impl ConfigOption for FileNameDisplay {
+    const OPTIONS: &'static [Self] = &[Self::Basename, Self::Relative, Self::Full];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Basename => "file name",
+            Self::Relative => "relative to common directory",
+            Self::Full => "full path",
+        }
+    }
+}
+
+impl ConfigOption for ManipulateButton {
+    const OPTIONS: &'static [Self] = &[Self::Primary, Self::Middle, Self::Secondary];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Primary => "primary",
+            Self::Middle => "middle",
+            Self::Secondary => "secondary",
+        }
+    }
+}
+
+impl ConfigOption for BackendCrashAction {
+    const OPTIONS: &'static [Self] = &[Self::Ask, Self::Restart];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Ask => "ask to restart",
+            Self::Restart => "restart",
+        }
+    }
+}
+
+impl ConfigOption for DuplicateLegendNames {
+    const OPTIONS: &'static [Self] = &[Self::AppendId, Self::Merge];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::AppendId => "append file ID",
+            Self::Merge => "merge entries",
+        }
+    }
+}
+
+impl ConfigOption for IntegralFormat {
+    const OPTIONS: &'static [Self] = &[Self::Auto, Self::Fixed, Self::Scientific];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Auto => "automatic",
+            Self::Fixed => "fixed decimals",
+            Self::Scientific => "scientific",
+        }
+    }
+}
+
+impl ConfigOption for PlotSize {
+    const OPTIONS: &'static [Self] = &[Self::Fill, Self::ExportAspect, Self::ExportSize];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Fill => "fill available space",
+            Self::ExportAspect => "aspect ratio of export",
+            Self::ExportSize => "size of export",
+        }
+    }
+}
+
+impl ConfigOption for ToolbarLayout {
+    const OPTIONS: &'static [Self] = &[Self::Wrap, Self::Scroll];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Wrap => "wrap",
+            Self::Scroll => "scroll",
+        }
+    }
+}
+
+impl ConfigOption for SizeUnit {
+    const OPTIONS: &'static [Self] = &[Self::Px, Self::Cm, Self::In];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Px => "px",
+            Self::Cm => "cm",
+            Self::In => "in",
+        }
+    }
+}
+
+impl ConfigOption for ColorMode {
+    const OPTIONS: &'static [Self] = &[Self::File, Self::Group];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::File => "by file",
+            Self::Group => "by group",
+        }
+    }
+}
+
+impl ConfigOption for Interpolation {
+    const OPTIONS: &'static [Self] = &[Self::Linear, Self::Nearest, Self::Cubic];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Linear => "linear",
+            Self::Nearest => "nearest",
+            Self::Cubic => "cubic",
+        }
+    }
+}
+
+impl ConfigOption for WindowFunction {
+    const OPTIONS: &'static [Self] = &[Self::Rectangular, Self::Hann];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Rectangular => "none",
+            Self::Hann => "hann",
+        }
+    }
+}
+
+impl ConfigOption for StackMode {
+    const OPTIONS: &'static [Self] = &[Self::Off, Self::Fixed, Self::Auto];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Fixed => "fixed offset",
+            Self::Auto => "automatic",
+        }
+    }
+}
+
+impl ConfigOption for TickMode {
+    const OPTIONS: &'static [Self] = &[Self::Auto, Self::Count, Self::Spacing];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Auto => "automatic",
+            Self::Count => "count",
+            Self::Spacing => "spacing",
+        }
+    }
+}
+
+// The labels are also the values of the SVG attributes.
+impl ConfigOption for LineCap {
+    const OPTIONS: &'static [Self] = &[Self::Butt, Self::Round, Self::Square];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Butt => "butt",
+            Self::Round => "round",
+            Self::Square => "square",
+        }
+    }
+}
+
+impl ConfigOption for LineJoin {
+    const OPTIONS: &'static [Self] = &[Self::Miter, Self::Round, Self::Bevel];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Miter => "miter",
+            Self::Round => "round",
+            Self::Bevel => "bevel",
+        }
+    }
+}
+
+impl ConfigOption for WhitespaceMode {
+    const OPTIONS: &'static [Self] = &[Self::TxtFiles, Self::Always, Self::Never];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::TxtFiles => ".txt files",
+            Self::Always => "always",
+            Self::Never => "never",
+        }
+    }
+}
+
+impl ConfigOption for Encoding {
+    const OPTIONS: &'static [Self] = &[
+        Self::Auto,
+        Self::Utf8,
+        Self::Utf16Le,
+        Self::Utf16Be,
+        Self::Latin1,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Auto => "detect",
+            Self::Utf8 => "UTF-8",
+            Self::Utf16Le => "UTF-16 LE",
+            Self::Utf16Be => "UTF-16 BE",
+            Self::Latin1 => "Latin-1",
+        }
+    }
 }
 
 impl Default for Config {
@@ -21,6 +614,57 @@ impl Default for Config {
         let svg_height = 600;
         let x_label = "x-label".to_string();
         let y_label = "y-label".to_string();
+        let x_unit = String::new();
+        let y_unit = String::new();
+        let show_navigator = true;
+        let empty_area_action = EmptyAreaAction::default();
+        let cache_file_data = false;
+        let search_debounce_ms = 150;
+        let undo_depth = 100;
+        let continuous_repaint = true;
+        let show_extrema = false;
+        let export_window = WindowFunction::default();
+        let interpolation = Interpolation::default();
+        let plot_size = PlotSize::default();
+        let simplify_tolerance = 0.0;
+        let click_tolerance_px = 6.0;
+        let group_files_by = String::new();
+        let file_names = FileNameDisplay::default();
+        let svg_margin_left = 100;
+        let svg_margin_right = 20;
+        let svg_margin_top = 60;
+        let svg_margin_bottom = 60;
+        let manipulate_button = ManipulateButton::default();
+        let integral_format = IntegralFormat::default();
+        let integral_precision = 3;
+        let integral_unit = String::new();
+        let duplicate_legend_names = DuplicateLegendNames::default();
+        let dim_unselected = true;
+        let preview_rows = 20;
+        let live_transform_max_points = 200_000;
+        let startup_mode = PlotterMode::Display;
+        let on_backend_crash = BackendCrashAction::default();
+        let hover_highlight = 0.5;
+        let auto_reload = false;
+        let auto_reload_debounce_ms = 500;
+        let peak_window = 0.1;
+        let peak_prominence = 0.05;
+        let toolbar_layout = ToolbarLayout::default();
+        let keep_selection_on_mode_switch = true;
+        let peak_label_format = "{x}".to_string();
+        let peak_label_decimals = 1;
+        let peak_label_offset = 8.0;
+        let paste_as_file = true;
+        let hidden_groups_hint = true;
+        let shortcuts = Keymap::default();
+        let adaptive_points_per_px = 2.0;
+        let group_tooltips = true;
+        let color_mode = ColorMode::default();
+        let svg_unit = SizeUnit::default();
+        let svg_physical_width = 16.0;
+        let svg_physical_height = 12.0;
+        let load_options = LoadOptions::default();
+        let file_path = config_file_path(None);
 
         Self {
             search_path,
@@ -28,6 +672,57 @@ impl Default for Config {
             svg_height,
             x_label,
             y_label,
+            x_unit,
+            y_unit,
+            show_navigator,
+            empty_area_action,
+            cache_file_data,
+            search_debounce_ms,
+            undo_depth,
+            continuous_repaint,
+            show_extrema,
+            export_window,
+            interpolation,
+            plot_size,
+            simplify_tolerance,
+            click_tolerance_px,
+            group_files_by,
+            file_names,
+            svg_margin_left,
+            svg_margin_right,
+            svg_margin_top,
+            svg_margin_bottom,
+            manipulate_button,
+            integral_format,
+            integral_precision,
+            integral_unit,
+            duplicate_legend_names,
+            dim_unselected,
+            preview_rows,
+            live_transform_max_points,
+            startup_mode,
+            on_backend_crash,
+            hover_highlight,
+            auto_reload,
+            auto_reload_debounce_ms,
+            peak_window,
+            peak_prominence,
+            toolbar_layout,
+            keep_selection_on_mode_switch,
+            peak_label_format,
+            peak_label_decimals,
+            peak_label_offset,
+            paste_as_file,
+            hidden_groups_hint,
+            shortcuts,
+            adaptive_points_per_px,
+            group_tooltips,
+            color_mode,
+            svg_unit,
+            svg_physical_width,
+            svg_physical_height,
+            load_options,
+            file_path,
         }
     }
 }
@@ -42,18 +737,263 @@ impl Config {
         ui.text_edit_singleline(&mut path);
         self.search_path = path.to_string().into();
 
-        ui.label("Width of exported SVG");
-        ui.add(egui::DragValue::new(&mut self.svg_width).speed(10));
-        ui.label("Height of exported SVG");
-        ui.add(egui::DragValue::new(&mut self.svg_height).speed(10));
+        ui.label("Size unit of exported SVG").on_hover_text(
+            "With cm or in, the SVG has this physical size at 96 pixels per inch, fonts and lines keep their nominal size",
+        );
+        option_combo_box(ui, "svg_unit", &mut self.svg_unit);
+        if self.svg_unit == SizeUnit::Px {
+            ui.label("Width of exported SVG");
+            ui.add(egui::DragValue::new(&mut self.svg_width).speed(10));
+            ui.label("Height of exported SVG");
+            ui.add(egui::DragValue::new(&mut self.svg_height).speed(10));
+        } else {
+            let unit = self.svg_unit.label();
+            ui.label("Width of exported SVG");
+            ui.add(
+                egui::DragValue::new(&mut self.svg_physical_width)
+                    .range(0.1..=f64::INFINITY)
+                    .speed(0.1)
+                    .suffix(format!(" {unit}")),
+            );
+            ui.label("Height of exported SVG");
+            ui.add(
+                egui::DragValue::new(&mut self.svg_physical_height)
+                    .range(0.1..=f64::INFINITY)
+                    .speed(0.1)
+                    .suffix(format!(" {unit}")),
+            );
+        }
+        ui.label("Margins of exported SVG (px)")
+            .on_hover_text("Space around the plot for tick labels and axis titles");
+        ui.horizontal(|ui| {
+            for (label, margin) in [
+                ("left", &mut self.svg_margin_left),
+                ("right", &mut self.svg_margin_right),
+                ("top", &mut self.svg_margin_top),
+                ("bottom", &mut self.svg_margin_bottom),
+            ] {
+                ui.add(egui::DragValue::new(margin).prefix(format!("{label}: ")));
+            }
+        });
         ui.label("X-Label");
         ui.text_edit_singleline(&mut self.x_label);
+        ui.label("X-Unit");
+        ui.text_edit_singleline(&mut self.x_unit);
         ui.label("Y-Label");
         ui.text_edit_singleline(&mut self.y_label);
+        ui.label("Y-Unit");
+        ui.text_edit_singleline(&mut self.y_unit);
+        ui.checkbox(&mut self.show_navigator, "Show navigator above plot");
+        ui.label("Empty plot area").on_hover_text(
+            "Deselect: click deselects the file, drag pans the plot.\n\
+             Box zoom: drag zooms, click the selected file again to deselect it.",
+        );
+        option_combo_box(ui, "empty_area_action", &mut self.empty_area_action);
+        ui.label("Search Delay (ms)")
+            .on_hover_text("Pause in typing after which the search query is run");
+        ui.add(egui::DragValue::new(&mut self.search_debounce_ms).range(0..=2000));
+        ui.label("Undo History Depth")
+            .on_hover_text("Number of states retained for undo, the oldest are dropped first");
+        ui.add(egui::DragValue::new(&mut self.undo_depth).range(0..=10_000));
+        parse_options_ui(&mut self.load_options, ui);
+        ui.label("Maximum file size (MB)").on_hover_text(
+            "Larger files are only loaded after confirming, 0 always allows loading",
+        );
+        ui.add(egui::DragValue::new(&mut self.load_options.max_file_size_mb).range(0..=1_000_000));
+        ui.label("Rows to preview")
+            .on_hover_text("Number of rows read when previewing a file before loading it");
+        ui.add(egui::DragValue::new(&mut self.preview_rows).range(1..=1000));
+        ui.checkbox(&mut self.cache_file_data, "Cache file data in session files")
+            .on_hover_text("Store parsed data when saving a session, so loading it does not re-read unchanged files");
+
+        ui.checkbox(&mut self.continuous_repaint, "Repaint continuously")
+            .on_hover_text("Needed for live-reload workflows, otherwise the plot is only repainted when something changes");
+
+        ui.checkbox(&mut self.show_extrema, "Mark minimum/maximum of each file");
+
+        ui.label("Window for exported processed data")
+            .on_hover_text("Apodization applied to the y-values of exported CSV data (plotted data, integration region and groups, also in bundles)");
+        option_combo_box(ui, "export_window", &mut self.export_window);
+
+        ui.label("Interpolation").on_hover_text(
+            "Used for traces derived from several files, e.g. differences and ratios",
+        );
+        option_combo_box(ui, "interpolation", &mut self.interpolation);
+
+        ui.label("Line colors").on_hover_text(
+            "By group gives all files of a group the same hue, varying their lightness",
+        );
+        option_combo_box(ui, "color_mode", &mut self.color_mode);
+
+        ui.label("Plot size on screen").on_hover_text(
+            "Constrain the plot to the export size, so that it looks like the exported figure",
+        );
+        option_combo_box(ui, "plot_size", &mut self.plot_size);
+
+        ui.label("Line simplification tolerance").on_hover_text(
+            "Drop points that deviate less than this fraction of the data range from the simplified line (0 is off)",
+        );
+        ui.add(
+            egui::DragValue::new(&mut self.simplify_tolerance)
+                .range(0.0..=0.1)
+                .speed(0.0001),
+        );
+
+        ui.label("Adaptive reduction (points per pixel)").on_hover_text(
+            "Files with adaptive point reduction draw all visible points when zoomed in, and reduce them once there are more than this many per pixel of the plot width",
+        );
+        ui.add(
+            egui::DragValue::new(&mut self.adaptive_points_per_px)
+                .range(0.1..=100.0)
+                .speed(0.1),
+        );
+
+        ui.label("Max. points for live transforms").on_hover_text(
+            "Transform and smoothing edits of larger files are only applied when clicking \"Apply\" (0 is always live)",
+        );
+        ui.add(egui::DragValue::new(&mut self.live_transform_max_points).speed(1000));
+
+        ui.label("Click tolerance (px)")
+            .on_hover_text("Clicking this close to a line selects it");
+        ui.add(egui::DragValue::new(&mut self.click_tolerance_px).range(0.0..=50.0));
+
+        ui.label("Group opened files by").on_hover_text(
+            "Regular expression for file names, files with the same capture (named 'group' or the first one) are put into the same group",
+        );
+        ui.add(
+            egui::TextEdit::singleline(&mut self.group_files_by)
+                .hint_text(r"e.g. (?P<group>sample\d+)_"),
+        );
+        if let Some(Err(err)) = self.group_pattern() {
+            ui.colored_label(ui.visuals().error_fg_color, "invalid pattern")
+                .on_hover_text(err);
+        }
+
+        ui.label("File names")
+            .on_hover_text("How files without alias are named in the file list and legend");
+        option_combo_box(ui, "file_names", &mut self.file_names);
+
+        ui.label("Mouse button to manipulate files").on_hover_text(
+            "Dragging with this button and a modifier held shifts/scales the selected file",
+        );
+        option_combo_box(ui, "manipulate_button", &mut self.manipulate_button);
+
+        ui.label("Integral readout").on_hover_text(
+            "How integrals are shown in the integrate menu, the unit is independent of the axis units",
+        );
+        ui.horizontal(|ui| {
+            option_combo_box(ui, "integral_format", &mut self.integral_format);
+            if self.integral_format != IntegralFormat::Auto {
+                ui.add(
+                    egui::DragValue::new(&mut self.integral_precision)
+                        .range(0..=12)
+                        .prefix("digits: "),
+                );
+            }
+        });
+        ui.add(
+            egui::TextEdit::singleline(&mut self.integral_unit).hint_text("unit, e.g. counts·nm"),
+        );
+
+        ui.label("Files with the same legend name")
+            .on_hover_text("Files with merged entries are shown and hidden together");
+        option_combo_box(
+            ui,
+            "duplicate_legend_names",
+            &mut self.duplicate_legend_names,
+        );
+
+        ui.checkbox(&mut self.paste_as_file, "Paste data as file")
+            .on_hover_text("CTRL + V outside of text fields adds the pasted columns (e.g. from a spreadsheet) as a new file");
+        ui.checkbox(&mut self.dim_unselected, "Dim unselected files")
+            .on_hover_text("Fade the other files when a file is selected");
+        ui.checkbox(
+            &mut self.hidden_groups_hint,
+            "Hint when all groups are hidden",
+        )
+        .on_hover_text(
+            "Show a message in the empty plot while files are loaded but no group is plotted",
+        );
+
+        ui.label("Mode at startup");
+        option_combo_box(ui, "startup_mode", &mut self.startup_mode);
+        ui.checkbox(
+            &mut self.keep_selection_on_mode_switch,
+            "Keep selection when switching modes",
+        )
+        .on_hover_text(
+            "Otherwise the selected file is deselected when entering or leaving integrate mode",
+        );
+
+        ui.label("When the backend stops unexpectedly")
+            .on_hover_text("The backend loads and searches files in the background");
+        option_combo_box(ui, "on_backend_crash", &mut self.on_backend_crash);
+
+        ui.label("Hover highlight").on_hover_text(
+            "How much the line under the pointer is brightened and thickened (0 = off)",
+        );
+        ui.add(
+            egui::DragValue::new(&mut self.hover_highlight)
+                .range(0.0..=1.0)
+                .speed(0.01),
+        );
+
+        ui.checkbox(&mut self.auto_reload, "Reload changed files")
+            .on_hover_text("Read files again when they change on disk, e.g. during an acquisition");
+        ui.add_enabled_ui(self.auto_reload, |ui| {
+            ui.label("Reload delay (ms)").on_hover_text(
+                "A changed file is reloaded once it did not change for this long (0 reloads on every change)",
+            );
+            ui.add(egui::DragValue::new(&mut self.auto_reload_debounce_ms).range(0..=60_000));
+        });
+
+        ui.label("Peak window").on_hover_text(
+            "Width of the view when centering on the highest peak, as a fraction of the x-range of the file",
+        );
+        ui.add(
+            egui::DragValue::new(&mut self.peak_window)
+                .range(0.001..=1.0)
+                .speed(0.001),
+        );
+
+        ui.label("Peak prominence").on_hover_text(
+            "How far a peak must rise above its surroundings to be stepped to, as a fraction of the y-range of the file",
+        );
+        ui.add(
+            egui::DragValue::new(&mut self.peak_prominence)
+                .range(0.0..=1.0)
+                .speed(0.001),
+        );
+
+        ui.label("Peak labels").on_hover_text(
+            "Text drawn above the peaks of files with peak labels enabled, {x} and {y} are replaced by the position of the peak",
+        );
+        ui.text_edit_singleline(&mut self.peak_label_format);
+        ui.label("Peak label decimals");
+        ui.add(egui::DragValue::new(&mut self.peak_label_decimals).range(0..=10));
+        ui.label("Peak label offset (px)").on_hover_text(
+            "Distance between a peak and its label, crowded labels are moved further up",
+        );
+        ui.add(egui::DragValue::new(&mut self.peak_label_offset).range(0.0..=100.0));
+
+        ui.label("Buttons above the plot")
+            .on_hover_text("Wrap the group toggles and view options into more rows if they do not fit, or scroll them");
+        option_combo_box(ui, "toolbar_layout", &mut self.toolbar_layout);
+        ui.checkbox(&mut self.group_tooltips, "Group details on hover")
+            .on_hover_text("Hovering a group toggle shows how many files the group contains and how many of them are still loading");
+
+        egui::CollapsingHeader::new("Keyboard Shortcuts").show(ui, |ui| {
+            self.shortcuts.render(ui);
+        });
 
         ui.separator();
 
-        if ui.button("Save to Config File").clicked() {
+        let save_button = ui.button("Save to Config File");
+        let save_button = match &self.file_path {
+            Some(path) => save_button.on_hover_text(format!("Saves to {}", path.display())),
+            None => save_button,
+        };
+        if save_button.clicked() {
             if let Err(e) = self.to_config_file() {
                 log::error!("{e}");
             };
@@ -61,15 +1001,139 @@ impl Config {
     }
 }
 
+impl Config {
+    /// The regular expression to group opened files by, if one is set.
+    pub fn group_pattern(&self) -> Option<Result<Regex, String>> {
+        let src = self.group_files_by.trim();
+        (!src.is_empty()).then(|| Regex::new(src).map_err(|err| err.to_string()))
+    }
+
+    /// Position and size of the plot area in the exported figure, as
+    /// fractions of the figure size (u, v, width, height). Margins that do
+    /// not fit are shrunk, so that the plot area stays visible.
+    pub fn svg_axis_placement(&self) -> [f64; 4] {
+        let fraction = |a: u64, b: u64, size: u64| {
+            let (a, b, size) = (a as f64, b as f64, size.max(1) as f64);
+            let scale = if a + b > 0.9 * size {
+                0.9 * size / (a + b)
+            } else {
+                1.0
+            };
+            (a * scale / size, b * scale / size)
+        };
+        let (width, height) = self.svg_size();
+        let (left, right) = fraction(self.svg_margin_left, self.svg_margin_right, width);
+        let (top, bottom) = fraction(self.svg_margin_top, self.svg_margin_bottom, height);
+        [left, top, 1.0 - left - right, 1.0 - top - bottom]
+    }
+
+    /// Width and height of exported SVGs in pixels.
+    pub fn svg_size(&self) -> (u64, u64) {
+        match self.svg_unit {
+            SizeUnit::Px => (self.svg_width, self.svg_height),
+            unit => {
+                let pixels = |size: f64| (size * unit.pixels()).round().max(1.0) as u64;
+                (
+                    pixels(self.svg_physical_width),
+                    pixels(self.svg_physical_height),
+                )
+            }
+        }
+    }
+
+    /// Physical width and height of exported SVGs (e.g. `8.5cm`), `None` if
+    /// the size is given in pixels.
+    pub fn svg_physical_size(&self) -> Option<(String, String)> {
+        let unit = self.svg_unit.label();
+        (self.svg_unit != SizeUnit::Px).then(|| {
+            (
+                format!("{}{unit}", self.svg_physical_width),
+                format!("{}{unit}", self.svg_physical_height),
+            )
+        })
+    }
+
+    /// Integral value as shown in readouts, with the integral unit appended
+    /// (if there is one).
+    pub fn format_integral(&self, area: f64) -> String {
+        let digits = self.integral_precision;
+        let value = match self.integral_format {
+            IntegralFormat::Auto => format!("{area}"),
+            IntegralFormat::Fixed => format!("{area:.digits$}"),
+            IntegralFormat::Scientific => format!("{area:.digits$e}"),
+        };
+        let unit = self.integral_unit.trim();
+        if unit.is_empty() {
+            value
+        } else {
+            format!("{value} {unit}")
+        }
+    }
+
+    /// Label of a peak at (`x`, `y`), from `format` (the label of the file)
+    /// or the `peak_label_format` if that is empty.
+    pub fn peak_label(&self, format: &str, x: f64, y: f64) -> String {
+        let format = if format.is_empty() {
+            &self.peak_label_format
+        } else {
+            format
+        };
+        let decimals = self.peak_label_decimals;
+        format
+            .replace("{x}", &format!("{x:.decimals$}"))
+            .replace("{y}", &format!("{y:.decimals$}"))
+    }
+
+    /// X-label with the unit appended in brackets (if there is a unit).
+    pub fn x_title(&self) -> String {
+        axis_title(&self.x_label, &self.x_unit)
+    }
+
+    /// Y-label with the unit appended in brackets (if there is a unit).
+    pub fn y_title(&self) -> String {
+        axis_title(&self.y_label, &self.y_unit)
+    }
+}
+
+impl Config {
+    /// Size of the plot on screen, given the `available` space.
+    pub fn plot_dimensions(&self, available: egui::Vec2) -> egui::Vec2 {
+        let (width, height) = self.svg_size();
+        let export = egui::vec2(width as f32, height as f32);
+        if export.x <= 0.0 || export.y <= 0.0 {
+            return available;
+        }
+        let fit = (available.x / export.x).min(available.y / export.y);
+        match self.plot_size {
+            PlotSize::Fill => available,
+            PlotSize::ExportAspect => export * fit,
+            PlotSize::ExportSize => export * fit.min(1.0),
+        }
+    }
+}
+
+fn axis_title(label: &str, unit: &str) -> String {
+    if unit.is_empty() {
+        label.to_string()
+    } else {
+        format!("{label} [{unit}]")
+    }
+}
+
 impl Config {
     pub fn from_config_file() -> Result<Self, String> {
-        let mut config = Self::default();
-        #[allow(deprecated)]
-        let Some(home) = std::env::home_dir() else {
+        let Some(path) = config_file_path(None) else {
             return Err("could not determine home directory to load config file".into());
         };
+        Self::from_config_file_at(&path)
+    }
+
+    pub fn from_config_file_at(path: &Path) -> Result<Self, String> {
+        let mut config = Self {
+            file_path: Some(path.to_path_buf()),
+            ..Default::default()
+        };
         let config_raw = {
-            let path = home.join(PathBuf::from(".flugs"));
             let mut file = std::fs::File::open(path).err_to_string("could not open config file")?;
             let mut buf = String::new();
             file.read_to_string(&mut buf)
@@ -81,7 +1145,8 @@ impl Config {
             if line.starts_with("#") {
                 continue;
             }
-            let mut iter = line.split("=");
+            // Values may contain "=" themselves, e.g. patterns.
+            let mut iter = line.splitn(2, "=");
             let key = iter.next();
             let val = iter.next();
             match (key, val) {
@@ -110,6 +1175,394 @@ impl Config {
                 (Some("y_label"), Some(y_label)) => {
                     config.y_label = y_label.to_string();
                 }
+                (Some("x_unit"), Some(x_unit)) => {
+                    config.x_unit = x_unit.to_string();
+                }
+                (Some("y_unit"), Some(y_unit)) => {
+                    config.y_unit = y_unit.to_string();
+                }
+                (Some("search_debounce_ms"), Some(ms_str)) => {
+                    if let Ok(ms) = ms_str.parse::<u64>() {
+                        config.search_debounce_ms = ms;
+                    } else {
+                        log::warn!("could not parse 'search_debounce_ms' as number")
+                    }
+                }
+                (Some("undo_depth"), Some(depth_str)) => {
+                    if let Ok(depth) = depth_str.parse::<usize>() {
+                        config.undo_depth = depth;
+                    } else {
+                        log::warn!("could not parse 'undo_depth' as number")
+                    }
+                }
+                (Some("single_column"), Some(label)) => {
+                    if let Some(mode) = SingleColumnMode::from_label(label) {
+                        config.load_options.single_column = mode;
+                    } else {
+                        log::warn!("unknown value for 'single_column': {label}")
+                    }
+                }
+                (Some("whitespace_delimited"), Some(label)) => {
+                    if let Some(mode) = WhitespaceMode::from_label(label) {
+                        config.load_options.whitespace_delimited = mode;
+                    } else {
+                        log::warn!("unknown value for 'whitespace_delimited': {label}")
+                    }
+                }
+                (Some("delimiter"), Some(label)) => {
+                    if let Some(delimiter) = Delimiter::from_label(label) {
+                        config.load_options.delimiter = delimiter;
+                    } else {
+                        log::warn!("unknown value for 'delimiter': {label}")
+                    }
+                }
+                (Some("decimal_separator"), Some(label)) => {
+                    if let Some(separator) = DecimalSeparator::from_label(label) {
+                        config.load_options.decimal_separator = separator;
+                    } else {
+                        log::warn!("unknown value for 'decimal_separator': {label}")
+                    }
+                }
+                (Some("thousands_separator"), Some(label)) => {
+                    if let Some(separator) = ThousandsSeparator::from_label(label) {
+                        config.load_options.thousands_separator = separator;
+                    } else {
+                        log::warn!("unknown value for 'thousands_separator': {label}")
+                    }
+                }
+                (Some("detect_number_format"), Some(flag_str)) => {
+                    if let Ok(flag) = flag_str.parse::<bool>() {
+                        config.load_options.detect_number_format = flag;
+                    } else {
+                        log::warn!("could not parse 'detect_number_format' as true/false")
+                    }
+                }
+                (Some("infinite_values"), Some(label)) => {
+                    if let Some(handling) = InfiniteValues::from_label(label) {
+                        config.load_options.infinite_values = handling;
+                    } else {
+                        log::warn!("unknown value for 'infinite_values': {label}")
+                    }
+                }
+                (Some("encoding"), Some(label)) => {
+                    if let Some(encoding) = Encoding::from_label(label) {
+                        config.load_options.encoding = encoding;
+                    } else {
+                        log::warn!("unknown value for 'encoding': {label}")
+                    }
+                }
+                (Some("max_file_size_mb"), Some(num_str)) => {
+                    if let Ok(num) = num_str.parse::<u64>() {
+                        config.load_options.max_file_size_mb = num;
+                    } else {
+                        log::warn!("could not parse 'max_file_size_mb' as number")
+                    }
+                }
+                (Some("show_navigator"), Some(flag_str)) => {
+                    if let Ok(flag) = flag_str.parse::<bool>() {
+                        config.show_navigator = flag;
+                    } else {
+                        log::warn!("could not parse 'show_navigator' as true/false")
+                    }
+                }
+                (Some("empty_area_action"), Some(label)) => {
+                    if let Some(action) = EmptyAreaAction::from_label(label) {
+                        config.empty_area_action = action;
+                    } else {
+                        log::warn!("unknown value for 'empty_area_action': {label}")
+                    }
+                }
+                (Some("cache_file_data"), Some(flag_str)) => {
+                    if let Ok(flag) = flag_str.parse::<bool>() {
+                        config.cache_file_data = flag;
+                    } else {
+                        log::warn!("could not parse 'cache_file_data' as true/false")
+                    }
+                }
+                (Some("continuous_repaint"), Some(flag_str)) => {
+                    if let Ok(flag) = flag_str.parse::<bool>() {
+                        config.continuous_repaint = flag;
+                    } else {
+                        log::warn!("could not parse 'continuous_repaint' as true/false")
+                    }
+                }
+                (Some("show_extrema"), Some(flag_str)) => {
+                    if let Ok(flag) = flag_str.parse::<bool>() {
+                        config.show_extrema = flag;
+                    } else {
+                        log::warn!("could not parse 'show_extrema' as true/false")
+                    }
+                }
+                (Some("export_window"), Some(label)) => {
+                    if let Some(value) = WindowFunction::from_label(label) {
+                        config.export_window = value;
+                    } else {
+                        log::warn!("unknown value for 'export_window': {label}")
+                    }
+                }
+                (Some("interpolation"), Some(label)) => {
+                    if let Some(value) = Interpolation::from_label(label) {
+                        config.interpolation = value;
+                    } else {
+                        log::warn!("unknown value for 'interpolation': {label}")
+                    }
+                }
+                (Some("plot_size"), Some(label)) => {
+                    if let Some(value) = PlotSize::from_label(label) {
+                        config.plot_size = value;
+                    } else {
+                        log::warn!("unknown value for 'plot_size': {label}")
+                    }
+                }
+                (Some("simplify_tolerance"), Some(num_str)) => {
+                    if let Ok(num) = num_str.parse::<f64>() {
+                        config.simplify_tolerance = num;
+                    } else {
+                        log::warn!("could not parse 'simplify_tolerance' as number")
+                    }
+                }
+                (Some("click_tolerance_px"), Some(num_str)) => {
+                    if let Ok(num) = num_str.parse::<f32>() {
+                        config.click_tolerance_px = num;
+                    } else {
+                        log::warn!("could not parse 'click_tolerance_px' as number")
+                    }
+                }
+                (Some("group_files_by"), Some(value)) => {
+                    config.group_files_by = value.to_string();
+                }
+                (Some("file_names"), Some(label)) => {
+                    if let Some(value) = FileNameDisplay::from_label(label) {
+                        config.file_names = value;
+                    } else {
+                        log::warn!("unknown value for 'file_names': {label}")
+                    }
+                }
+                (Some("svg_margin_left"), Some(num_str)) => {
+                    if let Ok(num) = num_str.parse::<u64>() {
+                        config.svg_margin_left = num;
+                    } else {
+                        log::warn!("could not parse 'svg_margin_left' as number")
+                    }
+                }
+                (Some("svg_margin_right"), Some(num_str)) => {
+                    if let Ok(num) = num_str.parse::<u64>() {
+                        config.svg_margin_right = num;
+                    } else {
+                        log::warn!("could not parse 'svg_margin_right' as number")
+                    }
+                }
+                (Some("svg_margin_top"), Some(num_str)) => {
+                    if let Ok(num) = num_str.parse::<u64>() {
+                        config.svg_margin_top = num;
+                    } else {
+                        log::warn!("could not parse 'svg_margin_top' as number")
+                    }
+                }
+                (Some("svg_margin_bottom"), Some(num_str)) => {
+                    if let Ok(num) = num_str.parse::<u64>() {
+                        config.svg_margin_bottom = num;
+                    } else {
+                        log::warn!("could not parse 'svg_margin_bottom' as number")
+                    }
+                }
+                (Some("manipulate_button"), Some(label)) => {
+                    if let Some(value) = ManipulateButton::from_label(label) {
+                        config.manipulate_button = value;
+                    } else {
+                        log::warn!("unknown value for 'manipulate_button': {label}")
+                    }
+                }
+                (Some("integral_format"), Some(label)) => {
+                    if let Some(value) = IntegralFormat::from_label(label) {
+                        config.integral_format = value;
+                    } else {
+                        log::warn!("unknown value for 'integral_format': {label}")
+                    }
+                }
+                (Some("integral_precision"), Some(num_str)) => {
+                    if let Ok(num) = num_str.parse::<usize>() {
+                        config.integral_precision = num;
+                    } else {
+                        log::warn!("could not parse 'integral_precision' as number")
+                    }
+                }
+                (Some("integral_unit"), Some(value)) => {
+                    config.integral_unit = value.to_string();
+                }
+                (Some("duplicate_legend_names"), Some(label)) => {
+                    if let Some(value) = DuplicateLegendNames::from_label(label) {
+                        config.duplicate_legend_names = value;
+                    } else {
+                        log::warn!("unknown value for 'duplicate_legend_names': {label}")
+                    }
+                }
+                (Some("dim_unselected"), Some(flag_str)) => {
+                    if let Ok(flag) = flag_str.parse::<bool>() {
+                        config.dim_unselected = flag;
+                    } else {
+                        log::warn!("could not parse 'dim_unselected' as true/false")
+                    }
+                }
+                (Some("preview_rows"), Some(num_str)) => {
+                    if let Ok(num) = num_str.parse::<usize>() {
+                        config.preview_rows = num;
+                    } else {
+                        log::warn!("could not parse 'preview_rows' as number")
+                    }
+                }
+                (Some("live_transform_max_points"), Some(num_str)) => {
+                    if let Ok(num) = num_str.parse::<usize>() {
+                        config.live_transform_max_points = num;
+                    } else {
+                        log::warn!("could not parse 'live_transform_max_points' as number")
+                    }
+                }
+                (Some("startup_mode"), Some(label)) => {
+                    if let Some(value) = PlotterMode::from_label(label) {
+                        config.startup_mode = value;
+                    } else {
+                        log::warn!("unknown value for 'startup_mode': {label}")
+                    }
+                }
+                (Some("on_backend_crash"), Some(label)) => {
+                    if let Some(value) = BackendCrashAction::from_label(label) {
+                        config.on_backend_crash = value;
+                    } else {
+                        log::warn!("unknown value for 'on_backend_crash': {label}")
+                    }
+                }
+                (Some("hover_highlight"), Some(num_str)) => {
+                    if let Ok(num) = num_str.parse::<f32>() {
+                        config.hover_highlight = num;
+                    } else {
+                        log::warn!("could not parse 'hover_highlight' as number")
+                    }
+                }
+                (Some("auto_reload"), Some(flag_str)) => {
+                    if let Ok(flag) = flag_str.parse::<bool>() {
+                        config.auto_reload = flag;
+                    } else {
+                        log::warn!("could not parse 'auto_reload' as true/false")
+                    }
+                }
+                (Some("auto_reload_debounce_ms"), Some(num_str)) => {
+                    if let Ok(num) = num_str.parse::<u64>() {
+                        config.auto_reload_debounce_ms = num;
+                    } else {
+                        log::warn!("could not parse 'auto_reload_debounce_ms' as number")
+                    }
+                }
+                (Some("peak_window"), Some(num_str)) => {
+                    if let Ok(num) = num_str.parse::<f64>() {
+                        config.peak_window = num;
+                    } else {
+                        log::warn!("could not parse 'peak_window' as number")
+                    }
+                }
+                (Some("peak_prominence"), Some(num_str)) => {
+                    if let Ok(num) = num_str.parse::<f64>() {
+                        config.peak_prominence = num;
+                    } else {
+                        log::warn!("could not parse 'peak_prominence' as number")
+                    }
+                }
+                (Some("toolbar_layout"), Some(label)) => {
+                    if let Some(value) = ToolbarLayout::from_label(label) {
+                        config.toolbar_layout = value;
+                    } else {
+                        log::warn!("unknown value for 'toolbar_layout': {label}")
+                    }
+                }
+                (Some("keep_selection_on_mode_switch"), Some(flag_str)) => {
+                    if let Ok(flag) = flag_str.parse::<bool>() {
+                        config.keep_selection_on_mode_switch = flag;
+                    } else {
+                        log::warn!("could not parse 'keep_selection_on_mode_switch' as true/false")
+                    }
+                }
+                (Some("peak_label_format"), Some(value)) => {
+                    config.peak_label_format = value.to_string();
+                }
+                (Some("peak_label_decimals"), Some(num_str)) => {
+                    if let Ok(num) = num_str.parse::<usize>() {
+                        config.peak_label_decimals = num;
+                    } else {
+                        log::warn!("could not parse 'peak_label_decimals' as number")
+                    }
+                }
+                (Some("peak_label_offset"), Some(num_str)) => {
+                    if let Ok(num) = num_str.parse::<f32>() {
+                        config.peak_label_offset = num;
+                    } else {
+                        log::warn!("could not parse 'peak_label_offset' as number")
+                    }
+                }
+                (Some("paste_as_file"), Some(flag_str)) => {
+                    if let Ok(flag) = flag_str.parse::<bool>() {
+                        config.paste_as_file = flag;
+                    } else {
+                        log::warn!("could not parse 'paste_as_file' as true/false")
+                    }
+                }
+                (Some("hidden_groups_hint"), Some(flag_str)) => {
+                    if let Ok(flag) = flag_str.parse::<bool>() {
+                        config.hidden_groups_hint = flag;
+                    } else {
+                        log::warn!("could not parse 'hidden_groups_hint' as true/false")
+                    }
+                }
+                (Some(key), Some(value)) if key.starts_with("shortcut.") => {
+                    let id = &key["shortcut.".len()..];
+                    let Some(action) = Action::from_id(id) else {
+                        log::warn!("unknown action for shortcut: {id}");
+                        continue;
+                    };
+                    if value.trim().is_empty() {
+                        config.shortcuts.set(action, None);
+                    } else {
+                        match value.parse() {
+                            Ok(shortcut) => config.shortcuts.set(action, Some(shortcut)),
+                            Err(err) => log::warn!("could not parse shortcut of '{id}': {err}"),
+                        }
+                    }
+                }
+                (Some("adaptive_points_per_px"), Some(num_str)) => {
+                    if let Ok(num) = num_str.parse::<f64>() {
+                        config.adaptive_points_per_px = num;
+                    } else {
+                        log::warn!("could not parse 'adaptive_points_per_px' as number")
+                    }
+                }
+                (Some("group_tooltips"), Some(flag_str)) => {
+                    if let Ok(flag) = flag_str.parse::<bool>() {
+                        config.group_tooltips = flag;
+                    } else {
+                        log::warn!("could not parse 'group_tooltips' as true/false")
+                    }
+                }
+                (Some("color_mode"), Some(label)) => {
+                    if let Some(value) = ColorMode::from_label(label) {
+                        config.color_mode = value;
+                    } else {
+                        log::warn!("unknown value for 'color_mode': {label}")
+                    }
+                }
+                (Some("svg_unit"), Some(label)) => {
+                    if let Some(value) = SizeUnit::from_label(label) {
+                        config.svg_unit = value;
+                    } else {
+                        log::warn!("unknown value for 'svg_unit': {label}")
+                    }
+                }
+                (Some("svg_physical_width"), Some(num_str)) => match num_str.parse::<f64>() {
+                    Ok(num) if num > 0.0 => config.svg_physical_width = num,
+                    _ => log::warn!("could not parse 'svg_physical_width' as positive number"),
+                },
+                (Some("svg_physical_height"), Some(num_str)) => match num_str.parse::<f64>() {
+                    Ok(num) if num > 0.0 => config.svg_physical_height = num,
+                    _ => log::warn!("could not parse 'svg_physical_height' as positive number"),
+                },
                 _ => continue,
             }
         }
@@ -117,15 +1570,16 @@ impl Config {
     }
 
     fn to_config_file(&self) -> Result<(), String> {
-        #[allow(deprecated)]
-        let Some(config_file_path) =
-            std::env::home_dir().map(|path| path.join(PathBuf::from(".flugs")))
-        else {
+        let Some(config_file_path) = &self.file_path else {
             return Err("could open config file".into());
         };
 
         log::info!("attempting to save config to {config_file_path:?}");
 
+        if let Some(dir) = config_file_path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|err| format!("could not create config directory {dir:?}: {err}"))?;
+        }
         let mut config_file = match std::fs::File::create(config_file_path) {
             Ok(file) => file,
             Err(err) => return Err(format!("could not open config file: {err}")),
@@ -144,6 +1598,315 @@ impl Config {
             .push(config_file.write_all(&format!("x_label={}\n", self.x_label).into_bytes()));
         wrt_results
             .push(config_file.write_all(&format!("y_label={}\n", self.y_label).into_bytes()));
+        wrt_results.push(config_file.write_all(&format!("x_unit={}\n", self.x_unit).into_bytes()));
+        wrt_results.push(config_file.write_all(&format!("y_unit={}\n", self.y_unit).into_bytes()));
+        wrt_results.push(
+            config_file
+                .write_all(&format!("show_navigator={}\n", self.show_navigator).into_bytes()),
+        );
+        wrt_results.push(config_file.write_all(
+            &format!("empty_area_action={}\n", self.empty_area_action.label()).into_bytes(),
+        ));
+        wrt_results.push(
+            config_file.write_all(
+                &format!("search_debounce_ms={}\n", self.search_debounce_ms).into_bytes(),
+            ),
+        );
+        wrt_results
+            .push(config_file.write_all(&format!("undo_depth={}\n", self.undo_depth).into_bytes()));
+        wrt_results.push(
+            config_file.write_all(
+                &format!(
+                    "single_column={}\n",
+                    self.load_options.single_column.label()
+                )
+                .into_bytes(),
+            ),
+        );
+        wrt_results.push(
+            config_file.write_all(
+                &format!(
+                    "whitespace_delimited={}\n",
+                    self.load_options.whitespace_delimited.label()
+                )
+                .into_bytes(),
+            ),
+        );
+        wrt_results.push(
+            config_file.write_all(
+                &format!("encoding={}\n", self.load_options.encoding.label()).into_bytes(),
+            ),
+        );
+        wrt_results.push(config_file.write_all(
+            &format!("max_file_size_mb={}\n", self.load_options.max_file_size_mb).into_bytes(),
+        ));
+        wrt_results.push(config_file.write_all(
+            &format!("delimiter={}\n", self.load_options.delimiter.label()).into_bytes(),
+        ));
+        wrt_results.push(
+            config_file.write_all(
+                &format!(
+                    "decimal_separator={}\n",
+                    self.load_options.decimal_separator.label()
+                )
+                .into_bytes(),
+            ),
+        );
+        wrt_results.push(
+            config_file.write_all(
+                &format!(
+                    "thousands_separator={}\n",
+                    self.load_options.thousands_separator.label()
+                )
+                .into_bytes(),
+            ),
+        );
+        wrt_results.push(
+            config_file.write_all(
+                &format!(
+                    "detect_number_format={}\n",
+                    self.load_options.detect_number_format
+                )
+                .into_bytes(),
+            ),
+        );
+        wrt_results.push(
+            config_file.write_all(
+                &format!(
+                    "infinite_values={}\n",
+                    self.load_options.infinite_values.label()
+                )
+                .into_bytes(),
+            ),
+        );
+        wrt_results.push(
+            config_file
+                .write_all(&format!("cache_file_data={}\n", self.cache_file_data).into_bytes()),
+        );
+
+        wrt_results.push(
+            config_file.write_all(
+                &format!("continuous_repaint={}\n", self.continuous_repaint).into_bytes(),
+            ),
+        );
+
+        wrt_results.push(
+            config_file.write_all(&format!("show_extrema={}\n", self.show_extrema).into_bytes()),
+        );
+
+        wrt_results
+            .push(config_file.write_all(
+                &format!("export_window={}\n", self.export_window.label()).into_bytes(),
+            ));
+
+        wrt_results
+            .push(config_file.write_all(
+                &format!("interpolation={}\n", self.interpolation.label()).into_bytes(),
+            ));
+
+        wrt_results.push(
+            config_file.write_all(&format!("plot_size={}\n", self.plot_size.label()).into_bytes()),
+        );
+
+        wrt_results.push(
+            config_file.write_all(
+                &format!("simplify_tolerance={}\n", self.simplify_tolerance).into_bytes(),
+            ),
+        );
+
+        wrt_results.push(
+            config_file.write_all(
+                &format!("click_tolerance_px={}\n", self.click_tolerance_px).into_bytes(),
+            ),
+        );
+
+        wrt_results.push(
+            config_file
+                .write_all(&format!("group_files_by={}\n", self.group_files_by).into_bytes()),
+        );
+
+        wrt_results.push(
+            config_file
+                .write_all(&format!("file_names={}\n", self.file_names.label()).into_bytes()),
+        );
+
+        wrt_results.push(
+            config_file
+                .write_all(&format!("svg_margin_left={}\n", self.svg_margin_left).into_bytes()),
+        );
+
+        wrt_results.push(
+            config_file
+                .write_all(&format!("svg_margin_right={}\n", self.svg_margin_right).into_bytes()),
+        );
+
+        wrt_results.push(
+            config_file
+                .write_all(&format!("svg_margin_top={}\n", self.svg_margin_top).into_bytes()),
+        );
+
+        wrt_results
+            .push(config_file.write_all(
+                &format!("svg_margin_bottom={}\n", self.svg_margin_bottom).into_bytes(),
+            ));
+
+        wrt_results.push(config_file.write_all(
+            &format!("manipulate_button={}\n", self.manipulate_button.label()).into_bytes(),
+        ));
+
+        wrt_results.push(config_file.write_all(
+            &format!("integral_format={}\n", self.integral_format.label()).into_bytes(),
+        ));
+
+        wrt_results.push(
+            config_file.write_all(
+                &format!("integral_precision={}\n", self.integral_precision).into_bytes(),
+            ),
+        );
+
+        wrt_results.push(
+            config_file.write_all(&format!("integral_unit={}\n", self.integral_unit).into_bytes()),
+        );
+
+        wrt_results.push(
+            config_file.write_all(
+                &format!(
+                    "duplicate_legend_names={}\n",
+                    self.duplicate_legend_names.label()
+                )
+                .into_bytes(),
+            ),
+        );
+
+        wrt_results.push(
+            config_file
+                .write_all(&format!("dim_unselected={}\n", self.dim_unselected).into_bytes()),
+        );
+
+        wrt_results.push(
+            config_file.write_all(&format!("preview_rows={}\n", self.preview_rows).into_bytes()),
+        );
+
+        wrt_results.push(
+            config_file.write_all(
+                &format!(
+                    "live_transform_max_points={}\n",
+                    self.live_transform_max_points
+                )
+                .into_bytes(),
+            ),
+        );
+
+        wrt_results.push(
+            config_file
+                .write_all(&format!("startup_mode={}\n", self.startup_mode.label()).into_bytes()),
+        );
+
+        wrt_results.push(config_file.write_all(
+            &format!("on_backend_crash={}\n", self.on_backend_crash.label()).into_bytes(),
+        ));
+
+        wrt_results.push(
+            config_file
+                .write_all(&format!("hover_highlight={}\n", self.hover_highlight).into_bytes()),
+        );
+
+        wrt_results.push(
+            config_file.write_all(&format!("auto_reload={}\n", self.auto_reload).into_bytes()),
+        );
+
+        wrt_results.push(config_file.write_all(
+            &format!("auto_reload_debounce_ms={}\n", self.auto_reload_debounce_ms).into_bytes(),
+        ));
+
+        wrt_results.push(
+            config_file.write_all(&format!("peak_window={}\n", self.peak_window).into_bytes()),
+        );
+
+        wrt_results.push(
+            config_file
+                .write_all(&format!("peak_prominence={}\n", self.peak_prominence).into_bytes()),
+        );
+
+        wrt_results.push(
+            config_file.write_all(
+                &format!("toolbar_layout={}\n", self.toolbar_layout.label()).into_bytes(),
+            ),
+        );
+
+        wrt_results.push(
+            config_file.write_all(
+                &format!(
+                    "keep_selection_on_mode_switch={}\n",
+                    self.keep_selection_on_mode_switch
+                )
+                .into_bytes(),
+            ),
+        );
+
+        wrt_results
+            .push(config_file.write_all(
+                &format!("peak_label_format={}\n", self.peak_label_format).into_bytes(),
+            ));
+
+        wrt_results.push(config_file.write_all(
+            &format!("peak_label_decimals={}\n", self.peak_label_decimals).into_bytes(),
+        ));
+
+        wrt_results
+            .push(config_file.write_all(
+                &format!("peak_label_offset={}\n", self.peak_label_offset).into_bytes(),
+            ));
+
+        wrt_results.push(
+            config_file.write_all(&format!("paste_as_file={}\n", self.paste_as_file).into_bytes()),
+        );
+
+        wrt_results.push(
+            config_file.write_all(
+                &format!("hidden_groups_hint={}\n", self.hidden_groups_hint).into_bytes(),
+            ),
+        );
+
+        // Only the remapped shortcuts are written, so that changed defaults
+        // apply to the others.
+        for (action, shortcut) in self.shortcuts.overrides() {
+            let shortcut = shortcut
+                .map(|shortcut| shortcut.to_string())
+                .unwrap_or_default();
+            wrt_results.push(
+                config_file
+                    .write_all(&format!("shortcut.{}={shortcut}\n", action.id()).into_bytes()),
+            );
+        }
+
+        wrt_results.push(config_file.write_all(
+            &format!("adaptive_points_per_px={}\n", self.adaptive_points_per_px).into_bytes(),
+        ));
+
+        wrt_results.push(
+            config_file
+                .write_all(&format!("group_tooltips={}\n", self.group_tooltips).into_bytes()),
+        );
+
+        wrt_results.push(
+            config_file
+                .write_all(&format!("color_mode={}\n", self.color_mode.label()).into_bytes()),
+        );
+
+        wrt_results.push(
+            config_file.write_all(&format!("svg_unit={}\n", self.svg_unit.label()).into_bytes()),
+        );
+
+        wrt_results.push(
+            config_file.write_all(
+                &format!("svg_physical_width={}\n", self.svg_physical_width).into_bytes(),
+            ),
+        );
+
+        wrt_results.push(config_file.write_all(
+            &format!("svg_physical_height={}\n", self.svg_physical_height).into_bytes(),
+        ));
 
         for res in wrt_results {
             if let Err(e) = res {
@@ -165,4 +1928,41 @@ mod tests {
         let res = Config::from_config_file();
         // dbg!(res);
     }
+
+    #[test]
+    fn test_format_integral() {
+        let mut config = Config::default();
+        assert_eq!(config.format_integral(1234.5), "1234.5");
+        config.integral_unit = "counts·nm".to_string();
+        config.integral_format = IntegralFormat::Scientific;
+        config.integral_precision = 2;
+        assert_eq!(config.format_integral(1234.5), "1.23e3 counts·nm");
+        config.integral_format = IntegralFormat::Fixed;
+        assert_eq!(config.format_integral(1234.5), "1234.50 counts·nm");
+    }
+
+    #[test]
+    fn test_svg_size() {
+        let mut config = Config::default();
+        assert_eq!(config.svg_size(), (config.svg_width, config.svg_height));
+        assert_eq!(config.svg_physical_size(), None);
+        config.svg_unit = SizeUnit::Cm;
+        config.svg_physical_width = 8.5;
+        config.svg_physical_height = 2.54;
+        assert_eq!(config.svg_size(), (321, 96));
+        assert_eq!(
+            config.svg_physical_size(),
+            Some(("8.5cm".to_string(), "2.54cm".to_string()))
+        );
+
+        // Sizes that are not positive are ignored.
+        let path = std::env::temp_dir().join("flugs_test_svg_size.flugs");
+        std::fs::write(&path, "svg_physical_width=-3\nsvg_physical_height=5\n").unwrap();
+        let config = Config::from_config_file_at(&path);
+        std::fs::remove_file(&path).unwrap();
+        let config = config.unwrap();
+        let default = Config::default();
+        assert_eq!(config.svg_physical_width, default.svg_physical_width);
+        assert_eq!(config.svg_physical_height, 5.0);
+    }
 }
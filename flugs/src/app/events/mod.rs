@@ -2,13 +2,20 @@ use std::{path::PathBuf, thread::JoinHandle};
 
 use derive_new::new;
 
-use crate::app::storage::{load_json, save_json};
+use crate::{
+    app::storage::{load_json, merge_json, save_json},
+    utils::{fit::PeakFit, zip::write_zip},
+    BackendAppState,
+};
 
 use super::{
-    components::{FileID, Group},
-    EguiApp,
+    components::{BundleContents, FileID, Group},
+    EguiApp, UISelection,
+};
+use app_core::{
+    backend::{send_request, BackendEventLoop, BackendLink, LinkReceiver},
+    event::{AppEvent, EventState},
 };
-use app_core::event::{AppEvent, EventState};
 
 // ---------------------------------------------------------------------------
 //
@@ -43,6 +50,16 @@ impl<EguiApp> EventQueue<EguiApp> {
         self.queue.push(event);
     }
 
+    /// Number of queued and busy events.
+    pub fn len(&self) -> usize {
+        self.queue.len() + self.tmp_backlog.len()
+    }
+
+    /// Whether there are no queued or busy events.
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty() && self.tmp_backlog.is_empty()
+    }
+
     pub fn discard_events(&mut self) {
         self.queue.drain(..);
         self.tmp_backlog.drain(..);
@@ -108,6 +125,30 @@ pub struct RemoveGroup {
     gid: usize,
 }
 
+/// Selects a file in the plot, optionally zooming on its data and switching
+/// to the plot view.
+#[derive(new)]
+pub struct SelectFile {
+    fid: FileID,
+    jump: bool,
+}
+
+/// Adds a group with the other files of a group divided by one of its files,
+/// see `FileHandler::normalize_group`.
+#[derive(new)]
+pub struct NormalizeGroup {
+    gid: usize,
+    reference: FileID,
+}
+
+/// Brings a group to the front of the plot (`to_front == true`) or sends it
+/// to the back.
+#[derive(new)]
+pub struct ReorderGroup {
+    gid: usize,
+    to_front: bool,
+}
+
 /// Handles both, saving and loading the app state, depending on whether
 /// `should_save` is true or false.
 #[derive(new)]
@@ -116,6 +157,13 @@ pub struct SaveLoadRequested {
     thread_handle: Option<JoinHandle<Option<PathBuf>>>,
 }
 
+/// Adds the groups of the selected session to the current ones, see
+/// `merge_json`.
+#[derive(new)]
+pub struct MergeSessionRequested {
+    thread_handle: Option<JoinHandle<Option<PathBuf>>>,
+}
+
 /// Handles both, saving and loading the app state, depending on whether
 /// `should_save` is true or false.
 #[derive(new)]
@@ -123,6 +171,43 @@ pub struct SavePlotRequested {
     thread_handle: Option<JoinHandle<Option<PathBuf>>>,
 }
 
+/// Exports the data within the integration region, either into one
+/// `combined` file or into one file per input in the selected directory.
+#[derive(new)]
+pub struct ExportRegionRequested {
+    combined: bool,
+    thread_handle: Option<JoinHandle<Option<PathBuf>>>,
+}
+
+/// Exports the plotted data into one CSV per plotted group, in the selected
+/// directory.
+#[derive(new)]
+pub struct ExportGroupsRequested {
+    thread_handle: Option<JoinHandle<Option<PathBuf>>>,
+}
+
+/// Exports the selected artifacts (plot, data) into one ZIP archive. The
+/// archive is written on the backend, the event is busy until it is done.
+#[derive(new)]
+pub struct ExportBundleRequested {
+    contents: BundleContents,
+    thread_handle: Option<JoinHandle<Option<PathBuf>>>,
+    #[new(default)]
+    written: Option<LinkReceiver<Result<(), String>>>,
+}
+
+/// Exports the Gaussian fits of the peaks of the file `name`, as shown in the
+/// Fit Peaks menu, to CSV. The file is written on the backend, the event is
+/// busy until it is done.
+#[derive(new)]
+pub struct ExportPeakFitsRequested {
+    name: String,
+    fits: Vec<PeakFit>,
+    thread_handle: Option<JoinHandle<Option<PathBuf>>>,
+    #[new(default)]
+    written: Option<LinkReceiver<Result<(), String>>>,
+}
+
 // ---------------------------------------------------------------------------
 //
 //
@@ -185,6 +270,17 @@ impl AppEvent for MoveFile {
     }
 }
 
+impl AppEvent for NormalizeGroup {
+    type App = EguiApp;
+
+    fn apply(&mut self, app: &mut Self::App) -> Result<EventState, String> {
+        let interpolation = app.config.interpolation;
+        app.file_handler
+            .normalize_group(self.gid, self.reference, interpolation)?;
+        Ok(EventState::Finished)
+    }
+}
+
 impl AppEvent for RemoveGroup {
     type App = EguiApp;
 
@@ -194,6 +290,37 @@ impl AppEvent for RemoveGroup {
     }
 }
 
+impl AppEvent for SelectFile {
+    type App = EguiApp;
+
+    fn apply(&mut self, app: &mut Self::App) -> Result<EventState, String> {
+        let extent = if self.jump {
+            let file = app
+                .file_handler
+                .registry
+                .get(&self.fid)
+                .ok_or(format!("file ID {:?} not in registry", self.fid))?;
+            app.ui_selection = UISelection::Plot;
+            file.extent()
+        } else {
+            None
+        };
+        app.plotter.select_file(self.fid, extent);
+        Ok(EventState::Finished)
+    }
+}
+
+impl AppEvent for ReorderGroup {
+    type App = EguiApp;
+
+    fn apply(&mut self, app: &mut Self::App) -> Result<EventState, String> {
+        app.file_handler
+            .move_group(self.gid, self.to_front)
+            .ok_or(format!("unable to reorder group with ID {}", self.gid))?;
+        Ok(EventState::Finished)
+    }
+}
+
 impl AppEvent for SaveLoadRequested {
     type App = EguiApp;
 
@@ -222,6 +349,23 @@ impl AppEvent for SaveLoadRequested {
     }
 }
 
+impl AppEvent for MergeSessionRequested {
+    type App = EguiApp;
+
+    fn apply(&mut self, app: &mut Self::App) -> Result<EventState, String> {
+        let Some(handle) = self.thread_handle.take_if(|handle| handle.is_finished()) else {
+            return Ok(EventState::Busy);
+        };
+        match handle.join() {
+            Ok(Some(path)) => merge_json(app, &path)
+                .map_err(|err| format!("could not merge session {path:?}: {err}"))?,
+            Ok(None) => (),
+            Err(err) => return Err(format!("unable to merge session: {err:?}")),
+        }
+        Ok(EventState::Finished)
+    }
+}
+
 impl AppEvent for SavePlotRequested {
     type App = EguiApp;
 
@@ -242,3 +386,123 @@ impl AppEvent for SavePlotRequested {
         }
     }
 }
+
+impl AppEvent for ExportRegionRequested {
+    type App = EguiApp;
+
+    fn apply(&mut self, app: &mut Self::App) -> Result<EventState, String> {
+        if let Some(handle) = self.thread_handle.take_if(|handle| handle.is_finished()) {
+            match handle.join() {
+                Ok(Some(path)) => {
+                    super::components::save_region_csv(app, &path, self.combined)?;
+                }
+                Ok(None) => (),
+                Err(err) => {
+                    log::error!("unable to export region data: {:?}", err)
+                }
+            };
+            Ok(EventState::Finished)
+        } else {
+            Ok(EventState::Busy)
+        }
+    }
+}
+
+impl AppEvent for ExportGroupsRequested {
+    type App = EguiApp;
+
+    fn apply(&mut self, app: &mut Self::App) -> Result<EventState, String> {
+        if let Some(handle) = self.thread_handle.take_if(|handle| handle.is_finished()) {
+            match handle.join() {
+                Ok(Some(dir)) => {
+                    super::components::save_group_csvs(app, &dir)?;
+                }
+                Ok(None) => (),
+                Err(err) => {
+                    log::error!("unable to export group data: {:?}", err)
+                }
+            };
+            Ok(EventState::Finished)
+        } else {
+            Ok(EventState::Busy)
+        }
+    }
+}
+
+impl AppEvent for ExportBundleRequested {
+    type App = EguiApp;
+
+    fn apply(&mut self, app: &mut Self::App) -> Result<EventState, String> {
+        // Wait for the backend to write the archive.
+        if let Some(rx) = &self.written {
+            return match rx.try_recv() {
+                Ok(result) => {
+                    result?;
+                    log::info!("exported bundle");
+                    Ok(EventState::Finished)
+                }
+                Err(std::sync::mpsc::TryRecvError::Empty) => Ok(EventState::Busy),
+                Err(err) => Err(format!("backend did not write bundle: {err}")),
+            };
+        }
+        let Some(handle) = self.thread_handle.take_if(|handle| handle.is_finished()) else {
+            return Ok(EventState::Busy);
+        };
+        let path = match handle.join() {
+            Ok(Some(path)) => path,
+            Ok(None) => return Ok(EventState::Finished),
+            Err(err) => return Err(format!("unable to export bundle: {err:?}")),
+        };
+        // The artifacts are collected here, since they depend on the UI state.
+        let entries = super::components::bundle_entries(app, &self.contents)?;
+        let (rx, linker) = BackendLink::new(
+            &format!("write bundle to {path:?}"),
+            move |_b: &mut BackendEventLoop<BackendAppState>| {
+                let mut file = std::fs::File::create(&path)
+                    .map_err(|err| format!("could not create {path:?}: {err}"))?;
+                write_zip(&mut file, &entries)
+            },
+        );
+        send_request(&app.request_tx, Box::new(linker));
+        self.written = Some(rx);
+        Ok(EventState::Busy)
+    }
+}
+
+impl AppEvent for ExportPeakFitsRequested {
+    type App = EguiApp;
+
+    fn apply(&mut self, app: &mut Self::App) -> Result<EventState, String> {
+        // Wait for the backend to write the file.
+        if let Some(rx) = &self.written {
+            return match rx.try_recv() {
+                Ok(result) => {
+                    result?;
+                    log::info!("exported peak fits");
+                    Ok(EventState::Finished)
+                }
+                Err(std::sync::mpsc::TryRecvError::Empty) => Ok(EventState::Busy),
+                Err(err) => Err(format!("backend did not write peak fits: {err}")),
+            };
+        }
+        let Some(handle) = self.thread_handle.take_if(|handle| handle.is_finished()) else {
+            return Ok(EventState::Busy);
+        };
+        let path = match handle.join() {
+            Ok(Some(path)) => path,
+            Ok(None) => return Ok(EventState::Finished),
+            Err(err) => return Err(format!("unable to export peak fits: {err:?}")),
+        };
+        let contents = super::components::peak_fits_csv(&self.name, &self.fits);
+        let (rx, linker) = BackendLink::new(
+            &format!("write peak fits to {path:?}"),
+            move |_b: &mut BackendEventLoop<BackendAppState>| {
+                std::fs::write(&path, &contents)
+                    .map_err(|err| format!("could not write {path:?}: {err}"))
+            },
+        );
+        send_request(&app.request_tx, Box::new(linker));
+        self.written = Some(rx);
+        Ok(EventState::Busy)
+    }
+}
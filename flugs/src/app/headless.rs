@@ -0,0 +1,79 @@
+//! Rendering a saved session to a file without opening a window.
+
+use std::{path::Path, time::Duration};
+
+use app_core::backend::{request_stop, BackendEventLoop};
+
+use super::{components::render_svg, config::Config, storage::load_json, EguiApp};
+use crate::BackendAppState;
+
+/// Load the session at `session_path`, wait until all its files are loaded
+/// and write the plot to `output_path`. The plot shows all plotted groups,
+/// as if zoomed out completely. Only SVG output is supported.
+pub fn render_session(
+    config: Config,
+    session_path: &Path,
+    output_path: &Path,
+) -> Result<(), String> {
+    if !output_path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("svg"))
+    {
+        return Err(format!(
+            "cannot render to {output_path:?}, only SVG output is supported"
+        ));
+    }
+
+    let (request_tx, request_rx) = std::sync::mpsc::channel();
+    let backend_state =
+        BackendAppState::new(config.search_path.clone(), config.load_options.clone());
+    let eventloop = BackendEventLoop::new(request_rx, backend_state);
+    let pending_requests = eventloop.pending_requests();
+    let eventloop_handle = eventloop.run();
+
+    let mut app = EguiApp::without_window(config, request_tx, eventloop_handle, pending_requests);
+    let result = app.render_session(session_path, output_path);
+
+    if let Some(handle) = app.backend_thread_handle.take() {
+        request_stop(&app.request_tx, handle);
+    }
+    result
+}
+
+impl EguiApp {
+    fn render_session(&mut self, session_path: &Path, output_path: &Path) -> Result<(), String> {
+        load_json(self, Some(session_path))?;
+        while self.file_handler.is_busy() {
+            std::thread::sleep(Duration::from_millis(10));
+            self.file_handler.try_update();
+        }
+
+        let failed: Vec<_> = self
+            .file_handler
+            .registry
+            .values()
+            .filter_map(|file| Some((file.path.clone(), file.load_error()?)))
+            .collect();
+        for (path, err) in failed.iter() {
+            log::error!("could not load {path:?}: {err}");
+        }
+        if !failed.is_empty() {
+            return Err(format!(
+                "{} file(s) of the session failed to load",
+                failed.len()
+            ));
+        }
+
+        let Some(extent) = self.file_handler.plotted_extent() else {
+            return Err("the session contains no data to plot".into());
+        };
+        self.plotter.fit_bounds(extent);
+        self.file_handler
+            .update_display_names(self.config.file_names);
+
+        std::fs::write(output_path, render_svg(self))
+            .map_err(|err| format!("could not write {output_path:?}: {err}"))?;
+        log::info!("rendered {session_path:?} to {output_path:?}");
+        Ok(())
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::VecDeque;
+
+/// Undo/redo history of application states.
+///
+/// The history is fed with the current state each frame (`record`), new
+/// entries are only created if the state differs from the last recorded one.
+/// At most `depth` states are retained, the oldest ones are dropped first.
+#[derive(Debug)]
+pub struct History<T> {
+    undo_stack: VecDeque<T>,
+    redo_stack: Vec<T>,
+    /// The state as it was when `record` was called the last time.
+    current: Option<T>,
+    depth: usize,
+}
+
+impl<T: Clone + PartialEq> History<T> {
+    pub fn new(depth: usize) -> Self {
+        Self {
+            undo_stack: VecDeque::with_capacity(depth),
+            redo_stack: Vec::new(),
+            current: None,
+            depth,
+        }
+    }
+
+    /// Store `state` as the current state, pushing the previous state to the
+    /// undo stack if it differs. Returns whether a state was pushed.
+    pub fn record(&mut self, state: &T) -> bool {
+        match &self.current {
+            Some(current) if current == state => false,
+            Some(_) => {
+                if let Some(previous) = self.current.replace(state.clone()) {
+                    self.push_undo(previous);
+                }
+                self.redo_stack.clear();
+                true
+            }
+            None => {
+                self.current = Some(state.clone());
+                false
+            }
+        }
+    }
+
+    /// Return the state before the current one, if any.
+    pub fn undo(&mut self) -> Option<T> {
+        let previous = self.undo_stack.pop_back()?;
+        if let Some(current) = self.current.replace(previous.clone()) {
+            self.redo_stack.push(current);
+        }
+        Some(previous)
+    }
+
+    /// Return the state that was undone last, if any.
+    pub fn redo(&mut self) -> Option<T> {
+        let next = self.redo_stack.pop()?;
+        if let Some(current) = self.current.replace(next.clone()) {
+            self.push_undo(current);
+        }
+        Some(next)
+    }
+
+    /// Set the maximum number of states to retain, dropping the oldest ones
+    /// if there are too many already.
+    pub fn set_depth(&mut self, depth: usize) {
+        self.depth = depth;
+        while self.undo_stack.len() > depth {
+            self.undo_stack.pop_front();
+        }
+    }
+
+    /// All retained states: the undo stack, the current state and the redo
+    /// stack.
+    pub fn states(&self) -> impl Iterator<Item = &T> {
+        self.undo_stack
+            .iter()
+            .chain(self.current.iter())
+            .chain(self.redo_stack.iter())
+    }
+
+    /// Forget all states, e.g. when a new session was loaded.
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.current = None;
+    }
+
+    fn push_undo(&mut self, state: T) {
+        if self.depth == 0 {
+            return;
+        }
+        if self.undo_stack.len() >= self.depth {
+            self.undo_stack.pop_front();
+        }
+        self.undo_stack.push_back(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_depth() {
+        let mut history = History::new(2);
+        assert!(!history.record(&0));
+        assert!(!history.record(&0));
+        for state in 1..5 {
+            assert!(history.record(&state));
+        }
+        // Only the two states before the current one are retained.
+        assert_eq!(history.undo(), Some(3));
+        assert_eq!(history.undo(), Some(2));
+        assert_eq!(history.undo(), None);
+        assert_eq!(history.redo(), Some(3));
+        assert_eq!(history.redo(), Some(4));
+        assert_eq!(history.redo(), None);
+        assert_eq!(history.states().collect::<Vec<_>>(), [&2, &3, &4]);
+    }
+}
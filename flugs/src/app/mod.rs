@@ -1,33 +1,95 @@
+mod commands;
 mod components;
 pub mod config;
 mod events;
+mod headless;
+mod history;
+mod palette;
+mod shortcuts;
 pub mod storage;
 
-use self::components::{Plotter, Search};
+use self::components::{
+    BundleContents, File, PlotTabs, Plotter, Search, Structure, CHANGE_CHECK_INTERVAL,
+};
 use crate::app::events::EventQueue;
+use crate::backend_state::LoadOptions;
+use crate::utils::transform::DataTransform;
 use crate::BackendAppState;
-use app_core::backend::BackendRequest;
-use config::Config;
-use events::{SaveLoadRequested, SavePlotRequested};
+use app_core::backend::{BackendEventLoop, BackendLink, BackendRequest, PendingRequests};
+use app_core::frontend::UIParameter;
+use app_core::BACKEND_HUNG_UP_MSG;
+use commands::{Command, CommandLine};
+use config::{BackendCrashAction, Config};
+use events::{
+    ExportBundleRequested, ExportGroupsRequested, MergeSessionRequested, SaveLoadRequested,
+    SavePlotRequested,
+};
+use history::History;
+use palette::{Action, CommandPalette};
+use shortcuts::Keymap;
 use storage::{load_json, save_json};
 
-pub use crate::app::components::FileHandler;
 pub use crate::app::components::PlotterMode;
+pub use crate::app::components::{
+    FileHandler, FileID, FileInfo, FileProperties, LineCap, LineJoin, TransformMode,
+};
+pub use headless::render_session;
 
-use std::{sync::mpsc::Sender, thread::JoinHandle, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    sync::mpsc::Sender,
+    thread::JoinHandle,
+    time::Duration,
+};
 
 pub type DynRequestSender = Sender<Box<dyn BackendRequest<BackendAppState>>>;
 
+/// The part of the app state that can be undone: the properties of the
+/// files and which files are in which group. The data of removed files is
+/// kept by the file handler, not in the snapshots.
+#[derive(Clone, Debug, PartialEq)]
+struct Snapshot {
+    properties: HashMap<FileID, FileProperties>,
+    structure: Structure,
+}
+
+/// What is shown in the plot, to detect changes that need a repaint.
+type ViewState = (Snapshot, [f64; 4], Option<FileID>);
+
 pub struct EguiApp {
     config: Config,
     backend_thread_handle: Option<JoinHandle<()>>,
+    /// Number of requests the backend did not handle yet.
+    pending_requests: PendingRequests,
     file_handler: FileHandler,
+    /// The plotter of the active plot tab.
     plotter: Plotter,
+    plot_tabs: PlotTabs,
     request_tx: DynRequestSender,
     search: Search,
     shortcuts_modal_open: bool,
+    debug_window_open: bool,
+    /// Commands typed into it are run, see [`Command`].
+    command_line: Option<CommandLine>,
+    /// The command palette, if it is open.
+    command_palette: Option<CommandPalette>,
+    /// Why the backend thread stopped, if it stopped unexpectedly and was
+    /// not restarted yet.
+    backend_crash: Option<String>,
+    /// Artifacts to include when exporting a bundle.
+    bundle_contents: BundleContents,
     ui_selection: UISelection,
     event_queue: EventQueue<Self>,
+    /// Load options currently used by the backend.
+    load_options: UIParameter<LoadOptions>,
+    history: History<Snapshot>,
+    /// The view state of the last frame, only tracked if the plot is not
+    /// repainted continuously.
+    last_view: Option<ViewState>,
+    /// The clipboard was requested to be pasted as a new file in this frame,
+    /// see `Action::PasteAsFile`.
+    paste_requested: bool,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -53,122 +115,521 @@ impl EguiApp {
         config: Config,
         request_tx: Sender<Box<dyn BackendRequest<BackendAppState>>>,
         backend_thread_handle: JoinHandle<()>,
+        pending_requests: PendingRequests,
+    ) -> Self {
+        Self::without_window(config, request_tx, backend_thread_handle, pending_requests)
+    }
+
+    /// Create the app without an egui context, e.g. to render headless.
+    fn without_window(
+        config: Config,
+        request_tx: Sender<Box<dyn BackendRequest<BackendAppState>>>,
+        backend_thread_handle: JoinHandle<()>,
+        pending_requests: PendingRequests,
     ) -> Self {
         let mut search = Search::new(request_tx.clone());
         search.set_search_path(&config.search_path);
+        let load_options = UIParameter::new(config.load_options.clone());
+        let history = History::new(config.undo_depth);
+        let plotter = Plotter::new().with_mode(config.startup_mode);
 
         Self {
             config,
             backend_thread_handle: Some(backend_thread_handle),
+            pending_requests,
             file_handler: Default::default(),
-            plotter: Plotter::new(),
+            plotter,
+            plot_tabs: PlotTabs::default(),
             request_tx,
             search,
             shortcuts_modal_open: false,
+            debug_window_open: false,
+            command_line: None,
+            command_palette: None,
+            backend_crash: None,
+            bundle_contents: BundleContents::default(),
             ui_selection: UISelection::Plot,
             event_queue: EventQueue::<Self>::new(),
+            load_options,
+            history,
+            last_view: None,
+            paste_requested: false,
         }
     }
 
+    /// The loaded files, e.g. to inspect them with [`FileHandler::files`].
+    pub fn file_handler(&self) -> &FileHandler {
+        &self.file_handler
+    }
+
+    /// Load files into the first group, e.g. files given on the command line.
+    /// If a grouping pattern is configured, files are sorted into groups
+    /// according to their name instead.
+    pub fn open_files(&mut self, paths: &[PathBuf]) {
+        match self.config.group_pattern() {
+            Some(Ok(pattern)) => {
+                self.file_handler
+                    .add_files_by_pattern(paths, &pattern, 0, &mut self.request_tx);
+                return;
+            }
+            Some(Err(err)) => log::warn!("ignoring grouping pattern: {err}"),
+            None => (),
+        }
+        for path in paths {
+            self.file_handler.add_file(path, 0, &mut self.request_tx);
+        }
+    }
+
+    /// Finish what can be finished before the app closes: events whose
+    /// dialogs were already closed run one last time (so that e.g. exports
+    /// are written completely), then the backend handles the requests sent
+    /// so far and its thread is joined.
+    fn shutdown(&mut self) {
+        log::info!("shutting down");
+        self.run_events();
+        if !self.event_queue.is_empty() {
+            log::debug!(
+                "discarding {} event(s) still waiting for input",
+                self.event_queue.len()
+            );
+            self.event_queue.discard_events();
+        }
+        log::debug!(
+            "waiting for {} pending backend request(s)",
+            self.pending_requests.get()
+        );
+        if let Some(handle) = self.backend_thread_handle.take() {
+            app_core::backend::request_stop(&self.request_tx, handle);
+        }
+        log::info!("shutdown complete");
+    }
+
+    /// Offer `transform` in the properties of each file, see
+    /// [`crate::utils::transform`].
+    pub fn register_transform(
+        &mut self,
+        transform: impl DataTransform + 'static,
+    ) -> Result<(), String> {
+        self.file_handler.transforms.register(transform)
+    }
+
     fn reset_state(&mut self) {
-        self.file_handler = Default::default();
+        // Registered transforms belong to the app rather than the session.
+        let transforms = std::mem::take(&mut self.file_handler.transforms);
+        // IDs are not reused, so that nothing refers to a new file by the ID
+        // of an old one.
+        let next_id = self.file_handler.current_id();
+        self.file_handler = FileHandler::new(Default::default(), HashMap::new(), next_id);
+        self.file_handler.transforms = transforms;
+        self.reset_plots();
         self.event_queue.discard_events();
+        self.history.clear();
     }
 
-    fn update_state(&mut self) {
-        self.run_events();
-        self.file_handler.try_update();
-        self.search.try_update();
+    /// Drop the plot tabs and the state of the plotter, which refer to the
+    /// groups and files of a file handler that was replaced.
+    fn reset_plots(&mut self) {
+        self.plot_tabs = PlotTabs::default();
+        self.plotter = Plotter::new().with_mode(self.config.startup_mode);
     }
-}
 
-impl eframe::App for EguiApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        ctx.request_repaint_after(Duration::from_millis(50));
-        self.update_state();
+    fn snapshot(&self) -> Snapshot {
+        let properties = self
+            .file_handler
+            .registry
+            .iter()
+            .map(|(fid, file)| (*fid, file.properties.clone()))
+            .collect();
+        Snapshot {
+            properties,
+            structure: self.file_handler.structure(),
+        }
+    }
 
-        let mut should_quit = false;
+    /// Record the current state in the undo history, unless the user is still
+    /// interacting (e.g. dragging a plot), so that one interaction results in
+    /// one undo step.
+    fn record_history(&mut self, snapshot: &Snapshot, ctx: &egui::Context) {
+        if ctx.input(|i| i.pointer.any_down()) {
+            return;
+        }
+        self.history.set_depth(self.config.undo_depth);
+        if !self.history.record(snapshot) {
+            return;
+        }
+        // Removed files are kept as long as undo may bring them back.
+        let referenced: HashSet<FileID> = self
+            .history
+            .states()
+            .flat_map(|snapshot| snapshot.properties.keys().copied())
+            .collect();
+        self.file_handler.forget_removed(&referenced);
+    }
 
-        // Handle keyboard input.
-        ctx.input(|i| {
-            // Help window.
-            if i.key_pressed(egui::Key::F1) {
-                self.shortcuts_modal_open = !self.shortcuts_modal_open;
+    fn restore_snapshot(&mut self, snapshot: Snapshot) {
+        self.file_handler.restore_structure(snapshot.structure);
+        for (fid, properties) in snapshot.properties {
+            if let Some(file) = self.file_handler.registry.get_mut(&fid) {
+                file.properties = properties;
             }
-            // Circle main window view.
-            if i.key_pressed(egui::Key::F3) {
-                self.ui_selection = self.ui_selection.next();
+        }
+    }
+
+    fn undo(&mut self) {
+        match self.history.undo() {
+            Some(snapshot) => self.restore_snapshot(snapshot),
+            None => log::debug!("nothing to undo"),
+        }
+    }
+
+    /// Request repaints as needed: continuously, or only while waiting for
+    /// the backend/events and when the view state changed. Input already
+    /// triggers repaints, so this covers changes from outside the UI.
+    fn request_repaint(&mut self, snapshot: Snapshot, ctx: &egui::Context) {
+        let interval = Duration::from_millis(50);
+        if self.config.continuous_repaint {
+            self.last_view = None;
+            ctx.request_repaint_after(interval);
+            return;
+        }
+        if !self.event_queue.is_empty()
+            || self.file_handler.is_busy()
+            || self.search.is_busy()
+            || !self.load_options.is_up_to_date()
+        {
+            ctx.request_repaint_after(interval);
+        }
+        if self.config.auto_reload {
+            // Files are checked for changes when the app updates.
+            ctx.request_repaint_after(CHANGE_CHECK_INTERVAL);
+        }
+        let (bounds, selected_fid) = self.plotter.view();
+        let view = (snapshot, bounds, selected_fid);
+        if self.last_view.as_ref() != Some(&view) {
+            // Draw one more frame, so that the plot settles on the new state.
+            ctx.request_repaint();
+            self.last_view = Some(view);
+        }
+    }
+
+    fn redo(&mut self) {
+        match self.history.redo() {
+            Some(snapshot) => self.restore_snapshot(snapshot),
+            None => log::debug!("nothing to redo"),
+        }
+    }
+
+    /// Run an action of the command palette or a keyboard shortcut.
+    fn run_action(&mut self, action: Action, ctx: &egui::Context) {
+        log::debug!("run action {action:?}");
+        match action {
+            Action::SaveSession => {
+                log::debug!("open dialog to select save path");
+                let handle = std::thread::spawn(|| rfd::FileDialog::new().save_file());
+                let event = SaveLoadRequested::new(true, Some(handle));
+                self.event_queue.queue_event(Box::new(event));
+            }
+            Action::LoadSession => {
+                log::debug!("open dialog to select load path");
+                let handle = std::thread::spawn(|| rfd::FileDialog::new().pick_file());
+                let event = SaveLoadRequested::new(false, Some(handle));
+                self.event_queue.queue_event(Box::new(event));
             }
-            // Circle mode.
-            if i.key_pressed(egui::Key::F4) {
-                self.plotter.mode = self.plotter.mode.next();
+            Action::MergeSession => {
+                log::debug!("open dialog to select session to merge");
+                let handle = std::thread::spawn(|| rfd::FileDialog::new().pick_file());
+                let event = MergeSessionRequested::new(Some(handle));
+                self.event_queue.queue_event(Box::new(event));
             }
-            // Quick save app state.
-            if i.key_pressed(egui::Key::F6) {
+            Action::QuickSave => {
                 if let Err(error) = save_json(self, None) {
                     log::error!("{}", error)
                 };
             }
-            // Quick load app state.
-            if i.key_pressed(egui::Key::F5) {
+            Action::QuickLoad => {
                 if let Err(error) = load_json(self, None) {
                     log::error!("{}", error)
                 };
             }
-            // Close app.
-            if i.key_pressed(egui::Key::F10) {
-                // Quitting cannot be requested from within here, the UI stops,
-                // but not the backend thread.
-                should_quit = true;
-            }
-            // Open preferences.
-            if i.key_pressed(egui::Key::F12) {
-                self.ui_selection = UISelection::Preferences;
-            }
-            if i.key_pressed(egui::Key::S) && i.modifiers.ctrl {
-                log::debug!("open dialog to select save path");
-                let handle = std::thread::spawn(|| rfd::FileDialog::new().save_file());
-                let event = SaveLoadRequested::new(true, Some(handle));
+            Action::ResetSession => self.reset_state(),
+            Action::ExportSvg => {
+                log::debug!("open dialog to select svg plot path");
+                let handle = std::thread::spawn(|| {
+                    rfd::FileDialog::new().set_file_name("plot.svg").save_file()
+                });
+                let event = SavePlotRequested::new(Some(handle));
                 self.event_queue.queue_event(Box::new(event));
             }
-            if i.key_pressed(egui::Key::L) && i.modifiers.ctrl {
-                log::debug!("open dialog to select load path");
-                let handle = std::thread::spawn(|| rfd::FileDialog::new().pick_file());
-                let event = SaveLoadRequested::new(false, Some(handle));
+            Action::ExportBundle => {
+                log::debug!("open dialog to select bundle path");
+                let handle = std::thread::spawn(|| {
+                    rfd::FileDialog::new()
+                        .set_file_name("bundle.zip")
+                        .save_file()
+                });
+                let event = ExportBundleRequested::new(self.bundle_contents, Some(handle));
                 self.event_queue.queue_event(Box::new(event));
             }
+            Action::ReloadAll => self.file_handler.reload_all(&mut self.request_tx),
+            Action::PasteAsFile => {
+                // The text arrives as paste event in the next frame.
+                ctx.send_viewport_cmd(egui::ViewportCommand::RequestPaste);
+                ctx.request_repaint();
+                self.paste_requested = true;
+            }
+            Action::Undo => self.undo(),
+            Action::Redo => self.redo(),
+            Action::ResetView => self.plotter.reset_view(&self.file_handler),
+            Action::FitY => self.plotter.fit_y_to_visible(&self.file_handler),
+            Action::CenterOnPeak => self
+                .plotter
+                .center_on_peak(&self.file_handler, self.config.peak_window),
+            Action::NextPeak => {
+                self.plotter
+                    .step_peak(&self.file_handler, true, self.config.peak_prominence)
+            }
+            Action::PreviousPeak => {
+                self.plotter
+                    .step_peak(&self.file_handler, false, self.config.peak_prominence)
+            }
+            Action::SwitchMode => self.plotter.mode = self.plotter.mode.next(),
+            Action::ToggleIntegrate => self.plotter.toggle_integrate(),
+            Action::ShowPlot => self.ui_selection = UISelection::Plot,
+            Action::ShowFileSettings => self.ui_selection = UISelection::FileSettings,
+            Action::CycleView => self.ui_selection = self.ui_selection.next(),
+            Action::ShowPreferences => self.ui_selection = UISelection::Preferences,
+            Action::ShowShortcuts => self.shortcuts_modal_open = !self.shortcuts_modal_open,
+            Action::ToggleDebugPanel => self.debug_window_open = !self.debug_window_open,
+            Action::ToggleCommandLine => {
+                self.command_line = match self.command_line {
+                    Some(_) => None,
+                    None => Some(CommandLine::default()),
+                };
+            }
+            Action::Quit => ctx.send_viewport_cmd(egui::ViewportCommand::Close),
+        }
+    }
+
+    /// Run a command of the command line.
+    fn run_command(&mut self, command: Command, ctx: &egui::Context) -> Result<(), String> {
+        log::debug!("run command {command:?}");
+        fn file(file_handler: &mut FileHandler, fid: FileID) -> Result<&mut File, String> {
+            file_handler
+                .registry
+                .get_mut(&fid)
+                .ok_or(format!("there is no file with ID {}", i32::from(fid)))
+        }
+        match command {
+            Command::Load(path) => self.file_handler.add_file(&path, 0, &mut self.request_tx),
+            Command::XOffset(fid, x) => file(&mut self.file_handler, fid)?.properties.xoffset = x,
+            Command::YOffset(fid, y) => file(&mut self.file_handler, fid)?.properties.yoffset = y,
+            Command::YScale(fid, factor) => {
+                file(&mut self.file_handler, fid)?.properties.yscale = factor
+            }
+            Command::Select(fid) => {
+                let extent = file(&mut self.file_handler, fid)?.extent();
+                self.plotter.select_file(fid, extent);
+            }
+            Command::Integrate(left, right) => self.plotter.set_integration_region(left, right),
+            Command::Plot(gid, shown) => {
+                self.file_handler.groups[gid]
+                    .as_mut()
+                    .ok_or(format!("there is no group {gid}"))?
+                    .is_plotted = shown;
+            }
+            Command::Run(action) => self.run_action(action, ctx),
+        }
+        Ok(())
+    }
+
+    fn render_command_line(&mut self, ctx: &egui::Context) {
+        let Some(command_line) = &mut self.command_line else {
+            return;
+        };
+        let mut entered = None;
+        egui::TopBottomPanel::bottom("command_line").show(ctx, |ui| {
+            entered = command_line.render(ui);
+        });
+        let Some(line) = entered else {
+            return;
+        };
+        let result = line
+            .parse::<Command>()
+            .and_then(|command| self.run_command(command, ctx));
+        if let Err(err) = &result {
+            log::error!("command '{}' failed: {err}", line.trim());
+        }
+        if let Some(command_line) = &mut self.command_line {
+            command_line.error = result.err();
+        }
+    }
+
+    /// Check whether the backend thread stopped (it only stops on its own
+    /// if it panicked) and, depending on the config, restart it.
+    fn check_backend(&mut self) {
+        let Some(handle) = self
+            .backend_thread_handle
+            .take_if(|handle| handle.is_finished())
+        else {
+            return;
+        };
+        let reason = match handle.join() {
+            Ok(()) => "backend stopped".to_string(),
+            Err(panic) => panic
+                .downcast_ref::<&str>()
+                .map(|msg| msg.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "backend panicked".to_string()),
+        };
+        log::error!("{BACKEND_HUNG_UP_MSG}: {reason}");
+        match self.config.on_backend_crash {
+            BackendCrashAction::Ask => self.backend_crash = Some(reason),
+            BackendCrashAction::Restart => self.restart_backend(),
+        }
+    }
+
+    /// Start a new backend thread and send the requests that were lost with
+    /// the old one again: loading files without data, the search path and
+    /// the load options.
+    fn restart_backend(&mut self) {
+        log::info!("restarting backend");
+        self.backend_crash = None;
+        let (request_tx, request_rx) = std::sync::mpsc::channel();
+        let search_path = self.search.get_search_path().to_path_buf();
+        let backend_state =
+            BackendAppState::new(search_path.clone(), self.config.load_options.clone());
+        let eventloop = BackendEventLoop::new(request_rx, backend_state);
+        self.pending_requests = eventloop.pending_requests();
+        self.backend_thread_handle = Some(eventloop.run());
+        self.request_tx = request_tx;
+        self.search.set_request_tx(self.request_tx.clone());
+        self.search.set_search_path(&search_path);
+        self.load_options = UIParameter::new(self.config.load_options.clone());
+        for file in self.file_handler.registry.values_mut() {
+            if file.data.value().is_err() {
+                file.reload(&mut self.request_tx);
+            }
+        }
+    }
+
+    fn update_state(&mut self) {
+        self.check_backend();
+        self.run_events();
+        if self.config.auto_reload {
+            let debounce = Duration::from_millis(self.config.auto_reload_debounce_ms);
+            self.file_handler
+                .reload_changed_files(debounce, &mut self.request_tx);
+        }
+        self.file_handler.try_update();
+        self.file_handler
+            .update_display_names(self.config.file_names);
+        self.search.try_update();
+        self.load_options.try_update();
+    }
+
+    /// Send the load options from the config to the backend, if they changed.
+    fn sync_load_options(&mut self) {
+        if self.config.load_options == *self.load_options.value() {
+            return;
+        }
+        let load_options = self.config.load_options.clone();
+        BackendLink::request_parameter_update(
+            &mut self.load_options,
+            "update load options",
+            move |b: &mut BackendEventLoop<BackendAppState>| {
+                b.state.set_load_options(load_options.clone());
+                b.state.get_load_options()
+            },
+            &mut self.request_tx,
+        );
+    }
+}
+
+impl eframe::App for EguiApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.update_state();
+
+        // Actions are run after handling the input, since some of them
+        // need the context.
+        let mut actions = Vec::new();
+
+        // Shortcuts without modifier must not fire while typing.
+        let editing_text = ctx.wants_keyboard_input();
+        let typing = editing_text || self.search.is_open();
+
+        // Handle keyboard input, shortcuts are remapped in the preferences.
+        let recording = Keymap::is_recording(ctx);
+        ctx.input(|i| {
+            if !recording {
+                actions = self.config.shortcuts.pressed(i, typing);
+            }
+            // Text fields undo their own edits.
+            if editing_text {
+                actions.retain(|action| !matches!(action, Action::Undo | Action::Redo));
+            }
+            // Command palette.
             if i.key_pressed(egui::Key::P) && i.modifiers.ctrl {
-                log::debug!("open dialog to select svg plot path");
-                let handle = std::thread::spawn(|| rfd::FileDialog::new().save_file());
-                let event = SavePlotRequested::new(Some(handle));
-                self.event_queue.queue_event(Box::new(event));
+                self.command_palette = match self.command_palette {
+                    Some(_) => None,
+                    None => Some(CommandPalette::default()),
+                };
             }
         });
 
+        // Pasted text becomes a new file if that was requested, or if it was
+        // pasted (CTRL + V) outside of text fields and the config allows it.
+        // Requested text arrives in the next frame, or not at all if the
+        // clipboard holds none, so a request only applies to that frame.
+        let paste_requested = std::mem::take(&mut self.paste_requested);
+        let pasted = ctx.input(|i| {
+            i.events.iter().find_map(|event| match event {
+                egui::Event::Paste(text) => Some(text.clone()),
+                _ => None,
+            })
+        });
+        if let Some(text) = pasted {
+            if paste_requested || (self.config.paste_as_file && !typing) {
+                self.file_handler.add_pasted(text, 0, &mut self.request_tx);
+            }
+        }
+
+        for action in actions {
+            self.run_action(action, ctx);
+        }
+
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             self.render_shortcut_modal(ctx);
+            self.render_command_palette(ctx);
+            self.render_backend_crash_modal(ctx);
+            self.render_debug_window(ctx);
             self.menu(ui, ctx);
         });
 
+        // The command line takes the bottom of the window, before the
+        // central panel fills the rest.
+        self.render_command_line(ctx);
+
         egui::CentralPanel::default().show(ctx, |ui| {
             self.central_panel(ui, ctx);
         });
 
-        if should_quit {
-            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
-        }
+        // The snapshot is taken once per frame, it is only cloned into the
+        // history if it changed.
+        let snapshot = self.snapshot();
+        self.record_history(&snapshot, ctx);
+        self.request_repaint(snapshot, ctx);
     }
 
     fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
-        if let Some(handle) = self.backend_thread_handle.take() {
-            app_core::backend::request_stop(&self.request_tx, handle);
-        }
+        self.shutdown();
     }
 }
 
 impl EguiApp {
     fn central_panel(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
-        let should_add_files = self.search.render(&mut self.request_tx, ui, ctx);
+        let should_add_files = self
+            .search
+            .render(&self.config, &mut self.request_tx, ui, ctx);
         if should_add_files {
             self.file_handler
                 .add_search_results(&mut self.search, &mut self.request_tx);
@@ -176,13 +637,36 @@ impl EguiApp {
 
         use UISelection as U;
         match self.ui_selection {
-            U::Plot => self.plotter.render(&mut self.file_handler, ui, ctx),
+            U::Plot => {
+                let startup_mode = self.config.startup_mode;
+                self.plot_tabs.render(
+                    &mut self.plotter,
+                    &mut self.file_handler,
+                    || Plotter::new().with_mode(startup_mode),
+                    ui,
+                );
+                self.plotter.render(
+                    &self.config,
+                    &mut self.file_handler,
+                    &mut self.event_queue,
+                    ui,
+                    ctx,
+                )
+            }
             U::FileSettings => {
-                self.file_handler
-                    .render(&mut self.request_tx, &mut self.event_queue, ui, ctx)
+                self.file_handler.render(
+                    &mut self.config,
+                    &mut self.request_tx,
+                    &mut self.event_queue,
+                    ui,
+                    ctx,
+                );
             }
             U::Preferences => {
                 self.config.render(ctx, ui);
+                if self.load_options.is_up_to_date() {
+                    self.sync_load_options();
+                }
             }
         }
     }
@@ -203,6 +687,13 @@ impl EguiApp {
                         let event = SaveLoadRequested::new(false, Some(handle));
                         self.event_queue.queue_event(Box::new(event));
                     }
+                    if ui
+                        .button("Merge")
+                        .on_hover_text("add the groups of another session, e.g. to compare them")
+                        .clicked()
+                    {
+                        self.run_action(Action::MergeSession, ctx);
+                    }
                     if ui.button("Quick Save").clicked() {
                         if let Err(error) = save_json(self, None) {
                             log::error!("{}", error)
@@ -219,6 +710,15 @@ impl EguiApp {
                     if ui.button("Preferences").clicked() {
                         self.ui_selection = UISelection::Preferences
                     };
+                    if ui
+                        .button("Reload All Files")
+                        .on_hover_text(
+                            "read all files from disk again, keeping their display options",
+                        )
+                        .clicked()
+                    {
+                        self.file_handler.reload_all(&mut self.request_tx);
+                    }
                     if ui.button("Reset Session").clicked() {
                         self.reset_state();
                     };
@@ -227,6 +727,15 @@ impl EguiApp {
                     }
                 });
 
+                ui.menu_button("Edit", |ui| {
+                    if ui.button("Undo").clicked() {
+                        self.undo();
+                    }
+                    if ui.button("Redo").clicked() {
+                        self.redo();
+                    }
+                });
+
                 // Selection of ui view.
                 ui.menu_button("View", |ui| {
                     ui.selectable_value(&mut self.ui_selection, UISelection::Plot, "Plot");
@@ -235,6 +744,8 @@ impl EguiApp {
                         UISelection::FileSettings,
                         "File Settings",
                     );
+                    ui.separator();
+                    ui.checkbox(&mut self.debug_window_open, "Debug Panel");
                 });
 
                 ui.menu_button("Mode", |ui| {
@@ -250,17 +761,63 @@ impl EguiApp {
                     );
                 });
 
-                if ui.button("Export").clicked() {
-                    log::debug!("open dialog to select svg plot path");
-                    let handle = std::thread::spawn(|| {
-                        rfd::FileDialog::new().set_file_name("plot.svg").save_file()
-                    });
-                    let event = SavePlotRequested::new(Some(handle));
-                    self.event_queue.queue_event(Box::new(event));
-                };
+                ui.menu_button("Export", |ui| {
+                    if ui.button("SVG").clicked() {
+                        log::debug!("open dialog to select svg plot path");
+                        let handle = std::thread::spawn(|| {
+                            rfd::FileDialog::new().set_file_name("plot.svg").save_file()
+                        });
+                        let event = SavePlotRequested::new(Some(handle));
+                        self.event_queue.queue_event(Box::new(event));
+                        ui.close_menu();
+                    };
+                    if ui
+                        .button("Groups (CSV)")
+                        .on_hover_text(
+                            "export the plotted data of each plotted group into one file, in a directory",
+                        )
+                        .clicked()
+                    {
+                        log::debug!("open dialog to select group export directory");
+                        let handle = std::thread::spawn(|| rfd::FileDialog::new().pick_folder());
+                        let event = ExportGroupsRequested::new(Some(handle));
+                        self.event_queue.queue_event(Box::new(event));
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    ui.label("Bundle");
+                    ui.checkbox(&mut self.bundle_contents.svg, "Plot (SVG)");
+                    ui.checkbox(&mut self.bundle_contents.data, "Plotted data (CSV)");
+                    ui.checkbox(&mut self.bundle_contents.region, "Integration region (CSV)");
+                    if ui.button("Export Bundle (ZIP)").clicked() {
+                        log::debug!("open dialog to select bundle path");
+                        let handle = std::thread::spawn(|| {
+                            rfd::FileDialog::new()
+                                .set_file_name("bundle.zip")
+                                .save_file()
+                        });
+                        let event = ExportBundleRequested::new(self.bundle_contents, Some(handle));
+                        self.event_queue.queue_event(Box::new(event));
+                        ui.close_menu();
+                    }
+                });
+
+                let integrating = self.plotter.mode == crate::app::PlotterMode::Integrate;
+                if ui
+                    .selectable_label(integrating, "Integrate (I)")
+                    .on_hover_text("toggle integrate mode and the integration region")
+                    .clicked()
+                {
+                    self.plotter.toggle_integrate();
+                }
 
                 ui.toggle_value(&mut self.shortcuts_modal_open, "Help (F1)");
 
+                if let Some((done, total)) = self.file_handler.reload_progress() {
+                    ui.spinner();
+                    ui.label(format!("reloading {done}/{total}"));
+                }
+
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                     egui::widgets::global_theme_preference_buttons(ui);
                 });
@@ -268,6 +825,66 @@ impl EguiApp {
         });
     }
 
+    fn render_debug_window(&mut self, ctx: &egui::Context) {
+        egui::Window::new("Debug")
+            .open(&mut self.debug_window_open)
+            .show(ctx, |ui| {
+                egui::Grid::new("debug_grid").show(ui, |ui| {
+                    ui.label("Pending backend requests");
+                    ui.label(self.pending_requests.get().to_string());
+                    ui.end_row();
+                    ui.label("Queued events");
+                    ui.label(self.event_queue.len().to_string());
+                    ui.end_row();
+                    ui.label("Files loading");
+                    let loading = self
+                        .file_handler
+                        .registry
+                        .values()
+                        .filter(|file| !file.data.is_up_to_date())
+                        .count();
+                    ui.label(loading.to_string());
+                    ui.end_row();
+                });
+            });
+    }
+
+    fn render_backend_crash_modal(&mut self, ctx: &egui::Context) {
+        let Some(reason) = &self.backend_crash else {
+            return;
+        };
+        let (mut restart, mut quit) = (false, false);
+        egui::Modal::new("backend_crash_modal".into()).show(ctx, |ui| {
+            ui.heading("Backend Stopped");
+            ui.label("The backend, which loads and searches files, stopped unexpectedly:");
+            ui.colored_label(ui.visuals().error_fg_color, reason);
+            ui.label("Files and searches requested since then were not handled.");
+            ui.horizontal(|ui| {
+                restart = ui.button("Restart Backend").clicked();
+                quit = ui.button("Quit").clicked();
+            });
+        });
+        if restart {
+            self.restart_backend();
+        }
+        if quit {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        }
+    }
+
+    fn render_command_palette(&mut self, ctx: &egui::Context) {
+        let Some(palette) = &mut self.command_palette else {
+            return;
+        };
+        let (action, close) = palette.show(ctx, &self.config.shortcuts);
+        if close {
+            self.command_palette = None;
+        }
+        if let Some(action) = action {
+            self.run_action(action, ctx);
+        }
+    }
+
     fn render_shortcut_modal(&mut self, ctx: &egui::Context) {
         if self.shortcuts_modal_open
             && egui::Modal::new("shortcut_modal".into())
@@ -276,24 +893,23 @@ impl EguiApp {
                     ui.separator();
                     ui.label("CTRL + Space = Open Search Menu");
                     ui.separator();
-                    ui.label("CTRL + S = Open Save Dialog");
-                    ui.separator();
-                    ui.label("CTRL + L = Open Load Dialog");
-                    ui.separator();
-                    ui.label("F1 = Show Keyboard Shortcuts");
-                    ui.separator();
-                    ui.label("F3 = Cycle View");
-                    ui.separator();
-                    ui.label("F4 = Cycle Mode");
-                    ui.separator();
-                    ui.label("F6 = Save App State");
-                    ui.separator();
-                    ui.label("F5 = Load App State");
-                    ui.separator();
-                    ui.label("F10 = Quit App");
-                    ui.separator();
-                    ui.label("F12 = Open Preferences");
+                    ui.label("CTRL + P = Open Command Palette");
                     ui.separator();
+                    if self.config.paste_as_file {
+                        ui.label("CTRL + V = Paste Data as File (outside of text fields)");
+                        ui.separator();
+                    }
+                    for action in Action::ALL {
+                        let shortcuts = [
+                            self.config.shortcuts.get(*action),
+                            action.alternative_shortcut(),
+                        ];
+                        for shortcut in shortcuts.into_iter().flatten() {
+                            ui.label(format!("{shortcut} = {}", action.label()));
+                            ui.separator();
+                        }
+                    }
+                    ui.weak("Shortcuts can be changed in the preferences.");
                 })
                 .should_close()
         {
@@ -0,0 +1,285 @@
+use std::path::PathBuf;
+
+use super::shortcuts::{Keymap, Shortcut};
+use crate::utils::matching::match_paths;
+
+/// An action that can be run from the command palette, see
+/// `EguiApp::run_action`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    SaveSession,
+    LoadSession,
+    MergeSession,
+    QuickSave,
+    QuickLoad,
+    ResetSession,
+    ExportSvg,
+    ExportBundle,
+    ReloadAll,
+    PasteAsFile,
+    Undo,
+    Redo,
+    ResetView,
+    FitY,
+    CenterOnPeak,
+    NextPeak,
+    PreviousPeak,
+    SwitchMode,
+    ToggleIntegrate,
+    ShowPlot,
+    ShowFileSettings,
+    CycleView,
+    ShowPreferences,
+    ShowShortcuts,
+    ToggleDebugPanel,
+    ToggleCommandLine,
+    Quit,
+}
+
+impl Action {
+    pub const ALL: &'static [Action] = &[
+        Action::SaveSession,
+        Action::LoadSession,
+        Action::MergeSession,
+        Action::QuickSave,
+        Action::QuickLoad,
+        Action::ResetSession,
+        Action::ExportSvg,
+        Action::ExportBundle,
+        Action::ReloadAll,
+        Action::PasteAsFile,
+        Action::Undo,
+        Action::Redo,
+        Action::ResetView,
+        Action::FitY,
+        Action::CenterOnPeak,
+        Action::NextPeak,
+        Action::PreviousPeak,
+        Action::SwitchMode,
+        Action::ToggleIntegrate,
+        Action::ShowPlot,
+        Action::ShowFileSettings,
+        Action::CycleView,
+        Action::ShowPreferences,
+        Action::ShowShortcuts,
+        Action::ToggleDebugPanel,
+        Action::ToggleCommandLine,
+        Action::Quit,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::SaveSession => "Save Session",
+            Action::LoadSession => "Load Session",
+            Action::MergeSession => "Merge Session",
+            Action::QuickSave => "Quick Save",
+            Action::QuickLoad => "Quick Load",
+            Action::ResetSession => "Reset Session",
+            Action::ExportSvg => "Export Plot as SVG",
+            Action::ExportBundle => "Export Bundle (ZIP)",
+            Action::ReloadAll => "Reload All Files",
+            Action::PasteAsFile => "Paste Data as File",
+            Action::Undo => "Undo",
+            Action::Redo => "Redo",
+            Action::ResetView => "Reset View",
+            Action::FitY => "Fit Y to Visible Range",
+            Action::CenterOnPeak => "Center on Highest Peak",
+            Action::NextPeak => "Next Peak",
+            Action::PreviousPeak => "Previous Peak",
+            Action::SwitchMode => "Switch Mode",
+            Action::ToggleIntegrate => "Toggle Integration Region",
+            Action::ShowPlot => "Show Plot",
+            Action::ShowFileSettings => "Show File Settings",
+            Action::CycleView => "Cycle View",
+            Action::ShowPreferences => "Show Preferences",
+            Action::ShowShortcuts => "Show Keyboard Shortcuts",
+            Action::ToggleDebugPanel => "Toggle Debug Panel",
+            Action::ToggleCommandLine => "Toggle Command Line",
+            Action::Quit => "Quit",
+        }
+    }
+
+    /// Name of the action in the config file, which should not change.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Action::SaveSession => "save_session",
+            Action::LoadSession => "load_session",
+            Action::MergeSession => "merge_session",
+            Action::QuickSave => "quick_save",
+            Action::QuickLoad => "quick_load",
+            Action::ResetSession => "reset_session",
+            Action::ExportSvg => "export_svg",
+            Action::ExportBundle => "export_bundle",
+            Action::ReloadAll => "reload_all",
+            Action::PasteAsFile => "paste_as_file",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+            Action::ResetView => "reset_view",
+            Action::FitY => "fit_y",
+            Action::CenterOnPeak => "center_on_peak",
+            Action::NextPeak => "next_peak",
+            Action::PreviousPeak => "previous_peak",
+            Action::SwitchMode => "switch_mode",
+            Action::ToggleIntegrate => "toggle_integrate",
+            Action::ShowPlot => "show_plot",
+            Action::ShowFileSettings => "show_file_settings",
+            Action::CycleView => "cycle_view",
+            Action::ShowPreferences => "show_preferences",
+            Action::ShowShortcuts => "show_shortcuts",
+            Action::ToggleDebugPanel => "toggle_debug_panel",
+            Action::ToggleCommandLine => "toggle_command_line",
+            Action::Quit => "quit",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        Action::ALL.iter().find(|action| action.id() == id).copied()
+    }
+
+    /// Keyboard shortcut that runs the action unless it is remapped in the
+    /// preferences, see [`Keymap`](super::shortcuts::Keymap).
+    pub fn default_shortcut(&self) -> Option<Shortcut> {
+        use egui::Key;
+        match self {
+            Action::SaveSession => Some(Shortcut::ctrl(Key::S)),
+            Action::LoadSession => Some(Shortcut::ctrl(Key::L)),
+            Action::QuickSave => Some(Shortcut::key(Key::F6)),
+            Action::QuickLoad => Some(Shortcut::key(Key::F5)),
+            Action::ExportSvg => Some(Shortcut::ctrl(Key::E)),
+            Action::Undo => Some(Shortcut::ctrl(Key::Z)),
+            Action::Redo => Some(Shortcut::ctrl(Key::Y)),
+            Action::SwitchMode => Some(Shortcut::key(Key::F4)),
+            Action::CycleView => Some(Shortcut::key(Key::F3)),
+            Action::CenterOnPeak => Some(Shortcut::key(Key::M)),
+            Action::NextPeak => Some(Shortcut::key(Key::Period)),
+            Action::PreviousPeak => Some(Shortcut::key(Key::Comma)),
+            Action::ToggleIntegrate => Some(Shortcut::key(Key::I)),
+            Action::ShowPreferences => Some(Shortcut::key(Key::F12)),
+            Action::ShowShortcuts => Some(Shortcut::key(Key::F1)),
+            Action::ToggleCommandLine => Some(Shortcut::key(Key::F2)),
+            Action::Quit => Some(Shortcut::key(Key::F10)),
+            _ => None,
+        }
+    }
+
+    /// Shortcut that runs the action in addition to its binding in the
+    /// [`Keymap`](super::shortcuts::Keymap), it cannot be remapped.
+    pub fn alternative_shortcut(&self) -> Option<Shortcut> {
+        match self {
+            Action::Redo => Some(Shortcut {
+                shift: true,
+                ..Shortcut::ctrl(egui::Key::Z)
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Searchable list of all actions, opened with CTRL + P.
+#[derive(Debug, Default)]
+pub struct CommandPalette {
+    query: String,
+    /// Index of the highlighted entry among the matches.
+    selected: usize,
+}
+
+impl CommandPalette {
+    /// Actions matching the query (ignoring case), best matches first.
+    pub fn matches(&self) -> Vec<Action> {
+        // The matcher works on paths, labels do not contain separators, so
+        // they are matched like file names.
+        let label_path = |action: &Action| PathBuf::from(action.label().to_lowercase());
+        let labels: Vec<_> = Action::ALL.iter().map(label_path).collect();
+        match_paths(&self.query.to_lowercase(), &labels, labels.len())
+            .into_iter()
+            .filter_map(|m| {
+                Action::ALL
+                    .iter()
+                    .find(|action| label_path(action) == m.path)
+            })
+            .copied()
+            .collect()
+    }
+
+    /// Show the palette with the shortcuts of `keymap`. Returns the chosen
+    /// action, if any, and whether the palette should be closed.
+    pub fn show(&mut self, ctx: &egui::Context, keymap: &Keymap) -> (Option<Action>, bool) {
+        let matches = self.matches();
+        // Keys are consumed, so that the text field does not move its cursor.
+        let (up, down, enter) = ctx.input_mut(|i| {
+            (
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::Enter),
+            )
+        });
+        if down {
+            self.selected += 1;
+        }
+        if up {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        self.selected = self.selected.min(matches.len().saturating_sub(1));
+
+        let mut chosen = if enter {
+            matches.get(self.selected).copied()
+        } else {
+            None
+        };
+        let modal = egui::Modal::new("command_palette".into()).show(ctx, |ui| {
+            ui.set_width(320.0);
+            let resp = ui.add(
+                egui::TextEdit::singleline(&mut self.query)
+                    .hint_text("type to search actions")
+                    .desired_width(f32::INFINITY),
+            );
+            resp.request_focus();
+            if resp.changed() {
+                self.selected = 0;
+            }
+            ui.separator();
+            if matches.is_empty() {
+                ui.weak("no matching actions");
+            }
+            for (i, action) in matches.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    if ui
+                        .selectable_label(i == self.selected, action.label())
+                        .clicked()
+                    {
+                        chosen = Some(*action);
+                    }
+                    if let Some(shortcut) = keymap.get(*action) {
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            ui.weak(shortcut.to_string());
+                        });
+                    }
+                });
+            }
+        });
+        (chosen, chosen.is_some() || modal.should_close())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(query: &str) -> Vec<Action> {
+        CommandPalette {
+            query: query.to_string(),
+            ..Default::default()
+        }
+        .matches()
+    }
+
+    #[test]
+    fn test_matches() {
+        assert_eq!(matches(""), Action::ALL);
+        assert_eq!(matches("svg"), [Action::ExportSvg]);
+        // Words can be given in any order, case is ignored.
+        assert_eq!(matches("session LOAD"), [Action::LoadSession]);
+        assert_eq!(matches("export !zip"), [Action::ExportSvg]);
+        assert!(matches("nothing like this").is_empty());
+    }
+}
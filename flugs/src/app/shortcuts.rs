@@ -0,0 +1,446 @@
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use super::palette::Action;
+
+/// A key together with the modifiers that must be held, written like
+/// "CTRL + SHIFT + Z".
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Shortcut {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    pub key: egui::Key,
+}
+
+impl Shortcut {
+    pub const fn key(key: egui::Key) -> Self {
+        Self {
+            ctrl: false,
+            alt: false,
+            shift: false,
+            key,
+        }
+    }
+
+    pub const fn ctrl(key: egui::Key) -> Self {
+        Self {
+            ctrl: true,
+            ..Self::key(key)
+        }
+    }
+
+    fn modifiers(&self) -> egui::Modifiers {
+        egui::Modifiers {
+            alt: self.alt,
+            ctrl: self.ctrl,
+            shift: self.shift,
+            ..Default::default()
+        }
+    }
+
+    /// Whether the shortcut was pressed, the modifiers must match exactly.
+    pub fn pressed(&self, input: &egui::InputState) -> bool {
+        input.key_pressed(self.key) && input.modifiers.matches_exact(self.modifiers())
+    }
+
+    /// Whether pressing the shortcut types text, so that it must not fire
+    /// while typing. Function keys do not type.
+    pub fn types_text(&self) -> bool {
+        let name = self.key.name();
+        let function_key = name.len() > 1
+            && name.starts_with('F')
+            && name[1..].chars().all(|c| c.is_ascii_digit());
+        !self.ctrl && !self.alt && !function_key
+    }
+}
+
+impl fmt::Display for Shortcut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (held, name) in [
+            (self.ctrl, "CTRL"),
+            (self.alt, "ALT"),
+            (self.shift, "SHIFT"),
+        ] {
+            if held {
+                write!(f, "{name} + ")?;
+            }
+        }
+        write!(f, "{}", self.key.symbol_or_name())
+    }
+}
+
+impl FromStr for Shortcut {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        // The key may be "+" itself, so it is split off last.
+        let (modifiers, key) = match text.trim().rsplit_once(" + ") {
+            Some((modifiers, key)) => (modifiers, key),
+            None => ("", text.trim()),
+        };
+        let key = egui::Key::from_name(key.trim()).ok_or(format!("unknown key '{key}'"))?;
+        let mut shortcut = Self::key(key);
+        for modifier in modifiers
+            .split('+')
+            .map(str::trim)
+            .filter(|m| !m.is_empty())
+        {
+            match modifier.to_uppercase().as_str() {
+                "CTRL" => shortcut.ctrl = true,
+                "ALT" => shortcut.alt = true,
+                "SHIFT" => shortcut.shift = true,
+                _ => return Err(format!("unknown modifier '{modifier}'")),
+            }
+        }
+        Ok(shortcut)
+    }
+}
+
+/// Shortcuts handled outside of the [`Keymap`], with what they do.
+pub const FIXED_SHORTCUTS: [(Shortcut, &str); 3] = [
+    (Shortcut::ctrl(egui::Key::Space), "Open Search Menu"),
+    (Shortcut::ctrl(egui::Key::P), "Open Command Palette"),
+    (Shortcut::ctrl(egui::Key::V), "Paste Data as File"),
+];
+
+/// What else a shortcut bound to an action runs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Conflict {
+    /// An action later in [`Action::ALL`] is bound to it, which does not run.
+    Action(Action),
+    /// It is an alternative shortcut of another action, which does not run.
+    Alternative(Action),
+    /// It is one of the [`FIXED_SHORTCUTS`], which runs as well.
+    Fixed(&'static str),
+}
+
+/// A shortcut being recorded and the pass it was last shown in.
+#[derive(Clone, Copy)]
+struct Recording {
+    action: Action,
+    pass: u64,
+}
+
+/// The shortcuts bound to the actions, see [`Action::default_shortcut`].
+/// Only the bindings that differ from the defaults are stored in the config
+/// file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Keymap {
+    /// Bindings that differ from the defaults, `None` if the action was
+    /// unbound.
+    overrides: HashMap<Action, Option<Shortcut>>,
+}
+
+impl Keymap {
+    pub fn get(&self, action: Action) -> Option<Shortcut> {
+        match self.overrides.get(&action) {
+            Some(shortcut) => *shortcut,
+            None => action.default_shortcut(),
+        }
+    }
+
+    pub fn set(&mut self, action: Action, shortcut: Option<Shortcut>) {
+        if shortcut == action.default_shortcut() {
+            self.overrides.remove(&action);
+        } else {
+            self.overrides.insert(action, shortcut);
+        }
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.overrides.is_empty()
+    }
+
+    pub fn reset(&mut self) {
+        self.overrides.clear();
+    }
+
+    /// The bindings that differ from the defaults, in the order of
+    /// [`Action::ALL`].
+    pub fn overrides(&self) -> impl Iterator<Item = (Action, Option<Shortcut>)> + '_ {
+        Action::ALL
+            .iter()
+            .filter_map(|action| Some((*action, *self.overrides.get(action)?)))
+    }
+
+    /// Actions whose shortcut was pressed, of conflicting actions only the
+    /// first. Bindings go before alternative shortcuts. Shortcuts that type
+    /// text are skipped while `typing`.
+    pub fn pressed(&self, input: &egui::InputState, typing: bool) -> Vec<Action> {
+        let bindings = Action::ALL.iter().filter_map(|a| Some((*a, self.get(*a)?)));
+        let alternatives = Action::ALL
+            .iter()
+            .filter_map(|a| Some((*a, a.alternative_shortcut()?)));
+        let mut pressed: Vec<(Action, Shortcut)> = Vec::new();
+        for (action, shortcut) in bindings.chain(alternatives) {
+            if !(typing && shortcut.types_text())
+                && shortcut.pressed(input)
+                && pressed
+                    .iter()
+                    .all(|(other_action, other)| *other != shortcut && *other_action != action)
+            {
+                pressed.push((action, shortcut));
+            }
+        }
+        pressed.into_iter().map(|(action, _)| action).collect()
+    }
+
+    /// Actions whose shortcut also runs or is meant for something else.
+    pub fn conflicts(&self) -> Vec<(Action, Conflict)> {
+        let mut conflicts = Vec::new();
+        for (i, first) in Action::ALL.iter().enumerate() {
+            let Some(shortcut) = self.get(*first) else {
+                continue;
+            };
+            for second in &Action::ALL[i + 1..] {
+                if self.get(*second) == Some(shortcut) {
+                    conflicts.push((*first, Conflict::Action(*second)));
+                }
+            }
+            for other in Action::ALL {
+                if other != first && other.alternative_shortcut() == Some(shortcut) {
+                    conflicts.push((*first, Conflict::Alternative(*other)));
+                }
+            }
+            for (fixed, name) in FIXED_SHORTCUTS {
+                if fixed == shortcut {
+                    conflicts.push((*first, Conflict::Fixed(name)));
+                }
+            }
+        }
+        conflicts
+    }
+
+    /// Whether a shortcut is being recorded in the preferences, shortcuts
+    /// must not run then.
+    pub fn is_recording(ctx: &egui::Context) -> bool {
+        recording(ctx).is_some()
+    }
+
+    /// List of the actions with their shortcuts. Clicking a shortcut records
+    /// the next key press (Escape cancels). Recording stops as well when the
+    /// list is not shown.
+    pub fn render(&mut self, ui: &mut egui::Ui) {
+        let recording = recording(ui.ctx());
+        if let Some(action) = recording {
+            let pass = ui.ctx().cumulative_pass_nr();
+            ui.data_mut(|data| data.insert_temp(recording_id(), Recording { action, pass }));
+            let pressed = ui.input(|i| {
+                i.events.iter().find_map(|event| match event {
+                    egui::Event::Key {
+                        key,
+                        pressed: true,
+                        modifiers,
+                        ..
+                    } => Some((*key, *modifiers)),
+                    _ => None,
+                })
+            });
+            if let Some((key, modifiers)) = pressed {
+                if key != egui::Key::Escape {
+                    let shortcut = Shortcut {
+                        ctrl: modifiers.ctrl || modifiers.command,
+                        alt: modifiers.alt,
+                        shift: modifiers.shift,
+                        key,
+                    };
+                    self.set(action, Some(shortcut));
+                }
+                ui.data_mut(|data| data.remove::<Recording>(recording_id()));
+            }
+        }
+
+        let conflicts = self.conflicts();
+        egui::Grid::new("keymap").striped(true).show(ui, |ui| {
+            for action in Action::ALL {
+                ui.label(action.label());
+                let text = if recording == Some(*action) {
+                    "press a key…".to_string()
+                } else {
+                    self.get(*action)
+                        .map_or("—".to_string(), |shortcut| shortcut.to_string())
+                };
+                let conflicting = conflicts.iter().any(|(first, conflict)| {
+                    first == action || *conflict == Conflict::Action(*action)
+                });
+                let text = if conflicting {
+                    egui::RichText::new(text).color(ui.visuals().warn_fg_color)
+                } else {
+                    egui::RichText::new(text)
+                };
+                if ui
+                    .button(text)
+                    .on_hover_text("click, then press the new shortcut")
+                    .clicked()
+                {
+                    let pass = ui.ctx().cumulative_pass_nr();
+                    let recording = Recording {
+                        action: *action,
+                        pass,
+                    };
+                    ui.data_mut(|data| data.insert_temp(recording_id(), recording));
+                }
+                if self.get(*action).is_some()
+                    && ui.small_button("✖").on_hover_text("unbind").clicked()
+                {
+                    self.set(*action, None);
+                }
+                ui.end_row();
+            }
+        });
+        for (first, conflict) in conflicts {
+            let text = match conflict {
+                Conflict::Action(second) | Conflict::Alternative(second) => format!(
+                    "⚠ {} and {} share a shortcut, only {} runs",
+                    first.label(),
+                    second.label(),
+                    first.label()
+                ),
+                Conflict::Fixed(name) => format!(
+                    "⚠ The shortcut of {} is fixed to {name}, both run",
+                    first.label()
+                ),
+            };
+            ui.colored_label(ui.visuals().warn_fg_color, text);
+        }
+        let fixed: Vec<_> = FIXED_SHORTCUTS
+            .iter()
+            .map(|(shortcut, name)| format!("{shortcut} ({name})"))
+            .chain(Action::ALL.iter().filter_map(|action| {
+                let shortcut = action.alternative_shortcut()?;
+                Some(format!("{shortcut} ({})", action.label()))
+            }))
+            .collect();
+        ui.label(format!("{} are fixed.", fixed.join(", ")));
+        if ui
+            .add_enabled(!self.is_default(), egui::Button::new("Reset to Defaults"))
+            .clicked()
+        {
+            self.reset();
+        }
+    }
+}
+
+fn recording_id() -> egui::Id {
+    egui::Id::new("recording shortcut")
+}
+
+/// The action whose shortcut is being recorded. The recording is dropped
+/// if the keymap was not shown in the previous pass, e.g. since the
+/// preferences were left in the middle of it.
+fn recording(ctx: &egui::Context) -> Option<Action> {
+    let recording = ctx.data(|data| data.get_temp::<Recording>(recording_id()))?;
+    if recording.pass + 1 < ctx.cumulative_pass_nr() {
+        ctx.data_mut(|data| data.remove::<Recording>(recording_id()));
+        return None;
+    }
+    Some(recording.action)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shortcut_text() {
+        for text in ["CTRL + S", "F4", ".", "CTRL + ALT + SHIFT + Z", "SHIFT + +"] {
+            let shortcut: Shortcut = text.parse().unwrap();
+            assert_eq!(shortcut.to_string(), text);
+        }
+        assert_eq!(
+            "ctrl + Period".parse(),
+            Ok(Shortcut::ctrl(egui::Key::Period))
+        );
+        assert!("CTRL + Nope".parse::<Shortcut>().is_err());
+        assert!("SUPER + S".parse::<Shortcut>().is_err());
+        assert!(Shortcut::key(egui::Key::I).types_text());
+        assert!(!Shortcut::key(egui::Key::F12).types_text());
+        assert!(!Shortcut::ctrl(egui::Key::I).types_text());
+    }
+
+    #[test]
+    fn test_keymap() {
+        let mut keymap = Keymap::default();
+        assert!(keymap.conflicts().is_empty());
+        assert_eq!(
+            keymap.get(Action::SaveSession),
+            Some(Shortcut::ctrl(egui::Key::S))
+        );
+
+        keymap.set(Action::ResetView, Some(Shortcut::ctrl(egui::Key::S)));
+        keymap.set(Action::Quit, None);
+        assert_eq!(
+            keymap.conflicts(),
+            [(Action::SaveSession, Conflict::Action(Action::ResetView))]
+        );
+        assert_eq!(
+            keymap.overrides().collect::<Vec<_>>(),
+            [
+                (Action::ResetView, Some(Shortcut::ctrl(egui::Key::S))),
+                (Action::Quit, None)
+            ]
+        );
+        // Setting the default again removes the override.
+        keymap.set(Action::Quit, Action::Quit.default_shortcut());
+        assert_eq!(keymap.overrides().count(), 1);
+        keymap.reset();
+        assert!(keymap.is_default());
+
+        // Redo keeps CTRL + SHIFT + Z, unless that is bound to another action.
+        let redo_alternative: Shortcut = "CTRL + SHIFT + Z".parse().unwrap();
+        let input = |shortcut: Shortcut| {
+            let mut input = egui::InputState::default();
+            input.modifiers = shortcut.modifiers();
+            input.events = vec![egui::Event::Key {
+                key: shortcut.key,
+                physical_key: None,
+                pressed: true,
+                repeat: false,
+                modifiers: shortcut.modifiers(),
+            }];
+            input
+        };
+        assert_eq!(
+            keymap.pressed(&input(redo_alternative), false),
+            [Action::Redo]
+        );
+        assert_eq!(
+            keymap.pressed(&input(Shortcut::ctrl(egui::Key::Z)), false),
+            [Action::Undo]
+        );
+        keymap.set(Action::FitY, Some(redo_alternative));
+        keymap.set(Action::ShowPlot, Some(Shortcut::ctrl(egui::Key::P)));
+        assert_eq!(
+            keymap.pressed(&input(redo_alternative), false),
+            [Action::FitY]
+        );
+        assert_eq!(
+            keymap.conflicts(),
+            [
+                (Action::FitY, Conflict::Alternative(Action::Redo)),
+                (Action::ShowPlot, Conflict::Fixed("Open Command Palette"))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_recording_stops_when_hidden() {
+        let ctx = egui::Context::default();
+        let mut keymap = Keymap::default();
+        let recording = Recording {
+            action: Action::Quit,
+            pass: 0,
+        };
+        ctx.data_mut(|data| data.insert_temp(recording_id(), recording));
+        for _ in 0..3 {
+            let _ = ctx.run(Default::default(), |ctx| {
+                assert!(Keymap::is_recording(ctx));
+                egui::CentralPanel::default().show(ctx, |ui| keymap.render(ui));
+            });
+        }
+        let _ = ctx.run(Default::default(), |_| {});
+        let _ = ctx.run(Default::default(), |ctx| {
+            assert!(!Keymap::is_recording(ctx));
+        });
+    }
+}
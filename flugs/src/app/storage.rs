@@ -6,10 +6,10 @@ use std::{
 use app_core::storage::Storage;
 use serde::{Deserialize, Serialize};
 
-use crate::EguiApp;
+use crate::{backend_state::PlotData, EguiApp};
 
 use super::{
-    components::{File, FileID, FileProperties, Group},
+    components::{AxisTicks, File, FileID, FileProperties, Group},
     DynRequestSender, FileHandler,
 };
 
@@ -24,10 +24,20 @@ struct FrontendStorage {
     groups: [Option<Group>; 10],
     registry: HashMap<FileID, FileStorage>,
     next_id: FileID,
+    /// What was measured when the session was saved, see
+    /// `Plotter::measurement`.
+    #[serde(default)]
+    selected_fid: Option<FileID>,
+    #[serde(default)]
+    integration_region: Option<(f64, f64)>,
+    /// Tick settings of the x- and y-axis, see `Plotter::ticks`.
+    #[serde(default)]
+    ticks: [AxisTicks; 2],
 }
 
 pub fn save_json(app: &EguiApp, path: Option<&Path>) -> Result<(), String> {
     let backend_storage = BackendStorage {};
+    let (selected_fid, integration_region) = app.plotter.measurement();
 
     let frontend_storage = FrontendStorage {
         search_path: app.search.get_search_path().to_path_buf(),
@@ -36,17 +46,28 @@ pub fn save_json(app: &EguiApp, path: Option<&Path>) -> Result<(), String> {
             .file_handler
             .registry
             .iter()
+            // Inspected files are temporary, they are not part of the session.
+            .filter(|(fid, _)| app.file_handler.is_grouped(fid))
             .map(|(fid, file)| {
                 (
                     *fid,
                     FileStorage {
                         path: file.path.clone(),
                         properties: file.properties.clone(),
+                        // Pasted data cannot be read again.
+                        cached_data: if app.config.cache_file_data || file.is_pasted() {
+                            file.data.value().as_ref().ok().cloned()
+                        } else {
+                            None
+                        },
                     },
                 )
             })
             .collect(),
         next_id: app.file_handler.current_id(),
+        selected_fid,
+        integration_region,
+        ticks: app.plotter.ticks(),
     };
     let storage = Storage::new(backend_storage, frontend_storage);
     storage.save_json(path)
@@ -59,16 +80,57 @@ pub fn load_json(app: &mut EguiApp, path: Option<&Path>) -> Result<(), String> {
     } = Storage::load_json(path)?;
 
     app.search.set_search_path(&frontend_storage.search_path);
+    let (selected_fid, integration_region, ticks) = (
+        frontend_storage.selected_fid,
+        frontend_storage.integration_region,
+        frontend_storage.ticks,
+    );
+    let transforms = std::mem::take(&mut app.file_handler.transforms);
     app.file_handler = frontend_storage.into_file_handler(&mut app.request_tx);
+    app.file_handler.transforms = transforms;
+    app.reset_plots();
+    app.plotter
+        .restore_measurement(selected_fid, integration_region, &app.file_handler);
+    app.plotter.set_ticks(ticks);
+    app.history.clear();
+    Ok(())
+}
+
+/// Add the groups and files of the session at `path` to the current ones,
+/// see [`FileHandler::merge`]. Group names that are taken already are
+/// prefixed with the name of the session file. The rest of the current state
+/// (search path, selection, undo history) is kept.
+pub fn merge_json(app: &mut EguiApp, path: &Path) -> Result<(), String> {
+    let Storage::<BackendStorage, FrontendStorage> {
+        backend_storage: _,
+        frontend_storage,
+    } = Storage::load_json(Some(path))?;
+
+    let prefix = path
+        .file_stem()
+        .map_or("merged".into(), |stem| stem.to_string_lossy());
+    // Files are only read once the groups are known to fit.
+    let mut registry = frontend_storage.registry;
+    let request_tx = &mut app.request_tx;
+    let added = app
+        .file_handler
+        .merge(&prefix, frontend_storage.groups, |fid| {
+            Some(registry.remove(&fid)?.into_file(request_tx))
+        })?;
+    log::info!("merged {} group(s) from {path:?}", added.len());
     Ok(())
 }
 
 // Serializing the files is a special case, because we do not want to store the
-// entire data contained in the csv files (plus it is non-trivial to do this).
+// entire data contained in the csv files by default. If caching is enabled in
+// the config, the parsed data is stored alongside, so that it does not need to
+// be re-read when the session is loaded (as long as the file is unchanged).
 #[derive(Serialize, Deserialize)]
 struct FileStorage {
     path: PathBuf,
     properties: FileProperties,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cached_data: Option<PlotData>,
 }
 
 impl FrontendStorage {
@@ -77,14 +139,191 @@ impl FrontendStorage {
         let registry = self
             .registry
             .into_iter()
-            .map(|(fid, file_storage)| {
-                (
-                    fid,
-                    File::new(file_storage.path, file_storage.properties, request_tx),
-                )
-            })
+            .map(|(fid, file_storage)| (fid, file_storage.into_file(request_tx)))
             .collect();
 
         FileHandler::new(groups, registry, self.next_id)
     }
 }
+
+impl FileStorage {
+    /// The stored file, from the cached data if it is still up to date,
+    /// otherwise it is read again.
+    fn into_file(self, request_tx: &mut DynRequestSender) -> File {
+        let FileStorage {
+            path,
+            properties,
+            cached_data,
+        } = self;
+        match cached_data {
+            Some(data) if data.is_pasted() || data.is_up_to_date_with(&path) => {
+                log::debug!("restored {path:?} from session cache");
+                File::from_data(path, properties, data)
+            }
+            Some(_) => {
+                log::info!("{path:?} changed since session was saved, re-reading");
+                File::new(path, properties, request_tx)
+            }
+            None => File::new(path, properties, request_tx),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{app::components::Plotter, backend_state::LoadOptions};
+
+    #[test]
+    fn test_session_round_trip_keeps_properties() {
+        let properties = FileProperties::custom();
+        let storage = FrontendStorage {
+            search_path: PathBuf::from("/data"),
+            groups: Default::default(),
+            registry: HashMap::from([(
+                FileID::default(),
+                FileStorage {
+                    path: PathBuf::from("/data/file.csv"),
+                    properties: properties.clone(),
+                    cached_data: Some(PlotData::default()),
+                },
+            )]),
+            next_id: FileID::default(),
+            selected_fid: None,
+            integration_region: None,
+            ticks: Default::default(),
+        };
+        let json = serde_json::to_string(&storage).unwrap();
+        let restored: FrontendStorage = serde_json::from_str(&json).unwrap();
+
+        let (mut request_tx, _request_rx) = std::sync::mpsc::channel();
+        let file_handler = restored.into_file_handler(&mut request_tx);
+        let file = &file_handler.registry[&FileID::default()];
+        assert_eq!(file.properties, properties);
+    }
+
+    #[test]
+    fn test_session_round_trip_keeps_measurement() {
+        let fid = FileID::default();
+        let mut storage = FrontendStorage {
+            search_path: PathBuf::from("/data"),
+            groups: Default::default(),
+            registry: HashMap::from([(
+                fid,
+                FileStorage {
+                    path: PathBuf::from("/data/file.csv"),
+                    properties: FileProperties::default(),
+                    cached_data: Some(PlotData::default()),
+                },
+            )]),
+            next_id: fid,
+            selected_fid: Some(fid),
+            integration_region: Some((1.5, 4.0)),
+            ticks: Default::default(),
+        };
+        let (mut request_tx, _request_rx) = std::sync::mpsc::channel();
+        let restore = |storage: &FrontendStorage, request_tx: &mut DynRequestSender| {
+            let json = serde_json::to_string(storage).unwrap();
+            let restored: FrontendStorage = serde_json::from_str(&json).unwrap();
+            let (selected_fid, region) = (restored.selected_fid, restored.integration_region);
+            let file_handler = restored.into_file_handler(request_tx);
+            let mut plotter = Plotter::new();
+            plotter.restore_measurement(selected_fid, region, &file_handler);
+            plotter.measurement()
+        };
+        assert_eq!(
+            restore(&storage, &mut request_tx),
+            (Some(fid), Some((1.5, 4.0)))
+        );
+
+        // A selected file that is no longer part of the session is dropped,
+        // the region is kept.
+        storage.registry.clear();
+        assert_eq!(restore(&storage, &mut request_tx), (None, Some((1.5, 4.0))));
+
+        // Sessions saved without a measurement restore none.
+        let json = r#"{"search_path": "/data", "groups": [null, null, null, null, null, null, null, null, null, null], "registry": {}, "next_id": 0}"#;
+        let restored: FrontendStorage = serde_json::from_str(json).unwrap();
+        assert_eq!(restored.selected_fid, None);
+        assert_eq!(restored.integration_region, None);
+        assert_eq!(restored.ticks, [AxisTicks::default(); 2]);
+    }
+
+    #[test]
+    fn test_session_round_trip_keeps_ticks() {
+        let json = r#"{"search_path": "/data", "groups": [null, null, null, null, null, null, null, null, null, null], "registry": {}, "next_id": 0, "ticks": [{"mode": "Spacing", "count": 5, "spacing": 100.0}, {"mode": "Count", "count": 4, "spacing": 1.0}]}"#;
+        let restored: FrontendStorage = serde_json::from_str(json).unwrap();
+        assert_ne!(restored.ticks[0], AxisTicks::default());
+        assert_ne!(restored.ticks[1], AxisTicks::default());
+        let json = serde_json::to_string(&restored).unwrap();
+        let again: FrontendStorage = serde_json::from_str(&json).unwrap();
+        assert_eq!(again.ticks, restored.ticks);
+    }
+
+    #[test]
+    fn test_session_round_trip_keeps_non_finite_data() {
+        let points = [
+            [0.0, f64::NAN],
+            [1.0, f64::INFINITY],
+            [2.0, f64::NEG_INFINITY],
+        ];
+        let data = PlotData::from_points(&points, Path::new("derived")).unwrap();
+        let storage = FileStorage {
+            path: PathBuf::from("derived"),
+            properties: FileProperties::default(),
+            cached_data: Some(data),
+        };
+        let json = serde_json::to_string(&storage).unwrap();
+        let restored: FileStorage = serde_json::from_str(&json).unwrap();
+        let (mut request_tx, _request_rx) = std::sync::mpsc::channel();
+        let file = restored.into_file(&mut request_tx);
+        let data = file.data.value().as_ref().unwrap();
+        let ys: Vec<_> = data.get_cache().data.iter().map(|[_, y]| *y).collect();
+        assert!(ys[0].is_nan());
+        assert_eq!(ys[1..], [f64::INFINITY, f64::NEG_INFINITY]);
+
+        // Older sessions stored all of them as null.
+        let json = r#"{"columns": [[0.0], [null]], "num_columns": 2, "comments": "", "cache": {"data": [[0.0, null]], "xcol": 0, "ycol": 1}, "source": null}"#;
+        let data: PlotData = serde_json::from_str(json).unwrap();
+        assert!(data.get_cache().data[0][1].is_nan());
+    }
+
+    #[test]
+    fn test_session_round_trip_keeps_pasted_gaps() {
+        // An empty cell in the pasted text is read as NaN.
+        let options = LoadOptions::default();
+        let text = "1\t2\n3\t\n5\t6\n";
+        let data = PlotData::from_text(text, Path::new("clipboard"), &options).unwrap();
+        assert!(data.get_cache().data[1][1].is_nan());
+        let storage = FileStorage {
+            path: PathBuf::from("clipboard"),
+            properties: FileProperties::default(),
+            cached_data: Some(data),
+        };
+        let json = serde_json::to_string(&storage).unwrap();
+        let restored: FileStorage = serde_json::from_str(&json).unwrap();
+        let (mut request_tx, _request_rx) = std::sync::mpsc::channel();
+        let file = restored.into_file(&mut request_tx);
+        assert!(file.is_pasted());
+        let data = file.data.value().as_ref().unwrap();
+        let points = &data.get_cache().data;
+        assert_eq!(points.len(), 3);
+        assert_eq!(points[0], [1.0, 2.0]);
+        assert!(points[1][1].is_nan());
+        assert_eq!(points[2], [5.0, 6.0]);
+    }
+
+    #[test]
+    fn test_missing_properties_default() {
+        // A file entry as written by a version without most display options.
+        let json = r#"{"path": "/data/file.csv", "properties": {"alias": "old", "yscale": 2.0}}"#;
+        let file: FileStorage = serde_json::from_str(json).unwrap();
+        let expected = FileProperties {
+            alias: "old".to_string(),
+            yscale: 2.0,
+            ..Default::default()
+        };
+        assert_eq!(file.properties, expected);
+        assert!(file.cached_data.is_none());
+    }
+}
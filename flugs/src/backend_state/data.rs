@@ -1,22 +1,212 @@
 #![allow(unused)]
 
-use std::{collections::HashMap, path::Path};
+use std::{cell::RefCell, collections::HashMap, path::Path, time::SystemTime};
 
 use app_core::string_error::ErrorStringExt;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Default, Clone)]
+use crate::csv::{delimiter_mode, finish_columns, read_csv, ReadColumns};
+use crate::utils::simplify::Reduction;
+use turbo_csv::{DecimalSeparator, NumberFormat, ThousandsSeparator};
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct PlotCache {
+    #[serde(with = "floats::points")]
     pub data: Vec<[f64; 2]>,
     pub xcol: Option<usize>,
     pub ycol: usize,
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct PlotData {
+    #[serde(with = "floats::columns")]
     pub columns: Vec<Vec<f64>>,
     num_columns: usize,
     comments: String,
     cache: PlotCache,
+    // Size and modification time of the file when it was read, used to decide
+    // whether a cached copy of the data is still valid.
+    source: Option<SourceStamp>,
+    // Number format of CSV files, not kept in cached copies.
+    #[serde(skip)]
+    number_format: Option<ParsedFormat>,
+    // Number of infinite values in the file, before they were handled
+    // according to `LoadOptions::infinite_values`.
+    #[serde(default)]
+    infinite_values: usize,
+    // Whether the data was pasted or derived from other files rather than
+    // read from a file. It cannot be reloaded then and is always kept in
+    // sessions.
+    #[serde(default)]
+    pasted: bool,
+    // Indices of the cached data that remain after point reduction, together
+    // with the reduction they were computed for.
+    #[serde(skip)]
+    reduced: RefCell<Option<(Reduction, Vec<usize>)>>,
+    // Cached data with a transform and smoothing applied, boxed since it is
+    // rarely used.
+    #[serde(skip)]
+    transformed: RefCell<Option<Box<TransformedCache>>>,
+    // Cached data with all corrections applied.
+    #[serde(skip)]
+    corrected: RefCell<Option<Box<CorrectedCache>>>,
+}
+
+#[derive(Clone, Debug)]
+struct TransformedCache {
+    // Transform expression, smoothing width and column swap the data was
+    // computed for.
+    transform: String,
+    sigma: f64,
+    swap_xy: bool,
+    data: Vec<[f64; 2]>,
+}
+
+#[derive(Clone, Debug)]
+struct CorrectedCache {
+    // The corrections the data was computed for.
+    corrections: Corrections,
+    data: Vec<[f64; 2]>,
+    // Indices of the peaks of the data, with the prominence and whether the
+    // y-axis was inverted by the scaling when they were found.
+    peaks: Option<(f64, bool, Vec<usize>)>,
+}
+
+/// The properties of a file that change its data before the offsets and
+/// scaling, see [`PlotData::corrected_data`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Corrections {
+    pub transform: String,
+    pub sigma: f64,
+    pub swap_xy: bool,
+    /// Anchors and degree of the baseline, if it is subtracted.
+    pub baseline: Option<(Vec<f64>, usize)>,
+    /// Names of the custom transforms.
+    pub transforms: Vec<String>,
+    pub masked_ranges: Vec<(f64, f64)>,
+}
+
+/// The number format a CSV file was parsed with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParsedFormat {
+    pub format: NumberFormat,
+    /// Whether the format was detected from the file, rather than taken
+    /// from the load options.
+    pub detected: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceStamp {
+    size: u64,
+    modified: SystemTime,
+}
+
+/// Settings that determine how files are turned into `PlotData`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoadOptions {
+    pub single_column: SingleColumnMode,
+    pub whitespace_delimited: WhitespaceMode,
+    pub encoding: turbo_csv::Encoding,
+    pub delimiter: Delimiter,
+    pub decimal_separator: DecimalSeparator,
+    pub thousands_separator: ThousandsSeparator,
+    /// Guess the separators from the first rows of each file, the ones
+    /// above are used if that is ambiguous.
+    pub detect_number_format: bool,
+    pub infinite_values: InfiniteValues,
+    /// Files larger than this (in MB) are not read, 0 allows any size.
+    pub max_file_size_mb: u64,
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        Self {
+            single_column: Default::default(),
+            whitespace_delimited: Default::default(),
+            encoding: Default::default(),
+            delimiter: Default::default(),
+            decimal_separator: Default::default(),
+            thousands_separator: Default::default(),
+            detect_number_format: true,
+            infinite_values: Default::default(),
+            max_file_size_mb: 500,
+        }
+    }
+}
+
+/// Which characters separate columns.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Delimiter {
+    /// Commas, semicolons, spaces and tabs, see also `WhitespaceMode`.
+    #[default]
+    Auto,
+    /// Only semicolons, spaces and tabs around the fields are trimmed.
+    Semicolon,
+}
+
+/// A named combination of load options for a common file format. Applying it
+/// only sets these options, they can still be adjusted individually.
+pub struct LoadPreset {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub apply: fn(&mut LoadOptions),
+}
+
+/// Presets for common file formats, offered next to the load options.
+pub const LOAD_PRESETS: &[LoadPreset] = &[
+    LoadPreset {
+        name: "Standard CSV",
+        description: "columns separated by commas, semicolons or whitespace, decimal point",
+        apply: |options| {
+            options.delimiter = Delimiter::Auto;
+            options.decimal_separator = DecimalSeparator::Point;
+        },
+    },
+    LoadPreset {
+        name: "European CSV",
+        description: "columns separated by semicolons, decimal comma",
+        apply: |options| {
+            options.delimiter = Delimiter::Semicolon;
+            options.decimal_separator = DecimalSeparator::Comma;
+        },
+    },
+];
+
+/// Start of the error message for files that exceed
+/// `LoadOptions::max_file_size_mb`, so that loading them anyway can be offered.
+pub const TOO_LARGE_MSG: &str = "file is larger than the size limit";
+
+/// When to split columns on whitespace only (e.g. space-aligned tables).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WhitespaceMode {
+    /// For files with the extension `.txt`.
+    #[default]
+    TxtFiles,
+    Always,
+    Never,
+}
+
+/// What to do with infinite values (e.g. `inf` or overflowing numbers like
+/// `1e999`), which would stretch the plot bounds.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum InfiniteValues {
+    /// Replace them with NaN, like missing values, so that the points are
+    /// not plotted.
+    #[default]
+    Drop,
+    /// Replace them with the largest (smallest) finite value of their column.
+    Clamp,
+    Keep,
+}
+
+/// What to do with files that contain only one column of numbers.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SingleColumnMode {
+    /// Plot the column against the row index.
+    #[default]
+    RowIndex,
+    /// Treat the file as invalid.
+    Reject,
 }
 
 // Helper struct to counts frequencies of potential delimiter characters.
@@ -28,25 +218,68 @@ struct DelimiterCounter {
 }
 
 impl PlotData {
-    pub fn from_path(path: &Path) -> Result<PlotData, String> {
-        let (comments, columns) = if path
-            .extension()
-            .and_then(|ext| ext.to_str())
-            .map(|ext| ext.parse::<u32>().is_ok())
-            .unwrap_or_default()
-        {
-            // If the file extension is an integer (.0, .1, etc.), we try to parse as a bruker file.
-            let bruker_parser::OpusAbsorbanceData {
-                wavenumber,
-                absorbance,
-            } = bruker_parser::OpusAbsorbanceData::from_path(path)?;
-            (String::new(), vec![wavenumber, absorbance])
+    pub fn from_path(path: &Path, options: &LoadOptions) -> Result<PlotData, String> {
+        Self::from_path_with_sample_rate(path, options, None)
+    }
+
+    /// Like [`PlotData::from_path`], but with `sample_rate`, all columns
+    /// that were read are y-columns and the x-column is generated from the
+    /// row index divided by the rate (e.g. for waveform captures without a
+    /// time column).
+    pub fn from_path_with_sample_rate(
+        path: &Path,
+        options: &LoadOptions,
+        sample_rate: Option<f64>,
+    ) -> Result<PlotData, String> {
+        // Only the metadata is read, so that huge files are rejected quickly.
+        if options.max_file_size_mb > 0 {
+            let size = std::fs::metadata(path)
+                .err_to_string("unable to read file")?
+                .len();
+            if size > options.max_file_size_mb.saturating_mul(1_000_000) {
+                return Err(format!(
+                    "{TOO_LARGE_MSG} ({:.1} MB > {} MB)",
+                    size as f64 / 1e6,
+                    options.max_file_size_mb
+                ));
+            }
+        }
+        // The stamp is taken before reading, so that a file changing while it
+        // is parsed invalidates the cache rather than going unnoticed.
+        let source = SourceStamp::from_path(path);
+        let read = read_columns(path, options, None, sample_rate)?;
+        Self::from_columns(path, read, source)
+    }
+
+    /// Parse pasted `text` (e.g. copied from a spreadsheet) like a CSV file,
+    /// shown as `name`. Text with tabs is split at tabs and spaces only, so
+    /// that commas in numbers are not taken as delimiters.
+    pub fn from_text(text: &str, name: &Path, options: &LoadOptions) -> Result<PlotData, String> {
+        let delimiter_mode = if text.contains('\t') {
+            turbo_csv::DelimiterMode::Whitespace
         } else {
-            // Otherwise, we try to parse as CSV.
-            let parser =
-                turbo_csv::Parser::from_path(path).err_to_string("unable to initialize parser")?;
-            parser.parse_as_floats()
+            delimiter_mode(options, false)
         };
+        let parser =
+            turbo_csv::Parser::from_string(text.to_string()).with_delimiter_mode(delimiter_mode);
+        let read = read_csv(parser, name, options, None)
+            .map_err(|err| format!("the pasted text {err}"))?;
+        let mut data = Self::from_columns(name, read, None)?;
+        data.pasted = true;
+        Ok(data)
+    }
+
+    fn from_columns(
+        path: &Path,
+        read: ReadColumns,
+        source: Option<SourceStamp>,
+    ) -> Result<PlotData, String> {
+        let ReadColumns {
+            comments,
+            columns,
+            number_format,
+            infinite_values,
+        } = read;
 
         let cache = if let Some(cache) = PlotCache::new(&columns, Some(0), 1) {
             log::debug!("add first two columns to cache");
@@ -64,19 +297,223 @@ impl PlotData {
             num_columns,
             comments,
             cache,
+            source,
+            number_format,
+            infinite_values,
+            pasted: false,
+            reduced: RefCell::default(),
+            transformed: RefCell::default(),
+            corrected: RefCell::default(),
         })
     }
 
+    /// Data computed from other files, e.g. their ratio. Like pasted data,
+    /// it is not backed by a file.
+    pub fn from_points(points: &[[f64; 2]], name: &Path) -> Result<PlotData, String> {
+        let read = ReadColumns {
+            comments: String::new(),
+            columns: vec![
+                points.iter().map(|[x, _]| *x).collect(),
+                points.iter().map(|[_, y]| *y).collect(),
+            ],
+            number_format: None,
+            infinite_values: 0,
+        };
+        let mut data = Self::from_columns(name, read, None)?;
+        data.pasted = true;
+        Ok(data)
+    }
+
+    /// Whether the data was pasted (see [`PlotData::from_text`]) or derived
+    /// (see [`PlotData::from_points`]) rather than read from a file.
+    pub fn is_pasted(&self) -> bool {
+        self.pasted
+    }
+
+    /// Columns of the first `rows` rows of the file at `path`, parsed like
+    /// [`PlotData::from_path`] would (but ignoring the size limit), to check
+    /// the load options before loading a large file.
+    pub fn preview(
+        path: &Path,
+        options: &LoadOptions,
+        rows: usize,
+    ) -> Result<Vec<Vec<f64>>, String> {
+        Ok(read_columns(path, options, Some(rows), None)?.columns)
+    }
+
+    /// The number format the file was parsed with, `None` for files that
+    /// are not CSV and cached copies.
+    pub fn number_format(&self) -> Option<ParsedFormat> {
+        self.number_format
+    }
+
+    /// Number of infinite values the file contained, see
+    /// [`LoadOptions::infinite_values`] for what happened to them.
+    pub fn infinite_values(&self) -> usize {
+        self.infinite_values
+    }
+
+    /// Size and modification time of the file when the data was read.
+    pub fn source(&self) -> Option<SourceStamp> {
+        self.source
+    }
+
+    /// Check whether the file the data was read from is unchanged on disk.
+    pub fn is_up_to_date_with(&self, path: &Path) -> bool {
+        self.source.is_some() && self.source == SourceStamp::from_path(path)
+    }
+
     pub fn get_cache(&self) -> &PlotCache {
         &self.cache
     }
 
-    pub fn ymin(&self) -> Option<f64> {
-        // TODO: This has to be changed when columns are selectable.
-        self.columns
-            .get(1)
-            .and_then(|ys| ys.iter().reduce(|a, b| if a < b { a } else { b }))
-            .copied()
+    /// Indices of the cached data that remain after applying `reduction`.
+    /// The result is kept until the reduction changes, reloaded data is a
+    /// new `PlotData` anyway.
+    pub fn reduced_indices(&self, reduction: Reduction) -> Vec<usize> {
+        let mut reduced = self.reduced.borrow_mut();
+        match &*reduced {
+            Some((cached_reduction, indices)) if *cached_reduction == reduction => indices.clone(),
+            _ => {
+                let indices = reduction.indices(&self.cache.data);
+                *reduced = Some((reduction, indices.clone()));
+                indices
+            }
+        }
+    }
+
+    /// The cached data transformed by `compute`, which is only called when
+    /// the transform expression, smoothing width or column swap in `key`
+    /// changes.
+    pub fn transformed_data(
+        &self,
+        key: (&str, f64, bool),
+        compute: impl FnOnce(&[[f64; 2]]) -> Vec<[f64; 2]>,
+    ) -> Vec<[f64; 2]> {
+        let mut transformed = self.transformed.borrow_mut();
+        match &*transformed {
+            Some(cache) if (cache.transform.as_str(), cache.sigma, cache.swap_xy) == key => {
+                cache.data.clone()
+            }
+            _ => {
+                let data = compute(&self.cache.data);
+                *transformed = Some(Box::new(TransformedCache {
+                    transform: key.0.to_string(),
+                    sigma: key.1,
+                    swap_xy: key.2,
+                    data: data.clone(),
+                }));
+                data
+            }
+        }
+    }
+
+    /// Call `f` with the data with `corrections` applied by `compute`, which
+    /// is only called when they change. The data is not copied, so `f` must
+    /// not change the cache (e.g. by [`PlotData::corrected_peaks`]).
+    pub fn with_corrected_data<R>(
+        &self,
+        corrections: &Corrections,
+        compute: impl FnOnce() -> Vec<[f64; 2]>,
+        f: impl FnOnce(&[[f64; 2]]) -> R,
+    ) -> R {
+        let is_current = self
+            .corrected
+            .borrow()
+            .as_ref()
+            .is_some_and(|cache| cache.corrections == *corrections);
+        if !is_current {
+            let data = compute();
+            *self.corrected.borrow_mut() = Some(Box::new(CorrectedCache {
+                corrections: corrections.clone(),
+                data,
+                peaks: None,
+            }));
+        }
+        let cache = self.corrected.borrow();
+        f(&cache.as_ref().expect("the cache was just filled").data)
+    }
+
+    /// Peak indices of the data with `corrections` applied, found by
+    /// `compute` for `prominence` and `inverted`. They are kept with the
+    /// corrected data, so `compute` is only called when one of them changes.
+    pub fn corrected_peaks(
+        &self,
+        corrections: &Corrections,
+        prominence: f64,
+        inverted: bool,
+        compute: impl FnOnce() -> Vec<usize>,
+    ) -> Vec<usize> {
+        if let Some(cache) = &*self.corrected.borrow() {
+            if let Some((p, i, peaks)) = &cache.peaks {
+                if cache.corrections == *corrections && (*p, *i) == (prominence, inverted) {
+                    return peaks.clone();
+                }
+            }
+        }
+        let peaks = compute();
+        if let Some(cache) = self
+            .corrected
+            .borrow_mut()
+            .as_mut()
+            .filter(|cache| cache.corrections == *corrections)
+        {
+            cache.peaks = Some((prominence, inverted, peaks.clone()));
+        }
+        peaks
+    }
+}
+
+/// Comments and columns of the file at `path`, parsed according to `options`
+/// (the size limit is not checked). With `max_rows`, only that many rows
+/// (lines of text files) are read. With `sample_rate`, an x-column is
+/// generated from the row index.
+fn read_columns(
+    path: &Path,
+    options: &LoadOptions,
+    max_rows: Option<usize>,
+    sample_rate: Option<f64>,
+) -> Result<ReadColumns, String> {
+    let is_bruker = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.parse::<u32>().is_ok())
+        .unwrap_or_default();
+    let read = if is_bruker {
+        // If the file extension is an integer (.0, .1, etc.), we try to parse as a bruker file.
+        let bruker_parser::OpusAbsorbanceData {
+            wavenumber,
+            absorbance,
+        } = bruker_parser::OpusAbsorbanceData::from_path(path)?;
+        let mut columns = vec![wavenumber, absorbance];
+        if let Some(rows) = max_rows {
+            columns.iter_mut().for_each(|col| col.truncate(rows));
+        }
+        finish_columns(path, options, sample_rate, String::new(), columns, None)
+    } else {
+        // Otherwise, we try to parse as CSV.
+        let is_txt = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("txt"));
+        let parser = match max_rows {
+            Some(rows) => turbo_csv::Parser::from_path_head(path, options.encoding, rows),
+            None => turbo_csv::Parser::from_path_with_encoding(path, options.encoding),
+        }
+        .err_to_string("unable to read file")?
+        .with_delimiter_mode(delimiter_mode(options, is_txt));
+        read_csv(parser, path, options, sample_rate)
+    };
+    read.map_err(|err| format!("{path:?} {err}"))
+}
+
+impl SourceStamp {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let metadata = std::fs::metadata(path).ok()?;
+        Some(Self {
+            size: metadata.len(),
+            modified: metadata.modified().ok()?,
+        })
     }
 }
 
@@ -96,3 +533,130 @@ impl PlotCache {
         Some(Self { data, xcol, ycol })
     }
 }
+
+/// Serialization of data that may contain NaN or infinite values, which JSON
+/// has no numbers for. They are stored as strings ("NaN", "inf", "-inf");
+/// `null`, as written by older versions for any of them, is read as NaN.
+mod floats {
+    use std::fmt;
+
+    use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+    struct Float(f64);
+
+    impl Serialize for Float {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            if self.0.is_finite() {
+                serializer.serialize_f64(self.0)
+            } else {
+                serializer.collect_str(&self.0)
+            }
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Float {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_any(FloatVisitor)
+        }
+    }
+
+    struct FloatVisitor;
+
+    impl de::Visitor<'_> for FloatVisitor {
+        type Value = Float;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+            formatter.write_str("a number, \"NaN\", \"inf\" or \"-inf\"")
+        }
+
+        fn visit_f64<E: de::Error>(self, v: f64) -> Result<Float, E> {
+            Ok(Float(v))
+        }
+
+        fn visit_i64<E: de::Error>(self, v: i64) -> Result<Float, E> {
+            Ok(Float(v as f64))
+        }
+
+        fn visit_u64<E: de::Error>(self, v: u64) -> Result<Float, E> {
+            Ok(Float(v as f64))
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<Float, E> {
+            v.parse().map(Float).map_err(E::custom)
+        }
+
+        fn visit_unit<E: de::Error>(self) -> Result<Float, E> {
+            Ok(Float(f64::NAN))
+        }
+    }
+
+    pub mod columns {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(
+            columns: &[Vec<f64>],
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(
+                columns
+                    .iter()
+                    .map(|column| column.iter().map(|v| Float(*v)).collect::<Vec<_>>()),
+            )
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Vec<Vec<f64>>, D::Error> {
+            let columns = Vec::<Vec<Float>>::deserialize(deserializer)?;
+            Ok(columns
+                .into_iter()
+                .map(|column| column.into_iter().map(|v| v.0).collect())
+                .collect())
+        }
+    }
+
+    pub mod points {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(
+            points: &[[f64; 2]],
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(points.iter().map(|[x, y]| [Float(*x), Float(*y)]))
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Vec<[f64; 2]>, D::Error> {
+            let points = Vec::<[Float; 2]>::deserialize(deserializer)?;
+            Ok(points.into_iter().map(|[x, y]| [x.0, y.0]).collect())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_text() {
+        let name = Path::new("pasted");
+        let options = LoadOptions::default();
+        // Copied from a spreadsheet: tabs separate the columns, the commas
+        // are decimal separators.
+        let data = PlotData::from_text("x\ty\n1,5\t2\n3\t4,25\n", name, &options).unwrap();
+        assert_eq!(data.get_cache().data, [[1.5, 2.0], [3.0, 4.25]]);
+        assert!(data.is_pasted());
+        let data = PlotData::from_text("1,2\n3,4\n", name, &options).unwrap();
+        assert_eq!(data.get_cache().data, [[1.0, 2.0], [3.0, 4.0]]);
+
+        let err = PlotData::from_text("no numbers\n", name, &options).unwrap_err();
+        assert_eq!(err, "the pasted text contains no numbers");
+        let options = LoadOptions {
+            single_column: SingleColumnMode::Reject,
+            ..Default::default()
+        };
+        let err = PlotData::from_text("1\n2\n", name, &options).unwrap_err();
+        assert_eq!(err, "the pasted text contains only one column");
+    }
+}
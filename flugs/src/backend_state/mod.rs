@@ -6,21 +6,28 @@ use std::{
     path::{Path, PathBuf},
 };
 
-pub use data::PlotData;
+pub use data::{
+    Corrections, Delimiter, InfiniteValues, LoadOptions, ParsedFormat, PlotData, SingleColumnMode,
+    SourceStamp, WhitespaceMode, LOAD_PRESETS, TOO_LARGE_MSG,
+};
+
+use crate::utils::matching::match_paths;
 
 #[derive(Default)]
 pub struct BackendAppState {
     search_path: PathBuf,
     child_paths_unfiltered: Vec<PathBuf>,
+    load_options: LoadOptions,
 }
 
 impl BackendState for BackendAppState {}
 
 impl BackendAppState {
-    pub fn new(search_path: PathBuf) -> Self {
+    pub fn new(search_path: PathBuf, load_options: LoadOptions) -> Self {
         Self {
             search_path,
             child_paths_unfiltered: Vec::new(),
+            load_options,
         }
     }
 }
@@ -65,44 +72,41 @@ impl BackendAppState {
         self.update_child_paths_unfiltered();
     }
 
+    pub fn get_load_options(&self) -> LoadOptions {
+        self.load_options.clone()
+    }
+
+    pub fn set_load_options(&mut self, load_options: LoadOptions) {
+        self.load_options = load_options;
+    }
+
+    /// Load data from `path` using the current load options. With
+    /// `check_size` unset, the size limit is ignored. With `sample_rate`, the
+    /// x-column is generated (see [`PlotData::from_path_with_sample_rate`]).
+    pub fn load_file(
+        &self,
+        path: &Path,
+        check_size: bool,
+        sample_rate: Option<f64>,
+    ) -> Result<PlotData, String> {
+        if check_size {
+            PlotData::from_path_with_sample_rate(path, &self.load_options, sample_rate)
+        } else {
+            let options = LoadOptions {
+                max_file_size_mb: 0,
+                ..self.load_options.clone()
+            };
+            PlotData::from_path_with_sample_rate(path, &options, sample_rate)
+        }
+    }
+
     /// Return the best file path matches for `query`, together with the
-    /// corresponding matching indices in the file path.
-    ///
-    /// For a file path to match, the file path must contain all words
-    /// (separated by white space).
+    /// corresponding matching indices in the file path (see
+    /// [`match_paths`]).
     pub fn search_filter(&self, query: &str) -> Vec<(PathBuf, HashSet<usize>)> {
-        let contains_query = |filename: &&PathBuf| {
-            let fp = filename.to_str();
-            if fp.is_none() {
-                return false;
-            }
-            let fp = fp.unwrap();
-            query.split(" ").all(|q| {
-                if q.starts_with("!") {
-                    !fp.contains(&q[1..])
-                } else {
-                    fp.contains(q)
-                }
-            })
-        };
-        let query_indices = |filename: &PathBuf| {
-            let mut indices = HashSet::new();
-            let fp = filename.to_str()?;
-            for q in query.split(" ") {
-                if q.starts_with("!") {
-                    continue;
-                }
-                let idx = fp.find(q)?;
-                indices.extend(idx..idx + q.len());
-            }
-            Some((filename.to_owned(), indices))
-        };
-
-        self.child_paths_unfiltered
-            .iter()
-            .filter(contains_query)
-            .take(10)
-            .filter_map(query_indices)
+        match_paths(query, &self.child_paths_unfiltered, 10)
+            .into_iter()
+            .map(|m| (m.path, m.indices))
             .collect()
     }
 }
@@ -0,0 +1,126 @@
+//! Command line arguments of the binary.
+
+use std::path::PathBuf;
+
+/// Arguments given on the command line.
+#[derive(Debug, Default, PartialEq)]
+pub struct CliArgs {
+    /// Directory to search files in, overrides the config.
+    pub search_dir: Option<PathBuf>,
+    /// Files to load directly at startup, all into the same group.
+    pub files: Vec<PathBuf>,
+    /// Directory of the config file (`--config-dir`), overrides the
+    /// environment variable.
+    pub config_dir: Option<PathBuf>,
+    /// Session and output file (`--render SESSION OUTPUT`): render the
+    /// session without opening a window.
+    pub render: Option<(PathBuf, PathBuf)>,
+}
+
+impl CliArgs {
+    /// Parse the arguments (without the program name).
+    ///
+    /// Files are loaded at startup (into one group), directories select the
+    /// search directory. If several directories are given, the first one is
+    /// used. Without any directory, the directory of the first file is
+    /// searched. Invalid paths are ignored with a warning, so that the app
+    /// starts with the default directory or the remaining files.
+    ///
+    /// `--config-dir DIR` (or `--config-dir=DIR`) selects the directory of the
+    /// config file, `--render SESSION OUTPUT` renders a session headless.
+    /// Options that lack their value are an error.
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut cli_args = Self::default();
+        let mut explicit_dir = false;
+        while let Some(arg) = args.next() {
+            if arg == "--config-dir" {
+                let dir = args.next().ok_or("'--config-dir' requires a directory")?;
+                cli_args.config_dir = Some(PathBuf::from(dir));
+                continue;
+            }
+            if arg == "--render" {
+                let (Some(session), Some(output)) = (args.next(), args.next()) else {
+                    return Err("'--render' requires a session and an output file".into());
+                };
+                cli_args.render = Some((PathBuf::from(session), PathBuf::from(output)));
+                continue;
+            }
+            if let Some(dir) = arg.strip_prefix("--config-dir=") {
+                cli_args.config_dir = Some(PathBuf::from(dir));
+                continue;
+            }
+            if arg.starts_with('-') {
+                log::warn!("ignoring unknown option '{arg}'");
+                continue;
+            }
+            let path = PathBuf::from(&arg);
+            if path.is_dir() {
+                if explicit_dir {
+                    log::warn!("ignoring additional directory {path:?}");
+                    continue;
+                }
+                explicit_dir = true;
+                cli_args.search_dir = Some(path);
+            } else if path.is_file() {
+                if !explicit_dir && cli_args.files.is_empty() {
+                    cli_args.search_dir = path.parent().map(|dir| dir.to_path_buf());
+                }
+                cli_args.files.push(path);
+            } else {
+                log::warn!("{path:?} is neither a directory nor a file, ignoring it");
+            }
+        }
+        Ok(cli_args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<CliArgs, String> {
+        CliArgs::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_paths() {
+        // Tests run in the package directory.
+        let args = parse(&["Cargo.toml", "src/lib.rs", "missing.csv"]).unwrap();
+        assert_eq!(
+            args.files,
+            [PathBuf::from("Cargo.toml"), "src/lib.rs".into()]
+        );
+        assert_eq!(args.search_dir, Some(PathBuf::from("")));
+
+        // An explicit directory wins over the directory of the files, only
+        // the first directory is used.
+        let args = parse(&["src/lib.rs", "src", "src/app"]).unwrap();
+        assert_eq!(args.search_dir, Some(PathBuf::from("src")));
+        assert_eq!(args.files, [PathBuf::from("src/lib.rs")]);
+        assert_eq!(parse(&["--unknown"]), Ok(CliArgs::default()));
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let args = parse(&[
+            "--config-dir",
+            "/etc/flugs",
+            "--render",
+            "s.json",
+            "out.svg",
+        ]);
+        assert_eq!(
+            args,
+            Ok(CliArgs {
+                config_dir: Some("/etc/flugs".into()),
+                render: Some(("s.json".into(), "out.svg".into())),
+                ..Default::default()
+            })
+        );
+        let args = parse(&["--config-dir=/etc/flugs"]).unwrap();
+        assert_eq!(args.config_dir, Some("/etc/flugs".into()));
+
+        assert!(parse(&["--config-dir"]).is_err());
+        assert!(parse(&["--render", "s.json"]).is_err());
+    }
+}
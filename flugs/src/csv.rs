@@ -0,0 +1,330 @@
+//! Parsing CSV text into x-y data, independent of the backend.
+//!
+//! [`load_csv`] applies the same [`LoadOptions`] as files opened in the app:
+//!
+//! ```
+//! use flugs::{csv::load_csv, LoadOptions};
+//!
+//! let text = "# time,signal\n0,1.5\n1,2.5\n";
+//! let data = load_csv(text, &LoadOptions::default()).unwrap();
+//! assert_eq!(data, [[0.0, 1.5], [1.0, 2.5]]);
+//! ```
+
+use std::{fmt, path::Path};
+
+use turbo_csv::NumberFormat;
+
+use crate::backend_state::{
+    Delimiter, InfiniteValues, LoadOptions, ParsedFormat, SingleColumnMode, WhitespaceMode,
+};
+
+/// Why text could not be turned into x-y data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// There are no rows with numbers.
+    NoNumbers,
+    /// There is no x-column, see [`SingleColumnMode::Reject`].
+    SingleColumn,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::NoNumbers => write!(f, "contains no numbers"),
+            ParseError::SingleColumn => write!(f, "contains only one column"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// The points of the first two columns of CSV `text`, parsed according to
+/// `options` (the size limit does not apply). Lines that do not start with
+/// a number are comments, missing or unparsable values are NaN.
+pub fn load_csv(text: &str, options: &LoadOptions) -> Result<Vec<[f64; 2]>, ParseError> {
+    let parser = turbo_csv::Parser::from_string(text.to_string())
+        .with_delimiter_mode(delimiter_mode(options, false));
+    let read = read_csv(parser, Path::new("<text>"), options, None)?;
+    match read.columns.as_slice() {
+        [x, y, ..] => Ok(x.iter().zip(y).map(|(x, y)| [*x, *y]).collect()),
+        _ => Err(ParseError::NoNumbers),
+    }
+}
+
+/// The comments and columns read from a file.
+pub(crate) struct ReadColumns {
+    pub comments: String,
+    pub columns: Vec<Vec<f64>>,
+    /// Only for CSV files.
+    pub number_format: Option<ParsedFormat>,
+    pub infinite_values: usize,
+}
+
+/// How the columns of a CSV file are separated, given the load options and
+/// whether it is a text file.
+pub(crate) fn delimiter_mode(options: &LoadOptions, is_txt: bool) -> turbo_csv::DelimiterMode {
+    match (options.delimiter, options.whitespace_delimited, is_txt) {
+        (Delimiter::Semicolon, _, _) => turbo_csv::DelimiterMode::Semicolon,
+        (_, WhitespaceMode::Always, _) | (_, WhitespaceMode::TxtFiles, true) => {
+            turbo_csv::DelimiterMode::Whitespace
+        }
+        _ => turbo_csv::DelimiterMode::Auto,
+    }
+}
+
+/// Parse the CSV input of `parser` (read from `path`) according to
+/// `options`, the same way for files, pasted text and [`load_csv`]. With
+/// `sample_rate`, an x-column is generated from the row index.
+pub(crate) fn read_csv(
+    parser: turbo_csv::Parser,
+    path: &Path,
+    options: &LoadOptions,
+    sample_rate: Option<f64>,
+) -> Result<ReadColumns, ParseError> {
+    let (parsed, (comments, columns)) = parse_csv(parser, path, options);
+    if columns.iter().all(|col| col.is_empty()) {
+        return Err(ParseError::NoNumbers);
+    }
+    finish_columns(path, options, sample_rate, comments, columns, Some(parsed))
+}
+
+/// Parse the comments and columns with the number format of the load
+/// options (or the detected one), see [`number_format_for`].
+fn parse_csv(
+    parser: turbo_csv::Parser,
+    path: &Path,
+    options: &LoadOptions,
+) -> (ParsedFormat, (String, Vec<Vec<f64>>)) {
+    let parsed = number_format_for(&parser, path, options);
+    let columns = parser
+        .with_decimal_separator(parsed.format.decimal_separator)
+        .with_thousands_separator(parsed.format.thousands_separator)
+        .parse_as_floats();
+    (parsed, columns)
+}
+
+/// Handle the infinite values and the x-column of the columns read from
+/// `path`. With `sample_rate`, an x-column is generated from the row index.
+pub(crate) fn finish_columns(
+    path: &Path,
+    options: &LoadOptions,
+    sample_rate: Option<f64>,
+    comments: String,
+    mut columns: Vec<Vec<f64>>,
+    number_format: Option<ParsedFormat>,
+) -> Result<ReadColumns, ParseError> {
+    let infinite_values = handle_infinite_values(&mut columns, options.infinite_values);
+    if infinite_values > 0 {
+        let handled = match options.infinite_values {
+            InfiniteValues::Drop => "dropped",
+            InfiniteValues::Clamp => "clamped to the finite range of their column",
+            InfiniteValues::Keep => "kept",
+        };
+        log::warn!("{infinite_values} infinite values in {path:?}, {handled}");
+    }
+    let read = |columns| ReadColumns {
+        comments,
+        columns,
+        number_format,
+        infinite_values,
+    };
+
+    if let Some(rate) = sample_rate {
+        let rows = columns.first().map_or(0, |col| col.len());
+        columns.insert(0, (0..rows).map(|i| i as f64 / rate).collect());
+        return Ok(read(columns));
+    }
+
+    // With only one column, there is no x-data, so we either generate it
+    // from the row index or bail out.
+    let columns = match (columns.len(), options.single_column) {
+        (1, SingleColumnMode::RowIndex) => {
+            log::debug!("only one column in {path:?}, using row index as x-data");
+            let index = (0..columns[0].len()).map(|i| i as f64).collect();
+            let mut columns = columns;
+            columns.insert(0, index);
+            columns
+        }
+        (1, SingleColumnMode::Reject) => {
+            return Err(ParseError::SingleColumn);
+        }
+        _ => columns,
+    };
+    Ok(read(columns))
+}
+
+/// Replace the infinite values of `columns` according to `handling`, returns
+/// how many there were.
+fn handle_infinite_values(columns: &mut [Vec<f64>], handling: InfiniteValues) -> usize {
+    let mut count = 0;
+    for column in columns {
+        let (min, max) = column
+            .iter()
+            .filter(|value| value.is_finite())
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), value| {
+                (min.min(*value), max.max(*value))
+            });
+        for value in column.iter_mut().filter(|value| value.is_infinite()) {
+            count += 1;
+            *value = match handling {
+                InfiniteValues::Drop => f64::NAN,
+                // Without finite values, there is nothing to clamp to.
+                InfiniteValues::Clamp if min > max => f64::NAN,
+                InfiniteValues::Clamp if *value > 0.0 => max,
+                InfiniteValues::Clamp => min,
+                InfiniteValues::Keep => *value,
+            };
+        }
+    }
+    count
+}
+
+/// The number format to parse the file at `path` with: detected from its
+/// first rows if enabled and unambiguous, else the one of the load options.
+fn number_format_for(
+    parser: &turbo_csv::Parser,
+    path: &Path,
+    options: &LoadOptions,
+) -> ParsedFormat {
+    let configured = NumberFormat {
+        decimal_separator: options.decimal_separator,
+        thousands_separator: options.thousands_separator,
+    };
+    if !options.detect_number_format {
+        return ParsedFormat {
+            format: configured,
+            detected: false,
+        };
+    }
+    match parser.detect_number_format() {
+        Some(format) => {
+            log::info!("detected number format of {path:?}: {format:?}");
+            ParsedFormat {
+                format,
+                detected: true,
+            }
+        }
+        None => {
+            log::info!(
+                "number format of {path:?} is ambiguous, using the load options: {configured:?}"
+            );
+            ParsedFormat {
+                format: configured,
+                detected: false,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Compare including NaN, which is not equal to itself.
+    fn assert_points(actual: &[[f64; 2]], expected: &[[f64; 2]]) {
+        let bits = |points: &[[f64; 2]]| -> Vec<_> {
+            points
+                .iter()
+                .map(|[x, y]| [x.to_bits(), y.to_bits()])
+                .collect()
+        };
+        assert_eq!(bits(actual), bits(expected), "{actual:?}");
+    }
+
+    #[test]
+    fn test_delimiters() {
+        let options = LoadOptions::default();
+        let expected = [[1.0, 2.5], [3.0, 4.5]];
+        assert_points(&load_csv("1,2.5\n3,4.5\n", &options).unwrap(), &expected);
+        assert_points(&load_csv("1;2.5\n3;4.5\n", &options).unwrap(), &expected);
+        assert_points(&load_csv("1\t2.5\n3\t4.5\n", &options).unwrap(), &expected);
+        assert_points(&load_csv("1  2.5\n3 4.5\n", &options).unwrap(), &expected);
+
+        let options = LoadOptions {
+            delimiter: Delimiter::Semicolon,
+            decimal_separator: turbo_csv::DecimalSeparator::Comma,
+            detect_number_format: false,
+            ..Default::default()
+        };
+        assert_points(&load_csv("1;2,5\n3;4,5\n", &options).unwrap(), &expected);
+    }
+
+    #[test]
+    fn test_comments() {
+        let text = "# measured today\ntime,signal\n1,2\n% note\n3,4\n";
+        let data = load_csv(text, &LoadOptions::default()).unwrap();
+        assert_points(&data, &[[1.0, 2.0], [3.0, 4.0]]);
+        assert_eq!(
+            load_csv("# only comments\nx,y\n", &LoadOptions::default()),
+            Err(ParseError::NoNumbers)
+        );
+        assert_eq!(
+            load_csv("", &LoadOptions::default()),
+            Err(ParseError::NoNumbers)
+        );
+    }
+
+    #[test]
+    fn test_nan_and_malformed_rows() {
+        let options = LoadOptions::default();
+        let data = load_csv("1,NaN\n2,\n3,abc\n4,5,6\n", &options).unwrap();
+        assert_eq!(data.len(), 4);
+        assert_points(&data[3..], &[[4.0, 5.0]]);
+        // Missing and unparsable values are NaN, extra columns are ignored.
+        for (i, [x, y]) in data[..3].iter().enumerate() {
+            assert_eq!(*x, i as f64 + 1.0);
+            assert!(y.is_nan());
+        }
+
+        let options = LoadOptions {
+            infinite_values: InfiniteValues::Clamp,
+            ..Default::default()
+        };
+        let data = load_csv("1,inf\n2,3\n3,-inf\n4,5\n", &options).unwrap();
+        assert_points(&data, &[[1.0, 5.0], [2.0, 3.0], [3.0, 3.0], [4.0, 5.0]]);
+    }
+
+    #[test]
+    fn test_single_column() {
+        let text = "5\n6\n";
+        let options = LoadOptions {
+            single_column: SingleColumnMode::RowIndex,
+            ..Default::default()
+        };
+        assert_points(
+            &load_csv(text, &options).unwrap(),
+            &[[0.0, 5.0], [1.0, 6.0]],
+        );
+        let options = LoadOptions {
+            single_column: SingleColumnMode::Reject,
+            ..Default::default()
+        };
+        assert_eq!(load_csv(text, &options), Err(ParseError::SingleColumn));
+    }
+
+    #[test]
+    fn test_sample_rate() {
+        let finish = |columns: Vec<Vec<f64>>| {
+            let options = LoadOptions {
+                single_column: SingleColumnMode::Reject,
+                ..Default::default()
+            };
+            let path = Path::new("data.wav");
+            finish_columns(path, &options, Some(4.0), String::new(), columns, None)
+                .unwrap()
+                .columns
+        };
+        // The x-column is generated even for a single column, and all
+        // columns that were read are kept as y-columns.
+        let xs = vec![0.0, 0.25, 0.5];
+        assert_eq!(
+            finish(vec![vec![5.0, 6.0, 7.0]]),
+            [xs.clone(), vec![5.0, 6.0, 7.0]]
+        );
+        let columns = vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]];
+        let mut expected = columns.clone();
+        expected.insert(0, xs);
+        assert_eq!(finish(columns), expected);
+        assert_eq!(finish(Vec::new()), [Vec::<f64>::new()]);
+    }
+}
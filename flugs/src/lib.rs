@@ -2,8 +2,16 @@
 
 mod app;
 mod backend_state;
+pub mod cli;
+pub mod csv;
+pub mod utils;
 
-pub use app::config::Config;
+pub use app::config::{config_file_path, window_state_path, Config, CONFIG_DIR_VAR};
 pub use app::storage;
-pub use app::EguiApp;
-pub use backend_state::BackendAppState;
+pub use app::{
+    render_session, EguiApp, FileHandler, FileID, FileInfo, FileProperties, LineCap, LineJoin,
+    TransformMode,
+};
+pub use backend_state::{
+    BackendAppState, Delimiter, InfiniteValues, LoadOptions, SingleColumnMode, WhitespaceMode,
+};
@@ -1,7 +1,10 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
 use app_core::backend::BackendEventLoop;
-use flugs::{BackendAppState, Config, EguiApp};
+use flugs::{
+    cli::CliArgs, config_file_path, render_session, window_state_path, BackendAppState, Config,
+    EguiApp,
+};
 
 const WINDOW_NAME: &str = "Flugs >>";
 const WINDOW_WIDTH: f32 = 400.0;
@@ -10,34 +13,63 @@ const WINDOW_HEIGHT: f32 = 300.0;
 fn main() -> eframe::Result {
     env_logger::init();
 
+    let args = match CliArgs::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{err}");
+            std::process::exit(2);
+        }
+    };
+    let config_path = config_file_path(args.config_dir.as_deref());
+    let mut config = match config_path.as_deref().map(Config::from_config_file_at) {
+        Some(Ok(config)) => config,
+        Some(Err(err)) => {
+            log::warn!("unable to load config file {config_path:?}: {err}");
+            Config {
+                file_path: config_path,
+                ..Default::default()
+            }
+        }
+        None => {
+            log::warn!("unable to determine location of config file");
+            Config::default()
+        }
+    };
+    if let Some(dir) = args.search_dir {
+        config.search_path = dir;
+    }
+    if let Some((session, output)) = &args.render {
+        match render_session(config, session, output) {
+            Ok(()) => std::process::exit(0),
+            Err(err) => {
+                log::error!("{err}");
+                eprintln!("rendering failed: {err}");
+                std::process::exit(1);
+            }
+        }
+    }
     // start backend loop
     let (command_tx, command_rx) = std::sync::mpsc::channel();
-    #[allow(deprecated)]
-    let config = if let Ok(config) = Config::from_config_file() {
-        config
-    } else {
-        log::warn!("unable to load config file \".flugs\" from home directory");
-        Config::default()
-    };
-    let backend_state = BackendAppState::new(config.search_path.clone());
-    let eventloop_handle = BackendEventLoop::new(command_rx, backend_state).run();
+    let backend_state =
+        BackendAppState::new(config.search_path.clone(), config.load_options.clone());
+    let eventloop = BackendEventLoop::new(command_rx, backend_state);
+    let pending_requests = eventloop.pending_requests();
+    let eventloop_handle = eventloop.run();
 
     let native_options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([WINDOW_HEIGHT, WINDOW_HEIGHT])
             .with_min_inner_size([WINDOW_WIDTH, WINDOW_HEIGHT]),
+        persistence_path: window_state_path(args.config_dir.as_deref()),
         ..Default::default()
     };
     eframe::run_native(
         WINDOW_NAME,
         native_options,
         Box::new(|cc| {
-            Ok(Box::new(EguiApp::new(
-                cc,
-                config,
-                command_tx,
-                eventloop_handle,
-            )))
+            let mut app = EguiApp::new(cc, config, command_tx, eventloop_handle, pending_requests);
+            app.open_files(&args.files);
+            Ok(Box::new(app))
         }),
     )
 }
@@ -0,0 +1,131 @@
+//! Polynomial baselines through anchor points.
+
+/// Polynomial in `(x - shift) / scale`, the shift and scale keep the fit
+/// well-conditioned for large x-values (e.g. wavenumbers).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Polynomial {
+    /// Coefficients, lowest order first.
+    coefficients: Vec<f64>,
+    shift: f64,
+    scale: f64,
+}
+
+impl Polynomial {
+    pub fn eval(&self, x: f64) -> f64 {
+        let t = (x - self.shift) / self.scale;
+        self.coefficients
+            .iter()
+            .rev()
+            .fold(0.0, |acc, c| acc * t + c)
+    }
+}
+
+/// Least-squares fit of a polynomial of `degree` through `points`. The degree
+/// is lowered to fit through fewer points exactly, `None` if there are no
+/// (finite) points.
+pub fn polyfit(points: &[[f64; 2]], degree: usize) -> Option<Polynomial> {
+    let points: Vec<_> = points
+        .iter()
+        .filter(|[x, y]| x.is_finite() && y.is_finite())
+        .collect();
+    let degree = degree.min(points.len().checked_sub(1)?);
+    let (xmin, xmax) = points
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), [x, _]| {
+            (min.min(*x), max.max(*x))
+        });
+    let shift = (xmin + xmax) / 2.0;
+    let scale = if xmax > xmin {
+        (xmax - xmin) / 2.0
+    } else {
+        1.0
+    };
+
+    // Normal equations A c = b, solved by Gaussian elimination.
+    let n = degree + 1;
+    let mut a = vec![vec![0.0; n + 1]; n];
+    for [x, y] in points {
+        let t = (x - shift) / scale;
+        let powers: Vec<_> = (0..2 * n).map(|k| t.powi(k as i32)).collect();
+        for (i, row) in a.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().take(n).enumerate() {
+                *value += powers[i + j];
+            }
+            row[n] += powers[i] * y;
+        }
+    }
+    for col in 0..n {
+        let pivot = (col..n).max_by(|i, j| a[*i][col].abs().total_cmp(&a[*j][col].abs()))?;
+        a.swap(col, pivot);
+        if a[col][col].abs() < f64::EPSILON {
+            // Duplicate x-positions, there is no unique solution.
+            return None;
+        }
+        let (upper, lower) = a.split_at_mut(col + 1);
+        let pivot_row = &upper[col];
+        for row in lower {
+            let factor = row[col] / pivot_row[col];
+            for (value, pivot_value) in row.iter_mut().zip(pivot_row).skip(col) {
+                *value -= factor * pivot_value;
+            }
+        }
+    }
+    let mut coefficients = vec![0.0; n];
+    for row in (0..n).rev() {
+        let sum: f64 = (row + 1..n).map(|k| a[row][k] * coefficients[k]).sum();
+        coefficients[row] = (a[row][n] - sum) / a[row][row];
+    }
+    Some(Polynomial {
+        coefficients,
+        shift,
+        scale,
+    })
+}
+
+/// The points of `data` closest in x to each of the `anchors`, which do not
+/// need to be at a data point exactly. The data does not need to be sorted.
+pub fn anchor_points(data: &[[f64; 2]], anchors: &[f64]) -> Vec<[f64; 2]> {
+    anchors
+        .iter()
+        .filter_map(|anchor| {
+            data.iter()
+                .filter(|[x, y]| x.is_finite() && y.is_finite())
+                .min_by(|[a, _], [b, _]| (a - anchor).abs().total_cmp(&(b - anchor).abs()))
+                .copied()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_polyfit() {
+        // y = 2 + 3x - x^2 around large x-values.
+        let f = |x: f64| 2.0 + 3.0 * (x - 1000.0) - (x - 1000.0).powi(2);
+        let points: Vec<_> = (0..5)
+            .map(|i| 1000.0 + i as f64)
+            .map(|x| [x, f(x)])
+            .collect();
+        let poly = polyfit(&points, 2).unwrap();
+        for x in [999.5, 1001.5, 1006.0] {
+            assert!((poly.eval(x) - f(x)).abs() < 1e-6);
+        }
+        // Two points only allow a straight line.
+        let line = polyfit(&points[..2], 3).unwrap();
+        assert!((line.eval(1002.0) - 6.0).abs() < 1e-9);
+        assert_eq!(polyfit(&[], 2), None);
+        assert_eq!(polyfit(&[[1.0, 1.0], [1.0, 2.0]], 1), None);
+    }
+
+    #[test]
+    fn test_anchor_points() {
+        let data = [[3.0, 30.0], [1.0, 10.0], [2.0, 20.0], [f64::NAN, 0.0]];
+        assert_eq!(
+            anchor_points(&data, &[0.0, 2.4, 10.0]),
+            [[1.0, 10.0], [2.0, 20.0], [3.0, 30.0]]
+        );
+        assert!(anchor_points(&[], &[1.0]).is_empty());
+    }
+}
@@ -0,0 +1,303 @@
+//! A small evaluator for arithmetic expressions in the variables `x` and `y`,
+//! used to transform data points, e.g. `log(y) + 3`.
+//!
+//! Supported are numbers, `+ - * / ^` (`^` binds tightest and is right
+//! associative), parentheses, the constants `pi` and `e` and the functions
+//! `abs`, `sqrt`, `exp`, `ln`, `log` (base 10), `sin`, `cos` and `tan`.
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    X,
+    Y,
+    Neg(Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>),
+    Call(Function, Box<Expr>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Function {
+    Abs,
+    Sqrt,
+    Exp,
+    Ln,
+    Log,
+    Sin,
+    Cos,
+    Tan,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Op(char),
+    Open,
+    Close,
+}
+
+impl Expr {
+    pub fn parse(src: &str) -> Result<Self, String> {
+        let tokens = tokenize(src)?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            depth: 0,
+        };
+        let expr = parser.sum()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(expr),
+            Some(token) => Err(format!("unexpected {token:?} after expression")),
+        }
+    }
+
+    pub fn eval(&self, x: f64, y: f64) -> f64 {
+        match self {
+            Expr::Number(val) => *val,
+            Expr::X => x,
+            Expr::Y => y,
+            Expr::Neg(expr) => -expr.eval(x, y),
+            Expr::Binary(op, lhs, rhs) => {
+                let (lhs, rhs) = (lhs.eval(x, y), rhs.eval(x, y));
+                match op {
+                    Op::Add => lhs + rhs,
+                    Op::Sub => lhs - rhs,
+                    Op::Mul => lhs * rhs,
+                    Op::Div => lhs / rhs,
+                    Op::Pow => lhs.powf(rhs),
+                }
+            }
+            Expr::Call(function, arg) => {
+                let arg = arg.eval(x, y);
+                match function {
+                    Function::Abs => arg.abs(),
+                    Function::Sqrt => arg.sqrt(),
+                    Function::Exp => arg.exp(),
+                    Function::Ln => arg.ln(),
+                    Function::Log => arg.log10(),
+                    Function::Sin => arg.sin(),
+                    Function::Cos => arg.cos(),
+                    Function::Tan => arg.tan(),
+                }
+            }
+        }
+    }
+}
+
+fn tokenize(src: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = src.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        match c {
+            c if c.is_whitespace() => (),
+            '+' | '-' | '*' | '/' | '^' => tokens.push(Token::Op(c)),
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            c if c.is_ascii_digit() || c == '.' => {
+                let mut end = start + c.len_utf8();
+                while let Some((i, c)) = chars.peek().copied() {
+                    // Allow exponents like "1e-3".
+                    let exponent_sign = (c == '-' || c == '+') && src[..i].ends_with(['e', 'E']);
+                    if c.is_ascii_digit() || c == '.' || c == 'e' || c == 'E' || exponent_sign {
+                        end = i + c.len_utf8();
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                let num = &src[start..end];
+                let num = num
+                    .parse::<f64>()
+                    .map_err(|_| format!("invalid number '{num}'"))?;
+                tokens.push(Token::Number(num));
+            }
+            c if c.is_alphabetic() => {
+                let mut end = start + c.len_utf8();
+                while let Some((i, c)) = chars.peek().copied() {
+                    if c.is_alphanumeric() || c == '_' {
+                        end = i + c.len_utf8();
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(Token::Ident(src[start..end].to_string()));
+            }
+            c => return Err(format!("unexpected character '{c}'")),
+        }
+    }
+    Ok(tokens)
+}
+
+/// Deepest nesting (of parentheses, functions and operators) accepted, so
+/// that neither parsing nor evaluating an expression overflows the stack.
+const MAX_DEPTH: usize = 200;
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    // Nesting of the expression parsed currently.
+    depth: usize,
+}
+
+impl Parser {
+    /// Go one level deeper into the expression.
+    fn descend(&mut self) -> Result<(), String> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(format!("expression nested deeper than {MAX_DEPTH} levels"));
+        }
+        Ok(())
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn peek_op(&self, ops: &[char]) -> Option<char> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Op(op)) if ops.contains(op) => Some(*op),
+            _ => None,
+        }
+    }
+
+    fn sum(&mut self) -> Result<Expr, String> {
+        let depth = self.depth;
+        let mut lhs = self.product()?;
+        while let Some(op) = self.peek_op(&['+', '-']) {
+            self.pos += 1;
+            // Each operation nests the ones before it.
+            self.descend()?;
+            let op = if op == '+' { Op::Add } else { Op::Sub };
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.product()?));
+        }
+        self.depth = depth;
+        Ok(lhs)
+    }
+
+    fn product(&mut self) -> Result<Expr, String> {
+        let depth = self.depth;
+        let mut lhs = self.unary()?;
+        while let Some(op) = self.peek_op(&['*', '/']) {
+            self.pos += 1;
+            self.descend()?;
+            let op = if op == '*' { Op::Mul } else { Op::Div };
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.unary()?));
+        }
+        self.depth = depth;
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        self.descend()?;
+        let expr = if self.peek_op(&['-']).is_some() {
+            self.pos += 1;
+            Expr::Neg(Box::new(self.unary()?))
+        } else {
+            self.power()?
+        };
+        self.depth -= 1;
+        Ok(expr)
+    }
+
+    fn power(&mut self) -> Result<Expr, String> {
+        let base = self.atom()?;
+        if self.peek_op(&['^']).is_some() {
+            self.pos += 1;
+            // Right associative, and "2^-1" is allowed.
+            let exponent = self.unary()?;
+            return Ok(Expr::Binary(Op::Pow, Box::new(base), Box::new(exponent)));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Number(val)) => Ok(Expr::Number(val)),
+            Some(Token::Open) => {
+                let expr = self.sum()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(expr),
+                    _ => Err("missing ')'".into()),
+                }
+            }
+            Some(Token::Ident(name)) => match name.as_str() {
+                "x" => Ok(Expr::X),
+                "y" => Ok(Expr::Y),
+                "pi" => Ok(Expr::Number(std::f64::consts::PI)),
+                "e" => Ok(Expr::Number(std::f64::consts::E)),
+                _ => {
+                    let function = match name.as_str() {
+                        "abs" => Function::Abs,
+                        "sqrt" => Function::Sqrt,
+                        "exp" => Function::Exp,
+                        "ln" => Function::Ln,
+                        "log" => Function::Log,
+                        "sin" => Function::Sin,
+                        "cos" => Function::Cos,
+                        "tan" => Function::Tan,
+                        _ => return Err(format!("unknown name '{name}'")),
+                    };
+                    if self.next() != Some(Token::Open) {
+                        return Err(format!("expected '(' after '{name}'"));
+                    }
+                    let arg = self.sum()?;
+                    match self.next() {
+                        Some(Token::Close) => Ok(Expr::Call(function, Box::new(arg))),
+                        _ => Err("missing ')'".into()),
+                    }
+                }
+            },
+            Some(token) => Err(format!("unexpected {token:?}")),
+            None => Err("unexpected end of expression".into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expression() {
+        let eval = |src: &str| Expr::parse(src).unwrap().eval(2.0, 100.0);
+        assert_eq!(eval("log(y)+3"), 5.0);
+        assert_eq!(eval("-x^2 + 2 * (y - 1e2) / 4"), -4.0);
+        assert_eq!(eval("2^3^2"), 512.0);
+        assert_eq!(eval("sqrt(y) * 1.5E-1"), 1.5);
+        for invalid in ["", "y +", "log y", "(x", "z", "x $ y", "1..2"] {
+            assert!(
+                Expr::parse(invalid).is_err(),
+                "'{invalid}' should not parse"
+            );
+        }
+    }
+
+    #[test]
+    fn test_nesting_limit() {
+        let nested = |depth: usize| format!("{}x{}", "(".repeat(depth), ")".repeat(depth));
+        assert_eq!(Expr::parse(&nested(50)).unwrap(), Expr::X);
+        // Long chains of operations nest as well.
+        for deep in [
+            nested(100_000),
+            "-".repeat(1000) + "x",
+            "x+".repeat(1000) + "x",
+        ] {
+            let err = Expr::parse(&deep).unwrap_err();
+            assert_eq!(err, "expression nested deeper than 200 levels");
+        }
+        assert!(Expr::parse(&("x^".repeat(1000) + "x")).is_err());
+        assert!(Expr::parse(&("x*".repeat(150) + "x")).is_ok());
+    }
+}
@@ -0,0 +1,223 @@
+//! Gaussian fits of detected peaks.
+
+use std::f64::consts::{LN_2, PI};
+
+use super::{baseline::polyfit, peaks::find_peaks};
+
+/// Fits that explain less of the variance of the peak are flagged as poor.
+pub const MIN_R_SQUARED: f64 = 0.9;
+
+/// Points below this fraction of the peak height are left out of the fit,
+/// their logarithm is dominated by noise.
+const TAIL_FRACTION: f64 = 0.2;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Gaussian {
+    pub center: f64,
+    pub height: f64,
+    /// Standard deviation.
+    pub sigma: f64,
+}
+
+impl Gaussian {
+    pub fn eval(&self, x: f64) -> f64 {
+        self.height * (-(x - self.center).powi(2) / (2.0 * self.sigma.powi(2))).exp()
+    }
+
+    /// Full width at half maximum.
+    pub fn fwhm(&self) -> f64 {
+        2.0 * (2.0 * LN_2).sqrt() * self.sigma
+    }
+
+    pub fn area(&self) -> f64 {
+        self.height * self.sigma * (2.0 * PI).sqrt()
+    }
+}
+
+/// The fit of one detected peak.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PeakFit {
+    /// Position of the detected maximum.
+    pub x: f64,
+    /// `None` if no Gaussian could be fitted, e.g. to a peak of one point.
+    pub gaussian: Option<Gaussian>,
+    /// Coefficient of determination of the fit over the points of the peak,
+    /// 0 without fit.
+    pub r_squared: f64,
+}
+
+impl PeakFit {
+    pub fn is_poor(&self) -> bool {
+        self.gaussian.is_none() || self.r_squared < MIN_R_SQUARED
+    }
+}
+
+/// Fit a Gaussian to each peak of `data` (see [`find_peaks`]), over the points
+/// between the lowest points separating it from its neighbouring peaks. The
+/// heights are measured above the straight line through these lowest points.
+/// Peaks that cannot be fitted are reported (and flagged) as well. The data
+/// should be sorted by x.
+pub fn fit_peaks(data: &[[f64; 2]], min_prominence: f64) -> Vec<PeakFit> {
+    let peaks = find_peaks(data, min_prominence);
+    peaks
+        .iter()
+        .enumerate()
+        .map(|(k, &peak)| {
+            let previous = if k > 0 { peaks[k - 1] } else { 0 };
+            let next = peaks.get(k + 1).copied().unwrap_or(data.len() - 1);
+            let start = lowest(data, previous, peak);
+            let end = lowest(data, peak, next);
+            fit_peak(&data[start..=end], data[peak][0])
+        })
+        .collect()
+}
+
+/// Index of the lowest finite point within `data[from..=to]`.
+fn lowest(data: &[[f64; 2]], from: usize, to: usize) -> usize {
+    (from..=to)
+        .filter(|i| data[*i][0].is_finite() && data[*i][1].is_finite())
+        .min_by(|a, b| data[*a][1].total_cmp(&data[*b][1]))
+        .unwrap_or(from)
+}
+
+/// Fit the peak at `x` spanning `points`, by fitting a parabola to the
+/// logarithm of the points above the line through the first and last point.
+fn fit_peak(points: &[[f64; 2]], x: f64) -> PeakFit {
+    let points: Vec<_> = points
+        .iter()
+        .filter(|[x, y]| x.is_finite() && y.is_finite())
+        .copied()
+        .collect();
+    let unfitted = PeakFit {
+        x,
+        gaussian: None,
+        r_squared: 0.0,
+    };
+    let (Some(first), Some(last)) = (points.first(), points.last()) else {
+        return unfitted;
+    };
+    let base = |px: f64| {
+        if last[0] > first[0] {
+            first[1] + (last[1] - first[1]) * (px - first[0]) / (last[0] - first[0])
+        } else {
+            first[1]
+        }
+    };
+    let corrected: Vec<_> = points.iter().map(|[x, y]| [*x, y - base(*x)]).collect();
+    let top = corrected
+        .iter()
+        .map(|[_, y]| *y)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let logs: Vec<_> = corrected
+        .iter()
+        .filter(|[_, y]| *y > top * TAIL_FRACTION && *y > 0.0)
+        .map(|[x, y]| [*x, y.ln()])
+        .collect();
+    let Some(gaussian) = gaussian_from_logs(&logs) else {
+        return unfitted;
+    };
+    PeakFit {
+        x,
+        gaussian: Some(gaussian),
+        r_squared: r_squared(&corrected, &gaussian),
+    }
+}
+
+/// The Gaussian whose logarithm is the least-squares parabola through `logs`,
+/// `None` if the parabola does not open downwards.
+fn gaussian_from_logs(logs: &[[f64; 2]]) -> Option<Gaussian> {
+    if logs.len() < 3 {
+        return None;
+    }
+    let parabola = polyfit(logs, 2)?;
+    // Recover the coefficients from three values of the parabola.
+    let (xmin, xmax) = (logs[0][0], logs[logs.len() - 1][0]);
+    let (x0, h) = ((xmin + xmax) / 2.0, (xmax - xmin) / 2.0);
+    if h <= 0.0 {
+        return None;
+    }
+    let (left, mid, right) = (
+        parabola.eval(x0 - h),
+        parabola.eval(x0),
+        parabola.eval(x0 + h),
+    );
+    let curvature = (right - 2.0 * mid + left) / (2.0 * h * h);
+    let slope = (right - left) / (2.0 * h);
+    if curvature >= 0.0 || curvature.is_nan() {
+        return None;
+    }
+    let gaussian = Gaussian {
+        center: x0 - slope / (2.0 * curvature),
+        height: (mid - slope * slope / (4.0 * curvature)).exp(),
+        sigma: (-1.0 / (2.0 * curvature)).sqrt(),
+    };
+    [gaussian.center, gaussian.height, gaussian.sigma]
+        .iter()
+        .all(|v| v.is_finite())
+        .then_some(gaussian)
+}
+
+fn r_squared(points: &[[f64; 2]], gaussian: &Gaussian) -> f64 {
+    let mean = points.iter().map(|[_, y]| y).sum::<f64>() / points.len() as f64;
+    let total: f64 = points.iter().map(|[_, y]| (y - mean).powi(2)).sum();
+    let residual: f64 = points
+        .iter()
+        .map(|[x, y]| (y - gaussian.eval(*x)).powi(2))
+        .sum();
+    if total > 0.0 {
+        1.0 - residual / total
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fit_peaks() {
+        let peaks = [
+            Gaussian {
+                center: 3.0,
+                height: 2.0,
+                sigma: 0.5,
+            },
+            Gaussian {
+                center: 8.0,
+                height: 1.0,
+                sigma: 0.8,
+            },
+        ];
+        // On a sloped background, with a spike of a single point at the end.
+        let mut data: Vec<_> = (0..=120)
+            .map(|i| {
+                let x = i as f64 / 10.0;
+                [
+                    x,
+                    0.5 + 0.05 * x + peaks.iter().map(|g| g.eval(x)).sum::<f64>(),
+                ]
+            })
+            .collect();
+        data[115][1] += 3.0;
+
+        let fits = fit_peaks(&data, 0.5);
+        assert_eq!(fits.len(), 3);
+        for (fit, expected) in fits.iter().zip(peaks) {
+            let gaussian = fit.gaussian.unwrap();
+            assert!(!fit.is_poor(), "{fit:?}");
+            assert!((gaussian.center - expected.center).abs() < 0.05);
+            assert!((gaussian.height - expected.height).abs() < 0.05 * expected.height);
+            assert!((gaussian.fwhm() - expected.fwhm()).abs() < 0.1 * expected.fwhm());
+        }
+        // The spike cannot be fitted, but does not stop the others.
+        assert_eq!(fits[2].x, data[115][0]);
+        assert!(fits[2].is_poor());
+
+        let gaussian = peaks[0];
+        let area: f64 = (0..=1000)
+            .map(|i| gaussian.eval(i as f64 / 100.0) / 100.0)
+            .sum();
+        assert!((gaussian.area() - area).abs() < 1e-6);
+    }
+}
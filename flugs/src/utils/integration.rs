@@ -0,0 +1,159 @@
+use super::interpolation::{linear_resample_array, lininterp};
+
+/// Integrate y over x numerically, using the trapezoidal method, within the
+/// bounds `left` and `right` (in any order).
+///
+/// Data points where x or y is not finite are ignored, x-values may be sorted
+/// in ascending or descending order. If `local_baseline` is true, a linear
+/// baseline through the data at the bounds is subtracted.
+pub fn integrate(
+    xs: &[f64],
+    ys: &[f64],
+    left: f64,
+    right: f64,
+    local_baseline: bool,
+) -> Result<f64, String> {
+    let (mut xs, mut ys): (Vec<_>, Vec<_>) = xs
+        .iter()
+        .zip(ys)
+        .filter(|(x, y)| x.is_finite() && y.is_finite())
+        .map(|(x, y)| (*x, *y))
+        .unzip();
+    // `trapz` expects ascending x-values.
+    if xs.first() > xs.last() {
+        xs.reverse();
+        ys.reverse();
+    }
+    trapz(&xs, &ys, left, right, local_baseline)
+}
+
+/// Trapezoidal integration.
+///
+/// Local baseline subtracts a linear baseline ranging from the start (left) to
+/// the end (right) point of the integration window.
+pub fn trapz(
+    x: &[f64],
+    y: &[f64],
+    left: f64,
+    right: f64,
+    local_baseline: bool,
+) -> Result<f64, String> {
+    let (mut left, right) = (left.min(right), left.max(right));
+
+    let n = x.len().min(y.len());
+    if n <= 1 {
+        return Err("Not enough values to integrate".into());
+    }
+    if x[0] >= right || x[n - 1] <= left {
+        return Err("Integration window out of bounds".into());
+    }
+
+    let mut area: f64;
+    // subtract local linear baseline, defined by start and end-point of integration window
+    if local_baseline {
+        let xs = vec![left, right];
+        let ys = linear_resample_array(x, y, &xs);
+        if ys.iter().any(|y| (*y).is_nan()) {
+            return Err("Integration window out of bounds.".into());
+        }
+        area = -singletrapz(left, right, ys[0], ys[1])
+    } else {
+        area = 0.0_f64;
+    }
+
+    let mut inside_integration_window = false;
+    let mut lastiter = false;
+    let mut j = 1;
+
+    while j < n {
+        let mut x0 = x[j - 1];
+        let mut x1 = x[j];
+        let mut y0 = y[j - 1];
+        let mut y1 = y[j];
+
+        if x1 <= left {
+            j += 1;
+            continue;
+        } else if !inside_integration_window {
+            // this will only run once, when we enter the integration window
+            // test whether x0 should be replaced by left
+            if x0 < left {
+                y0 = lininterp(left, x0, x1, y0, y1);
+                x0 = left;
+            } else {
+                // this case means that left <= x[0]
+                left = x0;
+            }
+            inside_integration_window = true;
+        }
+
+        // test whether x1 should be replaced by right
+        if x1 >= right {
+            // we move out of the integration window
+
+            if x1 != right {
+                y1 = lininterp(right, x0, x1, y0, y1)
+            };
+            x1 = right;
+            lastiter = true; // we shall break the loop after this iteration
+        }
+
+        area += singletrapz(x0, x1, y0, y1);
+
+        if lastiter {
+            break;
+        }
+
+        j += 1;
+    }
+    Ok(area)
+}
+
+/// Calculate area of single trapezoid.
+fn singletrapz(x0: f64, x1: f64, y0: f64, y1: f64) -> f64 {
+    0.5 * f64::abs(x1 - x0) * (y1 + y0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // y = x on [0, 4], sampled at integers.
+    fn ramp() -> (Vec<f64>, Vec<f64>) {
+        let xs: Vec<f64> = (0..=4).map(|x| x as f64).collect();
+        (xs.clone(), xs)
+    }
+
+    #[test]
+    fn test_integrate_without_baseline() {
+        let (xs, ys) = ramp();
+        // Full range, including the first segment.
+        assert_eq!(integrate(&xs, &ys, 0.0, 4.0, false), Ok(8.0));
+        // Bounds in reverse order and between data points.
+        assert_eq!(integrate(&xs, &ys, 3.5, 0.5, false), Ok(6.0));
+        // Right bound beyond the data is clipped to the data range.
+        assert_eq!(integrate(&xs, &ys, 2.0, 10.0, false), Ok(6.0));
+    }
+
+    #[test]
+    fn test_integrate_with_baseline() {
+        let (xs, ys) = ramp();
+        // A linear signal is its own baseline.
+        assert_eq!(integrate(&xs, &ys, 1.0, 3.0, true), Ok(0.0));
+
+        // Triangular peak on a constant offset of 1.
+        let xs = [0.0, 1.0, 2.0, 3.0, 4.0];
+        let ys = [1.0, 1.0, 3.0, 1.0, 1.0];
+        assert_eq!(integrate(&xs, &ys, 0.0, 4.0, false), Ok(6.0));
+        assert_eq!(integrate(&xs, &ys, 0.0, 4.0, true), Ok(2.0));
+        // Baseline requires data at both bounds.
+        assert!(integrate(&xs, &ys, 0.0, 10.0, true).is_err());
+    }
+
+    #[test]
+    fn test_integrate_descending_and_non_finite() {
+        let xs = [4.0, 3.0, f64::NAN, 2.0, 1.0, 0.0];
+        let ys = [4.0, 3.0, 1.0, 2.0, 1.0, 0.0];
+        assert_eq!(integrate(&xs, &ys, 0.0, 4.0, false), Ok(8.0));
+    }
+}
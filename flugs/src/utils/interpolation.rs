@@ -0,0 +1,176 @@
+/// Interpolation method used to evaluate data between (or at other x-values
+/// than) its data points, e.g. for traces derived from several files.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Interpolation {
+    #[default]
+    Linear,
+    /// The y-value of the data point with the closest x-value.
+    Nearest,
+    /// Cubic Hermite spline with finite difference tangents (Catmull-Rom).
+    /// On the first and last segment, or with less than four points, it
+    /// falls back to linear interpolation.
+    Cubic,
+}
+
+/// Evaluate the data `xs`, `ys` (with ascending x-values) at the x-values in
+/// `grid`, using `method`. Values outside the range of `xs` are NaN.
+pub fn resample(xs: &[f64], ys: &[f64], grid: &[f64], method: Interpolation) -> Vec<f64> {
+    let n = xs.len().min(ys.len());
+    let (xs, ys) = (&xs[..n], &ys[..n]);
+    match method {
+        Interpolation::Linear => linear_resample_array(xs, ys, grid),
+        Interpolation::Cubic if n < 4 => linear_resample_array(xs, ys, grid),
+        Interpolation::Nearest => grid
+            .iter()
+            .map(|xi| match segment(xs, *xi) {
+                Some(i) if xi - xs[i] <= xs[i + 1] - xi => ys[i],
+                Some(i) => ys[i + 1],
+                None if n > 0 && *xi == xs[n - 1] => ys[n - 1],
+                None => f64::NAN,
+            })
+            .collect(),
+        Interpolation::Cubic => grid
+            .iter()
+            .map(|xi| match segment(xs, *xi) {
+                Some(i) if i == 0 || i + 2 >= n => {
+                    lininterp(*xi, xs[i], xs[i + 1], ys[i], ys[i + 1])
+                }
+                Some(i) => {
+                    let (x0, x1) = (xs[i], xs[i + 1]);
+                    let h = x1 - x0;
+                    let m0 = (ys[i + 1] - ys[i - 1]) / (xs[i + 1] - xs[i - 1]) * h;
+                    let m1 = (ys[i + 2] - ys[i]) / (xs[i + 2] - xs[i]) * h;
+                    let t = (xi - x0) / h;
+                    let (t2, t3) = (t * t, t * t * t);
+                    (2.0 * t3 - 3.0 * t2 + 1.0) * ys[i]
+                        + (t3 - 2.0 * t2 + t) * m0
+                        + (-2.0 * t3 + 3.0 * t2) * ys[i + 1]
+                        + (t3 - t2) * m1
+                }
+                None if n > 0 && *xi == xs[n - 1] => ys[n - 1],
+                None => f64::NAN,
+            })
+            .collect(),
+    }
+}
+
+/// The points of `data` divided by `reference`, evaluated at the x-values of
+/// `data` with `method`. Points outside the x-range of the reference are
+/// dropped, so that only the overlap remains, as are points where the
+/// reference is zero. Neither needs to be sorted.
+pub fn ratio(data: &[[f64; 2]], reference: &[[f64; 2]], method: Interpolation) -> Vec<[f64; 2]> {
+    let mut reference: Vec<_> = reference
+        .iter()
+        .filter(|[x, y]| x.is_finite() && y.is_finite())
+        .copied()
+        .collect();
+    reference.sort_by(|a, b| a[0].total_cmp(&b[0]));
+    let (Some(first), Some(last)) = (reference.first(), reference.last()) else {
+        return Vec::new();
+    };
+    let (xmin, xmax) = (first[0], last[0]);
+    let data: Vec<_> = data
+        .iter()
+        .filter(|[x, _]| (xmin..=xmax).contains(x))
+        .copied()
+        .collect();
+    let (ref_xs, ref_ys): (Vec<_>, Vec<_>) = reference.iter().map(|[x, y]| (*x, *y)).unzip();
+    let grid: Vec<_> = data.iter().map(|[x, _]| *x).collect();
+    let ref_at_grid = resample(&ref_xs, &ref_ys, &grid, method);
+    data.iter()
+        .zip(ref_at_grid)
+        .map(|([x, y], r)| [*x, y / r])
+        .filter(|[_, ratio]| ratio.is_finite())
+        .collect()
+}
+
+/// Index `i` of the segment `xs[i] <= xp < xs[i + 1]`, if any.
+fn segment(xs: &[f64], xp: f64) -> Option<usize> {
+    let i = xs.partition_point(|x| *x <= xp);
+    (i > 0 && i < xs.len()).then(|| i - 1)
+}
+
+pub fn linear_resample_array(xs: &[f64], ys: &[f64], grid: &[f64]) -> Vec<f64> {
+    let segments = xs
+        .iter()
+        .zip(ys.iter())
+        .zip(xs.iter().skip(1).zip(ys.iter().skip(1)))
+        .map(|((x0, y0), (x1, y1))| (*x0, *y0, *x1, *y1))
+        .collect::<Vec<_>>();
+
+    let mut yp = Vec::with_capacity(grid.len());
+
+    for xi in grid.iter() {
+        if let Some((x0, y0, x1, y1)) = segments.iter().find(|(x0, _, x1, _)| xi >= x0 && xi < x1) {
+            yp.push(lininterp(*xi, *x0, *x1, *y0, *y1));
+            continue;
+        }
+        // only applies if xi happens to be == the last value in xs
+        else if let Some((_, _, _, y1)) = segments.iter().last().filter(|(_, _, x1, _)| xi == x1)
+        {
+            yp.push(*y1);
+            continue;
+        }
+        // applies if xi does not lie within the range of xs
+        else {
+            yp.push(f64::NAN)
+        };
+    }
+    yp
+}
+
+/// Linearly interpolate y-value at position xp between two points (x0, y0) and (x1, y1).
+pub fn lininterp(xp: f64, x0: f64, x1: f64, y0: f64, y1: f64) -> f64 {
+    (y1 * (xp - x0) + y0 * (x1 - xp)) / (x1 - x0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resample() {
+        // y = x^2, sampled at integers.
+        let xs: Vec<f64> = (0..=4).map(|x| x as f64).collect();
+        let ys: Vec<f64> = xs.iter().map(|x| x * x).collect();
+        let grid = [-1.0, 0.5, 1.5, 2.6, 4.0];
+
+        let linear = resample(&xs, &ys, &grid, Interpolation::Linear);
+        assert!(linear[0].is_nan());
+        assert_eq!(&linear[1..], &[0.5, 2.5, 7.0, 16.0]);
+
+        let nearest = resample(&xs, &ys, &grid, Interpolation::Nearest);
+        assert!(nearest[0].is_nan());
+        assert_eq!(&nearest[1..], &[0.0, 1.0, 9.0, 16.0]);
+
+        // Linear on the edge segments, exact for the parabola in between.
+        let cubic = resample(&xs, &ys, &grid, Interpolation::Cubic);
+        assert!(cubic[0].is_nan());
+        assert_eq!(cubic[1], 0.5);
+        assert!((cubic[2] - 2.25).abs() < 1e-12);
+        assert!((cubic[3] - 6.76).abs() < 1e-12);
+        assert_eq!(cubic[4], 16.0);
+
+        // Too few points for cubic interpolation.
+        let cubic = resample(&xs[..3], &ys[..3], &[1.5], Interpolation::Cubic);
+        assert_eq!(cubic, vec![2.5]);
+    }
+
+    #[test]
+    fn test_ratio() {
+        let data = [[0.0, 2.0], [1.0, 4.0], [2.0, 6.0], [3.0, 8.0]];
+        // Descending reference on x = 0.5..2.5, y = 2x.
+        let reference = [[2.5, 5.0], [1.5, 3.0], [0.5, 1.0]];
+        assert_eq!(
+            ratio(&data, &reference, Interpolation::Linear),
+            [[1.0, 2.0], [2.0, 1.5]]
+        );
+        assert!(ratio(&data, &[], Interpolation::Linear).is_empty());
+        assert!(ratio(&data, &[[10.0, 1.0], [11.0, 1.0]], Interpolation::Linear).is_empty());
+        // Dividing by zero drops the point.
+        assert_eq!(
+            ratio(&data, &[[0.0, 0.0], [2.0, 4.0]], Interpolation::Linear),
+            [[1.0, 2.0], [2.0, 1.5]]
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use std::{collections::HashSet, path::PathBuf};
+
+/// A file path that matches a search query.
+#[derive(Debug, PartialEq)]
+pub struct PathMatch {
+    pub path: PathBuf,
+    /// Byte indices of the matched characters in the path.
+    pub indices: HashSet<usize>,
+    /// Number of query words found in the file name (rather than only in
+    /// the directories), better matches have a higher score.
+    pub score: usize,
+}
+
+/// Return at most `limit` of the `paths` that match `query`, best matches
+/// first and otherwise in the order of `paths`.
+///
+/// The query is split into words at white space. For a path to match, it
+/// must contain all words, except those starting with "!", which it must not
+/// contain. An empty query matches all paths.
+pub fn match_paths(query: &str, paths: &[PathBuf], limit: usize) -> Vec<PathMatch> {
+    let (excluded, included): (Vec<_>, Vec<_>) = query
+        .split_whitespace()
+        .filter(|word| *word != "!")
+        .partition(|word| word.starts_with('!'));
+    let excluded: Vec<_> = excluded
+        .iter()
+        .filter_map(|word| word.strip_prefix('!'))
+        .collect();
+
+    let mut matches: Vec<_> = paths
+        .iter()
+        .filter_map(|path| {
+            let fp = path.to_str()?;
+            if excluded.iter().any(|word| fp.contains(word)) {
+                return None;
+            }
+            // Words are searched in the file name first, so that they are
+            // highlighted there.
+            let name_start = fp.len() - path.file_name()?.to_str()?.len();
+            let mut indices = HashSet::new();
+            let mut score = 0;
+            for word in included.iter() {
+                let idx = match fp[name_start..].find(word) {
+                    Some(idx) => {
+                        score += 1;
+                        name_start + idx
+                    }
+                    None => fp.find(word)?,
+                };
+                indices.extend(idx..idx + word.len());
+            }
+            Some(PathMatch {
+                path: path.to_owned(),
+                indices,
+                score,
+            })
+        })
+        .collect();
+    // The sort is stable, so equally good matches keep their order.
+    matches.sort_by_key(|m| std::cmp::Reverse(m.score));
+    matches.truncate(limit);
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(paths: &[&str]) -> Vec<PathBuf> {
+        paths.iter().map(PathBuf::from).collect()
+    }
+
+    fn matched_paths(query: &str, candidates: &[PathBuf], limit: usize) -> Vec<String> {
+        match_paths(query, candidates, limit)
+            .into_iter()
+            .map(|m| m.path.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_match_paths_ordering() {
+        let candidates = paths(&[
+            "/data/raman/a.csv",
+            "/data/ir/raman.csv",
+            "/data/raman/b.csv",
+            "/data/ir/c.csv",
+        ]);
+        // The match in the file name comes first, the others are in order.
+        assert_eq!(
+            matched_paths("raman", &candidates, 10),
+            [
+                "/data/ir/raman.csv",
+                "/data/raman/a.csv",
+                "/data/raman/b.csv"
+            ]
+        );
+        // All words must match, negated words must not.
+        assert_eq!(
+            matched_paths("raman csv !b.csv", &candidates, 10),
+            ["/data/ir/raman.csv", "/data/raman/a.csv"]
+        );
+        assert_eq!(matched_paths("ir", &candidates, 1), ["/data/ir/raman.csv"]);
+    }
+
+    #[test]
+    fn test_match_paths_edge_cases() {
+        let candidates = paths(&["/data/a.csv", "/data/b.csv"]);
+        // Empty queries (or only a lone "!") match everything.
+        for query in ["", "   ", "!"] {
+            assert_eq!(matched_paths(query, &candidates, 10).len(), 2);
+        }
+        assert!(matched_paths("missing", &candidates, 10).is_empty());
+        assert!(matched_paths("csv", &[], 10).is_empty());
+        assert!(matched_paths("csv", &candidates, 0).is_empty());
+
+        // Indices point at the match in the file name.
+        let m = &match_paths("b.c", &candidates, 10)[0];
+        assert_eq!(m.indices, HashSet::from([6, 7, 8]));
+        assert_eq!(m.score, 1);
+    }
+}
@@ -0,0 +1,14 @@
+//! Routines that are independent of the UI.
+
+pub mod baseline;
+pub mod expression;
+pub mod fit;
+pub mod integration;
+pub mod interpolation;
+pub mod matching;
+pub mod peaks;
+pub mod simplify;
+pub mod smoothing;
+pub mod transform;
+pub mod window;
+pub mod zip;
@@ -0,0 +1,79 @@
+//! Simple peak detection.
+
+/// Indices of the local maxima of `data` that rise at least `min_prominence`
+/// above the higher of the two minima separating them from a higher point
+/// (or the end of the data) on either side. Plateaus count as one peak at
+/// their first point. Non-finite points are skipped, the data should be
+/// sorted by x.
+pub fn find_peaks(data: &[[f64; 2]], min_prominence: f64) -> Vec<usize> {
+    let finite: Vec<usize> = (0..data.len())
+        .filter(|i| data[*i][0].is_finite() && data[*i][1].is_finite())
+        .collect();
+    let ys: Vec<f64> = finite.iter().map(|i| data[*i][1]).collect();
+    let y = |k: usize| ys[k];
+    let n = ys.len();
+    let left = bases(ys.iter().copied());
+    let mut right = bases(ys.iter().rev().copied());
+    right.reverse();
+    let mut peaks = Vec::new();
+    let mut k = 1;
+    while k + 1 < n {
+        if y(k) <= y(k - 1) {
+            k += 1;
+            continue;
+        }
+        // Skip over a plateau.
+        let mut end = k;
+        while end + 1 < n && y(end + 1) == y(k) {
+            end += 1;
+        }
+        if end + 1 < n && y(end + 1) < y(k) && y(k) - left[k].max(right[end]) >= min_prominence {
+            peaks.push(finite[k]);
+        }
+        k = end + 1;
+    }
+    peaks
+}
+
+/// For each of `ys`, the minimum of the values from the previous higher one
+/// (or the start) up to and including itself. A stack of the values not
+/// passed by a higher one yet, each with the minimum since the one below,
+/// makes this a single pass.
+fn bases(ys: impl Iterator<Item = f64>) -> Vec<f64> {
+    let mut stack: Vec<(f64, f64)> = Vec::new();
+    ys.map(|y| {
+        let mut min = y;
+        while let Some((_, segment_min)) = stack.last_mut().filter(|(top, _)| *top <= y) {
+            min = min.min(*segment_min);
+            stack.pop();
+        }
+        stack.push((y, min));
+        min
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_peaks() {
+        let ys = [0.0, 3.0, 1.0, 1.5, 1.0, 5.0, 5.0, 2.0, f64::NAN, 4.0, 0.0];
+        let data: Vec<_> = ys.iter().enumerate().map(|(i, y)| [i as f64, *y]).collect();
+        assert_eq!(find_peaks(&data, 0.0), [1, 3, 5, 9]);
+        // The small bump at 3 only rises 0.5 above its surroundings.
+        assert_eq!(find_peaks(&data, 1.0), [1, 5, 9]);
+        // The highest peak is measured against the lowest point on the
+        // side with the higher minimum.
+        assert_eq!(find_peaks(&data, 4.5), [5]);
+        assert!(find_peaks(&data, 10.0).is_empty());
+        assert!(find_peaks(&[], 0.0).is_empty());
+    }
+
+    #[test]
+    fn test_bases() {
+        let ys = [2.0, 1.0, 3.0, 0.5, 1.0, 1.0, 4.0];
+        assert_eq!(bases(ys.into_iter()), [2.0, 1.0, 1.0, 0.5, 0.5, 0.5, 0.5]);
+    }
+}
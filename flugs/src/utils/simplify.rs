@@ -0,0 +1,258 @@
+use serde::{Deserialize, Serialize};
+
+/// How the points of a line are reduced before it is drawn. Markers,
+/// extrema and integrals always use all points.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Reduction {
+    /// Use the simplification tolerance from the preferences.
+    #[default]
+    Global,
+    /// Keep all points.
+    None,
+    /// Keep every n-th point.
+    Stride(usize),
+    /// Keep n points chosen with Largest-Triangle-Three-Buckets, see [`lttb`].
+    Lttb(usize),
+    /// Ramer-Douglas-Peucker with the given tolerance, see [`simplify`].
+    Rdp(f64),
+    /// Reduce the visible points only if they are much denser than the
+    /// pixels, see [`adaptive`]. Needs the view, so it is applied when
+    /// plotting.
+    Adaptive,
+}
+
+impl Reduction {
+    pub const OPTIONS: [Reduction; 6] = [
+        Reduction::Global,
+        Reduction::None,
+        Reduction::Stride(10),
+        Reduction::Lttb(2000),
+        Reduction::Rdp(0.001),
+        Reduction::Adaptive,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Reduction::Global => "preferences",
+            Reduction::None => "none",
+            Reduction::Stride(_) => "stride",
+            Reduction::Lttb(_) => "LTTB",
+            Reduction::Rdp(_) => "RDP",
+            Reduction::Adaptive => "adaptive",
+        }
+    }
+
+    /// Replace `Global` with the strategy the preferences stand for.
+    pub fn resolve(self, global_tolerance: f64) -> Self {
+        match self {
+            Reduction::Global if global_tolerance > 0.0 => Reduction::Rdp(global_tolerance),
+            Reduction::Global => Reduction::None,
+            reduction => reduction,
+        }
+    }
+
+    /// Indices of the points that remain, `Global` (see
+    /// [`Reduction::resolve`]) and `Adaptive` keep all of them.
+    pub fn indices(self, points: &[[f64; 2]]) -> Vec<usize> {
+        match self {
+            Reduction::Global | Reduction::None | Reduction::Adaptive => {
+                (0..points.len()).collect()
+            }
+            Reduction::Stride(step) => stride(points.len(), step),
+            Reduction::Lttb(threshold) => lttb(points, threshold),
+            Reduction::Rdp(tolerance) => simplify(points, tolerance),
+        }
+    }
+}
+
+/// Indices of the points with x between `xmin` and `xmax`, and of their
+/// neighbours outside, so that the line leaves the view. More than
+/// `max_points` are reduced to that many with [`lttb`]. The points should be
+/// sorted by x.
+pub fn adaptive(points: &[[f64; 2]], xmin: f64, xmax: f64, max_points: usize) -> Vec<usize> {
+    let visible = |i: usize| {
+        points
+            .get(i)
+            .is_some_and(|[x, _]| (xmin..=xmax).contains(x))
+    };
+    let indices: Vec<_> = (0..points.len())
+        .filter(|i| visible(*i) || visible(i + 1) || (*i > 0 && visible(i - 1)))
+        .collect();
+    if indices.len() <= max_points {
+        return indices;
+    }
+    let section: Vec<_> = indices.iter().map(|i| points[*i]).collect();
+    lttb(&section, max_points)
+        .into_iter()
+        .map(|k| indices[k])
+        .collect()
+}
+
+/// Indices of every `step`-th of `len` points, the last point is always kept.
+pub fn stride(len: usize, step: usize) -> Vec<usize> {
+    let mut indices: Vec<_> = (0..len).step_by(step.max(1)).collect();
+    if len > 0 && indices.last() != Some(&(len - 1)) {
+        indices.push(len - 1);
+    }
+    indices
+}
+
+/// Indices of `threshold` points chosen with the Largest-Triangle-Three-Buckets
+/// algorithm: the points in between the first and the last are split into
+/// buckets, from each the point spanning the largest triangle with the
+/// previously chosen point and the average of the next bucket is kept. This
+/// preserves the visual shape well. Fewer than 3 or more than the available
+/// points keep all of them.
+pub fn lttb(points: &[[f64; 2]], threshold: usize) -> Vec<usize> {
+    let len = points.len();
+    if threshold < 3 || threshold >= len {
+        return (0..len).collect();
+    }
+    let bucket_size = (len - 2) as f64 / (threshold - 2) as f64;
+    let bucket_start = |bucket: usize| ((bucket as f64 * bucket_size) as usize + 1).min(len - 1);
+
+    let mut indices = Vec::with_capacity(threshold);
+    indices.push(0);
+    let mut previous = 0;
+    for bucket in 0..threshold - 2 {
+        let (start, end) = (bucket_start(bucket), bucket_start(bucket + 1));
+        // The bucket after the last one is the last point.
+        let next = &points[end..bucket_start(bucket + 2).max(end + 1).min(len)];
+        let (sum_x, sum_y) = next
+            .iter()
+            .fold((0.0, 0.0), |(sx, sy), [x, y]| (sx + x, sy + y));
+        let (avg_x, avg_y) = (sum_x / next.len() as f64, sum_y / next.len() as f64);
+        let [px, py] = points[previous];
+        let area = |i: usize| {
+            let [x, y] = points[i];
+            ((px - avg_x) * (y - py) - (px - x) * (avg_y - py)).abs()
+        };
+        previous = (start..end.max(start + 1))
+            .max_by(|a, b| area(*a).total_cmp(&area(*b)))
+            .unwrap_or(start);
+        indices.push(previous);
+    }
+    indices.push(len - 1);
+    indices
+}
+
+/// Indices of the points that remain after simplifying the line with the
+/// Ramer-Douglas-Peucker algorithm. Distances are measured with x and y
+/// scaled to the unit square spanned by the points, so `tolerance` is a
+/// fraction of the data range (e.g. 0.001) and scaling or shifting the data
+/// does not change the result. The first and last point are always kept.
+pub fn simplify(points: &[[f64; 2]], tolerance: f64) -> Vec<usize> {
+    if points.len() < 3 || tolerance <= 0.0 {
+        return (0..points.len()).collect();
+    }
+    let range = |coord: usize| {
+        let (min, max) = points
+            .iter()
+            .map(|point| point[coord])
+            .filter(|val| val.is_finite())
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), val| {
+                (min.min(val), max.max(val))
+            });
+        let range = max - min;
+        if range > 0.0 && range.is_finite() {
+            range
+        } else {
+            1.0
+        }
+    };
+    let (xrange, yrange) = (range(0), range(1));
+    let scaled = |i: usize| [points[i][0] / xrange, points[i][1] / yrange];
+
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+    // Segments still to check, an explicit stack avoids deep recursion on
+    // large series.
+    let mut segments = vec![(0, points.len() - 1)];
+    while let Some((first, last)) = segments.pop() {
+        let ([x0, y0], [x1, y1]) = (scaled(first), scaled(last));
+        let (dx, dy) = (x1 - x0, y1 - y0);
+        let length = dx.hypot(dy);
+        let distance = |i: usize| {
+            let [x, y] = scaled(i);
+            if length > 0.0 {
+                (dy * (x - x0) - dx * (y - y0)).abs() / length
+            } else {
+                (x - x0).hypot(y - y0)
+            }
+        };
+        let farthest = (first + 1..last).map(|i| (i, distance(i))).reduce(|a, b| {
+            if b.1 > a.1 || b.1.is_nan() {
+                b
+            } else {
+                a
+            }
+        });
+        if let Some((i, dist)) = farthest {
+            // Also keep points that are not finite, the distance is NaN then.
+            if dist > tolerance || dist.is_nan() {
+                keep[i] = true;
+                segments.push((first, i));
+                segments.push((i, last));
+            }
+        }
+    }
+    keep.iter()
+        .enumerate()
+        .filter_map(|(i, keep)| keep.then_some(i))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simplify() {
+        // Collinear points in between are dropped, the peak is kept.
+        let points = [[0.0, 0.0], [1.0, 1.0], [2.0, 2.0], [3.0, 10.0], [4.0, 0.0]];
+        assert_eq!(simplify(&points, 0.01), vec![0, 2, 3, 4]);
+        // Scaling the data does not change the result.
+        let scaled: Vec<_> = points.iter().map(|[x, y]| [x * 100.0, y * 0.01]).collect();
+        assert_eq!(simplify(&scaled, 0.01), vec![0, 2, 3, 4]);
+        // Zero tolerance keeps everything.
+        assert_eq!(simplify(&points, 0.0), vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_stride_and_lttb() {
+        assert_eq!(stride(7, 3), vec![0, 3, 6]);
+        assert_eq!(stride(8, 3), vec![0, 3, 6, 7]);
+        assert_eq!(stride(0, 3), Vec::<usize>::new());
+
+        // The peak of each bucket is kept.
+        let points: Vec<_> = (0..9)
+            .map(|i| match i {
+                2 => [2.0, 5.0],
+                6 => [6.0, 10.0],
+                _ => [i as f64, 0.0],
+            })
+            .collect();
+        let indices = lttb(&points, 4);
+        assert_eq!(indices, vec![0, 2, 6, 8]);
+        // Too few points to reduce.
+        assert_eq!(lttb(&points, 20).len(), 9);
+
+        assert_eq!(Reduction::Global.resolve(0.0), Reduction::None);
+        assert_eq!(Reduction::Global.resolve(0.01), Reduction::Rdp(0.01));
+        assert_eq!(Reduction::Lttb(4).resolve(0.01).indices(&points), indices);
+    }
+
+    #[test]
+    fn test_adaptive() {
+        let points: Vec<_> = (0..100).map(|i| [i as f64, (i % 7) as f64]).collect();
+        // Sparse enough, the visible points and their neighbours are kept.
+        assert_eq!(adaptive(&points, 10.5, 14.0, 10), [10, 11, 12, 13, 14, 15]);
+        assert_eq!(adaptive(&points, -5.0, 1.0, 10), [0, 1, 2]);
+        assert!(adaptive(&points, 200.0, 300.0, 10).is_empty());
+        // Zoomed out, the points are reduced, keeping the ends.
+        let reduced = adaptive(&points, 0.0, 99.0, 10);
+        assert_eq!(reduced.len(), 10);
+        assert_eq!((reduced[0], reduced[9]), (0, 99));
+    }
+}
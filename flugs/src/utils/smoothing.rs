@@ -0,0 +1,42 @@
+/// Smooth `ys` with a Gaussian kernel of width `sigma` (in points). The
+/// kernel is cut off at 3 sigma. Near the boundaries it is truncated and
+/// renormalized, so that the ends are not pulled towards zero.
+pub fn gaussian_smooth(ys: &[f64], sigma: f64) -> Vec<f64> {
+    if sigma <= 0.0 || !sigma.is_finite() {
+        return ys.to_vec();
+    }
+    let radius = (3.0 * sigma).ceil() as usize;
+    let kernel: Vec<f64> = (0..=radius)
+        .map(|i| (-0.5 * (i as f64 / sigma).powi(2)).exp())
+        .collect();
+    (0..ys.len())
+        .map(|i| {
+            let window = i.saturating_sub(radius)..(i + radius + 1).min(ys.len());
+            let (sum, norm) = window.fold((0.0, 0.0), |(sum, norm), j| {
+                let weight = kernel[i.abs_diff(j)];
+                (sum + weight * ys[j], norm + weight)
+            });
+            sum / norm
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gaussian_smooth() {
+        // Constant data stays constant, also at the edges.
+        assert_eq!(gaussian_smooth(&[2.0; 10], 1.5), vec![2.0; 10]);
+        // A spike is spread symmetrically, keeping its area.
+        let mut spike = vec![0.0; 41];
+        spike[20] = 1.0;
+        let smoothed = gaussian_smooth(&spike, 2.0);
+        assert!((smoothed.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+        assert!((smoothed[18] - smoothed[22]).abs() < 1e-12);
+        assert!(smoothed[20] < 1.0 && smoothed[20] > smoothed[19]);
+        // Zero width does nothing.
+        assert_eq!(gaussian_smooth(&spike, 0.0), spike);
+    }
+}
@@ -0,0 +1,156 @@
+//! Extension point for custom per-file transforms.
+//!
+//! Code linking the crate can register transforms with
+//! [`EguiApp::register_transform`](crate::EguiApp::register_transform), they
+//! are then offered in the properties of each file (under "Advanced") and
+//! applied after the built-in transform, smoothing and baseline, before the
+//! offsets and scaling. For example, a transform that takes the absolute
+//! value:
+//!
+//! ```
+//! use flugs::utils::transform::{DataTransform, TransformRegistry};
+//!
+//! struct Abs;
+//!
+//! impl DataTransform for Abs {
+//!     fn name(&self) -> &str {
+//!         "absolute value"
+//!     }
+//!
+//!     fn apply(&self, data: &mut Vec<[f64; 2]>) {
+//!         for [_, y] in data.iter_mut() {
+//!             *y = y.abs();
+//!         }
+//!     }
+//! }
+//!
+//! let mut registry = TransformRegistry::default();
+//! registry.register(Abs).unwrap();
+//! let mut data = vec![[0.0, -1.0], [1.0, 2.0]];
+//! for transform in registry.resolve(&["absolute value".to_string()]) {
+//!     transform.apply(&mut data);
+//! }
+//! assert_eq!(data, [[0.0, 1.0], [1.0, 2.0]]);
+//! ```
+
+use std::{fmt, sync::Arc};
+
+/// A transform of the x-y data of a file.
+pub trait DataTransform {
+    /// Name shown in the file properties, also stored in sessions to refer
+    /// to the transform, so it should not change.
+    fn name(&self) -> &str;
+
+    /// Transform the points in place, points may also be added or removed.
+    fn apply(&self, data: &mut Vec<[f64; 2]>);
+}
+
+impl fmt::Debug for dyn DataTransform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DataTransform({:?})", self.name())
+    }
+}
+
+/// The offsets and scaling of a file, the built-in transform that is always
+/// applied last. The data is scaled around `ymin`, so that scaling does not
+/// move the lowest point.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OffsetScale {
+    pub xoffset: f64,
+    pub yoffset: f64,
+    pub yscale: f64,
+    pub ymin: f64,
+}
+
+impl OffsetScale {
+    /// The point `[x, y]` with the offsets and scaling applied.
+    pub fn point(&self, [x, y]: [f64; 2]) -> [f64; 2] {
+        let OffsetScale {
+            xoffset,
+            yoffset,
+            yscale,
+            ymin,
+        } = *self;
+        [x + xoffset, (y - ymin) * yscale + yoffset + ymin]
+    }
+}
+
+impl DataTransform for OffsetScale {
+    fn name(&self) -> &str {
+        "offset and scale"
+    }
+
+    fn apply(&self, data: &mut Vec<[f64; 2]>) {
+        for point in data.iter_mut() {
+            *point = self.point(*point);
+        }
+    }
+}
+
+/// Transforms that can be enabled per file, looked up by name.
+#[derive(Clone, Debug, Default)]
+pub struct TransformRegistry {
+    transforms: Vec<Arc<dyn DataTransform>>,
+}
+
+impl TransformRegistry {
+    /// Add `transform`, unless there is one with the same name already.
+    pub fn register(&mut self, transform: impl DataTransform + 'static) -> Result<(), String> {
+        if self.get(transform.name()).is_some() {
+            return Err(format!(
+                "a transform named '{}' is registered already",
+                transform.name()
+            ));
+        }
+        self.transforms.push(Arc::new(transform));
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<dyn DataTransform>> {
+        self.transforms
+            .iter()
+            .find(|transform| transform.name() == name)
+            .cloned()
+    }
+
+    /// Names of the registered transforms, in the order they were added.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.transforms.iter().map(|transform| transform.name())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transforms.is_empty()
+    }
+
+    /// The transforms called `names`, in that order. Names that are not
+    /// registered are skipped.
+    pub fn resolve(&self, names: &[String]) -> Vec<Arc<dyn DataTransform>> {
+        names.iter().filter_map(|name| self.get(name)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry() {
+        let mut registry = TransformRegistry::default();
+        let offset = OffsetScale {
+            xoffset: 1.0,
+            yoffset: 2.0,
+            yscale: 3.0,
+            ymin: 1.0,
+        };
+        registry.register(offset).unwrap();
+        assert!(registry.register(offset).is_err());
+        assert_eq!(registry.names().collect::<Vec<_>>(), ["offset and scale"]);
+
+        let names = ["unknown".to_string(), "offset and scale".to_string()];
+        let transforms = registry.resolve(&names);
+        assert_eq!(transforms.len(), 1);
+        let mut data = vec![[0.0, 1.0], [1.0, 2.0]];
+        transforms[0].apply(&mut data);
+        assert_eq!(data, [[1.0, 3.0], [2.0, 6.0]]);
+    }
+}
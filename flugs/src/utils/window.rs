@@ -0,0 +1,56 @@
+use std::f64::consts::PI;
+
+/// Window (apodization) function applied to processed data, e.g. before an
+/// FFT or when exporting smoothed data.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WindowFunction {
+    /// Leave the data as it is.
+    #[default]
+    Rectangular,
+    Hann,
+}
+
+impl WindowFunction {
+    /// Weight of the `i`-th of `n` points.
+    pub fn weight(&self, i: usize, n: usize) -> f64 {
+        match self {
+            Self::Rectangular => 1.0,
+            Self::Hann if n < 2 => 1.0,
+            Self::Hann => 0.5 - 0.5 * (2.0 * PI * i as f64 / (n - 1) as f64).cos(),
+        }
+    }
+
+    /// Multiply `ys` with the window in place.
+    pub fn apply(&self, ys: &mut [f64]) {
+        let n = ys.len();
+        for (i, y) in ys.iter_mut().enumerate() {
+            *y *= self.weight(i, n);
+        }
+    }
+
+    /// Multiply the y-values of `points` with the window in place.
+    pub fn apply_to_points(&self, points: &mut [[f64; 2]]) {
+        let n = points.len();
+        for (i, [_, y]) in points.iter_mut().enumerate() {
+            *y *= self.weight(i, n);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hann_window() {
+        let mut ys = vec![1.0; 5];
+        WindowFunction::Hann.apply(&mut ys);
+        let expected = [0.0, 0.5, 1.0, 0.5, 0.0];
+        for (y, e) in ys.iter().zip(expected) {
+            assert!((y - e).abs() < 1e-12, "{ys:?} != {expected:?}");
+        }
+        let mut ys = vec![2.0; 3];
+        WindowFunction::Rectangular.apply(&mut ys);
+        assert_eq!(ys, vec![2.0; 3]);
+    }
+}
@@ -0,0 +1,126 @@
+//! Minimal writer for ZIP archives without compression ("stored" entries).
+
+use std::io::Write;
+
+/// Write the `entries` (file name within the archive and file contents) as a
+/// ZIP archive to `writer`.
+pub fn write_zip<W: Write>(writer: &mut W, entries: &[(String, Vec<u8>)]) -> Result<(), String> {
+    let mut buf = Vec::new();
+    let mut central_directory = Vec::new();
+
+    for (name, contents) in entries {
+        let (name_len, size) = (checked_u16(name.len())?, checked_u32(contents.len())?);
+        let offset = checked_u32(buf.len())?;
+        let crc = crc32(contents);
+
+        // Local file header.
+        push_u32(&mut buf, 0x04034b50);
+        push_common_header(&mut buf, crc, size, name_len);
+        buf.extend_from_slice(name.as_bytes());
+        buf.extend_from_slice(contents);
+
+        // Central directory file header.
+        push_u32(&mut central_directory, 0x02014b50);
+        push_u16(&mut central_directory, 20); // version made by
+        push_common_header(&mut central_directory, crc, size, name_len);
+        push_u16(&mut central_directory, 0); // file comment length
+        push_u16(&mut central_directory, 0); // disk number
+        push_u16(&mut central_directory, 0); // internal attributes
+        push_u32(&mut central_directory, 0); // external attributes
+        push_u32(&mut central_directory, offset);
+        central_directory.extend_from_slice(name.as_bytes());
+    }
+
+    // End of central directory record.
+    let n_entries = checked_u16(entries.len())?;
+    let (cd_size, cd_offset) = (
+        checked_u32(central_directory.len())?,
+        checked_u32(buf.len())?,
+    );
+    buf.extend(central_directory);
+    push_u32(&mut buf, 0x06054b50);
+    push_u16(&mut buf, 0); // number of this disk
+    push_u16(&mut buf, 0); // disk where central directory starts
+    push_u16(&mut buf, n_entries);
+    push_u16(&mut buf, n_entries);
+    push_u32(&mut buf, cd_size);
+    push_u32(&mut buf, cd_offset);
+    push_u16(&mut buf, 0); // comment length
+
+    writer
+        .write_all(&buf)
+        .map_err(|err| format!("could not write zip archive: {err}"))
+}
+
+/// Fields shared by local and central directory headers.
+fn push_common_header(buf: &mut Vec<u8>, crc: u32, size: u32, name_len: u16) {
+    push_u16(buf, 20); // version needed to extract
+    push_u16(buf, 0x0800); // flags: file name is UTF-8
+    push_u16(buf, 0); // compression method: stored
+    push_u16(buf, 0); // modification time: 00:00:00
+    push_u16(buf, 0x21); // modification date: 1980-01-01
+    push_u32(buf, crc);
+    push_u32(buf, size); // compressed size
+    push_u32(buf, size); // uncompressed size
+    push_u16(buf, name_len);
+    push_u16(buf, 0); // extra field length
+}
+
+fn push_u16(buf: &mut Vec<u8>, val: u16) {
+    buf.extend_from_slice(&val.to_le_bytes());
+}
+
+fn push_u32(buf: &mut Vec<u8>, val: u32) {
+    buf.extend_from_slice(&val.to_le_bytes());
+}
+
+fn checked_u16(val: usize) -> Result<u16, String> {
+    u16::try_from(val).map_err(|_| format!("{val} exceeds the limits of the zip format"))
+}
+
+fn checked_u32(val: usize) -> Result<u32, String> {
+    u32::try_from(val).map_err(|_| format!("{val} exceeds the limits of the zip format"))
+}
+
+/// CRC-32 (as used by ZIP) of `data`.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xffffffff_u32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb88320 & mask);
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_zip() {
+        assert_eq!(crc32(b"123456789"), 0xcbf43926);
+
+        let entries = vec![
+            ("plot.svg".to_string(), b"<svg/>".to_vec()),
+            ("data/a.csv".to_string(), b"1,2\n".to_vec()),
+        ];
+        let mut buf = Vec::new();
+        write_zip(&mut buf, &entries).unwrap();
+
+        assert_eq!(&buf[..4], &0x04034b50_u32.to_le_bytes());
+        // The contents are stored right after the first local header.
+        assert_eq!(&buf[30 + 8..30 + 8 + 6], b"<svg/>");
+        // End of central directory record with two entries.
+        let eocd = &buf[buf.len() - 22..];
+        assert_eq!(&eocd[..4], &0x06054b50_u32.to_le_bytes());
+        assert_eq!(u16::from_le_bytes([eocd[10], eocd[11]]), 2);
+        let cd_offset = u32::from_le_bytes([eocd[16], eocd[17], eocd[18], eocd[19]]) as usize;
+        assert_eq!(
+            &buf[cd_offset..cd_offset + 4],
+            &0x02014b50_u32.to_le_bytes()
+        );
+    }
+}
@@ -0,0 +1,33 @@
+//! Exit codes of the binary for invalid arguments and failed rendering.
+
+use std::process::Command;
+
+fn flugs(args: &[&str]) -> Option<i32> {
+    Command::new(env!("CARGO_BIN_EXE_flugs"))
+        .args(args)
+        // Keep the config of the user out of the tests.
+        .env(
+            "FLUGS_CONFIG_DIR",
+            std::env::temp_dir().join("flugs-cli-test"),
+        )
+        .output()
+        .expect("the binary runs")
+        .status
+        .code()
+}
+
+#[test]
+fn test_invalid_arguments_exit_with_2() {
+    assert_eq!(flugs(&["--render", "session.json"]), Some(2));
+    assert_eq!(flugs(&["--config-dir"]), Some(2));
+}
+
+#[test]
+fn test_failed_render_exits_with_1() {
+    let output = std::env::temp_dir().join("flugs-cli-test.svg");
+    let output = output.to_str().unwrap();
+    assert_eq!(
+        flugs(&["--render", "missing-session.json", output]),
+        Some(1)
+    );
+}
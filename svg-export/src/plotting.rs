@@ -29,6 +29,9 @@ trait Element {
 pub struct Figure {
     width: u64,
     height: u64,
+    /// Width and height with units (e.g. `8.5cm`) the figure is scaled to
+    /// when shown or printed.
+    physical_size: Option<(String, String)>,
     axes: Vec<Axis>,
     annotations: Vec<Box<dyn Element>>,
 }
@@ -38,6 +41,7 @@ impl Figure {
         Self {
             width,
             height,
+            physical_size: None,
             axes: Vec::new(),
             annotations: Vec::new(),
         }
@@ -48,11 +52,20 @@ impl Figure {
         Self {
             width,
             height,
+            physical_size: None,
             axes: vec![ax],
             annotations: Vec::new(),
         }
     }
 
+    /// Give the figure a physical size, e.g. `("8.5cm", "6cm")`. The
+    /// figure is still laid out in pixels, which are stretched to fill it.
+    /// With 96 pixels per inch, fonts and lines keep their nominal size.
+    pub fn with_physical_size(mut self, width: &str, height: &str) -> Self {
+        self.physical_size = Some((width.to_string(), height.to_string()));
+        self
+    }
+
     pub fn add_axis(&mut self, ax: Axis) {
         self.axes.push(ax);
     }
@@ -60,6 +73,10 @@ impl Figure {
     /// Render this `Figure` to raw SVG markup.
     pub fn render(&self) -> String {
         let mut root = Tag::<svg::SVG>::new(self.width, self.height, None);
+        if let Some((width, height)) = &self.physical_size {
+            root.set_parameter("width", width);
+            root.set_parameter("height", height);
+        }
         for ax in self.axes.iter() {
             let ax_tags = ax.to_tags(self);
             root.add_children(ax_tags);
@@ -86,6 +103,9 @@ impl Default for Figure {
 pub struct Axis {
     draw_legend: bool,
     elements: Vec<Box<dyn Element>>,
+    /// Tick positions of the x- and y-axis that were set explicitly, see
+    /// `Axis::with_xticks`.
+    fixed_ticks: [Option<Vec<f64>>; 2],
     height: f64,
     limits: [f64; 4],
     plots: Vec<LinePlot>,
@@ -107,6 +127,7 @@ impl Axis {
             height,
             limits: [0.0, 1.0, 0.0, 1.0],
             ticks: Default::default(),
+            fixed_ticks: [None, None],
             draw_legend: false,
             plots: Vec::new(),
             elements: Vec::new(),
@@ -156,6 +177,23 @@ impl Axis {
         self
     }
 
+    /// Place the ticks of the x-axis at `positions` instead of choosing
+    /// them, positions outside of the limits are left out.
+    pub fn with_xticks(mut self, positions: &[f64]) -> Self {
+        self.fixed_ticks[0] = Some(positions.to_vec());
+        self.autoticks();
+        self
+    }
+
+    /// Place the ticks of the y-axis at `positions`, see
+    /// [`Axis::with_xticks`]. Set them before the y-label, which is placed
+    /// next to the tick labels.
+    pub fn with_yticks(mut self, positions: &[f64]) -> Self {
+        self.fixed_ticks[1] = Some(positions.to_vec());
+        self.autoticks();
+        self
+    }
+
     pub fn with_ylabel(mut self, text: &str) -> Self {
         let y_tick_label_width = self.ticks.y_tick_label_character_width();
         let ylabel = Text {
@@ -215,8 +253,21 @@ impl Axis {
             (2.5, 5.0)
         };
 
-        self.ticks.xpos = positions(xmin, xmax, mx);
-        self.ticks.ypos = positions(ymin, ymax, my);
+        let within = |fixed: &[f64], min: f64, max: f64| -> Vec<f64> {
+            fixed
+                .iter()
+                .copied()
+                .filter(|pos| (min..=max).contains(pos))
+                .collect()
+        };
+        self.ticks.xpos = match &self.fixed_ticks[0] {
+            Some(fixed) => within(fixed, xmin, xmax),
+            None => positions(xmin, xmax, mx),
+        };
+        self.ticks.ypos = match &self.fixed_ticks[1] {
+            Some(fixed) => within(fixed, ymin, ymax),
+            None => positions(ymin, ymax, my),
+        };
 
         // Make room for the ytick labels by resizing and moving the axis.
         // TODO: This will make the axis shrink with each call to this function.
@@ -228,6 +279,7 @@ impl Axis {
         // self.width -= shift;
     }
 
+    #[allow(clippy::type_complexity)]
     fn transformations(
         &self,
         fig: &Figure,
@@ -400,10 +452,10 @@ impl Axis {
                 }
             // The ith point lies within axis, we add it to the current segment.
             } else {
-                segments.last_mut().map(|(xp_cur, yp_cur)| {
+                if let Some((xp_cur, yp_cur)) = segments.last_mut() {
                     xp_cur.push(xs[i]);
                     yp_cur.push(ys[i]);
-                });
+                }
                 // If (i + 1)th datapoint is also within axis, we do not need to check
                 // for crossings ...
                 if outside(i + 1) {
@@ -413,10 +465,10 @@ impl Axis {
                     // ... but we have to check if it is the last point, because then
                     // we have to add it to the current (and last) segment.
                     if i + 1 == n {
-                        segments.last_mut().map(|(xp_cur, yp_cur)| {
+                        if let Some((xp_cur, yp_cur)) = segments.last_mut() {
                             xp_cur.push(xs[i]);
                             yp_cur.push(ys[i]);
-                        });
+                        }
                         break;
                     }
                     continue;
@@ -428,16 +480,16 @@ impl Axis {
             if xm == xn {
                 // Because of the earlier check, we know that 0 < x < 1.
                 if (ym > 1.0 || yn > 1.0) {
-                    segments.last_mut().map(|(xp_cur, yp_cur)| {
+                    if let Some((xp_cur, yp_cur)) = segments.last_mut() {
                         xp_cur.push(xs[i]);
                         yp_cur.push(ymax);
-                    });
+                    }
                 }
                 if (ym < 0.0 || yn < 0.0) {
-                    segments.last_mut().map(|(xp_cur, yp_cur)| {
+                    if let Some((xp_cur, yp_cur)) = segments.last_mut() {
                         xp_cur.push(xs[i]);
                         yp_cur.push(ymin);
-                    });
+                    }
                 }
                 // There cannot be any other crossings, so we continue.
                 continue;
@@ -454,19 +506,19 @@ impl Axis {
             // This tests whether the line defined by data points crosses left boundary:
             if 0.0 < b && b <= 1.0 && xm < 0.0 {
                 num_crossings += 1;
-                segments.last_mut().map(|(xp_cur, yp_cur)| {
+                if let Some((xp_cur, yp_cur)) = segments.last_mut() {
                     xp_cur.push(xmin);
                     yp_cur.push(b * yspan + ymin);
-                });
+                }
             }
 
             // This tests whether the line defined by data points crosses top boundary:
             if 0.0 <= (1.0 - b) / m && (1.0 - b) / m < 1.0 && (yn > 1.0 || ym > 1.0) {
                 num_crossings += 1;
-                segments.last_mut().map(|(xp_cur, yp_cur)| {
+                if let Some((xp_cur, yp_cur)) = segments.last_mut() {
                     xp_cur.push(xspan * (1.0 - b) / m + xmin);
                     yp_cur.push(ymax);
-                });
+                }
             }
 
             // This tests whether the line defined by data points crosses right
@@ -474,10 +526,10 @@ impl Axis {
             // crossings, so we can skip this check.
             if num_crossings < 2 && 0.0 < m + b && m + b <= 1.0 && xn > 1.0 {
                 num_crossings += 1;
-                segments.last_mut().map(|(xp_cur, yp_cur)| {
+                if let Some((xp_cur, yp_cur)) = segments.last_mut() {
                     xp_cur.push(xmax);
                     yp_cur.push((m + b) * yspan + ymin);
-                });
+                }
             }
 
             // This tests whether the line defined by data points crosses bottom
@@ -485,19 +537,19 @@ impl Axis {
             // crossings, so we can skip this check.
             if num_crossings < 2 && 0.0 <= -b / m && -b / m < 1.0 && (yn < 0.0 || ym < 0.0) {
                 num_crossings += 1;
-                segments.last_mut().map(|(xp_cur, yp_cur)| {
+                if let Some((xp_cur, yp_cur)) = segments.last_mut() {
                     xp_cur.push(-b * xspan / m + xmin);
                     yp_cur.push(ymin);
-                });
+                }
             }
 
             // If the last datapoint falls within the axis, we add it to the current (and last)
             // datapoint segment.
             if i == n - 1 && !outside(i + 1) {
-                segments.last_mut().map(|(xp_cur, yp_cur)| {
+                if let Some((xp_cur, yp_cur)) = segments.last_mut() {
                     xp_cur.push(xs[i + 1]);
                     yp_cur.push(ys[i + 1]);
-                });
+                }
             }
         }
         segments
@@ -695,6 +747,22 @@ impl LinePlot {
         self
     }
 
+    /// Shape of the line ends, a value of the SVG `stroke-linecap`
+    /// attribute (`butt`, `round` or `square`).
+    pub fn with_linecap(mut self, cap: &str) -> Self {
+        self.style
+            .insert("stroke-linecap".to_string(), cap.to_string());
+        self
+    }
+
+    /// Shape of the corners, a value of the SVG `stroke-linejoin` attribute
+    /// (e.g. `miter`, `round` or `bevel`).
+    pub fn with_linejoin(mut self, join: &str) -> Self {
+        self.style
+            .insert("stroke-linejoin".to_string(), join.to_string());
+        self
+    }
+
     pub fn with_name(mut self, name: &str) -> Self {
         self.name.drain(..);
         self.name.write_str(name);
@@ -837,4 +905,33 @@ mod tests {
         assert_eq!(magnitude_min, 4);
         assert_eq!(magnitude_max, 4);
     }
+
+    #[test]
+    fn test_fixed_ticks() {
+        let ax = Axis::new(0.1, 0.1, 0.8, 0.8)
+            .with_xticks(&[-1.0, 0.0, 2.5, 5.0, 7.5])
+            .with_xlim(5.0, 0.0)
+            .with_ylim(0.0, 1.0);
+        // Changing the limits keeps the fixed ticks, the others are chosen.
+        assert_eq!(ax.ticks.xpos, [0.0, 2.5, 5.0]);
+        assert!(!ax.ticks.ypos.is_empty());
+        let ax = ax.with_yticks(&[0.5]);
+        assert_eq!(ax.ticks.ypos, [0.5]);
+    }
+    #[test]
+    fn test_line_cap_and_join() {
+        let mut fig = Figure::empty(100, 100);
+        let line = LinePlot::new(&[0.0, 1.0], &[0.0, 1.0])
+            .with_linecap("round")
+            .with_linejoin("bevel");
+        Axis::new(0.1, 0.1, 0.8, 0.8)
+            .with_xlim(0.0, 1.0)
+            .with_ylim(0.0, 1.0)
+            .with_lineplot(line)
+            .insert_into(&mut fig);
+        let svg = fig.render();
+        // The style is written as CSS in the `style` attribute.
+        assert!(svg.contains("stroke-linecap:round"), "{svg}");
+        assert!(svg.contains("stroke-linejoin:bevel"), "{svg}");
+    }
 }
@@ -86,6 +86,9 @@ where
     pub fn add_children(&mut self, children: Vec<Box<dyn RenderTag>>) {
         self.children.extend(children);
     }
+    pub fn set_parameter(&mut self, key: &str, value: &str) {
+        self.parameters.insert(key.to_string(), value.to_string());
+    }
 }
 
 // ----------------------------------------------------------------------------
@@ -216,7 +219,7 @@ impl Tag<Polyline> {
     ) -> Self {
         let children = Vec::new();
         let mut raw_points = String::new();
-        for (x, y) in xs.into_iter().zip(ys.into_iter()) {
+        for (x, y) in xs.into_iter().zip(ys) {
             write!(raw_points, "{x},{y} ");
         }
         let mut parameters: Params = [("points", raw_points), ("fill", "none".to_string())]
@@ -243,6 +246,7 @@ impl Tag<Polyline> {
 // ----------------------------------------------------------------------------
 
 #[derive(Debug)]
+#[allow(clippy::upper_case_acronyms)]
 pub struct SVG {}
 #[derive(Debug)]
 pub struct Circle {}
@@ -0,0 +1,135 @@
+/// Text encoding of a file.
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Default)]
+pub enum Encoding {
+    /// Detect the encoding from a byte order mark, otherwise use UTF-16 if
+    /// it looks like (ASCII) text with every other byte zero, UTF-8 if the
+    /// file is valid UTF-8 and Latin-1 as last resort.
+    #[default]
+    Auto,
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Latin1,
+}
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF16LE_BOM: &[u8] = &[0xFF, 0xFE];
+const UTF16BE_BOM: &[u8] = &[0xFE, 0xFF];
+
+impl Encoding {
+    /// Guess the encoding of `bytes`, never returns `Auto`.
+    pub fn detect(bytes: &[u8]) -> Self {
+        if bytes.starts_with(UTF8_BOM) {
+            return Encoding::Utf8;
+        }
+        if bytes.starts_with(UTF16LE_BOM) {
+            return Encoding::Utf16Le;
+        }
+        if bytes.starts_with(UTF16BE_BOM) {
+            return Encoding::Utf16Be;
+        }
+        // Without BOM, UTF-16 encoded numbers and delimiters have a zero in
+        // every high byte (which is valid, but unlikely UTF-8). Only the
+        // start of the file is checked.
+        let sample = &bytes[..bytes.len().min(1024) & !1];
+        let zeros_at = |offset: usize| {
+            sample
+                .iter()
+                .skip(offset)
+                .step_by(2)
+                .filter(|b| **b == 0)
+                .count()
+        };
+        let half = sample.len() / 2;
+        if half > 0 && zeros_at(1) > half * 9 / 10 {
+            Encoding::Utf16Le
+        } else if half > 0 && zeros_at(0) > half * 9 / 10 {
+            Encoding::Utf16Be
+        } else if std::str::from_utf8(bytes).is_ok() {
+            Encoding::Utf8
+        } else {
+            Encoding::Latin1
+        }
+    }
+
+    /// Decode `bytes` into a string, dropping a leading byte order mark.
+    /// `Auto` detects the encoding first (see [`Encoding::detect`]).
+    pub fn decode(self, bytes: &[u8]) -> Result<String, String> {
+        match self {
+            Encoding::Auto => Encoding::detect(bytes).decode(bytes),
+            Encoding::Utf8 => {
+                let bytes = bytes.strip_prefix(UTF8_BOM).unwrap_or(bytes);
+                String::from_utf8(bytes.to_vec()).map_err(|err| format!("invalid UTF-8: {err}"))
+            }
+            Encoding::Utf16Le | Encoding::Utf16Be => {
+                let (bom, from_bytes): (_, fn([u8; 2]) -> u16) = if self == Encoding::Utf16Le {
+                    (UTF16LE_BOM, u16::from_le_bytes)
+                } else {
+                    (UTF16BE_BOM, u16::from_be_bytes)
+                };
+                let bytes = bytes.strip_prefix(bom).unwrap_or(bytes);
+                if bytes.len() % 2 != 0 {
+                    return Err("invalid UTF-16: odd number of bytes".into());
+                }
+                let units: Vec<u16> = bytes
+                    .chunks_exact(2)
+                    .map(|pair| from_bytes([pair[0], pair[1]]))
+                    .collect();
+                String::from_utf16(&units).map_err(|err| format!("invalid UTF-16: {err}"))
+            }
+            // Latin-1 bytes are the first 256 Unicode code points.
+            Encoding::Latin1 => Ok(bytes.iter().map(|b| *b as char).collect()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn utf16(text: &str, bom: bool, little_endian: bool) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let units = bom.then_some(0xFEFF).into_iter().chain(text.encode_utf16());
+        for unit in units {
+            if little_endian {
+                bytes.extend(unit.to_le_bytes());
+            } else {
+                bytes.extend(unit.to_be_bytes());
+            }
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_detect_and_decode() {
+        let text = "x;y\n1.5;2µ\n";
+        let cases = [
+            ([UTF8_BOM, text.as_bytes()].concat(), Encoding::Utf8),
+            (text.as_bytes().to_vec(), Encoding::Utf8),
+            (utf16(text, true, true), Encoding::Utf16Le),
+            (utf16(text, true, false), Encoding::Utf16Be),
+            (utf16(text, false, true), Encoding::Utf16Le),
+            (utf16(text, false, false), Encoding::Utf16Be),
+            (text.chars().map(|c| c as u8).collect(), Encoding::Latin1),
+        ];
+        for (bytes, encoding) in cases {
+            assert_eq!(Encoding::detect(&bytes), encoding);
+            assert_eq!(Encoding::Auto.decode(&bytes).unwrap(), text);
+        }
+        // Plain ASCII in UTF-16 is valid UTF-8 as well.
+        assert_eq!(
+            Encoding::detect(&utf16("1;2\n", false, true)),
+            Encoding::Utf16Le
+        );
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert!(Encoding::Utf8.decode(&[b'1', 0xB5]).is_err());
+        assert!(Encoding::Utf16Le.decode(&[b'1', 0, b'2']).is_err());
+        // Unpaired surrogate.
+        assert!(Encoding::Utf16Le.decode(&[0x00, 0xD8]).is_err());
+        // Overriding the detected encoding.
+        assert_eq!(Encoding::Latin1.decode("µ".as_bytes()).unwrap(), "Âµ");
+    }
+}
@@ -1,14 +1,69 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
-use std::{collections::HashMap, path::Path};
+mod encoding;
+mod number_format;
+
+use std::{collections::HashMap, io::Read, path::Path};
+
+pub use encoding::Encoding;
+pub use number_format::{NumberFormat, ThousandsSeparator};
 
 pub struct Parser {
     lexer: Lexer,
 }
 
+/// Which characters separate the columns of a file.
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Default)]
+pub enum DelimiterMode {
+    /// Spaces, tabs, commas and semicolons.
+    #[default]
+    Auto,
+    /// Only spaces and tabs (runs of them count as one delimiter), e.g. for
+    /// space-aligned text tables.
+    Whitespace,
+    /// Only semicolons, spaces and tabs around the fields are trimmed. A
+    /// field with whitespace between two values is no number.
+    Semicolon,
+}
+
+/// The character that separates the integer from the fractional part of
+/// numbers.
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Default)]
+pub enum DecimalSeparator {
+    #[default]
+    Point,
+    /// Commas are then never treated as delimiters.
+    Comma,
+}
+
+impl DelimiterMode {
+    fn delimiters(&self, format: NumberFormat) -> &'static [char] {
+        let commas_in_numbers = format.decimal_separator == DecimalSeparator::Comma
+            || format.thousands_separator == ThousandsSeparator::Comma;
+        match (self, commas_in_numbers) {
+            (DelimiterMode::Auto, false) => &[' ', ',', '\t', ';'],
+            (DelimiterMode::Auto, true) => &[' ', '\t', ';'],
+            (DelimiterMode::Whitespace, _) => &[' ', '\t'],
+            (DelimiterMode::Semicolon, _) => &[' ', '\t', ';'],
+        }
+    }
+}
+
+impl DecimalSeparator {
+    fn char(&self) -> char {
+        match self {
+            DecimalSeparator::Point => '.',
+            DecimalSeparator::Comma => ',',
+        }
+    }
+}
+
 struct Lexer {
     // Holds the raw data from reading the CSV file.
     raw_input: String,
+    delimiter_mode: DelimiterMode,
+    number_format: NumberFormat,
+    delimiters: &'static [char],
 }
 
 #[derive(PartialEq, Copy, Clone, Debug)]
@@ -33,19 +88,77 @@ enum Token {
 }
 
 impl Parser {
+    /// Read the file at `path`, detecting its encoding.
     pub fn from_path(path: &Path) -> Result<Self, std::io::Error> {
+        Self::from_path_with_encoding(path, Encoding::Auto)
+    }
+
+    /// Read the file at `path` with the given encoding. Failing to decode
+    /// the file is reported as `InvalidData` error.
+    pub fn from_path_with_encoding(
+        path: &Path,
+        encoding: Encoding,
+    ) -> Result<Self, std::io::Error> {
         Ok(Self {
-            lexer: Lexer::from_path(path)?,
+            lexer: Lexer::from_path(path, encoding)?,
         })
     }
 
-    #[allow(unused)]
-    fn from_string(raw_input: String) -> Self {
+    /// Read only the first `max_lines` lines of the file at `path`, e.g. to
+    /// preview large files. With `Encoding::Auto`, the encoding is detected
+    /// from these lines only.
+    pub fn from_path_head(
+        path: &Path,
+        encoding: Encoding,
+        max_lines: usize,
+    ) -> Result<Self, std::io::Error> {
+        let bytes = read_head(path, encoding, max_lines)?;
+        let raw_input = encoding
+            .decode(&bytes)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+        Ok(Self {
+            lexer: Lexer::from_string(raw_input),
+        })
+    }
+
+    /// Parse `raw_input` rather than a file, e.g. pasted text.
+    pub fn from_string(raw_input: String) -> Self {
         Self {
             lexer: Lexer::from_string(raw_input),
         }
     }
 
+    pub fn with_delimiter_mode(mut self, mode: DelimiterMode) -> Self {
+        self.lexer.delimiter_mode = mode;
+        self.lexer.delimiters = mode.delimiters(self.lexer.number_format);
+        self
+    }
+
+    pub fn with_decimal_separator(mut self, separator: DecimalSeparator) -> Self {
+        self.lexer.number_format.decimal_separator = separator;
+        self.lexer.delimiters = self
+            .lexer
+            .delimiter_mode
+            .delimiters(self.lexer.number_format);
+        self
+    }
+
+    /// Skip `separator` within the integer part of numbers.
+    pub fn with_thousands_separator(mut self, separator: ThousandsSeparator) -> Self {
+        self.lexer.number_format.thousands_separator = separator;
+        self.lexer.delimiters = self
+            .lexer
+            .delimiter_mode
+            .delimiters(self.lexer.number_format);
+        self
+    }
+
+    /// Guess the number format from the first lines of the input, see
+    /// [`NumberFormat::detect`].
+    pub fn detect_number_format(&self) -> Option<NumberFormat> {
+        NumberFormat::detect(&self.lexer.raw_input)
+    }
+
     pub fn parse_as_floats(mut self) -> (String, Vec<Vec<f64>>) {
         // We collect columns into this vector.
         let mut data: Vec<Vec<f64>> = Vec::new();
@@ -65,6 +178,9 @@ impl Parser {
         let tokens = self.lexer.walk_input();
         let mut tokens = tokens.into_iter().peekable();
         let mut line_valid = true;
+        let trim_fields = self.lexer.delimiter_mode == DelimiterMode::Semicolon;
+        // Values are ignored until the next field after a field is invalid.
+        let mut skip_field = false;
         while let Some(tok) = tokens.next() {
             // If the current column index goes beyond the
             // currently known maximum number of columns, we
@@ -73,6 +189,7 @@ impl Parser {
                 max_column_idx = current_column_idx;
             }
             match tok {
+                Token::Integer(_) | Token::Float(_) if skip_field => {}
                 Token::Integer(x) => {
                     current_row.insert(current_column_idx, x as f64);
                 }
@@ -95,7 +212,16 @@ impl Parser {
                     // If all repeated delimiters are whitespace, we count them as
                     // a single delimiter.
                     if delimiters.chars().all(is_whitespace) {
-                        current_column_idx += 1;
+                        if !trim_fields {
+                            current_column_idx += 1;
+                        } else if !matches!(
+                            tokens.peek(),
+                            None | Some(Token::Newline) | Some(Token::Comment(_))
+                        ) {
+                            // Another value follows within the field.
+                            current_row.remove(&current_column_idx);
+                            skip_field = true;
+                        }
                     // If repeated delimiters contain non-whitespace delimiters,
                     // we ignore the whitespace and count only non-whitespace
                     // delimiters.
@@ -104,6 +230,7 @@ impl Parser {
                             .chars()
                             .filter(|chr| !is_whitespace(*chr))
                             .count();
+                        skip_field = false;
                     }
                 }
                 Token::Comment(c) => comments.extend(c.chars().chain(['\n'])),
@@ -124,6 +251,7 @@ impl Parser {
                     current_column_idx = 0;
                     current_row.clear();
                     line_valid = true;
+                    skip_field = false;
                 }
             }
         }
@@ -144,17 +272,71 @@ fn add_column(result: &mut Vec<Vec<f64>>) {
     }
 }
 
+/// The bytes of the first `max_lines` lines of the file (all of it, if it is
+/// shorter). The file is read in chunks until enough line breaks were found,
+/// they are counted in code units of the encoding.
+fn read_head(path: &Path, encoding: Encoding, max_lines: usize) -> Result<Vec<u8>, std::io::Error> {
+    let mut file = std::fs::File::open(path)?;
+    let mut bytes = Vec::new();
+    let mut chunk = [0; 8192];
+    let mut newline: Option<&[u8]> = None;
+    // Bytes before this offset were searched for line breaks already.
+    let mut searched = 0;
+    let mut lines = 0;
+    while lines < max_lines {
+        let n = file.read(&mut chunk)?;
+        bytes.extend_from_slice(&chunk[..n]);
+        // The start of the file tells whether it is UTF-16 (see
+        // `Encoding::detect`), in the other encodings a line break is a byte.
+        if n > 0 && newline.is_none() && bytes.len() < 1024 {
+            continue;
+        }
+        let newline = *newline.get_or_insert_with(|| -> &[u8] {
+            let encoding = match encoding {
+                Encoding::Auto => Encoding::detect(&bytes[..bytes.len().min(1024)]),
+                encoding => encoding,
+            };
+            match encoding {
+                Encoding::Utf16Le => b"\n\0",
+                Encoding::Utf16Be => b"\0\n",
+                _ => b"\n",
+            }
+        });
+        while searched + newline.len() <= bytes.len() {
+            searched += newline.len();
+            if bytes[searched - newline.len()..searched] == *newline {
+                lines += 1;
+                if lines == max_lines {
+                    bytes.truncate(searched);
+                    break;
+                }
+            }
+        }
+        if n == 0 {
+            break;
+        }
+    }
+    Ok(bytes)
+}
+
 impl Lexer {
-    fn from_path(path: &Path) -> Result<Self, std::io::Error> {
+    fn from_path(path: &Path, encoding: Encoding) -> Result<Self, std::io::Error> {
         // Note: Control characters are ignored throughout lexing.
         // TODO: Maybe there is a more performant way to do this
         // with a byte reader...
-        let raw_input: String = std::fs::read_to_string(path)?.chars().collect();
+        let raw_input = encoding
+            .decode(&std::fs::read(path)?)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
         Ok(Lexer::from_string(raw_input))
     }
 
     fn from_string(raw_input: String) -> Self {
-        Self { raw_input }
+        Self {
+            raw_input,
+            delimiter_mode: DelimiterMode::default(),
+            number_format: NumberFormat::default(),
+            delimiters: DelimiterMode::default().delimiters(NumberFormat::default()),
+        }
     }
 
     fn walk_input(&mut self) -> Vec<Token> {
@@ -166,6 +348,11 @@ impl Lexer {
         for (line_no, line) in raw_input.lines().enumerate() {
             self.lex_line(line_no, line, &mut lex_buffer, &mut tokens);
         }
+        // Newlines are emitted at the start of each line, so the last line
+        // needs one as well to be completed by the parser.
+        if !tokens.is_empty() {
+            tokens.push(Token::Newline);
+        }
         tokens
     }
 
@@ -203,7 +390,10 @@ impl Lexer {
                         lex_buffer.push(chr);
                     }
                 },
-                ' ' | ',' | '\t' | ';' => match state {
+                // Digits of the integer part may be grouped.
+                chr if state == State::InInteger
+                    && self.number_format.thousands_separator.char() == Some(chr) => {}
+                chr if self.delimiters.contains(&chr) => match state {
                     State::InInteger => {
                         tokens.push(Token::Integer(lex_buffer.parse().unwrap()));
                         lex_buffer.clear();
@@ -217,7 +407,7 @@ impl Lexer {
                         state = State::OnDelimiter;
                     }
                     State::MaybeFloat | State::MaybeScientific => {
-                        invalid(&lex_buffer, chr, i, line_no, state);
+                        invalid(lex_buffer, chr, i, line_no, state);
                         state = State::InComment;
                     }
                     State::InComment => lex_buffer.push(chr),
@@ -230,28 +420,29 @@ impl Lexer {
                     State::OnDelimiter => tokens.push(Token::Delimiter(chr)),
                 },
 
-                '.' => match state {
+                // The buffer always holds a point, so that it parses as float.
+                chr if chr == self.number_format.decimal_separator.char() => match state {
                     State::StartOfLine | State::OnDelimiter => {
                         lex_buffer.clear();
-                        lex_buffer.push(chr);
+                        lex_buffer.push('.');
                         state = State::MaybeFloat;
                     }
                     State::InComment => {
                         lex_buffer.push(chr);
                     }
                     State::InInteger => {
-                        lex_buffer.push(chr);
+                        lex_buffer.push('.');
                         state = State::InFloat;
                     }
                     State::MaybeFloat
                     | State::MaybeScientific
                     | State::InFloat
-                    | State::InScientific => invalid(&lex_buffer, chr, i, line_no, state),
+                    | State::InScientific => invalid(lex_buffer, chr, i, line_no, state),
                 },
 
                 '+' | '-' => match state {
                     State::InInteger | State::InFloat | State::MaybeFloat | State::InScientific => {
-                        invalid(&lex_buffer, chr, i, line_no, state);
+                        invalid(lex_buffer, chr, i, line_no, state);
                     }
                     State::InComment => lex_buffer.push(chr),
                     State::StartOfLine | State::OnDelimiter => {
@@ -276,10 +467,38 @@ impl Lexer {
                         state = State::InComment;
                     }
                     State::InScientific | State::MaybeScientific => {
-                        invalid(&lex_buffer, chr, i, line_no, state)
+                        invalid(lex_buffer, chr, i, line_no, state)
                     }
                 },
 
+                // Infinity, e.g. `inf` or `-Infinity`.
+                'i' | 'I'
+                    if matches!(state, State::StartOfLine | State::OnDelimiter)
+                        || (state == State::InInteger
+                            && ["+", "-"].contains(&lex_buffer.as_str())) =>
+                {
+                    match infinity_len(line.chars().skip(i), self.delimiters) {
+                        Some(len) => {
+                            if state != State::InInteger {
+                                lex_buffer.clear();
+                            }
+                            lex_buffer.push_str("inf");
+                            for _ in 1..len {
+                                chrs.next();
+                            }
+                            state = State::InFloat;
+                        }
+                        None if state == State::InInteger => {
+                            invalid(lex_buffer, chr, i, line_no, state)
+                        }
+                        None => {
+                            lex_buffer.clear();
+                            lex_buffer.push(chr);
+                            state = State::InComment;
+                        }
+                    }
+                }
+
                 _ => match state {
                     State::StartOfLine | State::OnDelimiter => {
                         lex_buffer.clear();
@@ -293,7 +512,7 @@ impl Lexer {
                     | State::MaybeFloat
                     | State::MaybeScientific
                     | State::InFloat
-                    | State::InScientific => invalid(&lex_buffer, chr, i, line_no, state),
+                    | State::InScientific => invalid(lex_buffer, chr, i, line_no, state),
                 },
             }
 
@@ -309,7 +528,7 @@ impl Lexer {
                         tokens.push(Token::Float(lex_buffer.parse().unwrap()));
                     }
                     State::MaybeFloat | State::MaybeScientific => {
-                        invalid(&lex_buffer, chr, i, line_no, state);
+                        invalid(lex_buffer, chr, i, line_no, state);
                     }
                     _ => {}
                 };
@@ -318,6 +537,16 @@ impl Lexer {
     }
 }
 
+/// Number of characters of `inf` or `infinity` (ignoring case) at the start
+/// of `rest`, if the word ends there.
+fn infinity_len(rest: impl Iterator<Item = char>, delimiters: &[char]) -> Option<usize> {
+    let word: String = rest.take_while(|chr| !delimiters.contains(chr)).collect();
+    ["inf", "infinity"]
+        .iter()
+        .any(|inf| word.eq_ignore_ascii_case(inf))
+        .then(|| word.chars().count())
+}
+
 fn invalid(raw_text: &str, chr: char, i: usize, line_no: usize, state: State) {
     let parse_as = match state {
         State::OnDelimiter => "delimiter",
@@ -485,6 +714,23 @@ mod test {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn test_parse_whitespace_delimited() {
+        init();
+
+        let input = "# x      y\n  1.0    2.0\n  2.0\t\t4.0\n";
+        let parser =
+            Parser::from_string(input.into()).with_delimiter_mode(DelimiterMode::Whitespace);
+        let (_, result) = parser.parse_as_floats();
+        assert_eq!(result, vec![vec![1.0, 2.0], vec![2.0, 4.0]]);
+
+        // Commas are no delimiters in whitespace mode, so the line is invalid.
+        let parser =
+            Parser::from_string("1.0,2.0\n".into()).with_delimiter_mode(DelimiterMode::Whitespace);
+        let (_, result) = parser.parse_as_floats();
+        assert_eq!(result.len(), 1);
+    }
+
     #[test]
     fn test_watercluster_paper_file() {
         let input = r#"# This is a comment
@@ -497,4 +743,104 @@ mod test {
         let (_, result) = parser.parse_as_floats();
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn test_parse_head() {
+        init();
+        let text: String = (0..100).map(|i| format!("{i};{}\n", 2 * i)).collect();
+        let utf16: Vec<u8> = text.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let path = std::env::temp_dir().join("turbo_csv_test_parse_head.csv");
+        for bytes in [text.as_bytes().to_vec(), utf16] {
+            std::fs::write(&path, bytes).unwrap();
+            let parser = Parser::from_path_head(&path, Encoding::Auto, 3).unwrap();
+            let (_, result) = parser.parse_as_floats();
+            assert_eq!(result, vec![vec![0.0, 1.0, 2.0], vec![0.0, 2.0, 4.0]]);
+            // Asking for more lines than there are reads the whole file.
+            let parser = Parser::from_path_head(&path, Encoding::Auto, 1000).unwrap();
+            assert_eq!(parser.parse_as_floats().1[0].len(), 100);
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_parse_head_utf16() {
+        init();
+        // The comment spans several chunks, and its characters contain 0x0A
+        // bytes, which are no line breaks in UTF-16.
+        let comment = "\u{0A30}".repeat(5000);
+        let text = format!("#{comment}\n1;2\n3;4\n5;6\n");
+        let path = std::env::temp_dir().join("turbo_csv_test_parse_head_utf16.csv");
+        for little_endian in [true, false] {
+            let bytes: Vec<u8> = std::iter::once(0xFEFF)
+                .chain(text.encode_utf16())
+                .flat_map(|unit| {
+                    if little_endian {
+                        unit.to_le_bytes()
+                    } else {
+                        unit.to_be_bytes()
+                    }
+                })
+                .collect();
+            std::fs::write(&path, bytes).unwrap();
+            let parser = Parser::from_path_head(&path, Encoding::Auto, 3).unwrap();
+            let (comments, result) = parser.parse_as_floats();
+            assert!(comments.contains(&comment));
+            assert_eq!(result, vec![vec![1.0, 3.0], vec![2.0, 4.0]]);
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_parse_decimal_comma() {
+        init();
+        let input = "x;y\n1,5; -2,25\n3;4,0e1\n";
+        for mode in [DelimiterMode::Auto, DelimiterMode::Semicolon] {
+            let parser = Parser::from_string(input.into())
+                .with_decimal_separator(DecimalSeparator::Comma)
+                .with_delimiter_mode(mode);
+            let (_, result) = parser.parse_as_floats();
+            assert_eq!(result, vec![vec![1.5, 3.0], vec![-2.25, 40.0]]);
+        }
+        // Only semicolons separate columns in semicolon mode.
+        let parser =
+            Parser::from_string("1.5;2\n".into()).with_delimiter_mode(DelimiterMode::Semicolon);
+        assert_eq!(parser.parse_as_floats().1, vec![vec![1.5], vec![2.0]]);
+        let parser = Parser::from_string(" 1 ;\t2 \n3 4;5\n".into())
+            .with_delimiter_mode(DelimiterMode::Semicolon);
+        let result = parser.parse_as_floats().1;
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0][0], 1.0);
+        assert!(result[0][1].is_nan());
+        assert_eq!(result[1], [2.0, 5.0]);
+    }
+
+    #[test]
+    fn test_parse_infinity() {
+        init();
+        let parser = Parser::from_string("x y\ninf -Infinity\n+INF 1e999\n1 info\n".into());
+        let (comments, result) = parser.parse_as_floats();
+        assert_eq!(result[0], [f64::INFINITY, f64::INFINITY, 1.0]);
+        assert_eq!(result[1][..2], [f64::NEG_INFINITY, f64::INFINITY]);
+        assert!(result[1][2].is_nan());
+        // Words that only start like infinity are comments.
+        assert!(comments.contains("info"));
+    }
+
+    #[test]
+    fn test_parse_thousands_separator() {
+        init();
+        let parser = Parser::from_string("1.234,5;-2.000\n".into())
+            .with_decimal_separator(DecimalSeparator::Comma)
+            .with_thousands_separator(ThousandsSeparator::Point);
+        assert_eq!(
+            parser.parse_as_floats().1,
+            vec![vec![1234.5], vec![-2000.0]]
+        );
+        // Commas are not delimiters then.
+        let input = "1,234.5 2\n";
+        let parser = Parser::from_string(input.into());
+        assert_eq!(parser.detect_number_format(), NumberFormat::detect(input));
+        let parser = parser.with_thousands_separator(ThousandsSeparator::Comma);
+        assert_eq!(parser.parse_as_floats().1, vec![vec![1234.5], vec![2.0]]);
+    }
 }
@@ -0,0 +1,236 @@
+use crate::DecimalSeparator;
+
+/// The character that groups the digits of the integer part of numbers,
+/// e.g. the comma in `1,234.5`.
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Default)]
+pub enum ThousandsSeparator {
+    #[default]
+    None,
+    Point,
+    /// Commas are then never treated as delimiters.
+    Comma,
+    Apostrophe,
+}
+
+impl ThousandsSeparator {
+    pub(crate) fn char(&self) -> Option<char> {
+        match self {
+            ThousandsSeparator::None => None,
+            ThousandsSeparator::Point => Some('.'),
+            ThousandsSeparator::Comma => Some(','),
+            ThousandsSeparator::Apostrophe => Some('\''),
+        }
+    }
+
+    fn from_char(chr: char) -> Self {
+        match chr {
+            '.' => ThousandsSeparator::Point,
+            ',' => ThousandsSeparator::Comma,
+            _ => ThousandsSeparator::Apostrophe,
+        }
+    }
+}
+
+/// How the numbers of a file are written.
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Default)]
+pub struct NumberFormat {
+    pub decimal_separator: DecimalSeparator,
+    pub thousands_separator: ThousandsSeparator,
+}
+
+/// Number of lines starting with a number that are checked by
+/// [`NumberFormat::detect`].
+const SAMPLE_LINES: usize = 50;
+
+impl NumberFormat {
+    /// Guess the format from the first lines of `text` that start with a
+    /// number. `None` if the lines do not tell (e.g. `1.234` may be a
+    /// thousand or one) or contradict each other.
+    pub fn detect(text: &str) -> Option<Self> {
+        let mut votes = Votes::default();
+        let lines = text
+            .lines()
+            .filter(|line| {
+                line.trim_start()
+                    .starts_with(|chr: char| chr.is_ascii_digit() || "+-.".contains(chr))
+            })
+            .take(SAMPLE_LINES);
+        for line in lines {
+            let fields: Vec<_> = line
+                .split([' ', '\t', ';'])
+                .filter(|field| !field.is_empty())
+                .collect();
+            // Commas are delimiters if there are no others, or if they
+            // are next to them (e.g. `1.5, 2.5`).
+            let comma_delimited = fields.len() == 1 && fields[0].contains(',')
+                || fields
+                    .iter()
+                    .any(|field| field.starts_with(',') || field.ends_with(','));
+            if comma_delimited {
+                line.split([' ', '\t', ';', ','])
+                    .for_each(|field| votes.add(field, false));
+            } else {
+                fields.iter().for_each(|field| votes.add(field, true));
+            }
+        }
+        votes.result()
+    }
+}
+
+/// Evidence for the separators collected by [`NumberFormat::detect`].
+#[derive(Debug, Default)]
+struct Votes {
+    point: usize,
+    comma: usize,
+    thousands: Vec<char>,
+}
+
+impl Votes {
+    /// Look at the separators in `field`, which is ignored unless it is a
+    /// (possibly signed) number in decimal or scientific notation.
+    /// `may_have_commas` is false if commas separate the columns.
+    fn add(&mut self, field: &str, may_have_commas: bool) {
+        let field = field.trim_start_matches(['+', '-']);
+        let mantissa = match field.split_once(['e', 'E']) {
+            Some((mantissa, exponent)) => {
+                let exponent = exponent.trim_start_matches(['+', '-']);
+                if exponent.is_empty() || !exponent.chars().all(|chr| chr.is_ascii_digit()) {
+                    return;
+                }
+                mantissa
+            }
+            None => field,
+        };
+        let separators = if may_have_commas { ".,'" } else { ".'" };
+        if mantissa.is_empty()
+            || !mantissa
+                .chars()
+                .all(|chr| chr.is_ascii_digit() || separators.contains(chr))
+            || mantissa.ends_with(|chr: char| !chr.is_ascii_digit())
+        {
+            return;
+        }
+
+        let count = |sep: char| mantissa.matches(sep).count();
+        let (points, commas, apostrophes) = (count('.'), count(','), count('\''));
+        // The last point or comma is the decimal separator, unless it is
+        // repeated (then it groups thousands), digits before it must be
+        // grouped by the other separator.
+        let Some(last) = mantissa.chars().rev().find(|chr| ['.', ','].contains(chr)) else {
+            if apostrophes > 0 && is_grouped(mantissa, '\'') {
+                self.thousands.push('\'');
+            }
+            return;
+        };
+        let other = if last == '.' { ',' } else { '.' };
+        let last_count = if last == '.' { points } else { commas };
+        let other_count = if last == '.' { commas } else { points };
+        let vote = |votes: &mut Self, decimal: char| match decimal {
+            '.' => votes.point += 1,
+            _ => votes.comma += 1,
+        };
+
+        if last_count > 1 {
+            // e.g. 1,234,567
+            if other_count == 0 && is_grouped(mantissa, last) {
+                self.thousands.push(last);
+                vote(self, other);
+            }
+            return;
+        }
+        let (integer, fraction) = mantissa.rsplit_once(last).unwrap_or((mantissa, ""));
+        let grouping = if other_count > 0 {
+            Some(other)
+        } else if apostrophes > 0 {
+            Some('\'')
+        } else {
+            None
+        };
+        match grouping {
+            Some(sep) => {
+                // e.g. 1.234,5 or 1'234.5
+                if is_grouped(integer, sep) {
+                    self.thousands.push(sep);
+                    vote(self, last);
+                }
+            }
+            None => {
+                // 1.234 or 1,234 may group thousands as well.
+                let ambiguous = fraction.len() == 3 && (1..=3).contains(&integer.len());
+                if !ambiguous {
+                    vote(self, last);
+                }
+            }
+        }
+    }
+
+    fn result(self) -> Option<NumberFormat> {
+        let decimal_separator = match (self.point > 0, self.comma > 0) {
+            (true, false) => DecimalSeparator::Point,
+            (false, true) => DecimalSeparator::Comma,
+            _ => return None,
+        };
+        let decimal = match decimal_separator {
+            DecimalSeparator::Point => '.',
+            DecimalSeparator::Comma => ',',
+        };
+        let thousands_separator = match self.thousands.first() {
+            None => ThousandsSeparator::None,
+            Some(sep) if *sep != decimal && self.thousands.iter().all(|other| other == sep) => {
+                ThousandsSeparator::from_char(*sep)
+            }
+            Some(_) => return None,
+        };
+        Some(NumberFormat {
+            decimal_separator,
+            thousands_separator,
+        })
+    }
+}
+
+/// Whether `integer` consists of digits grouped by `sep` into thousands,
+/// with up to three digits in the first group.
+fn is_grouped(integer: &str, sep: char) -> bool {
+    let mut groups = integer.split(sep);
+    let first = groups.next().unwrap_or_default();
+    (1..=3).contains(&first.len())
+        && first.chars().all(|chr| chr.is_ascii_digit())
+        && groups.all(|group| group.len() == 3 && group.chars().all(|chr| chr.is_ascii_digit()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn format(decimal: DecimalSeparator, thousands: ThousandsSeparator) -> Option<NumberFormat> {
+        Some(NumberFormat {
+            decimal_separator: decimal,
+            thousands_separator: thousands,
+        })
+    }
+
+    #[test]
+    fn test_detect() {
+        use DecimalSeparator as D;
+        use ThousandsSeparator as T;
+
+        let detect = NumberFormat::detect;
+        assert_eq!(detect("x,y\n1.5,2.5\n3.0,4\n"), format(D::Point, T::None));
+        assert_eq!(detect("1.5, 2.5\n"), format(D::Point, T::None));
+        assert_eq!(detect("x;y\n1,5;2,5\n"), format(D::Comma, T::None));
+        assert_eq!(detect("1,5 2,5e-3\n"), format(D::Comma, T::None));
+        assert_eq!(detect("1.234,56;7\n"), format(D::Comma, T::Point));
+        assert_eq!(detect("1,234.56\t7\n"), format(D::Point, T::Comma));
+        assert_eq!(detect("1'234.5 2\n"), format(D::Point, T::Apostrophe));
+        // Repeated separators group thousands.
+        assert_eq!(detect("1.234.567;8\n"), format(D::Comma, T::Point));
+        // Only integers, or a thousand or one.
+        assert_eq!(detect("1,2\n3,4\n"), None);
+        assert_eq!(detect("1.234 5\n"), None);
+        // Dates do not group thousands.
+        assert_eq!(detect("12.03.2024 1.5\n"), format(D::Point, T::None));
+        // Contradicting lines.
+        assert_eq!(detect("1.5;2\n1,5;2\n"), None);
+        assert_eq!(detect("no numbers\n"), None);
+    }
+}